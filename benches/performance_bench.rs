@@ -113,7 +113,7 @@ fn bench_different_themes(c: &mut Criterion) {
         group.bench_with_input(format!("theme_{}", theme), theme, |b, theme| {
             b.iter(|| {
                 let output = Command::new("./target/release/claude-powerline")
-                    .args(&["--theme", theme])
+                    .args(["--theme", theme])
                     .output()
                     .expect("Failed to execute");
                 black_box(output)
@@ -130,7 +130,7 @@ fn bench_with_git_operations(c: &mut Criterion) {
     c.bench_function("rust_with_git", |b| {
         b.iter(|| {
             let output = Command::new("./target/release/claude-powerline")
-                .args(&["--theme", "dark"])
+                .args(["--theme", "dark"])
                 .output()
                 .expect("Failed to execute");
             black_box(output)
@@ -0,0 +1,39 @@
+use crate::alerts::{AlertEvent, AlertKind};
+use anyhow::Result;
+
+/// Only events that need attention right away are raised on the desktop
+pub fn wants_desktop_notification(event: &AlertEvent) -> bool {
+    matches!(event.kind, AlertKind::BudgetExceeded | AlertKind::ContextCritical)
+}
+
+/// Raise `event` as a desktop notification through the platform's notification service
+/// (libnotify over D-Bus, the macOS Notification Center or Windows toasts)
+#[cfg(feature = "desktop-notifications")]
+pub async fn show_desktop_notification(event: &AlertEvent) -> Result<()> {
+    use anyhow::Context;
+
+    const NOTIFICATION_TITLE: &str = "Claude Powerline";
+
+    let message = event.message.clone();
+    // Showing a notification blocks on the platform service, so keep it off the runtime threads
+    tokio::task::spawn_blocking(move || {
+        let mut notification = notify_rust::Notification::new();
+        notification.appname(NOTIFICATION_TITLE).summary(NOTIFICATION_TITLE).body(&message);
+        #[cfg(all(unix, not(target_os = "macos")))]
+        notification.urgency(notify_rust::Urgency::Critical);
+        notification.show().map(|_| ())
+    })
+    .await?
+    .context("Failed to show the desktop notification")
+}
+
+/// Without the `desktop-notifications` feature there is no notifier, so the notification is
+/// skipped and only logged at debug level, since it would be skipped the same way on every render
+#[cfg(not(feature = "desktop-notifications"))]
+pub async fn show_desktop_notification(event: &AlertEvent) -> Result<()> {
+    crate::utils::debug_with_context("alerts", &format!(
+        "No desktop notification for `{}`: built without the `desktop-notifications` feature",
+        event.message
+    ));
+    Ok(())
+}
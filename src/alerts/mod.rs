@@ -0,0 +1,316 @@
+pub mod desktop;
+pub mod webhook;
+
+pub use desktop::*;
+pub use webhook::*;
+
+use crate::config::{AlertsConfig, BudgetAmount, BudgetConfig};
+use crate::segments::{budget_usage, BlockInfo, BlockSegment, ContextInfo, ContextSegment, Segment, SegmentContext, SessionInfo, SessionSegment, TodayInfo, TodaySegment};
+use crate::utils::{debug_with_context, state_root, warn_with_context, with_file_lock, write_atomic};
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use futures::future::{join_all, BoxFuture, FutureExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Default share of a budget, in percent, at which a warning fires
+pub const DEFAULT_WARNING_THRESHOLD: f64 = 80.0;
+
+/// Default share of the context window, in percent, below which the context alert fires
+pub const DEFAULT_CONTEXT_CRITICAL_PERCENT: u32 = 10;
+
+/// Default minimum time between two alerts of the same kind and scope
+pub const DEFAULT_ALERT_DEBOUNCE_SECS: u64 = 60 * 60;
+
+/// How long a render waits for all of its webhooks and notifications together
+const DELIVERY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    BudgetWarning,
+    BudgetExceeded,
+    BlockReset,
+    ContextCritical,
+}
+
+/// Something worth telling the user about, detected after a render
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AlertEvent {
+    #[serde(rename = "event")]
+    pub kind: AlertKind,
+    /// `session`, `today`, `block` or `context`
+    pub scope: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub used: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<f64>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl AlertEvent {
+    /// Events of the same kind and scope share a debounce window
+    fn debounce_key(&self) -> String {
+        format!("{:?}|{}", self.kind, self.scope)
+    }
+}
+
+/// How far usage has progressed through a budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum BudgetLevel {
+    #[default]
+    Normal,
+    Warning,
+    Exceeded,
+}
+
+/// Usage the alerts are evaluated against, read from one render's entries
+#[derive(Debug, Clone, Default)]
+pub struct UsageSnapshot {
+    pub session: SessionInfo,
+    pub today: TodayInfo,
+    pub block: BlockInfo,
+    /// Only read when context alerts are wanted
+    pub context: Option<ContextInfo>,
+}
+
+impl UsageSnapshot {
+    /// Usage as the configured session, today and block segments read it, so their `costSource`,
+    /// `source` and `includeCacheTokens` apply here too (hook-reported costs come from `ctx`).
+    /// Budgets are watched even when those segments are hidden.
+    pub async fn collect(ctx: &SegmentContext, with_context: bool) -> Result<Self> {
+        let segments = &ctx.config.segments;
+        let mut session = SessionSegment::from_config(segments.session.as_ref());
+        session.enabled = true;
+        let mut today = TodaySegment::from_config(segments.today.as_ref());
+        today.enabled = true;
+        // Past days only feed the segment's comparisons, not any alert
+        today.compare_yesterday = false;
+        today.weekly_average = false;
+        let mut block = BlockSegment::from_config(segments.block.as_ref());
+        block.enabled = true;
+
+        Ok(Self {
+            session: session.data(ctx).await?,
+            today: today.data(ctx).await?,
+            block: block.data(ctx).await?,
+            context: if with_context { Some(ContextSegment::new().get_context_info().await?) } else { None },
+        })
+    }
+}
+
+/// What previous invocations saw, persisted in `<state root>/alerts.json` so an event
+/// fires when a threshold is crossed rather than on every render past it
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AlertState {
+    levels: HashMap<String, BudgetLevel>,
+    /// When the active block seen last ends
+    block_reset: Option<DateTime<Utc>>,
+    /// When each debounce key last fired
+    last_fired: HashMap<String, DateTime<Utc>>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl AlertState {
+    pub fn default_path() -> Option<PathBuf> {
+        state_root().map(|dir| dir.join("alerts.json"))
+    }
+
+    /// Load the state from `path`; a missing or unreadable file yields a fresh state
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let mut state = path
+            .as_deref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice::<AlertState>(&bytes).ok())
+            .unwrap_or_default();
+
+        state.path = path;
+        state
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        write_atomic(path, &serde_json::to_vec(self)?)
+    }
+
+    /// Events for every budget threshold crossed and every block that ended since the
+    /// previous call. Falling back below a threshold (a new day or block) is recorded silently.
+    pub fn detect(&mut self, budgets: Option<&BudgetConfig>, usage: &UsageSnapshot, now: DateTime<Utc>) -> Vec<AlertEvent> {
+        let mut events = Vec::new();
+
+        if let Some(budgets) = budgets {
+            let scopes = [
+                ("session", &budgets.session, usage.session.cost, usage.session.tokens),
+                ("today", &budgets.today, usage.today.cost, usage.today.tokens),
+                ("block", &budgets.block, usage.block.cost, usage.block.tokens),
+            ];
+            for (scope, budget, cost, tokens) in scopes {
+                let Some(budget) = budget else {
+                    continue;
+                };
+                let used = budget_usage(budget, cost, tokens);
+                let level = budget_level(budget, used);
+                let previous = self.levels.insert(scope.to_string(), level).unwrap_or_default();
+
+                if level > previous {
+                    events.push(budget_event(scope, budget, level, used, now));
+                }
+            }
+        }
+
+        if self.block_reset.is_some_and(|reset| now >= reset) {
+            events.push(AlertEvent {
+                kind: AlertKind::BlockReset,
+                scope: "block".to_string(),
+                message: "The 5-hour usage block has reset".to_string(),
+                used: None,
+                limit: None,
+                timestamp: now,
+            });
+        }
+        self.block_reset = usage.block.reset_time;
+
+        events
+    }
+
+    /// An event when the context window left drops below `critical_percent`, once per drop
+    pub fn detect_context(&mut self, critical_percent: u32, usage: &UsageSnapshot, now: DateTime<Utc>) -> Option<AlertEvent> {
+        let context = usage.context.as_ref()?;
+        let level = if context.context_left_percentage < critical_percent {
+            BudgetLevel::Exceeded
+        } else {
+            BudgetLevel::Normal
+        };
+        let previous = self.levels.insert("context".to_string(), level).unwrap_or_default();
+
+        (level > previous).then(|| AlertEvent {
+            kind: AlertKind::ContextCritical,
+            scope: "context".to_string(),
+            message: format!("Context window is nearly full: {}% left", context.context_left_percentage),
+            used: Some(context.input_tokens as f64),
+            limit: Some(context.usable_tokens as f64),
+            timestamp: now,
+        })
+    }
+
+    /// Drop events whose kind and scope already fired within `window`, so frequent
+    /// renders around a threshold don't repeat the same alert
+    pub fn debounce(&mut self, events: Vec<AlertEvent>, window: Duration) -> Vec<AlertEvent> {
+        events
+            .into_iter()
+            .filter(|event| {
+                let key = event.debounce_key();
+                let recent = self.last_fired.get(&key).is_some_and(|fired| event.timestamp - *fired < window);
+                if !recent {
+                    self.last_fired.insert(key, event.timestamp);
+                }
+                !recent
+            })
+            .collect()
+    }
+}
+
+fn budget_level(budget: &BudgetAmount, used: f64) -> BudgetLevel {
+    if budget.amount <= 0.0 {
+        return BudgetLevel::Normal;
+    }
+
+    let percent = used / budget.amount * 100.0;
+    if percent >= 100.0 {
+        BudgetLevel::Exceeded
+    } else if percent >= budget.warning_threshold.unwrap_or(DEFAULT_WARNING_THRESHOLD) {
+        BudgetLevel::Warning
+    } else {
+        BudgetLevel::Normal
+    }
+}
+
+fn budget_event(scope: &str, budget: &BudgetAmount, level: BudgetLevel, used: f64, now: DateTime<Utc>) -> AlertEvent {
+    let amount = |value: f64| match budget.budget_type.as_deref() {
+        Some("tokens") => format!("{:.0} tokens", value),
+        _ => format!("${:.2}", value),
+    };
+    let (kind, verb) = match level {
+        BudgetLevel::Exceeded => (AlertKind::BudgetExceeded, "exceeded"),
+        _ => (AlertKind::BudgetWarning, "is nearing"),
+    };
+
+    AlertEvent {
+        kind,
+        scope: scope.to_string(),
+        message: format!(
+            "{} usage {} its budget: {} of {} ({:.0}%)",
+            capitalize(scope), verb, amount(used), amount(budget.amount), used / budget.amount * 100.0
+        ),
+        used: Some(used),
+        limit: Some(budget.amount),
+        timestamp: now,
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Detect alerts for this render and deliver them to every configured webhook and,
+/// if enabled, the desktop. Returns the events that were delivered.
+pub async fn run_alerts(alerts: &AlertsConfig, budgets: Option<&BudgetConfig>, ctx: &SegmentContext) -> Result<Vec<AlertEvent>> {
+    let desktop = alerts.desktop.unwrap_or(false);
+    let usage = UsageSnapshot::collect(ctx, desktop || alerts.context_critical_percent.is_some()).await?;
+
+    // Concurrent renders would otherwise both fire an alert and overwrite each other's state
+    let detect = || {
+        let mut state = AlertState::load(AlertState::default_path());
+        let now = Utc::now();
+        let mut events = state.detect(budgets, &usage, now);
+        let critical_percent = alerts.context_critical_percent.unwrap_or(DEFAULT_CONTEXT_CRITICAL_PERCENT);
+        events.extend(state.detect_context(critical_percent, &usage, now));
+        let window = Duration::seconds(alerts.debounce_secs.unwrap_or(DEFAULT_ALERT_DEBOUNCE_SECS) as i64);
+        let events = state.debounce(events, window);
+        state.save().map(|_| events)
+    };
+    let events = match AlertState::default_path() {
+        Some(path) => with_file_lock(&path, detect)?,
+        None => detect()?,
+    };
+
+    let mut deliveries: Vec<BoxFuture<'_, ()>> = Vec::new();
+    for event in &events {
+        debug_with_context("alerts", &event.message);
+        // Hooks are named by their place in `alerts.webhooks`: the URL often carries a secret token
+        for (index, hook) in alerts.webhooks.iter().flatten().enumerate() {
+            deliveries.push(
+                async move {
+                    if let Err(e) = send_webhook(hook, event).await {
+                        warn_with_context("alerts", &format!("Webhook #{} failed: {:#}", index + 1, e));
+                    }
+                }
+                .boxed(),
+            );
+        }
+        if desktop && wants_desktop_notification(event) {
+            deliveries.push(
+                async move {
+                    if let Err(e) = show_desktop_notification(event).await {
+                        warn_with_context("alerts", &format!("Desktop notification failed: {:#}", e));
+                    }
+                }
+                .boxed(),
+            );
+        }
+    }
+    // All at once, and abandoned together (killing their processes) if they run long
+    if tokio::time::timeout(DELIVERY_TIMEOUT, join_all(deliveries)).await.is_err() {
+        warn_with_context("alerts", &format!("Alert delivery gave up after {}s", DELIVERY_TIMEOUT.as_secs()));
+    }
+
+    Ok(events)
+}
@@ -0,0 +1,44 @@
+use crate::alerts::AlertEvent;
+use crate::config::WebhookConfig;
+use anyhow::{Context, Result};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Give up on a webhook that hasn't answered within this long
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Request body for `hook`: the event itself, or `{"text": ...}` for Slack-compatible hooks
+pub fn webhook_body(hook: &WebhookConfig, event: &AlertEvent) -> Result<String> {
+    let body = match hook.format.as_deref() {
+        Some("slack") => serde_json::json!({ "text": format!(":warning: {}", event.message) }),
+        _ => serde_json::to_value(event)?,
+    };
+    Ok(body.to_string())
+}
+
+/// POST `event` to `hook` through `curl`, which handles HTTPS without pulling a TLS stack into the binary
+pub async fn send_webhook(hook: &WebhookConfig, event: &AlertEvent) -> Result<()> {
+    let body = webhook_body(hook, event)?;
+
+    let child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time"])
+        .arg(WEBHOOK_TIMEOUT.as_secs().to_string())
+        .args(["--header", "Content-Type: application/json", "--data-binary"])
+        .arg(&body)
+        .arg(&hook.url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to run curl")?;
+
+    let output = tokio::time::timeout(WEBHOOK_TIMEOUT + Duration::from_secs(1), child.wait_with_output())
+        .await
+        .context("Webhook timed out")??;
+    if !output.status.success() {
+        anyhow::bail!("curl exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
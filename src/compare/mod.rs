@@ -0,0 +1,285 @@
+use crate::config::Config;
+use crate::utils::strip_ansi;
+use anyhow::{Context, Result};
+use std::env;
+use std::ffi::OsStr;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// The npm package of the original TypeScript implementation
+pub const ORIGINAL_PACKAGE: &str = "@owloops/claude-powerline";
+
+/// How long the original gets to render; `npx` may have to fetch it first
+const ORIGINAL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Glyphs the original draws between segments in its powerline and capsule styles
+const SEPARATOR_GLYPHS: &[char] = &['\u{e0b0}', '\u{e0b1}', '\u{e0b2}', '\u{e0b3}', '\u{e0b4}', '\u{e0b6}'];
+
+/// How to run the original claude-powerline
+#[derive(Debug, Clone, PartialEq)]
+pub struct OriginalCommand {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+}
+
+impl OriginalCommand {
+    /// A command line such as `npx -y @owloops/claude-powerline`, split on whitespace
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut words = spec.split_whitespace().map(str::to_string);
+        let program = PathBuf::from(words.next()?);
+        Some(Self { program, args: words.collect() })
+    }
+
+    /// `explicit` (`--original`), else `CLAUDE_POWERLINE_ORIGINAL`, else the first npm-installed
+    /// `claude-powerline` on `PATH`. This binary shares the name, so only a Node script counts.
+    pub fn locate(explicit: Option<&str>) -> Result<Self> {
+        let spec = explicit
+            .map(str::to_string)
+            .or_else(|| env::var("CLAUDE_POWERLINE_ORIGINAL").ok())
+            .filter(|spec| !spec.trim().is_empty());
+        if let Some(spec) = spec {
+            return Self::parse(&spec).ok_or_else(|| anyhow::anyhow!("Empty --original command"));
+        }
+
+        let path_var = env::var_os("PATH").unwrap_or_default();
+        find_original_on_path(&path_var)
+            .map(|program| Self { program, args: Vec::new() })
+            .ok_or_else(|| anyhow::anyhow!(
+                "The original claude-powerline isn't installed; install it with `npm install -g {}` or pass --original <COMMAND>",
+                ORIGINAL_PACKAGE
+            ))
+    }
+
+    /// The command line, for the report header
+    pub fn display(&self) -> String {
+        std::iter::once(self.program.display().to_string())
+            .chain(self.args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Run the original with `config` (written out as its `--config` file) and the hook JSON on
+    /// stdin, in its powerline style so the segments can be told apart, and return what it printed
+    pub async fn render(&self, config: &Config, hook_input: Option<&str>) -> Result<String> {
+        // Created with unique names and private permissions, and removed when dropped
+        let temp_file = |kind: &str, contents: &[u8]| -> Result<tempfile::NamedTempFile> {
+            let mut file = tempfile::Builder::new()
+                .prefix(&format!("claude-powerline-compare-{}.", kind))
+                .suffix(".json")
+                .tempfile()
+                .context("Failed to create a temporary file")?;
+            file.write_all(contents)
+                .with_context(|| format!("Failed to write {}", file.path().display()))?;
+            Ok(file)
+        };
+        let config_file = temp_file("config", &serde_json::to_vec_pretty(&original_config(config)?)?)?;
+        let hook_file = temp_file("hook", hook_input.unwrap_or_default().as_bytes())?;
+
+        self.run(config_file.path(), hook_file.path()).await
+    }
+
+    async fn run(&self, config_path: &Path, hook_path: &Path) -> Result<String> {
+        let child = Command::new(&self.program)
+            .args(&self.args)
+            .arg("--style=powerline")
+            .arg(format!("--config={}", config_path.display()))
+            .stdin(std::fs::File::open(hook_path)?)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to run {}", self.display()))?;
+
+        let output = tokio::time::timeout(ORIGINAL_TIMEOUT, child.wait_with_output())
+            .await
+            .map_err(|_| anyhow::anyhow!("{} took longer than {}s", self.display(), ORIGINAL_TIMEOUT.as_secs()))??;
+        if !output.status.success() {
+            anyhow::bail!("{} exited with {}: {}", self.display(), output.status, String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// The first `claude-powerline` in `path_var` that resolves to a Node script (or, on Windows,
+/// npm's `.cmd` shim)
+pub fn find_original_on_path(path_var: &OsStr) -> Option<PathBuf> {
+    let names: &[&str] = if cfg!(windows) {
+        &["claude-powerline.cmd", "claude-powerline.ps1"]
+    } else {
+        &["claude-powerline"]
+    };
+
+    env::split_paths(path_var)
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file() && is_node_script(candidate))
+}
+
+fn is_node_script(path: &Path) -> bool {
+    let Ok(resolved) = path.canonicalize() else {
+        return false;
+    };
+    let extension = resolved.extension().and_then(OsStr::to_str).unwrap_or_default();
+    matches!(extension, "js" | "mjs" | "cjs" | "cmd" | "ps1")
+        || resolved.components().any(|component| component.as_os_str() == "node_modules")
+}
+
+/// `config` as JSON for the original, leaving out unset options so its own defaults apply
+fn original_config(config: &Config) -> Result<serde_json::Value> {
+    let mut value = serde_json::to_value(config)?;
+    drop_nulls(&mut value);
+    Ok(value)
+}
+
+fn drop_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(drop_nulls);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(drop_nulls),
+        _ => {}
+    }
+}
+
+/// The segments of the original's output: every line split at its separator glyphs, with
+/// colors and padding removed
+pub fn split_original_segments(output: &str) -> Vec<String> {
+    strip_ansi(output)
+        .lines()
+        .flat_map(|line| line.split(SEPARATOR_GLYPHS).map(normalize).collect::<Vec<_>>())
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+/// `text` without colors and with runs of whitespace collapsed, as segments are compared
+fn normalize(text: &str) -> String {
+    strip_ansi(text).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// How one segment of this implementation compares with the original's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentMatch {
+    Same,
+    Differs,
+    /// The original rendered nothing more at this position
+    OnlyOurs,
+    /// This implementation rendered nothing more at this position
+    OnlyOriginal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentComparison {
+    /// Name of our segment; `None` past the end of ours
+    pub name: Option<String>,
+    pub ours: Option<String>,
+    pub original: Option<String>,
+}
+
+impl SegmentComparison {
+    pub fn status(&self) -> SegmentMatch {
+        match (&self.ours, &self.original) {
+            (Some(ours), Some(original)) if ours == original => SegmentMatch::Same,
+            (Some(_), Some(_)) => SegmentMatch::Differs,
+            (Some(_), None) => SegmentMatch::OnlyOurs,
+            _ => SegmentMatch::OnlyOriginal,
+        }
+    }
+}
+
+/// Results of the `compare` subcommand
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompareReport {
+    /// The original's command line
+    pub original: String,
+    pub segments: Vec<SegmentComparison>,
+}
+
+impl CompareReport {
+    /// Pair our rendered `(name, text)` segments with the original's, position by position;
+    /// segments that rendered empty are left out first, as neither implementation draws them
+    pub fn new(original: String, ours: Vec<(String, String)>, theirs: Vec<String>) -> Self {
+        let mut ours = ours
+            .into_iter()
+            .map(|(name, text)| (name, normalize(&text)))
+            .filter(|(_, text)| !text.is_empty());
+        let mut theirs = theirs.into_iter();
+
+        let mut segments = Vec::new();
+        loop {
+            let (name, ours) = ours.next().unzip();
+            let original = theirs.next();
+            if ours.is_none() && original.is_none() {
+                break;
+            }
+            segments.push(SegmentComparison { name, ours, original });
+        }
+
+        Self { original, segments }
+    }
+
+    /// Segments that aren't the same in both
+    pub fn differences(&self) -> usize {
+        self.segments.iter().filter(|segment| segment.status() != SegmentMatch::Same).count()
+    }
+
+    /// One line per segment, followed by the original's rendering where it differs, with the
+    /// words that differ highlighted
+    pub fn render(&self, use_colors: bool) -> String {
+        let paint = |code: &str, text: &str| {
+            if use_colors {
+                format!("\x1b[{}m{}\x1b[0m", code, text)
+            } else {
+                text.to_string()
+            }
+        };
+        let width = self.segments
+            .iter()
+            .filter_map(|segment| segment.name.as_deref())
+            .map(str::len)
+            .chain(["original".len()])
+            .max()
+            .unwrap_or_default();
+
+        let mut lines = vec![format!("Original: {}", self.original), String::new()];
+        for segment in &self.segments {
+            let name = segment.name.as_deref().unwrap_or("(original)");
+            let ours = segment.ours.as_deref().unwrap_or_default();
+            let original = segment.original.as_deref().unwrap_or_default();
+
+            match segment.status() {
+                SegmentMatch::Same => lines.push(format!("{} {:width$}  {}", paint("32", "✓"), name, ours)),
+                SegmentMatch::Differs => {
+                    lines.push(format!("{} {:width$}  {}", paint("31", "≠"), name, highlight_words(ours, original, &paint)));
+                    lines.push(format!("  {:width$}  {}", "original", highlight_words(original, ours, &paint)));
+                }
+                SegmentMatch::OnlyOurs => {
+                    lines.push(format!("{} {:width$}  {}  {}", paint("33", "+"), name, ours, paint("2", "(not in the original)")));
+                }
+                SegmentMatch::OnlyOriginal => {
+                    lines.push(format!("{} {:width$}  {}  {}", paint("33", "-"), name, original, paint("2", "(only in the original)")));
+                }
+            }
+        }
+
+        let differences = self.differences();
+        lines.push(String::new());
+        lines.push(if differences == 0 {
+            format!("All {} segments match", self.segments.len())
+        } else {
+            format!("{} of {} segments differ", differences, self.segments.len())
+        });
+        lines.join("\n")
+    }
+}
+
+/// `text` with the words that `other` doesn't have painted bold red
+fn highlight_words(text: &str, other: &str, paint: &dyn Fn(&str, &str) -> String) -> String {
+    let other: Vec<&str> = other.split_whitespace().collect();
+    text.split_whitespace()
+        .map(|word| if other.contains(&word) { word.to_string() } else { paint("1;31", word) })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
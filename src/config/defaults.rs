@@ -1,14 +1,39 @@
 use crate::config::*;
 
+pub(crate) fn default_theme() -> String {
+    "dark".to_string()
+}
+
+pub(crate) fn default_style() -> String {
+    "minimal".to_string()
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
-            theme: "dark".to_string(),
-            style: "minimal".to_string(),
+            theme: default_theme(),
+            style: default_style(),
+            auto_theme: None,
+            theme_file: None,
             segments: SegmentConfig::default(),
             colors: None,
             budget: None,
             display: None,
+            performance: None,
+            cache: None,
+            cache_dir: None,
+            state_dir: None,
+            claude_paths: None,
+            wsl_all_profiles: None,
+            transcript_globs: None,
+            team: None,
+            sources: None,
+            pricing: None,
+            pricing_file: None,
+            statsd: None,
+            alerts: None,
+            logging: None,
+            profiles: None,
         }
     }
 }
@@ -24,6 +49,21 @@ impl Default for SegmentConfig {
             context: Some(ContextConfig::default()),
             metrics: Some(MetricsConfig::default()),
             model: Some(ModelConfig::default()),
+            health: None,
+            update: None,
+            team: None,
+        }
+    }
+}
+
+impl SegmentConfig {
+    /// Every segment set up with its defaults, including the opt-in ones `default()` leaves out
+    pub fn with_every_segment() -> Self {
+        Self {
+            health: Some(HealthConfig::default()),
+            update: Some(UpdateConfig::default()),
+            team: Some(TeamSegmentConfig::default()),
+            ..Self::default()
         }
     }
 }
@@ -33,6 +73,7 @@ impl Default for DirectoryConfig {
         Self {
             enabled: true,
             show_basename: Some(false),
+            layout: LayoutConfig::default(),
         }
     }
 }
@@ -46,6 +87,7 @@ impl Default for GitConfig {
             show_upstream: Some(false),
             show_stash_count: Some(false),
             show_repo_name: Some(false),
+            layout: LayoutConfig::default(),
         }
     }
 }
@@ -56,6 +98,12 @@ impl Default for BlockConfig {
             enabled: true,
             display_type: Some("tokens".to_string()),
             burn_type: Some("cost".to_string()),
+            burn_trend: None,
+            messages_left: None,
+            cost_source: None,
+            source: None,
+            include_cache_tokens: None,
+            layout: LayoutConfig::default(),
         }
     }
 }
@@ -65,6 +113,13 @@ impl Default for TodayConfig {
         Self {
             enabled: true,
             display_type: Some("cost".to_string()),
+            cost_source: None,
+            source: None,
+            include_cache_tokens: None,
+            heat_map: None,
+            compare_yesterday: None,
+            weekly_average: None,
+            layout: LayoutConfig::default(),
         }
     }
 }
@@ -75,6 +130,10 @@ impl Default for SessionConfig {
             enabled: true,
             display_type: Some("tokens".to_string()),
             cost_source: Some("calculated".to_string()),
+            source: None,
+            include_cache_tokens: None,
+            include_resumed: None,
+            layout: LayoutConfig::default(),
         }
     }
 }
@@ -84,6 +143,10 @@ impl Default for ContextConfig {
         Self {
             enabled: true,
             show_percentage_only: Some(false),
+            critical_percentage: None,
+            critical_icon: None,
+            compact_eta: None,
+            layout: LayoutConfig::default(),
         }
     }
 }
@@ -98,6 +161,7 @@ impl Default for MetricsConfig {
             show_message_count: Some(true),
             show_lines_added: Some(true),
             show_lines_removed: Some(true),
+            layout: LayoutConfig::default(),
         }
     }
 }
@@ -106,6 +170,8 @@ impl Default for ModelConfig {
     fn default() -> Self {
         Self {
             enabled: true,
+            source: None,
+            layout: LayoutConfig::default(),
         }
     }
 }
\ No newline at end of file
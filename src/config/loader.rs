@@ -1,15 +1,23 @@
-use crate::config::Config;
+use crate::config::{Config, SegmentConfig};
+use crate::utils::logger::{debug_with_context, warn_with_context};
 use anyhow::{Context, Result};
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use tokio::fs;
 
 /// Load configuration with priority: CLI args > Env vars > Config files > Defaults
 pub async fn load_config(config_path: Option<PathBuf>) -> Result<Config> {
+    load_profile_config(config_path, None).await
+}
+
+/// `load_config` with the named entry of `profiles` merged over the config files, field by
+/// field; naming a profile the config doesn't define is an error
+pub async fn load_profile_config(config_path: Option<PathBuf>, profile: Option<&str>) -> Result<Config> {
     let mut config = if let Some(path) = config_path {
-        load_config_file(&path).await?
+        load_config_file(&path, profile).await?
     } else {
-        load_config_from_default_locations().await?
+        load_config_from_default_locations(profile).await?
     };
 
     // Apply environment variable overrides
@@ -18,27 +26,170 @@ pub async fn load_config(config_path: Option<PathBuf>) -> Result<Config> {
     Ok(config)
 }
 
-/// Load configuration from default locations
-async fn load_config_from_default_locations() -> Result<Config> {
-    let search_paths = get_config_search_paths();
-    
-    for path in search_paths {
-        if path.exists() {
-            match load_config_file(&path).await {
-                Ok(config) => return Ok(config),
-                Err(e) => {
-                    eprintln!("Warning: Failed to load config from {}: {}", path.display(), e);
+/// The profile to apply: the one asked for, otherwise the one whose `match` rules fit this process
+fn select_profile(config: &serde_json::Value, requested: Option<&str>) -> Option<String> {
+    if let Some(name) = requested {
+        return Some(name.to_string());
+    }
+
+    let cwd = env::current_dir().ok()?;
+    let name = matching_profile(config, &cwd, |var| env::var(var).ok())?;
+    debug_with_context("config", &format!("Using profile `{}` for {}", name, cwd.display()));
+    Some(name)
+}
+
+/// The profile whose `match` rules select it for `cwd` and the variables `env` looks up.
+/// A profile matches when `cwd` is at or below one of its `match.cwd` directories (a leading
+/// `~/` is the home directory, a trailing `/**` is ignored, and a glob matches any directory
+/// above `cwd` too), or one of its `match.env` entries holds: `NAME` when the variable is set
+/// and not empty, `NAME=value` when it equals `value`. Of several matching profiles, the one
+/// with the longest matching `cwd` pattern wins; `env` matches rank below any `cwd` match.
+pub fn matching_profile(config: &serde_json::Value, cwd: &Path, env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let profiles = config.get("profiles")?.as_object()?;
+    let strings = |rules: Option<&serde_json::Value>| -> Vec<String> {
+        rules
+            .and_then(|rules| rules.as_array())
+            .map(|rules| rules.iter().filter_map(|rule| rule.as_str().map(str::to_string)).collect())
+            .unwrap_or_default()
+    };
+
+    let mut best: Option<(usize, &String)> = None;
+    for (name, profile) in profiles {
+        let rules = profile.get("match");
+        let cwd_rank = strings(rules.and_then(|rules| rules.get("cwd")))
+            .iter()
+            .filter(|pattern| cwd_pattern_matches(pattern, cwd))
+            .map(|pattern| pattern.len() + 1)
+            .max();
+        let env_matches = strings(rules.and_then(|rules| rules.get("env")))
+            .iter()
+            .any(|rule| match rule.split_once('=') {
+                Some((var, expected)) => env(var).is_some_and(|value| value == expected),
+                None => env(rule).is_some_and(|value| !value.is_empty()),
+            });
+
+        let Some(rank) = cwd_rank.or(env_matches.then_some(0)) else {
+            continue;
+        };
+        if best.is_none_or(|(best_rank, _)| rank > best_rank) {
+            best = Some((rank, name));
+        }
+    }
+    best.map(|(_, name)| name.clone())
+}
+
+/// Whether `cwd` is at or below the directory `pattern` names
+fn cwd_pattern_matches(pattern: &str, cwd: &Path) -> bool {
+    let pattern = pattern.strip_suffix("/**").unwrap_or(pattern);
+    let pattern = match (pattern.strip_prefix("~/").or((pattern == "~").then_some("")), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().into_owned(),
+        _ => pattern.to_string(),
+    };
+
+    if !pattern.contains(['*', '?', '[']) {
+        return cwd.starts_with(&pattern);
+    }
+    cwd.ancestors().any(|dir| {
+        gix::glob::wildmatch(
+            pattern.as_str().into(),
+            dir.to_string_lossy().as_ref().into(),
+            gix::glob::wildmatch::Mode::NO_MATCH_SLASH_LITERAL,
+        )
+    })
+}
+
+/// Merge the profile called `name` from the config's `profiles` over the rest of it
+pub fn apply_profile(config: &mut serde_json::Value, name: &str) -> Result<()> {
+    let profiles = config.get("profiles").and_then(|profiles| profiles.as_object());
+    let Some(mut profile) = profiles.and_then(|profiles| profiles.get(name)).cloned() else {
+        let known = profiles.map(|profiles| profiles.keys().cloned().collect::<Vec<_>>()).unwrap_or_default();
+        if known.is_empty() {
+            anyhow::bail!("Unknown profile `{}`: the config defines no profiles", name);
+        }
+        anyhow::bail!("Unknown profile `{}` (configured: {})", name, known.join(", "));
+    };
+
+    // The selection rules aren't settings
+    if let Some(profile) = profile.as_object_mut() {
+        profile.remove("match");
+    }
+    merge_config_values(config, profile);
+    Ok(())
+}
+
+/// Load configuration from default locations: the global config with the project's
+/// `.claude-powerline.json` merged over it, field by field, then the selected profile
+async fn load_config_from_default_locations(profile: Option<&str>) -> Result<Config> {
+    let mut merged: Option<serde_json::Value> = None;
+    let mut loaded = Vec::new();
+
+    for path in find_config_files() {
+        match read_config_value(&path).await {
+            Ok(value) => {
+                match &mut merged {
+                    Some(base) => merge_config_values(base, value),
+                    None => merged = Some(value),
                 }
+                loaded.push(path.display().to_string());
+            }
+            Err(e) => {
+                eprintln!("Warning: Failed to load config from {}: {}", path.display(), e);
             }
         }
     }
 
     // Return default config if no config file found
-    Ok(Config::default())
+    let Some(mut merged) = merged else {
+        return match profile {
+            Some(name) => apply_profile(&mut serde_json::json!({}), name).map(|_| Config::default()),
+            None => Ok(Config::default()),
+        };
+    };
+    if let Some(name) = select_profile(&merged, profile) {
+        apply_profile(&mut merged, &name)?;
+    }
+
+    serde_json::from_value(merged)
+        .with_context(|| format!("Failed to parse config file: {}", loaded.join(" + ")))
+}
+
+/// The config files that apply here, in merge order: the first global config found, then the
+/// project's `.claude-powerline.json`
+pub fn find_config_files() -> Vec<PathBuf> {
+    let search_paths = get_config_search_paths();
+    let Some((project, global)) = search_paths.split_first() else {
+        return Vec::new();
+    };
+
+    global
+        .iter()
+        .find(|path| path.exists())
+        .into_iter()
+        .chain(Some(project).filter(|path| path.exists()))
+        .cloned()
+        .collect()
+}
+
+/// Merge `overlay` into `base`: objects are merged key by key, recursively; anything else
+/// (including arrays) in `overlay` replaces what `base` had
+pub fn merge_config_values(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_config_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
 }
 
 /// Get list of paths to search for configuration files
-fn get_config_search_paths() -> Vec<PathBuf> {
+pub fn get_config_search_paths() -> Vec<PathBuf> {
     let mut paths = Vec::new();
 
     // Current directory
@@ -50,20 +201,124 @@ fn get_config_search_paths() -> Vec<PathBuf> {
         paths.push(home.join(".config").join("claude-powerline").join("config.json"));
     }
 
+    // Platform config directory: %APPDATA% on Windows, ~/Library/Application Support on macOS
+    if let Some(config_dir) = dirs::config_dir() {
+        let path = config_dir.join("claude-powerline").join("config.json");
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
     paths
 }
 
-/// Load configuration from a specific file
-async fn load_config_file(path: &PathBuf) -> Result<Config> {
+/// Load configuration from a specific file, with the selected profile merged over it
+async fn load_config_file(path: &PathBuf, profile: Option<&str>) -> Result<Config> {
     let content = fs::read_to_string(path).await
         .with_context(|| format!("Failed to read config file: {}", path.display()))?;
-    
+    let content = strip_json_comments(&content);
+
+    let mut value: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+    if let Some(name) = select_profile(&value, profile) {
+        apply_profile(&mut value, &name)?;
+        return serde_json::from_value(value)
+            .with_context(|| format!("Failed to parse config file: {} (profile `{}`)", path.display(), name));
+    }
+
+    // Parsed from the text itself, so errors point at a line
     let config: Config = serde_json::from_str(&content)
         .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
     
     Ok(config)
 }
 
+/// Read a config file as untyped JSON so it can be merged with another before parsing
+async fn read_config_value(path: &Path) -> Result<serde_json::Value> {
+    let content = fs::read_to_string(path).await
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+
+    serde_json::from_str(&strip_json_comments(&content))
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))
+}
+
+/// Turn JSON with comments into plain JSON: `//` and `/* */` comments and trailing commas
+/// before `}` or `]` are blanked out. Text inside strings is left alone, and line breaks
+/// are kept so parse errors still point at the right line.
+pub fn strip_json_comments(content: &str) -> String {
+    let mut stripped = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            stripped.push(c);
+            match c {
+                '\\' => stripped.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                stripped.push(c);
+            }
+            ('/', Some('/')) => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    if c == '\n' {
+                        stripped.push('\n');
+                    }
+                    previous = c;
+                }
+            }
+            _ => stripped.push(c),
+        }
+    }
+
+    remove_trailing_commas(&stripped)
+}
+
+/// Blank out commas that only have whitespace between them and a closing `}` or `]`
+fn remove_trailing_commas(content: &str) -> String {
+    let chars: Vec<char> = content.chars().collect();
+    let mut result = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']')) {
+                result.push(' ');
+                continue;
+            }
+        }
+        result.push(c);
+    }
+
+    result
+}
+
 /// Apply environment variable overrides to configuration
 fn apply_env_overrides(config: &mut Config) {
     if let Ok(theme) = env::var("CLAUDE_POWERLINE_THEME") {
@@ -74,5 +329,112 @@ fn apply_env_overrides(config: &mut Config) {
         config.style = style;
     }
 
-    // Add more environment variable overrides as needed
+    apply_segment_overrides(config, env::vars());
+}
+
+/// The tool's own `CLAUDE_POWERLINE_*` variables (without the prefix), never read as segment
+/// overrides even when they start with a segment's name, like `UPDATE_URL`. A test checks this
+/// against every variable the source reads.
+pub const RESERVED_VARIABLES: &[&str] = &[
+    "THEME", "STYLE", "CONFIG", "PROFILE", "DEBUG", "ENTRY_CACHE", "MMAP_THRESHOLD", "THREADS",
+    "PARSE_CONCURRENCY", "RUNTIME", "NO_SESSION_INDEX", "ORIGINAL", "INDEX", "UPDATE_URL",
+];
+
+/// Every segment's options with their defaults, keyed by segment name as in the config file
+static ENV_SEGMENT_DEFAULTS: OnceLock<serde_json::Map<String, serde_json::Value>> = OnceLock::new();
+
+fn env_segment_defaults() -> &'static serde_json::Map<String, serde_json::Value> {
+    ENV_SEGMENT_DEFAULTS.get_or_init(|| match serde_json::to_value(SegmentConfig::with_every_segment()) {
+        Ok(serde_json::Value::Object(segments)) => segments,
+        _ => serde_json::Map::new(),
+    })
+}
+
+/// The segment and option a `CLAUDE_POWERLINE_<SEGMENT>_<OPTION>` variable overrides, or `None`
+/// for any other variable (only overrides are checked against the segment's options and warned about).
+/// Every segment in `SegmentConfig` can be overridden.
+pub fn env_segment_override(name: &str) -> Option<(&'static str, &str)> {
+    let rest = name.strip_prefix("CLAUDE_POWERLINE_")?;
+    if RESERVED_VARIABLES.contains(&rest) {
+        return None;
+    }
+    env_segment_defaults().keys().find_map(|segment| {
+        let option = rest.strip_prefix(segment.to_ascii_uppercase().as_str())?.strip_prefix('_')?;
+        (!option.is_empty()).then_some((segment.as_str(), option))
+    })
+}
+
+/// Apply `CLAUDE_POWERLINE_<SEGMENT>_<OPTION>=value` pairs from `vars` to the segment options,
+/// e.g. `CLAUDE_POWERLINE_GIT_SHOW_SHA=false` or `CLAUDE_POWERLINE_TODAY_TYPE=tokens`. The option
+/// is the config key in upper snake case; values are read as the option's type. Unknown options
+/// and values that don't fit are logged and ignored.
+pub fn apply_segment_overrides<I>(config: &mut Config, vars: I)
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut overrides: Vec<(&str, String, String)> = vars
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let (segment, option) = env_segment_override(&name)?;
+            Some((segment, option.to_string(), value))
+        })
+        .collect();
+    if overrides.is_empty() {
+        return;
+    }
+    // Environment order is arbitrary; sort so repeated runs resolve the same way
+    overrides.sort();
+
+    let Ok(mut segments) = serde_json::to_value(&config.segments) else {
+        return;
+    };
+    let defaults = env_segment_defaults();
+
+    for (segment, option, value) in overrides {
+        let variable = format!("CLAUDE_POWERLINE_{}_{}", segment.to_ascii_uppercase(), option);
+
+        // A segment left out of the config file starts from its defaults
+        if segments[segment].is_null() {
+            segments[segment] = defaults[segment].clone();
+        }
+        let Some(options) = segments[segment].as_object_mut() else {
+            continue;
+        };
+
+        let wanted = option.replace('_', "").to_ascii_lowercase();
+        let Some(key) = options.keys().find(|key| key.to_ascii_lowercase() == wanted).cloned() else {
+            warn_with_context("config", &format!("Ignoring {}: `{}` has no such option", variable, segment));
+            continue;
+        };
+
+        match env_value(&options[&key], &value) {
+            Some(parsed) => {
+                options.insert(key, parsed);
+            }
+            None => warn_with_context("config", &format!("Ignoring {}: invalid value `{}`", variable, value)),
+        }
+    }
+
+    match serde_json::from_value(segments) {
+        Ok(segments) => config.segments = segments,
+        Err(e) => warn_with_context("config", &format!("Ignoring segment overrides from the environment: {}", e)),
+    }
+}
+
+/// Read `value` as the same JSON type as `current`; an unset option takes whatever the text looks like
+fn env_value(current: &serde_json::Value, value: &str) -> Option<serde_json::Value> {
+    let boolean = || match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(serde_json::Value::Bool(true)),
+        "0" | "false" | "no" | "off" => Some(serde_json::Value::Bool(false)),
+        _ => None,
+    };
+    let number = || value.trim().parse::<serde_json::Number>().ok().map(serde_json::Value::Number);
+    let string = || serde_json::Value::String(value.to_string());
+
+    match current {
+        serde_json::Value::Bool(_) => boolean(),
+        serde_json::Value::Number(_) => number(),
+        serde_json::Value::String(_) => Some(string()),
+        _ => Some(boolean().or_else(number).unwrap_or_else(string)),
+    }
 }
\ No newline at end of file
@@ -1,23 +1,109 @@
 pub mod loader;
 pub mod defaults;
+pub mod schema;
 
 pub use loader::*;
-pub use defaults::*;
+pub use schema::*;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
+    #[serde(default = "defaults::default_theme")]
     pub theme: String,
+    #[serde(default = "defaults::default_style")]
     pub style: String,
+    /// The themes `"theme": "auto"` picks between
+    #[serde(rename = "autoTheme")]
+    pub auto_theme: Option<AutoThemeConfig>,
+    /// A theme JSON file (`extends` plus per-segment `colors`) used instead of `theme`
+    #[serde(rename = "themeFile")]
+    pub theme_file: Option<PathBuf>,
+    #[serde(default)]
     pub segments: SegmentConfig,
     pub colors: Option<HashMap<String, ThemeColors>>,
     pub budget: Option<BudgetConfig>,
     pub display: Option<DisplayConfig>,
+    pub performance: Option<PerformanceConfig>,
+    pub cache: Option<CacheConfig>,
+    /// Root of every on-disk cache; defaults to `<XDG cache dir>/claude-powerline`
+    #[serde(rename = "cacheDir")]
+    pub cache_dir: Option<PathBuf>,
+    /// Root of persistent state and logs; defaults to `<XDG state dir>/claude-powerline`
+    #[serde(rename = "stateDir")]
+    pub state_dir: Option<PathBuf>,
+    /// Claude data directories to scan instead of the platform defaults, e.g. both the Linux and
+    /// the Windows side of a WSL setup; Windows and `/mnt/<drive>` paths are translated as needed
+    #[serde(rename = "claudePaths")]
+    pub claude_paths: Option<Vec<PathBuf>>,
+    /// Inside WSL, read the Claude data of every Windows profile under `C:\Users` instead of
+    /// only the current Windows user's
+    #[serde(rename = "wslAllProfiles")]
+    pub wsl_all_profiles: Option<bool>,
+    /// Extra transcript files, directories or globs outside the Claude directories (network
+    /// shares, synced folders), read on top of them
+    #[serde(rename = "transcriptGlobs")]
+    pub transcript_globs: Option<Vec<String>>,
+    /// Teammates' Claude data directories, read by the `team` segment and `usage --team`
+    pub team: Option<TeamConfig>,
+    pub sources: Option<SourcesConfig>,
+    pub pricing: Option<PricingConfig>,
+    /// A JSON rate card mapping model ids to per-million-token rates, matched before the built-in prices
+    #[serde(rename = "pricingFile")]
+    pub pricing_file: Option<PathBuf>,
+    pub statsd: Option<StatsdConfig>,
+    pub alerts: Option<AlertsConfig>,
+    pub logging: Option<LoggingConfig>,
+    /// Named partial configs (e.g. one per Claude account, with its own `claudePaths`, pricing,
+    /// budgets and theme); the one picked with `--profile`, or else the one whose `match` rules
+    /// fit the current directory or environment, is merged over the rest of the file
+    pub profiles: Option<HashMap<String, serde_json::Value>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Config keys applied once at startup to process-wide settings (data directories, logging,
+/// transcript discovery, pricing, number and cost formats, the parse pool), as JSON pointers.
+/// A config reload in `--watch` can't change them.
+pub const STARTUP_ONLY_KEYS: &[&str] = &[
+    "/cacheDir",
+    "/stateDir",
+    "/logging",
+    "/claudePaths",
+    "/transcriptGlobs",
+    "/wslAllProfiles",
+    "/pricing",
+    "/pricingFile",
+    "/display/numberFormat",
+    "/display/numberDecimals",
+    "/display/costPrecision",
+    "/display/costUnit",
+    "/display/costRounding",
+    "/display/costRoundingPoint",
+    "/display/costMultiplier",
+    "/performance/threads",
+    "/performance/parseConcurrency",
+];
+
+impl Config {
+    /// The `STARTUP_ONLY_KEYS` set differently in `other`, as dotted names (`display.costUnit`)
+    pub fn changed_startup_keys(&self, other: &Config) -> Vec<String> {
+        let (Ok(old), Ok(new)) = (serde_json::to_value(self), serde_json::to_value(other)) else {
+            return Vec::new();
+        };
+        let value = |config: &'_ serde_json::Value, key: &str| config.pointer(key).filter(|value| !value.is_null()).cloned();
+
+        STARTUP_ONLY_KEYS
+            .iter()
+            .filter(|key| value(&old, key) != value(&new, key))
+            .map(|key| key.trim_start_matches('/').replace('/', "."))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SegmentConfig {
     pub directory: Option<DirectoryConfig>,
     pub git: Option<GitConfig>,
@@ -27,16 +113,61 @@ pub struct SegmentConfig {
     pub context: Option<ContextConfig>,
     pub metrics: Option<MetricsConfig>,
     pub model: Option<ModelConfig>,
+    pub health: Option<HealthConfig>,
+    pub update: Option<UpdateConfig>,
+    pub team: Option<TeamSegmentConfig>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl SegmentConfig {
+    /// Whether the segment called `name` is set up here (enabled or not)
+    pub fn is_configured(&self, name: &str) -> bool {
+        match name {
+            "directory" => self.directory.is_some(),
+            "git" => self.git.is_some(),
+            "block" => self.block.is_some(),
+            "today" => self.today.is_some(),
+            "session" => self.session.is_some(),
+            "context" => self.context.is_some(),
+            "metrics" => self.metrics.is_some(),
+            "model" => self.model.is_some(),
+            "health" => self.health.is_some(),
+            "update" => self.update.is_some(),
+            "team" => self.team.is_some(),
+            _ => false,
+        }
+    }
+
+    /// A copy keeping only the segments named in `names`
+    pub fn only(&self, names: &[&str]) -> Self {
+        let keep = |name: &str| names.contains(&name);
+        Self {
+            directory: self.directory.clone().filter(|_| keep("directory")),
+            git: self.git.clone().filter(|_| keep("git")),
+            block: self.block.clone().filter(|_| keep("block")),
+            today: self.today.clone().filter(|_| keep("today")),
+            session: self.session.clone().filter(|_| keep("session")),
+            context: self.context.clone().filter(|_| keep("context")),
+            metrics: self.metrics.clone().filter(|_| keep("metrics")),
+            model: self.model.clone().filter(|_| keep("model")),
+            health: self.health.clone().filter(|_| keep("health")),
+            update: self.update.clone().filter(|_| keep("update")),
+            team: self.team.clone().filter(|_| keep("team")),
+        }
+    }
+}
+
+/// The working directory, or with `showBasename` just its last component
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DirectoryConfig {
     pub enabled: bool,
     #[serde(rename = "showBasename")]
     pub show_basename: Option<bool>,
+    #[serde(flatten)]
+    pub layout: LayoutConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The repository's branch, with its SHA, working tree, upstream and stash counts on request
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GitConfig {
     pub enabled: bool,
     #[serde(rename = "showSha")]
@@ -49,41 +180,107 @@ pub struct GitConfig {
     pub show_stash_count: Option<bool>,
     #[serde(rename = "showRepoName")]
     pub show_repo_name: Option<bool>,
+    #[serde(flatten)]
+    pub layout: LayoutConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Usage in the current 5-hour billing block
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BlockConfig {
     pub enabled: bool,
     #[serde(rename = "type")]
     pub display_type: Option<String>,
     #[serde(rename = "burnType")]
     pub burn_type: Option<String>,
+    /// Follow the figure with ↑, → or ↓ as the last 30 minutes burn faster, as fast or slower than the block so far
+    #[serde(rename = "burnTrend")]
+    pub burn_trend: Option<bool>,
+    /// Estimate how many more messages fit in `budget.block` at the block's average message size
+    #[serde(rename = "messagesLeft")]
+    pub messages_left: Option<bool>,
+    /// `calculated` from transcripts (default) or `hook`: summed from the session costs Claude Code
+    /// reports to the statusline, without reading transcripts. The hook only reports costs.
+    #[serde(rename = "costSource")]
+    pub cost_source: Option<String>,
+    /// Usage source this segment reads instead of the merged `sources.enabled`
+    pub source: Option<String>,
+    /// Count cache reads and writes in the token totals shown (on by default); costs always do
+    #[serde(rename = "includeCacheTokens")]
+    pub include_cache_tokens: Option<bool>,
+    #[serde(flatten)]
+    pub layout: LayoutConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Today's usage across every session
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TodayConfig {
     pub enabled: bool,
     #[serde(rename = "type")]
     pub display_type: Option<String>,
+    /// `calculated` from transcripts (default) or `hook`: summed from the session costs Claude Code
+    /// reports to the statusline, without reading transcripts. The hook only reports costs.
+    #[serde(rename = "costSource")]
+    pub cost_source: Option<String>,
+    /// Usage source this segment reads instead of the merged `sources.enabled`
+    pub source: Option<String>,
+    /// Count cache reads and writes in the token totals shown (on by default); costs always do
+    #[serde(rename = "includeCacheTokens")]
+    pub include_cache_tokens: Option<bool>,
+    /// Shade the background from green through yellow to red as spend nears `budget.today`
+    #[serde(rename = "heatMap")]
+    pub heat_map: Option<bool>,
+    /// Follow the cost with its change against yesterday up to the same time of day (`↑34%`)
+    #[serde(rename = "compareYesterday")]
+    pub compare_yesterday: Option<bool>,
+    /// Show the average daily cost of the 7 days before today next to today's (`7d avg $4.10`)
+    #[serde(rename = "weeklyAverage")]
+    pub weekly_average: Option<bool>,
+    #[serde(flatten)]
+    pub layout: LayoutConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Usage of the current session
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SessionConfig {
     pub enabled: bool,
     #[serde(rename = "type")]
     pub display_type: Option<String>,
+    /// `calculated` from the session's transcript (default) or `hook`: the session cost Claude Code reports
     #[serde(rename = "costSource")]
     pub cost_source: Option<String>,
+    /// Usage source this segment reads instead of the merged `sources.enabled`
+    pub source: Option<String>,
+    /// Count cache reads and writes in the token totals shown (on by default); costs always do
+    #[serde(rename = "includeCacheTokens")]
+    pub include_cache_tokens: Option<bool>,
+    /// Add up the sessions this one was resumed or branched from (off by default)
+    #[serde(rename = "includeResumed")]
+    pub include_resumed: Option<bool>,
+    #[serde(flatten)]
+    pub layout: LayoutConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How much of the context window the conversation fills
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ContextConfig {
     pub enabled: bool,
     #[serde(rename = "showPercentageOnly")]
     pub show_percentage_only: Option<bool>,
+    /// Below this much context left the segment switches to its critical (inverted, bold) style
+    #[serde(rename = "criticalPercentage")]
+    pub critical_percentage: Option<u32>,
+    /// Prefix `⚠` while the context is critical
+    #[serde(rename = "criticalIcon")]
+    pub critical_icon: Option<bool>,
+    /// Estimate when auto-compaction kicks in from the last 30 minutes of context growth (`compact in ~25m`)
+    #[serde(rename = "compactEta")]
+    pub compact_eta: Option<bool>,
+    #[serde(flatten)]
+    pub layout: LayoutConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Response times, session duration, message count and lines changed
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct MetricsConfig {
     pub enabled: bool,
     #[serde(rename = "showResponseTime")]
@@ -98,27 +295,102 @@ pub struct MetricsConfig {
     pub show_lines_added: Option<bool>,
     #[serde(rename = "showLinesRemoved")]
     pub show_lines_removed: Option<bool>,
+    #[serde(flatten)]
+    pub layout: LayoutConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The model answering in this session
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ModelConfig {
     pub enabled: bool,
+    /// Usage source this segment reads instead of the merged `sources.enabled`
+    pub source: Option<String>,
+    #[serde(flatten)]
+    pub layout: LayoutConfig,
+}
+
+/// Flags anomalies in the current session's transcript: a truncated last line, lines that
+/// aren't JSON, out-of-order timestamps and assistant messages without usage
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct HealthConfig {
+    pub enabled: bool,
+    /// Show `🩺 ok` when nothing is wrong instead of hiding the segment
+    #[serde(rename = "showHealthy")]
+    pub show_healthy: Option<bool>,
+    #[serde(flatten)]
+    pub layout: LayoutConfig,
+}
+
+/// Marks a newer claude-powerline release with `⬆ v1.4.0`. Opt-in: the releases are looked up
+/// in the background, at most once per `intervalHours`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct UpdateConfig {
+    pub enabled: bool,
+    /// Hours between release lookups (24 by default)
+    #[serde(rename = "intervalHours")]
+    pub interval_hours: Option<u64>,
+    #[serde(flatten)]
+    pub layout: LayoutConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Today's combined cost of everyone in `team` (`👥 $12.40`), against `budget.team` if set.
+/// Opt-in: every teammate's transcripts are read whenever its cache TTL runs out
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TeamSegmentConfig {
+    pub enabled: bool,
+    /// List each person's cost after the total (`👥 $12.40 · me $4.10 alice $8.30`)
+    #[serde(rename = "perPerson")]
+    pub per_person: Option<bool>,
+    #[serde(flatten)]
+    pub layout: LayoutConfig,
+}
+
+/// The width, alignment and priority options every segment accepts, flattened into each
+/// segment's config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LayoutConfig {
+    /// Pad the segment's content to at least this many terminal cells
+    #[serde(rename = "minWidth")]
+    pub min_width: Option<usize>,
+    /// Spaces on each side of the content (1 by default)
+    pub padding: Option<usize>,
+    /// Where content narrower than `minWidth` sits: `left`, `right` or `center`
+    pub align: Option<String>,
+    /// Segments with lower priorities are dropped first when the line is too wide
+    pub priority: Option<u32>,
+}
+
+/// The pair of themes `"theme": "auto"` switches between, `dark` and `light` unless set
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AutoThemeConfig {
+    pub dark: Option<String>,
+    pub light: Option<String>,
+    /// What decides between them: `terminal` (its background, the default) or `system`
+    /// (the macOS, Windows or GNOME light/dark appearance)
+    pub follow: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+/// Overrides for one segment's theme entry; anything left out keeps the theme's value
 pub struct ThemeColors {
-    pub bg: String,
-    pub fg: String,
+    pub bg: Option<String>,
+    pub fg: Option<String>,
+    pub bold: Option<bool>,
+    pub dim: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<bool>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BudgetConfig {
     pub session: Option<BudgetAmount>,
     pub today: Option<BudgetAmount>,
     pub block: Option<BudgetAmount>,
+    /// Daily budget shared by everyone in `team`
+    pub team: Option<BudgetAmount>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BudgetAmount {
     pub amount: f64,
     #[serde(rename = "type")]
@@ -127,12 +399,305 @@ pub struct BudgetAmount {
     pub warning_threshold: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// People whose usage is added up for the `team` segment and report. The local Claude data is
+/// always included; each member adds the Claude data directories exported or synced from their machine.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TeamConfig {
+    /// Name the local usage is listed under (`me` by default)
+    #[serde(rename = "selfName")]
+    pub self_name: Option<String>,
+    pub members: Option<Vec<TeamMember>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TeamMember {
+    pub name: String,
+    /// Claude data directories (each holding a `projects` directory) of this person
+    pub paths: Vec<PathBuf>,
+}
+
+impl TeamConfig {
+    /// Everyone on the team in display order, the local user first; `None` paths mean the
+    /// local Claude directories
+    pub fn people(&self) -> Vec<(String, Option<Vec<PathBuf>>)> {
+        let me = self.self_name.clone().filter(|name| !name.is_empty()).unwrap_or_else(|| "me".to_string());
+        std::iter::once((me, None))
+            .chain(self.members.iter().flatten().map(|member| (member.name.clone(), Some(member.paths.clone()))))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DisplayConfig {
+    /// Render several lines, each with its own segments, instead of the single `segments` line
     pub lines: Option<Vec<LineConfig>>,
+    /// `framed` connects the lines with a `╭─` / `╰─` frame, Powerlevel10k style;
+    /// without `lines` it splits the segments over two lines
+    pub layout: Option<String>,
+    /// Powerline separator between segments: `hard` (default), `soft`, `rounded`, `slanted`,
+    /// `flame`, `none` or a glyph of your own
+    pub separator: Option<String>,
+    /// Cap before the first segment of each group, in the same styles as `separator`
+    #[serde(rename = "startCap")]
+    pub start_cap: Option<String>,
+    /// Cap after the last segment of each group
+    #[serde(rename = "endCap")]
+    pub end_cap: Option<String>,
+    /// Widest the line may get before low-priority segments are dropped; defaults to the terminal width
+    #[serde(rename = "maxWidth")]
+    pub max_width: Option<usize>,
+    /// How counts are written: `compact` (`1.2M`, the default), `exact` (`1,234,567`) or `scientific` (`1.2e6`)
+    #[serde(rename = "numberFormat")]
+    pub number_format: Option<String>,
+    /// Decimals of compact and scientific counts (1 by default)
+    #[serde(rename = "numberDecimals")]
+    pub number_decimals: Option<usize>,
+    /// Decimals of dollar amounts (2 by default); smaller amounts still show up to 4
+    #[serde(rename = "costPrecision")]
+    pub cost_precision: Option<usize>,
+    /// `dollars` (the default) or `cents` (`42¢`)
+    #[serde(rename = "costUnit")]
+    pub cost_unit: Option<String>,
+    /// How costs are rounded to the digits shown: `half-up` (the default), `bankers` or `truncate`
+    #[serde(rename = "costRounding")]
+    pub cost_rounding: Option<String>,
+    /// Where costs are rounded: `total` (the default) rounds only the amount shown, `entry`
+    /// rounds each request's cost first so totals add up like an invoice's line items
+    #[serde(rename = "costRoundingPoint")]
+    pub cost_rounding_point: Option<String>,
+    /// Factor applied to every cost shown and compared against budgets, such as 1.21 for VAT or
+    /// an internal markup; JSON output keeps the raw API cost alongside the billed one
+    #[serde(rename = "costMultiplier")]
+    pub cost_multiplier: Option<f64>,
+    /// Show a `⚠ n` badge when more than this many transcript lines couldn't be parsed
+    #[serde(rename = "parseErrorBadge")]
+    pub parse_error_badge: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PerformanceConfig {
+    /// Overall render budget; segments still running when it expires are shown as placeholders
+    #[serde(rename = "renderDeadlineMs")]
+    pub render_deadline_ms: Option<u64>,
+    /// Threads parsing transcripts; defaults to the CPU count, which oversubscribes shared machines
+    pub threads: Option<usize>,
+    /// Transcripts parsed at once; defaults to `threads`
+    #[serde(rename = "parseConcurrency")]
+    pub parse_concurrency: Option<usize>,
+}
+
+/// Per-segment cache TTLs in seconds and the cache directory size cap. Within its TTL a segment
+/// is served from the last rendering instead of being recomputed; 0 (the default for all but git
+/// and team) disables caching.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CacheConfig {
+    pub directory: Option<u64>,
+    pub git: Option<u64>,
+    pub session: Option<u64>,
+    pub today: Option<u64>,
+    pub block: Option<u64>,
+    pub context: Option<u64>,
+    pub metrics: Option<u64>,
+    pub model: Option<u64>,
+    pub health: Option<u64>,
+    pub team: Option<u64>,
+    pub update: Option<u64>,
+    /// Cap on the cache directory; least recently used files are evicted beyond it
+    #[serde(rename = "maxSizeMb")]
+    pub max_size_mb: Option<u64>,
+}
+
+impl CacheConfig {
+    /// Git status has always been cached briefly; every other segment is fresh by default
+    pub const DEFAULT_GIT_TTL_SECS: u64 = 5;
+
+    /// The team segment reads every teammate's transcripts, so it is only recomputed once a minute
+    pub const DEFAULT_TEAM_TTL_SECS: u64 = 60;
+
+    /// TTL for `segment`, falling back to the defaults
+    pub fn ttl_for(&self, segment: &str) -> Duration {
+        let secs = match segment {
+            "directory" => self.directory,
+            "git" => self.git.or(Some(Self::DEFAULT_GIT_TTL_SECS)),
+            "session" => self.session,
+            "today" => self.today,
+            "block" => self.block,
+            "context" => self.context,
+            "metrics" => self.metrics,
+            "model" => self.model,
+            "health" => self.health,
+            "team" => self.team.or(Some(Self::DEFAULT_TEAM_TTL_SECS)),
+            "update" => self.update,
+            _ => None,
+        };
+
+        Duration::from_secs(secs.unwrap_or(0))
+    }
+
+    /// Render every segment fresh, overriding the TTLs that are set (and git's and team's defaults)
+    /// while keeping the rest of the cache options
+    pub fn disable_ttls(&mut self) {
+        let ttls = [
+            &mut self.directory, &mut self.session, &mut self.today, &mut self.block,
+            &mut self.context, &mut self.metrics, &mut self.model, &mut self.health,
+            &mut self.update,
+        ];
+        for ttl in ttls.into_iter().filter(|ttl| ttl.is_some()) {
+            *ttl = Some(0);
+        }
+        self.git = Some(0);
+        self.team = Some(0);
+    }
+
+    /// Cache directory size cap in bytes
+    pub fn max_size_bytes(&self) -> u64 {
+        self.max_size_mb
+            .map(megabytes_to_bytes)
+            .unwrap_or(crate::utils::DEFAULT_MAX_CACHE_BYTES)
+    }
+}
+
+/// A size from the config in MB as bytes, saturating rather than wrapping for absurd values
+fn megabytes_to_bytes(mb: u64) -> u64 {
+    mb.saturating_mul(1024 * 1024)
+}
+
+/// Cost calculation switches
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PricingConfig {
+    /// Bill Message Batches API requests at half price (default: true)
+    #[serde(rename = "batchDiscount")]
+    pub batch_discount: Option<bool>,
+    /// Treat every request as batch-priced, for workflows whose transcripts don't record the service tier
+    #[serde(rename = "assumeBatch")]
+    pub assume_batch: Option<bool>,
+    /// Cache-write TTL (`5m` or `1h`) billed when usage doesn't report one (default: `5m`)
+    #[serde(rename = "cacheWriteTtl")]
+    pub cache_write_ttl: Option<String>,
+    /// Mark costs with `≠` when the calculated cost strays from the transcript's `costUSD` by more
+    /// than this percentage (default: 5; 0 turns the check off)
+    #[serde(rename = "costDivergencePercent")]
+    pub cost_divergence_percent: Option<f64>,
+    /// Count subagent (sidechain) usage in session, today, block and report totals (default: true)
+    #[serde(rename = "includeSidechains")]
+    pub include_sidechains: Option<bool>,
+}
+
+impl PricingConfig {
+    pub fn batch_pricing(&self) -> crate::utils::BatchPricing {
+        crate::utils::BatchPricing {
+            discount: self.batch_discount.unwrap_or(true),
+            assume_batch: self.assume_batch.unwrap_or(false),
+        }
+    }
+
+    pub fn default_cache_ttl(&self) -> crate::utils::CacheTtl {
+        match self.cache_write_ttl.as_deref() {
+            Some("1h") => crate::utils::CacheTtl::OneHour,
+            _ => crate::utils::CacheTtl::FiveMinutes,
+        }
+    }
+}
+
+/// Usage metrics pushed to a StatsD or DogStatsD agent after each render
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct StatsdConfig {
+    pub enabled: bool,
+    /// `host:port` of the agent (default: `127.0.0.1:8125`)
+    pub address: Option<String>,
+    /// Metric name prefix (default: `claude_powerline`)
+    pub prefix: Option<String>,
+    /// DogStatsD tags such as `team:platform`; leave unset for plain StatsD agents
+    pub tags: Option<Vec<String>>,
+    /// Minimum seconds between pushes (default: 10)
+    #[serde(rename = "intervalSecs")]
+    pub interval_secs: Option<u64>,
+}
+
+/// Log file for diagnosing statusline failures after the fact, since Claude Code hides stderr
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct LoggingConfig {
+    pub enabled: bool,
+    /// Log file path (default: `<stateDir>/logs/claude-powerline.log`)
+    pub file: Option<PathBuf>,
+    /// Lowest level written: `debug`, `info`, `warn` or `error` (default: `warn`)
+    pub level: Option<String>,
+    /// Size at which the file is rotated (default: 1)
+    #[serde(rename = "maxSizeMb")]
+    pub max_size_mb: Option<u64>,
+    /// Rotated files kept as `<file>.1`, `<file>.2`, ... (default: 3)
+    #[serde(rename = "maxFiles")]
+    pub max_files: Option<u32>,
+}
+
+impl LoggingConfig {
+    /// File logging settings, or `None` when disabled or no log location is available
+    pub fn file_log_settings(&self) -> Option<crate::utils::FileLogSettings> {
+        if !self.enabled {
+            return None;
+        }
+
+        Some(crate::utils::FileLogSettings {
+            path: self.file.clone().or_else(crate::utils::log_file_path)?,
+            level: self.level
+                .as_deref()
+                .and_then(crate::utils::LogLevel::parse)
+                .unwrap_or(crate::utils::LogLevel::Warn),
+            max_bytes: self.max_size_mb
+                .map(megabytes_to_bytes)
+                .unwrap_or(crate::utils::DEFAULT_LOG_MAX_BYTES),
+            max_files: self.max_files.unwrap_or(crate::utils::DEFAULT_LOG_MAX_FILES),
+        })
+    }
+}
+
+/// Notifications fired when a `budget` threshold is crossed, a usage block resets or the context runs low
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AlertsConfig {
+    pub webhooks: Option<Vec<WebhookConfig>>,
+    /// Minimum seconds between two alerts of the same kind and scope (default: 3600)
+    #[serde(rename = "debounceSecs")]
+    pub debounce_secs: Option<u64>,
+    /// Raise a desktop notification when a budget is exceeded or the context runs low
+    pub desktop: Option<bool>,
+    /// Context window left, in percent, below which the context alert fires (default: 10)
+    #[serde(rename = "contextCriticalPercent")]
+    pub context_critical_percent: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WebhookConfig {
+    pub url: String,
+    /// `json` posts the event itself (default), `slack` a Slack-compatible `{"text": ...}` message
+    #[serde(rename = "type")]
+    pub format: Option<String>,
+}
+
+/// Which tools' usage feeds the statusline; totals of all enabled sources are merged
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SourcesConfig {
+    /// Source names: `claude`, `opencode`, `codex`, `gemini` (default: just `claude`)
+    pub enabled: Option<Vec<String>>,
+    /// OpenCode data directory; defaults to `~/.local/share/opencode`
+    #[serde(rename = "opencodeDir")]
+    pub opencode_dir: Option<PathBuf>,
+    /// Codex CLI home directory; defaults to `$CODEX_HOME` or `~/.codex`
+    #[serde(rename = "codexDir")]
+    pub codex_dir: Option<PathBuf>,
+    /// Gemini CLI home directory; defaults to `~/.gemini`
+    #[serde(rename = "geminiDir")]
+    pub gemini_dir: Option<PathBuf>,
+}
+
+impl SourcesConfig {
+    pub fn enabled_sources(&self) -> Vec<String> {
+        self.enabled.clone().unwrap_or_else(|| vec!["claude".to_string()])
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct LineConfig {
     pub segments: SegmentConfig,
+    /// Segments pushed to the right edge of the line
+    pub right: Option<SegmentConfig>,
 }
\ No newline at end of file
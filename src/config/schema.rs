@@ -0,0 +1,14 @@
+use crate::config::Config;
+use schemars::generate::SchemaSettings;
+use serde_json::{json, Value};
+
+/// JSON Schema (draft-07) of the config file, derived with schemars from the config structs so
+/// field names, renames, types, defaults and doc comments always match the code. Nested structs
+/// become definitions under `definitions`.
+pub fn config_schema() -> Value {
+    let mut schema = SchemaSettings::draft07().into_generator().into_root_schema_for::<Config>().to_value();
+    schema["title"] = json!("claude-powerline configuration");
+    // Lets a config file point editors at this schema
+    schema["properties"]["$schema"] = json!({ "type": "string" });
+    schema
+}
@@ -0,0 +1,304 @@
+use crate::config;
+use crate::utils::{color_mode, color_override, find_project_paths, ColorMode, get_claude_paths, is_transcript_path, parse_error_counts, should_use_colors, total_parse_stats, supports_rgb_colors, DataAggregator};
+use std::env;
+use std::path::PathBuf;
+use tokio::fs;
+
+/// Outcome of a single diagnostic check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// A single diagnostic check with an optional actionable hint
+#[derive(Debug, Clone)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl DoctorCheck {
+    fn new(name: &str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+/// Results of the `doctor` subcommand
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// Run every check against the current environment, validating the config file at
+    /// `config_path` (or the discovered ones) with `profile` applied
+    pub async fn run(config_path: Option<PathBuf>, profile: Option<&str>) -> Self {
+        let mut checks = Vec::new();
+
+        let claude_paths = match get_claude_paths() {
+            Ok(paths) => {
+                checks.push(check_claude_dirs(&paths));
+                paths
+            }
+            Err(e) => {
+                checks.push(
+                    DoctorCheck::new("Claude config directory", CheckStatus::Fail, e.to_string())
+                        .with_hint("Set CLAUDE_CONFIG_DIR to the directory containing Claude's `projects` folder"),
+                );
+                Vec::new()
+            }
+        };
+
+        if !claude_paths.is_empty() {
+            checks.push(check_transcripts(&claude_paths).await);
+            checks.push(check_parse_errors().await);
+        }
+
+        checks.push(check_status_line(&settings_files(&claude_paths)).await);
+        checks.push(check_config_file(config_path, profile).await);
+        checks.push(check_colors());
+
+        Self { checks }
+    }
+
+    pub fn has_failures(&self) -> bool {
+        self.checks.iter().any(|c| c.status == CheckStatus::Fail)
+    }
+
+    /// Render one line per check, followed by its hint when present
+    pub fn render(&self, use_colors: bool) -> String {
+        let mut lines = Vec::new();
+
+        for check in &self.checks {
+            let (symbol, color) = match check.status {
+                CheckStatus::Ok => ("✓", "32"),
+                CheckStatus::Warn => ("!", "33"),
+                CheckStatus::Fail => ("✗", "31"),
+            };
+
+            let symbol = if use_colors {
+                format!("\x1b[{}m{}\x1b[0m", color, symbol)
+            } else {
+                symbol.to_string()
+            };
+
+            lines.push(format!("{} {}: {}", symbol, check.name, check.message));
+            if let Some(hint) = &check.hint {
+                lines.push(format!("    → {}", hint));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Report which Claude config directories were discovered
+pub fn check_claude_dirs(paths: &[PathBuf]) -> DoctorCheck {
+    let source = if env::var("CLAUDE_CONFIG_DIR").is_ok() {
+        " (from CLAUDE_CONFIG_DIR)"
+    } else {
+        ""
+    };
+
+    let dirs = paths
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    DoctorCheck::new("Claude config directory", CheckStatus::Ok, format!("{}{}", dirs, source))
+}
+
+/// Count transcript files under `<claude_path>/projects/*/`, archived ones included
+pub async fn check_transcripts(claude_paths: &[PathBuf]) -> DoctorCheck {
+    let project_paths = match find_project_paths(claude_paths).await {
+        Ok(paths) => paths,
+        Err(e) => {
+            return DoctorCheck::new("Transcripts", CheckStatus::Fail, format!("Failed to read projects directory: {}", e))
+                .with_hint("Check that the projects directory is readable");
+        }
+    };
+
+    let mut transcript_count = 0;
+    for project_path in &project_paths {
+        if let Ok(mut entries) = fs::read_dir(project_path).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if is_transcript_path(&entry.path()) {
+                    transcript_count += 1;
+                }
+            }
+        }
+    }
+
+    if transcript_count == 0 {
+        DoctorCheck::new(
+            "Transcripts",
+            CheckStatus::Warn,
+            format!("No transcript files found in {} project(s)", project_paths.len()),
+        )
+        .with_hint("Usage segments stay empty until Claude Code has written a session transcript")
+    } else {
+        DoctorCheck::new(
+            "Transcripts",
+            CheckStatus::Ok,
+            format!("{} transcript file(s) across {} project(s)", transcript_count, project_paths.len()),
+        )
+    }
+}
+
+/// Read every transcript and report lines that aren't valid JSON, which usage totals silently skip
+pub async fn check_parse_errors() -> DoctorCheck {
+    const NAME: &str = "Transcript integrity";
+
+    if let Err(e) = DataAggregator::new().fold_usage_by(|_| None::<()>).await {
+        return DoctorCheck::new(NAME, CheckStatus::Warn, format!("Failed to read transcripts: {:#}", e));
+    }
+
+    let counts = parse_error_counts();
+    let totals = total_parse_stats();
+    let recovered = match totals.recovered_lines {
+        0 => String::new(),
+        lines => format!("; {} line(s) ({} bytes) recovered", lines, totals.recovered_bytes),
+    };
+    let Some((worst, worst_count)) = counts.first() else {
+        return DoctorCheck::new(NAME, CheckStatus::Ok, format!("Every transcript line parses{}", recovered));
+    };
+
+    DoctorCheck::new(
+        NAME,
+        CheckStatus::Warn,
+        format!(
+            "{} unparseable line(s) ({} bytes) in {} transcript(s), most in {} ({}){}",
+            totals.skipped_lines,
+            totals.skipped_bytes,
+            counts.len(),
+            worst.display(),
+            worst_count,
+            recovered
+        ),
+    )
+    .with_hint("These lines are left out of usage totals; truncated or hand-edited transcripts are the usual cause")
+}
+
+/// Claude settings files that may configure the statusLine hook, in order of precedence
+pub fn settings_files(claude_paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = vec![
+        PathBuf::from(".claude").join("settings.local.json"),
+        PathBuf::from(".claude").join("settings.json"),
+    ];
+
+    files.extend(claude_paths.iter().map(|p| p.join("settings.json")));
+    files
+}
+
+/// Verify that a statusLine command is configured and points at claude-powerline
+pub async fn check_status_line(settings_files: &[PathBuf]) -> DoctorCheck {
+    const NAME: &str = "statusLine hook";
+    const EXAMPLE: &str = r#"add "statusLine": {"type": "command", "command": "claude-powerline"} to ~/.claude/settings.json"#;
+
+    for path in settings_files {
+        let Ok(content) = fs::read_to_string(path).await else {
+            continue;
+        };
+
+        let settings: serde_json::Value = match serde_json::from_str(&content) {
+            Ok(settings) => settings,
+            Err(e) => {
+                return DoctorCheck::new(NAME, CheckStatus::Fail, format!("{} is not valid JSON: {}", path.display(), e))
+                    .with_hint("Fix the syntax error; Claude Code ignores settings it cannot parse");
+            }
+        };
+
+        let Some(status_line) = settings.get("statusLine") else {
+            continue;
+        };
+
+        let command = status_line.get("command").and_then(|c| c.as_str()).unwrap_or("");
+        return if command.contains("claude-powerline") {
+            DoctorCheck::new(NAME, CheckStatus::Ok, format!("`{}` in {}", command, path.display()))
+        } else if command.is_empty() {
+            DoctorCheck::new(NAME, CheckStatus::Fail, format!("statusLine in {} has no command", path.display()))
+                .with_hint(EXAMPLE)
+        } else {
+            DoctorCheck::new(NAME, CheckStatus::Warn, format!("statusLine in {} runs `{}`", path.display(), command))
+                .with_hint("Point the statusLine command at claude-powerline")
+        };
+    }
+
+    DoctorCheck::new(NAME, CheckStatus::Fail, "No statusLine configured in any Claude settings.json")
+        .with_hint(EXAMPLE)
+}
+
+/// Check that the claude-powerline config file parses with `profile` applied: `explicit`
+/// (`--config` or `CLAUDE_POWERLINE_CONFIG`) when given, otherwise the discovered files
+pub async fn check_config_file(explicit: Option<PathBuf>, profile: Option<&str>) -> DoctorCheck {
+    const NAME: &str = "Config file";
+
+    let paths = match &explicit {
+        Some(path) => vec![path.clone()],
+        None => config::find_config_files(),
+    };
+
+    if paths.is_empty() {
+        return DoctorCheck::new(NAME, CheckStatus::Ok, "None found, using defaults");
+    }
+    let names = paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>();
+
+    match config::load_profile_config(explicit, profile).await {
+        // Project settings are merged over the global file
+        Ok(_) => match profile {
+            Some(profile) => DoctorCheck::new(NAME, CheckStatus::Ok, format!("{} (profile {})", names.join(" + "), profile)),
+            None => DoctorCheck::new(NAME, CheckStatus::Ok, names.join(" + ")),
+        },
+        Err(e) => DoctorCheck::new(NAME, CheckStatus::Fail, format!("{:#}", e))
+            .with_hint(format!("Fix or remove {}", names.join(" or "))),
+    }
+}
+
+/// Describe the color support the statusline will use
+pub fn check_colors() -> DoctorCheck {
+    const NAME: &str = "Colors";
+
+    let override_ = match color_mode() {
+        ColorMode::Always => Some(("--color always", true)),
+        ColorMode::Never => Some(("--color never", false)),
+        ColorMode::Auto => color_override(),
+    };
+    let forced = match override_ {
+        Some((source, false)) => {
+            return DoctorCheck::new(NAME, CheckStatus::Warn, format!("Disabled by {}", source))
+                .with_hint(format!("Drop {} to get themed segments", source));
+        }
+        Some((source, true)) => Some(source),
+        None => None,
+    };
+
+    if !should_use_colors() {
+        let term = env::var("TERM").unwrap_or_default();
+        let term = if term.is_empty() { "unset".to_string() } else { term };
+        return DoctorCheck::new(NAME, CheckStatus::Warn, format!("Disabled because TERM is {}", term))
+            .with_hint("Set TERM (e.g. xterm-256color) in the environment Claude Code runs in");
+    }
+
+    let forced = forced.map(|var| format!(" (forced by {})", var)).unwrap_or_default();
+    if supports_rgb_colors() {
+        DoctorCheck::new(NAME, CheckStatus::Ok, format!("24-bit color{}", forced))
+    } else {
+        DoctorCheck::new(NAME, CheckStatus::Warn, format!("256-color fallback{}", forced))
+            .with_hint("Set COLORTERM=truecolor if your terminal supports 24-bit color")
+    }
+}
@@ -2,8 +2,29 @@ pub mod segments;
 pub mod utils; 
 pub mod config;
 pub mod themes;
+pub mod reports;
+pub mod doctor;
+pub mod sources;
+pub mod statsd;
+pub mod alerts;
+pub mod render;
+pub mod setup;
+pub mod compare;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
 
+// `ModelInfo` exists in both segments and utils; reach it through its module path
+#[allow(ambiguous_glob_reexports)]
 pub use segments::*;
+#[allow(ambiguous_glob_reexports)]
 pub use utils::*;
 pub use config::*;
-pub use themes::*;
\ No newline at end of file
+pub use themes::*;
+pub use reports::*;
+pub use doctor::*;
+pub use sources::*;
+pub use statsd::*;
+pub use alerts::*;
+pub use render::*;
+pub use setup::*;
+pub use compare::*;
\ No newline at end of file
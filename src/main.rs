@@ -1,472 +1,916 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use claude_powerline_rust::*;
 use pico_args::Arguments;
 use std::env;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Debug)]
 struct Args {
-    theme: String,
-    style: String,
+    command: Option<String>,
+    /// Second positional word, e.g. `clear` in `cache clear`
+    action: Option<String>,
+    /// `--theme`; the config file (or `CLAUDE_POWERLINE_THEME`) decides otherwise
+    theme: Option<String>,
+    style: Option<String>,
     config: Option<PathBuf>,
+    /// `--profile`, or `CLAUDE_POWERLINE_PROFILE`
+    profile: Option<String>,
+    /// `--theme-file`: a theme JSON file, instead of `theme`
+    theme_file: Option<PathBuf>,
+    /// `--settings`: the Claude settings file `setup` edits
+    settings: Option<PathBuf>,
+    /// `setup --remove`
+    remove: bool,
+    check_update: bool,
+    /// `--color auto|always|never`
+    color: Option<String>,
     help: bool,
+    schema: bool,
     install_fonts: bool,
     basename: bool,
+    days: Option<u32>,
+    format: Option<String>,
+    by_project: bool,
+    team: bool,
+    timing: bool,
+    watch: bool,
+    interval: Option<u64>,
+    svg: Option<PathBuf>,
+    png: Option<PathBuf>,
+    /// `compare --original <COMMAND>`: how to run the TypeScript claude-powerline
+    original: Option<String>,
+    /// `--debug` or `-v`
+    debug: bool,
+    /// `-vv`
+    trace: bool,
+    debug_filter: Option<String>,
 }
 
 impl Args {
+    /// What the verbosity flags ask to print to stderr; `None` leaves it to `CLAUDE_POWERLINE_DEBUG`
+    fn log_filter(&self) -> Option<LogFilter> {
+        if let Some(spec) = &self.debug_filter {
+            return LogFilter::parse(spec);
+        }
+        if self.trace {
+            Some(LogFilter::all(LogLevel::Trace))
+        } else if self.debug {
+            Some(LogFilter::all(LogLevel::Debug))
+        } else {
+            None
+        }
+    }
+
     fn from_env() -> Result<Self> {
         let mut args = Arguments::from_env();
+        let command = args.subcommand().unwrap_or(None);
+        let action = if command.is_some() { args.subcommand().unwrap_or(None) } else { None };
         
         Ok(Self {
-            theme: args.opt_value_from_str("--theme")
-                .unwrap_or(None)
-                .or_else(|| env::var("CLAUDE_POWERLINE_THEME").ok())
-                .unwrap_or_else(|| "dark".to_string()),
-            style: args.opt_value_from_str("--style")
-                .unwrap_or(None)
-                .or_else(|| env::var("CLAUDE_POWERLINE_STYLE").ok())
-                .unwrap_or_else(|| "minimal".to_string()),
+            command,
+            action,
+            theme: args.opt_value_from_str("--theme").unwrap_or(None),
+            style: args.opt_value_from_str("--style").unwrap_or(None),
             config: args.opt_value_from_str::<_, PathBuf>("--config")
                 .unwrap_or(None)
                 .or_else(|| env::var("CLAUDE_POWERLINE_CONFIG").ok().map(PathBuf::from)),
+            profile: args.opt_value_from_str("--profile")
+                .unwrap_or(None)
+                .or_else(|| env::var("CLAUDE_POWERLINE_PROFILE").ok().filter(|name| !name.is_empty())),
+            theme_file: args.opt_value_from_str::<_, PathBuf>("--theme-file").unwrap_or(None),
+            settings: args.opt_value_from_str::<_, PathBuf>("--settings").unwrap_or(None),
+            remove: args.contains("--remove"),
+            check_update: args.contains("--check-update"),
+            color: args.opt_value_from_str("--color").unwrap_or(None),
             help: args.contains("--help"),
+            schema: args.contains("--schema"),
             install_fonts: args.contains("--install-fonts"),
             basename: args.contains("--basename"),
+            days: args.opt_value_from_str("--days").unwrap_or(None),
+            format: args.opt_value_from_str("--format").unwrap_or(None),
+            by_project: args.contains("--by-project"),
+            team: args.contains("--team"),
+            timing: args.contains("--timing"),
+            watch: args.contains("--watch"),
+            interval: args.opt_value_from_str("--interval").unwrap_or(None),
+            svg: args.opt_value_from_str::<_, PathBuf>("--svg").unwrap_or(None),
+            png: args.opt_value_from_str::<_, PathBuf>("--png").unwrap_or(None),
+            original: args.opt_value_from_str("--original").unwrap_or(None),
+            debug: args.contains(["-v", "--debug"]),
+            trace: args.contains("-vv"),
+            debug_filter: args.opt_value_from_str("--debug-filter").unwrap_or(None),
         })
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
+fn main() -> Result<()> {
     let args = Args::from_env()?;
+    if let Some(mode) = &args.color {
+        set_color_mode(mode.parse()?);
+    }
+    if let Some(filter) = args.log_filter() {
+        set_stderr_filter(filter);
+    }
+    enable_virtual_terminal();
+
+    let runtime = build_runtime(runtime_flavor()).context("Failed to start the async runtime")?;
+
+    // Errors reach stderr through the returned Result, which Claude Code doesn't show; keep them in the log too
+    let result = runtime.block_on(run(args));
+    // Don't wait on segments abandoned at the render deadline; the line is already printed
+    runtime.shutdown_background();
+    // Commands that fail or never load a config still get what was logged written out
+    finish_file_logging_setup();
+    if let Err(e) = &result {
+        error_with_context("main", &format!("{:#}", e));
+    }
+    result
+}
+
+async fn run(args: Args) -> Result<()> {
 
     if args.help {
         print_help();
         return Ok(());
     }
 
+    if args.schema {
+        println!("{}", serde_json::to_string_pretty(&config::config_schema())?);
+        return Ok(());
+    }
+
     if args.install_fonts {
         install_fonts().await?;
         return Ok(());
     }
 
-    // Load configuration
-    let mut config = config::load_config(args.config).await?;
-    config.theme = args.theme.clone();
-    config.style = args.style.clone();
+    if args.check_update {
+        return run_check_update(&args).await;
+    }
+
+    match args.command.as_deref() {
+        Some("usage") => return run_usage_report(&args).await,
+        Some("doctor") => return run_doctor(&args).await,
+        Some("cache") => return run_cache_command(&args).await,
+        Some("render") => return run_render(&args).await,
+        Some("setup") => return run_setup(&args),
+        Some("compare") => return run_compare(&args).await,
+        Some(other) => anyhow::bail!("Unknown command: {}", other),
+        None => {}
+    }
+
+    let config = load_statusline_config(&args).await?;
+
+    if args.watch {
+        return run_watch(&args, config).await;
+    }
+
+    // Generate and display statusline
+    let start = std::time::Instant::now();
+    let mut timings = Vec::new();
+    let hook = read_hook_data();
+    let hook_costs = record_hook_costs(&config, hook.as_ref());
+    let segment_ctx = segment_context(&config, hook, hook_costs);
+    let statusline = generate_statusline(&config, segment_ctx.clone(), &mut timings).await?;
+    println!("{}", statusline);
+
+    // Pushing and alerting read the render's shared entries, which a segment past the deadline may still be loading
+    if !timings.iter().any(|t| t.timed_out) {
+        push_statsd(&config, &segment_ctx).await;
+        check_alerts(&config, &segment_ctx).await;
+    }
+
+    if let Some(root) = cache_root() {
+        let max_bytes = config.cache.clone().unwrap_or_default().max_size_bytes();
+        if let Err(e) = evict_if_due(&root, max_bytes) {
+            warn_with_context("cache_dir", &format!("Cache eviction failed: {:#}", e));
+        }
+    }
+
+    // Timings go to stderr so the statusline itself stays untouched
+    if args.timing {
+        eprintln!("{}", format_timings(&timings, start.elapsed()));
+    }
+
+    // Segments that missed the deadline are still running; exit now instead of waiting on them
+    if timings.iter().any(|t| t.timed_out) {
+        use std::io::Write;
+        std::io::stdout().flush()?;
+        std::process::exit(0);
+    }
+
+    Ok(())
+}
+
+/// Load the config and apply the statusline flags (`--theme`, `--style`, `--basename`) on top of it
+async fn load_statusline_config(args: &Args) -> Result<Config> {
+    let mut config = config::load_profile_config(args.config.clone(), args.profile.as_deref()).await?;
+    init_data_dirs(&config);
+    init_claude_paths(&config);
+    init_parallelism(&config);
+    init_pricing(&config);
+    init_formats(&config);
+    if let Some(theme) = &args.theme {
+        config.theme = theme.clone();
+    }
+    if let Some(style) = &args.style {
+        config.style = style.clone();
+    }
+    if let Some(path) = &args.theme_file {
+        config.theme_file = Some(path.clone());
+    }
+    // A theme file that can't be read is reported up front; later renders fall back to `theme`
+    if let Some(path) = &config.theme_file {
+        themes::load_theme_file(path, &config.theme)?;
+    }
     
     // Override directory config with CLI flag
     if args.basename {
         if config.segments.directory.is_none() {
             config.segments.directory = Some(config::DirectoryConfig {
-                enabled: true,
                 show_basename: Some(true),
+                ..Default::default()
             });
         } else if let Some(ref mut dir_config) = config.segments.directory {
             dir_config.show_basename = Some(true);
         }
     }
 
-    // Generate and display statusline
-    let statusline = generate_statusline(&config).await?;
-    println!("{}", statusline);
-
-    Ok(())
+    Ok(config)
 }
 
-async fn generate_statusline(config: &Config) -> Result<String> {
-    let mut segments = Vec::new();
-    let theme = themes::get_theme(&config.theme);
-
-    // Directory segment
-    if config.segments.directory.as_ref().map_or(true, |c| c.enabled) {
-        let dir_segment = render_directory_segment(&config, &theme)?;
-        segments.push(dir_segment);
+/// Render the statusline once into an image instead of the terminal
+async fn run_render(args: &Args) -> Result<()> {
+    if args.svg.is_none() && args.png.is_none() {
+        anyhow::bail!("Nothing to render: pass --svg <FILE> and/or --png <FILE>");
     }
 
-    // Git segment
-    if config.segments.git.as_ref().map_or(true, |c| c.enabled) {
-        let git_segment = render_git_segment(&config, &theme).await?;
-        if !git_segment.is_empty() {
-            segments.push(git_segment);
-        }
-    }
+    // Images always carry the theme's full colors, whatever the current terminal supports
+    force_rgb_colors();
+    let mut config = load_statusline_config(args).await?;
+    // Cached segments carry the colors of the terminal they were rendered for, so render everything fresh
+    config.cache.get_or_insert_with(Default::default).disable_ttls();
+    let statusline = generate_statusline(&config, segment_context(&config, None, None), &mut Vec::new()).await?;
+    let svg = render_svg(&statusline, &SvgOptions::default());
 
-    // Session segment
-    if config.segments.session.as_ref().map_or(true, |c| c.enabled) {
-        let session_segment = render_session_segment(&config, &theme).await?;
-        if !session_segment.is_empty() {
-            segments.push(session_segment);
-        }
+    if let Some(path) = &args.svg {
+        tokio::fs::write(path, &svg).await
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    if let Some(path) = &args.png {
+        write_png(&svg, path).await?;
     }
+    Ok(())
+}
 
-    // Today segment
-    if config.segments.today.as_ref().map_or(true, |c| c.enabled) {
-        let today_segment = render_today_segment(&config, &theme).await?;
-        if !today_segment.is_empty() {
-            segments.push(today_segment);
+/// Context for one render. Today, block and model all read the same recent window;
+/// the context loads it once for all of them.
+fn segment_context(config: &Config, hook: Option<ClaudeHookData>, hook_costs: Option<HookCostLedger>) -> Arc<SegmentContext> {
+    let theme_name = themes::resolve_theme_name(&config.theme, config.auto_theme.as_ref());
+    let theme_file = config.theme_file.as_deref().and_then(|path| match themes::load_theme_file(path, &theme_name) {
+        Ok(theme) => Some(theme),
+        // Mid-edit in watch mode, most likely; the named theme stands in until it parses again
+        Err(e) => {
+            warn_with_context("theme", &format!("{:#}", e));
+            None
         }
+    });
+    let mut theme = theme_file.unwrap_or_else(|| themes::get_theme(&theme_name));
+    theme.transparent = config.style == "transparent";
+    if let Some(colors) = &config.colors {
+        theme.apply_overrides(colors);
+    }
+    Arc::new(SegmentContext::with_source(
+        Arc::new(config.clone()),
+        Arc::new(theme),
+        source_from_config(config),
+    ).with_hook(hook, hook_costs))
+}
+
+/// How long to wait for Claude Code's hook JSON on stdin before rendering without it
+const HOOK_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// The JSON Claude Code pipes to the statusline command. Nothing is read from a terminal, and a
+/// pipe that stays open without sending anything is abandoned after `HOOK_READ_TIMEOUT`.
+fn read_hook_data() -> Option<ClaudeHookData> {
+    parse_hook_data(&read_hook_input()?)
+}
+
+/// The hook JSON as it arrived on stdin, if any
+fn read_hook_input() -> Option<String> {
+    use std::io::{IsTerminal, Read};
+
+    if std::io::stdin().is_terminal() {
+        return None;
     }
 
-    // Block segment
-    if config.segments.block.as_ref().map_or(true, |c| c.enabled) {
-        let block_segment = render_block_segment(&config, &theme).await?;
-        if !block_segment.is_empty() {
-            segments.push(block_segment);
+    // A detached thread, so a stalled pipe can't hold up the exit either
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut input = String::new();
+        let _ = std::io::stdin().read_to_string(&mut input);
+        let _ = sender.send(input);
+    });
+
+    let input = receiver.recv_timeout(HOOK_READ_TIMEOUT).ok()?;
+    (!input.trim().is_empty()).then_some(input)
+}
+
+fn parse_hook_data(input: &str) -> Option<ClaudeHookData> {
+    match serde_json::from_str(input) {
+        Ok(hook) => Some(hook),
+        Err(e) => {
+            debug_with_context("hook", &format!("Ignoring unparseable hook data: {}", e));
+            None
         }
     }
+}
+
+/// Record the session cost reported by the hook when a segment takes its cost from there, and
+/// return the ledger for today and block to sum
+fn record_hook_costs(config: &Config, hook: Option<&ClaudeHookData>) -> Option<HookCostLedger> {
+    let segments = &config.segments;
+    // Alerts read today and block the way they're configured, even when they're hidden
+    let alerting = config.alerts.is_some();
+    let uses_hook = segments.today.as_ref().is_some_and(|c| (c.enabled || alerting) && c.cost_source.as_deref() == Some("hook"))
+        || segments.block.as_ref().is_some_and(|c| (c.enabled || alerting) && c.cost_source.as_deref() == Some("hook"));
+    if !uses_hook {
+        return None;
+    }
+
+    let hook = hook?;
+    let cost = hook.cost.as_ref()?;
+    Some(record_hook_cost(&hook.session_id, cost.total_cost_usd))
+}
+
+async fn generate_statusline(config: &Config, segment: Arc<SegmentContext>, timings: &mut Vec<SegmentTiming>) -> Result<String> {
+    let framed = config.display.as_ref().and_then(|display| display.layout.as_deref()) == Some("framed");
+    let lines = line_groups(config, framed);
+    let mut ctx = RenderContext {
+        segment,
+        deadline: config.performance.as_ref()
+            .and_then(|p| p.render_deadline_ms)
+            .map(|ms| tokio::time::Instant::now() + std::time::Duration::from_millis(ms)),
+        cache_ttls: config.cache.clone().unwrap_or_default(),
+        cache: SegmentCache::load(SegmentCache::default_path()),
+    };
 
-    // Context segment
-    if config.segments.context.as_ref().map_or(true, |c| c.enabled) {
-        let context_segment = render_context_segment(&config, &theme).await?;
-        if !context_segment.is_empty() {
-            segments.push(context_segment);
+    // Segments are independent, so each runs as its own task (across all lines) and the render
+    // takes as long as the slowest one
+    let groups: Vec<&SegmentRegistry> = lines.iter().flat_map(|(left, right)| [left, right]).collect();
+    let results = futures::future::join_all(
+        groups.iter().flat_map(|group| group.iter()).map(|segment| spawn_segment(&ctx, segment))
+    ).await;
+
+    // Keep the display order fixed regardless of which segment finished first
+    let cache = &mut ctx.cache;
+    let mut results = results.into_iter();
+    let mut rendered = Vec::new();
+    for group in &groups {
+        let mut segments = Vec::new();
+        for (registered, result) in group.iter().zip(results.by_ref()) {
+            let Some((segment, timing)) = result? else {
+                continue;
+            };
+
+            // Fall back to the last good rendering, marked as stale, when fresh data is unavailable
+            let segment = match segment {
+                Ok(text) => {
+                    if !timing.from_cache {
+                        cache.store(&timing.name, &text, ctx.cache_ttls.ttl_for(&timing.name));
+                    }
+                    text
+                }
+                Err(e) => match cache.get(&timing.name) {
+                    Some(cached) => {
+                        warn_with_context("render", &format!("Serving cached '{}' segment: {:#}", timing.name, e));
+                        mark_stale(cached)
+                    }
+                    None if timing.timed_out => apply_theme_colors(" … ", &timing.name, &ctx.segment.theme),
+                    None => return Err(e),
+                },
+            };
+            timings.push(timing);
+
+            if !segment.is_empty() {
+                segments.push((segment, registered.priority()));
+            }
         }
+        rendered.push(segments);
     }
 
-    // Model segment
-    if config.segments.model.as_ref().map_or(true, |c| c.enabled) {
-        let model_segment = render_model_segment(&config, &theme).await?;
-        if !model_segment.is_empty() {
-            segments.push(model_segment);
+    // Corrupt transcripts quietly shrink the numbers, so say so once they pass the threshold
+    let badge_threshold = config.display.as_ref().and_then(|display| display.parse_error_badge);
+    if let (Some(threshold), Some(first_line)) = (badge_threshold, rendered.first_mut()) {
+        let parse_errors = total_parse_errors();
+        if parse_errors > threshold {
+            let badge = apply_theme_colors(&format!(" ⚠ {} ", parse_errors), "parse_errors", &ctx.segment.theme);
+            first_line.push((badge, PARSE_ERROR_BADGE_PRIORITY));
         }
     }
 
-    // Join segments with appropriate separators
-    let separator = if config.style == "powerline" { " ⮀ " } else { "  " };
-    Ok(segments.join(separator))
+    if let Err(e) = cache.save() {
+        warn_with_context("segment_cache", &format!("Failed to save segment cache: {:#}", e));
+    }
+
+    // Join segments with appropriate separators, dropping low-priority ones that don't fit
+    let display = config.display.as_ref();
+    let separators = Separators::for_style(
+        &config.style,
+        display.and_then(|display| display.separator.as_deref()),
+        display.and_then(|display| display.start_cap.as_deref()),
+        display.and_then(|display| display.end_cap.as_deref()),
+    );
+    let max_width = config.display.as_ref().and_then(|display| display.max_width).or_else(terminal_width);
+    let mut rendered = rendered.into_iter();
+    let output: Vec<String> = (0..lines.len())
+        .map(|index| {
+            let left = rendered.next().unwrap_or_default();
+            let right = rendered.next().unwrap_or_default();
+            let frame = framed.then(|| FramePosition::of(index, lines.len()));
+            compose_line(left, right, &separators, max_width, frame)
+        })
+        .collect();
+    Ok(output.join("\n"))
 }
 
-fn render_directory_segment(config: &Config, theme: &themes::Theme) -> Result<String> {
-    let current_dir = env::current_dir()?;
-    let show_basename = config.segments.directory
-        .as_ref()
-        .and_then(|c| c.show_basename)
-        .unwrap_or(false);
+/// The (left, right) segment groups of every line: `display.lines` when set, otherwise the
+/// `segments` line, which a framed layout splits in two with the model on the right of the first
+fn line_groups(config: &Config, framed: bool) -> Vec<(SegmentRegistry, SegmentRegistry)> {
+    let lines = config.display.as_ref().and_then(|display| display.lines.as_ref()).filter(|lines| !lines.is_empty());
+    if let Some(lines) = lines {
+        return lines
+            .iter()
+            .map(|line| {
+                let right = line.right.as_ref().map(|right| SegmentRegistry::from_segments(config, right));
+                (SegmentRegistry::from_segments(config, &line.segments), right.unwrap_or_default())
+            })
+            .collect();
+    }
+
+    if framed {
+        let segments = &config.segments;
+        let group = |names: &[&str]| SegmentRegistry::from_segments(config, &segments.only(names));
+        return vec![
+            (group(&["directory", "git"]), group(&["model", "update"])),
+            (group(&["session", "today", "block", "context", "team", "health"]), SegmentRegistry::new()),
+        ];
+    }
+
+    vec![(SegmentRegistry::from_config(config), SegmentRegistry::new())]
+}
 
-    let dir_name = if show_basename {
-        current_dir.file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("?")
-    } else {
-        &current_dir.to_string_lossy()
-    };
+/// The parse error badge outlasts every built-in segment: it explains why the others look off
+const PARSE_ERROR_BADGE_PRIORITY: u32 = 100;
 
-    let formatted = format!(" {} ", dir_name);
-    Ok(apply_theme_colors(&formatted, "directory", theme))
+/// Inputs shared by every segment task of one render
+struct RenderContext {
+    segment: Arc<SegmentContext>,
+    /// Segments still running at this point are abandoned
+    deadline: Option<tokio::time::Instant>,
+    cache_ttls: config::CacheConfig,
+    cache: SegmentCache,
 }
 
-async fn render_git_segment(config: &Config, theme: &themes::Theme) -> Result<String> {
-    let default_git_config = config::GitConfig::default();
-    let git_config = config.segments.git.as_ref().unwrap_or(&default_git_config);
-    let mut git_segment = segments::GitSegment::new();
-    
-    git_segment.show_sha = git_config.show_sha.unwrap_or(true);
-    git_segment.show_working_tree = git_config.show_working_tree.unwrap_or(false);
-    git_segment.show_upstream = git_config.show_upstream.unwrap_or(false);
-    git_segment.show_stash_count = git_config.show_stash_count.unwrap_or(false);
-    git_segment.show_repo_name = git_config.show_repo_name.unwrap_or(false);
-
-    let git_info = git_segment.get_git_info().await?;
-    
-    if git_info.branch.is_none() {
-        return Ok(String::new());
+/// Render one segment on its own task, timing it; disabled segments resolve to `None`.
+/// A segment cached within its TTL is served without running, and one still running
+/// at the deadline is abandoned and resolves to an error.
+async fn spawn_segment(
+    ctx: &RenderContext,
+    segment: &RegisteredSegment,
+) -> Result<Option<(Result<String>, SegmentTiming)>> {
+    let name = segment.name();
+    if !segment.is_enabled() {
+        return Ok(None);
     }
 
-    let mut parts = Vec::new();
-    parts.push("⎇".to_string());
-    
-    if let Some(branch) = &git_info.branch {
-        parts.push(branch.clone());
+    if let Some(cached) = ctx.cache.get_fresh(name, ctx.cache_ttls.ttl_for(name)) {
+        debug_with_context("render", &format!("Serving '{}' from the segment cache", name));
+        return Ok(Some((Ok(cached.text.clone()), SegmentTiming::cached(name))));
     }
-    
-    if git_segment.show_sha {
-        if let Some(sha) = &git_info.sha {
-            parts.push(format!("♯{}", sha));
+
+    let start = std::time::Instant::now();
+    let render = segment.render(ctx.segment.clone());
+    let Some(deadline) = ctx.deadline else {
+        return Ok(Some(tokio::spawn(timed(name, render)).await?));
+    };
+
+    // Transcript parsing runs synchronously inside segments, so on the single-threaded runtime
+    // it would hold the only thread and the deadline could never fire; give each segment a
+    // thread of its own instead (the runtime thread still drives I/O and timers)
+    let runtime = tokio::runtime::Handle::current();
+    let handle = match runtime.runtime_flavor() {
+        tokio::runtime::RuntimeFlavor::CurrentThread => {
+            tokio::task::spawn_blocking(move || runtime.block_on(timed(name, render)))
         }
-    }
+        _ => tokio::spawn(timed(name, render)),
+    };
 
-    if git_info.is_dirty {
-        parts.push("●".to_string());
-    } else {
-        parts.push("✓".to_string());
+    match tokio::time::timeout_at(deadline, handle).await {
+        Ok(joined) => Ok(Some(joined?)),
+        Err(_) => {
+            warn_with_context("render", &format!("Segment '{}' missed the render deadline", name));
+            let error = anyhow::anyhow!("'{}' missed the render deadline", name);
+            Ok(Some((Err(error), SegmentTiming::missed_deadline(name, start.elapsed()))))
+        }
     }
-
-    let formatted = format!(" {} ", parts.join(" "));
-    Ok(apply_theme_colors(&formatted, "git", theme))
 }
 
-async fn render_session_segment(config: &Config, theme: &themes::Theme) -> Result<String> {
-    let default_session_config = config::SessionConfig::default();
-    let session_config = config.segments.session.as_ref().unwrap_or(&default_session_config);
-    let mut session_segment = segments::SessionSegment::new();
-    
-    session_segment.display_type = session_config.display_type.clone().unwrap_or_else(|| "tokens".to_string());
-    session_segment.cost_source = session_config.cost_source.clone().unwrap_or_else(|| "calculated".to_string());
-
-    let session_info = session_segment.get_session_info().await?;
-    
-    if session_info.tokens.is_none() && session_info.cost.is_none() {
-        return Ok(String::new());
-    }
+/// Re-render the statusline in place every `interval` seconds, or sooner when a transcript changes
+async fn run_watch(args: &Args, mut config: Config) -> Result<()> {
+    let interval = std::time::Duration::from_secs(args.interval.unwrap_or(5).max(1));
+    let poll = std::time::Duration::from_secs(1).min(interval);
+    let claude_paths = get_claude_paths().unwrap_or_default();
+
+    // Prefer filesystem events (which also pre-warm parsed entries); fall back to polling mtimes
+    let watcher = match TranscriptWatcher::start(&claude_paths) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            debug_with_context("watch", &format!("Filesystem watcher unavailable, polling instead: {}", e));
+            None
+        }
+    };
+    let mut last_mtime = latest_transcript_mtime(&claude_paths).await;
 
-    let mut parts = vec!["§".to_string()];
-    
-    match session_segment.display_type.as_str() {
-        "cost" => {
-            if let Some(cost) = session_info.cost {
-                parts.push(format!("${:.2}", cost));
-            }
+    // Every file that could contribute to the config, including ones not created yet
+    let mut config_paths = match &args.config {
+        Some(path) => vec![path.clone()],
+        None => config::get_config_search_paths(),
+    };
+    // Saving the theme file being worked on redraws the line too
+    config_paths.extend(config.theme_file.clone());
+    let mut config_stamp = config_fingerprint(&config_paths);
+    let mut reloaded = false;
+
+    loop {
+        // Right after a reload, bypass the segment cache so no segment keeps the old look
+        let mut render_config = config.clone();
+        if reloaded {
+            render_config.cache.get_or_insert_with(Default::default).disable_ttls();
         }
-        "tokens" => {
-            if let Some(tokens) = session_info.tokens {
-                parts.push(format!("{}T", format_number(tokens)));
+        let segment_ctx = segment_context(&render_config, None, None);
+        let statusline = match generate_statusline(&render_config, segment_ctx.clone(), &mut Vec::new()).await {
+            Ok(statusline) => statusline,
+            Err(e) => format!("Error: {}", e),
+        };
+        // A line wider than the terminal would wrap and scroll the redraw out of place
+        let statusline = match terminal_width() {
+            Some(width) => truncate_to_width(&statusline, width),
+            None => statusline,
+        };
+        // Clear the screen and move the cursor home before redrawing
+        print!("\x1b[2J\x1b[H{}", statusline);
+        std::io::Write::flush(&mut std::io::stdout())?;
+        push_statsd(&config, &segment_ctx).await;
+        check_alerts(&config, &segment_ctx).await;
+
+        let config_changed = if let Some(watcher) = &watcher {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => false,
+                _ = watcher.changed() => false,
+                _ = wait_for_config_change(&config_paths, &mut config_stamp, poll) => true,
             }
-        }
-        "both" => {
-            if let Some(cost) = session_info.cost {
-                parts.push(format!("${:.2}", cost));
+        } else {
+            let mut config_changed = false;
+            let rendered_at = std::time::Instant::now();
+            while rendered_at.elapsed() < interval {
+                tokio::time::sleep(poll).await;
+
+                let stamp = config_fingerprint(&config_paths);
+                if stamp != config_stamp {
+                    config_stamp = stamp;
+                    config_changed = true;
+                    break;
+                }
+
+                let mtime = latest_transcript_mtime(&claude_paths).await;
+                if mtime != last_mtime {
+                    last_mtime = mtime;
+                    break;
+                }
             }
-            if let Some(tokens) = session_info.tokens {
-                parts.push(format!("{}T", format_number(tokens)));
+            config_changed
+        };
+
+        reloaded = false;
+        if config_changed {
+            // A half-saved or invalid file keeps the previous settings until it's fixed
+            match load_statusline_config(args).await {
+                Ok(new_config) => {
+                    info_with_context("watch", "Config changed, reloaded");
+                    let restart_keys = config.changed_startup_keys(&new_config);
+                    if !restart_keys.is_empty() {
+                        warn_with_context("watch", &format!("Restart to apply the new {}", restart_keys.join(", ")));
+                    }
+                    config = new_config;
+                    reloaded = true;
+                }
+                Err(e) => warn_with_context("watch", &format!("Keeping previous config, reload failed: {:#}", e)),
             }
         }
-        _ => {}
     }
-
-    let formatted = format!(" {} ", parts.join(" "));
-    Ok(apply_theme_colors(&formatted, "session", theme))
 }
 
-async fn render_today_segment(config: &Config, theme: &themes::Theme) -> Result<String> {
-    let default_today_config = config::TodayConfig::default();
-    let today_config = config.segments.today.as_ref().unwrap_or(&default_today_config);
-    let mut today_segment = segments::TodaySegment::new();
-    
-    today_segment.display_type = today_config.display_type.clone().unwrap_or_else(|| "cost".to_string());
-
-    let today_info = today_segment.get_today_info().await?;
-    
-    if today_info.tokens.is_none() && today_info.cost.is_none() {
-        return Ok(String::new());
-    }
+/// Modification time and size of each config file (`None` while one doesn't exist)
+fn config_fingerprint(paths: &[PathBuf]) -> Vec<Option<(std::time::SystemTime, u64)>> {
+    paths
+        .iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
 
-    let mut parts = vec!["💰".to_string()];
-    
-    match today_segment.display_type.as_str() {
-        "cost" => {
-            if let Some(cost) = today_info.cost {
-                parts.push(format!("${:.2}", cost));
-            }
+/// Check the config files every `poll` and return once one of them was created, edited or removed
+async fn wait_for_config_change(
+    paths: &[PathBuf],
+    stamp: &mut Vec<Option<(std::time::SystemTime, u64)>>,
+    poll: std::time::Duration,
+) {
+    loop {
+        tokio::time::sleep(poll).await;
+        let current = config_fingerprint(paths);
+        if current != *stamp {
+            *stamp = current;
+            return;
         }
-        "tokens" => {
-            if let Some(tokens) = today_info.tokens {
-                parts.push(format!("{}T", format_number(tokens)));
-            }
-        }
-        "both" => {
-            if let Some(cost) = today_info.cost {
-                parts.push(format!("${:.2}", cost));
-            }
-            if let Some(tokens) = today_info.tokens {
-                parts.push(format!("{}T", format_number(tokens)));
-            }
-        }
-        _ => {}
     }
-
-    let formatted = format!(" {} ", parts.join(" "));
-    Ok(apply_theme_colors(&formatted, "today", theme))
 }
 
-async fn render_block_segment(config: &Config, theme: &themes::Theme) -> Result<String> {
-    let default_block_config = config::BlockConfig::default();
-    let block_config = config.segments.block.as_ref().unwrap_or(&default_block_config);
-    let mut block_segment = segments::BlockSegment::new();
-    
-    block_segment.display_type = block_config.display_type.clone().unwrap_or_else(|| "tokens".to_string());
-    block_segment.burn_type = block_config.burn_type.clone().unwrap_or_else(|| "cost".to_string());
+async fn run_usage_report(args: &Args) -> Result<()> {
+    let config = config::load_profile_config(args.config.clone(), args.profile.as_deref()).await?;
+    init_data_dirs(&config);
+    init_claude_paths(&config);
+    init_parallelism(&config);
+    init_pricing(&config);
+    init_formats(&config);
+    let days = args.days.unwrap_or(7);
+    let format = args.format.as_deref().unwrap_or("table");
+    if !reports::REPORT_FORMATS.contains(&format) {
+        anyhow::bail!("Unknown report format: {} (expected {})", format, reports::REPORT_FORMATS.join(", "));
+    }
+
+    let output = if args.team {
+        let team = config.team.clone().unwrap_or_default();
+        reports::render_report(&reports::TeamUsageReport::load(&team, days).await?, format, should_use_colors())?
+    } else if args.by_project {
+        reports::render_report(&reports::ProjectUsageReport::load(days).await?, format, should_use_colors())?
+    } else {
+        reports::render_report(&reports::UsageReport::load(days).await?, format, should_use_colors())?
+    };
 
-    let block_info = block_segment.get_active_block_info().await?;
-    
-    if block_info.tokens.is_none() && block_info.cost.is_none() {
-        return Ok(String::new());
-    }
+    println!("{}", output);
+    Ok(())
+}
 
-    let mut parts = vec!["🎪".to_string()];
-    
-    match block_segment.display_type.as_str() {
-        "cost" => {
-            if let Some(cost) = block_info.cost {
-                parts.push(format!("${:.2}", cost));
-            }
-        }
-        "tokens" => {
-            if let Some(tokens) = block_info.tokens {
-                parts.push(format!("{}T", format_number(tokens)));
-            }
-        }
-        "weighted" => {
-            if let Some(weighted) = block_info.weighted_tokens {
-                parts.push(format!("{}T", format_tokens(weighted)));
-            }
-        }
-        _ => {}
+/// Point every on-disk cache, state file and log at the configured `cacheDir`, `stateDir` and `logging`, if any
+fn init_data_dirs(config: &Config) {
+    if let Some(dir) = &config.cache_dir {
+        set_cache_root(dir.clone());
+    }
+    if let Some(dir) = &config.state_dir {
+        set_state_root(dir.clone());
     }
+    if let Some(settings) = config.logging.as_ref().and_then(|logging| logging.file_log_settings()) {
+        set_file_logging(settings);
+    }
+    finish_file_logging_setup();
+}
 
-    // Show reset time instead of minutes remaining
-    if let Some(reset_time) = block_info.reset_time {
-        let now = chrono::Local::now();
-        let local_reset_time = reset_time.with_timezone(&chrono::Local);
-        parts.push(format!("Reset@:{}->{}", 
-                          now.format("%H:%M"), 
-                          local_reset_time.format("%H:%M")));
+/// Scan the configured `claudePaths`, if any, instead of the default Claude directories, plus
+/// any `transcriptGlobs`
+fn init_claude_paths(config: &Config) {
+    if let Some(paths) = &config.claude_paths {
+        set_claude_paths(paths.clone());
+    }
+    if let Some(globs) = &config.transcript_globs {
+        set_transcript_globs(globs.clone());
     }
+    if let Some(all) = config.wsl_all_profiles {
+        set_wsl_all_profiles(all);
+    }
+}
 
-    let formatted = format!(" {} ", parts.join(" "));
-    Ok(apply_theme_colors(&formatted, "block", theme))
+/// Size the parse thread pool and concurrency from `performance` (or their environment overrides)
+fn init_parallelism(config: &Config) {
+    let performance = config.performance.as_ref();
+    set_parallelism(
+        performance.and_then(|performance| performance.threads),
+        performance.and_then(|performance| performance.parse_concurrency),
+    );
+    init_thread_pool();
 }
 
-async fn render_model_segment(config: &Config, theme: &themes::Theme) -> Result<String> {
-    let default_model_config = config::ModelConfig::default();
-    let model_config = config.segments.model.as_ref().unwrap_or(&default_model_config);
-    
-    if !model_config.enabled {
-        return Ok(String::new());
+/// Push usage metrics if a StatsD agent is configured; failures are only logged
+async fn push_statsd(config: &Config, ctx: &SegmentContext) {
+    let Some(statsd) = &config.statsd else {
+        return;
+    };
+    if let Err(e) = statsd::push_if_due(statsd, ctx).await {
+        warn_with_context("statsd", &format!("Metrics push failed: {:#}", e));
     }
+}
 
-    let mut model_segment = segments::ModelSegment::new();
-    let model_info = model_segment.get_current_model_info().await?;
-    
-    if model_info.display_name.is_none() {
-        return Ok(String::new());
+/// Fire alerts for crossed budget thresholds and block resets; failures are only logged
+async fn check_alerts(config: &Config, ctx: &SegmentContext) {
+    let Some(alerts) = &config.alerts else {
+        return;
+    };
+    if let Err(e) = alerts::run_alerts(alerts, config.budget.as_ref(), ctx).await {
+        warn_with_context("alerts", &format!("Alert check failed: {:#}", e));
     }
+}
 
-    let mut parts = vec!["🤖".to_string()];
-    if let Some(name) = model_info.display_name {
-        parts.push(name);
+fn init_pricing(config: &Config) {
+    let pricing = config.pricing.clone().unwrap_or_default();
+    set_batch_pricing(pricing.batch_pricing());
+    set_default_cache_ttl(pricing.default_cache_ttl());
+    set_cost_divergence_percent(pricing.cost_divergence_percent.unwrap_or(DEFAULT_COST_DIVERGENCE_PERCENT));
+    set_include_sidechains(pricing.include_sidechains.unwrap_or(true));
+    if let Some(path) = &config.pricing_file {
+        match load_pricing_file(path) {
+            Ok(table) => set_custom_pricing(table),
+            Err(e) => warn_with_context("pricing", &format!("Using the built-in prices: {:#}", e)),
+        }
     }
+}
 
-    let text = parts.join(" ");
-    Ok(apply_theme_colors(&text, "model", theme))
+/// How every segment writes counts and costs, from `display`
+fn init_formats(config: &Config) {
+    let display = config.display.as_ref();
+    set_number_format(NumberFormat::from_config(
+        display.and_then(|display| display.number_format.as_deref()),
+        display.and_then(|display| display.number_decimals),
+    ));
+    let cost_format = CostFormat {
+        precision: display.and_then(|display| display.cost_precision).unwrap_or(2),
+        cents: display.and_then(|display| display.cost_unit.as_deref()) == Some("cents"),
+        rounding: RoundingMode::from_config(display.and_then(|display| display.cost_rounding.as_deref())),
+        multiplier: display
+            .and_then(|display| display.cost_multiplier)
+            .filter(|multiplier| multiplier.is_finite() && *multiplier > 0.0)
+            .unwrap_or(1.0),
+    };
+    set_cost_format(cost_format);
+    if display.and_then(|display| display.cost_rounding_point.as_deref()) == Some("entry") {
+        set_entry_cost_rounding(CostRounding {
+            mode: cost_format.rounding,
+            decimals: if cost_format.cents { 2 } else { cost_format.precision },
+        });
+    }
 }
 
-async fn render_context_segment(config: &Config, theme: &themes::Theme) -> Result<String> {
-    let default_context_config = config::ContextConfig::default();
-    let context_config = config.segments.context.as_ref().unwrap_or(&default_context_config);
-    let mut context_segment = segments::ContextSegment::new();
-    
-    context_segment.show_percentage_only = context_config.show_percentage_only.unwrap_or(false);
+async fn run_cache_command(args: &Args) -> Result<()> {
+    let config = config::load_profile_config(args.config.clone(), args.profile.as_deref()).await?;
+    init_data_dirs(&config);
+    init_claude_paths(&config);
+    let root = cache_root().ok_or_else(|| anyhow::anyhow!("No cache directory available"))?;
 
-    let context_info = context_segment.get_context_info().await?;
-    
-    // Always show context info (even default values are useful)
-    // Default shows "◔ 0 (100%)" indicating 100% context remaining
+    match args.action.as_deref() {
+        Some("clear") => {
+            let freed = clear_cache(&root)?;
+            println!("Cleared {} ({:.1} MB)", root.display(), freed as f64 / (1024.0 * 1024.0));
+        }
+        None | Some("info") => {
+            let max_bytes = config.cache.unwrap_or_default().max_size_bytes();
+            println!("Cache directory: {}", root.display());
+            if let Some(state) = state_root() {
+                println!("State directory: {}", state.display());
+            }
+            println!(
+                "Size: {:.1} MB of {:.1} MB",
+                cache_size(&root) as f64 / (1024.0 * 1024.0),
+                max_bytes as f64 / (1024.0 * 1024.0)
+            );
+        }
+        Some(other) => anyhow::bail!("Unknown cache command: {} (expected info or clear)", other),
+    }
+    Ok(())
+}
 
-    let mut parts = vec!["🧠".to_string()];
-    
-    if context_segment.show_percentage_only {
-        parts.push(format!("{}%", context_info.context_left_percentage));
-    } else {
-        parts.push(format_number(context_info.input_tokens).to_string());
-        parts.push(format!("({}%)", context_info.context_left_percentage));
+async fn run_doctor(args: &Args) -> Result<()> {
+    // Broken configs are reported by the config file check itself
+    if let Ok(config) = config::load_profile_config(args.config.clone(), args.profile.as_deref()).await {
+        init_data_dirs(&config);
+        init_claude_paths(&config);
+        init_parallelism(&config);
     }
+    let report = doctor::DoctorReport::run(args.config.clone(), args.profile.as_deref()).await;
+    println!("{}", report.render(should_use_colors()));
 
-    let formatted = format!(" {} ", parts.join(" "));
-    Ok(apply_theme_colors(&formatted, "context", theme))
+    if report.has_failures() {
+        std::process::exit(1);
+    }
+    Ok(())
 }
 
-fn apply_theme_colors(text: &str, segment: &str, theme: &themes::Theme) -> String {
-    // Check if we should use colors
-    if !should_use_colors() {
-        return text.to_string();
-    }
-    
-    if let Some((bg_color, fg_color)) = theme.get_colors(segment) {
-        let bg_rgb = parse_color(bg_color);
-        let fg_rgb = parse_color(fg_color);
-        
-        // Try 24-bit RGB first, fallback to 8-bit if not supported
-        if supports_rgb_colors() {
-            format!("\x1b[48;2;{};{};{}m\x1b[38;2;{};{};{}m{}\x1b[0m", 
-                    bg_rgb.0, bg_rgb.1, bg_rgb.2,
-                    fg_rgb.0, fg_rgb.1, fg_rgb.2,
-                    text)
-        } else {
-            // Fallback to basic 8-bit colors
-            let bg_code = rgb_to_8bit(bg_rgb);
-            let fg_code = rgb_to_8bit(fg_rgb);
-            format!("\x1b[48;5;{}m\x1b[38;5;{}m{}\x1b[0m", bg_code, fg_code, text)
+/// Render every segment here and with the original TypeScript claude-powerline, from the same
+/// config and hook JSON, and show where they differ; exits 1 when anything does
+async fn run_compare(args: &Args) -> Result<()> {
+    let original = compare::OriginalCommand::locate(args.original.as_deref())?;
+    let config = load_statusline_config(args).await?;
+    let hook_input = read_hook_input();
+    let hook = hook_input.as_deref().and_then(parse_hook_data);
+    // A comparison isn't a real render, so it leaves the hook cost ledger alone
+    let segment_ctx = segment_context(&config, hook, None);
+
+    let mut ours = Vec::new();
+    for (left, right) in line_groups(&config, false) {
+        for segment in left.iter().chain(right.iter()).filter(|segment| segment.is_enabled()) {
+            let text = segment.render(segment_ctx.clone()).await
+                .unwrap_or_else(|e| format!("error: {:#}", e));
+            ours.push((segment.name().to_string(), text));
         }
-    } else {
-        text.to_string()
     }
-}
 
-fn should_use_colors() -> bool {
-    // Always use colors unless explicitly disabled
-    // Claude Code can handle ANSI escape codes even when not in direct TTY
-    env::var("NO_COLOR").is_err() &&
-        env::var("TERM").map_or(true, |term| term != "dumb") &&
-        env::var("TERM").map_or(false, |term| !term.is_empty())
-}
+    let output = original.render(&config, hook_input.as_deref()).await?;
+    let report = compare::CompareReport::new(original.display(), ours, compare::split_original_segments(&output));
+    println!("{}", report.render(should_use_colors()));
 
-fn supports_rgb_colors() -> bool {
-    env::var("COLORTERM").map_or(false, |ct| ct.contains("truecolor") || ct.contains("24bit")) ||
-    env::var("TERM").map_or(false, |term| 
-        term.contains("256") || 
-        term.contains("color") || 
-        term == "xterm-kitty" ||
-        term == "alacritty"
-    )
+    if report.differences() > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
 }
 
-fn rgb_to_8bit((r, g, b): (u8, u8, u8)) -> u8 {
-    // Convert RGB to closest 8-bit color (216 color cube + grayscale)
-    if r == g && g == b {
-        // Grayscale
-        if r < 8 { 16 }
-        else if r > 248 { 231 }
-        else { ((r - 8) / 10) + 232 }
-    } else {
-        // Color cube: 16 + 36*r + 6*g + b
-        let r6 = (r * 5 / 255);
-        let g6 = (g * 5 / 255); 
-        let b6 = (b * 5 / 255);
-        16 + 36 * r6 + 6 * g6 + b6
+/// Point Claude Code's statusLine at this binary, passing on the look chosen with the flags;
+/// with `--remove`, take it out again
+fn run_setup(args: &Args) -> Result<()> {
+    let settings = args.settings.clone()
+        .or_else(setup::default_settings_path)
+        .ok_or_else(|| anyhow::anyhow!("No home directory; pass --settings <FILE>"))?;
+    let binary = env::current_exe().context("Failed to locate the claude-powerline binary")?;
+
+    let mut flags = Vec::new();
+    if let Some(theme) = &args.theme {
+        flags.extend(["--theme".to_string(), theme.clone()]);
+    }
+    if let Some(style) = &args.style {
+        flags.extend(["--style".to_string(), style.clone()]);
+    }
+    if let Some(profile) = &args.profile {
+        flags.extend(["--profile".to_string(), profile.clone()]);
+    }
+    if let Some(config) = &args.config {
+        // Claude Code runs the command from the project directory
+        let config = std::path::absolute(config).unwrap_or_else(|_| config.clone());
+        flags.extend(["--config".to_string(), config.display().to_string()]);
+    }
+    if args.basename {
+        flags.push("--basename".to_string());
     }
-}
 
-fn parse_color(color: &str) -> (u8, u8, u8) {
-    if color.starts_with('#') && color.len() == 7 {
-        let r = u8::from_str_radix(&color[1..3], 16).unwrap_or(255);
-        let g = u8::from_str_radix(&color[3..5], 16).unwrap_or(255);
-        let b = u8::from_str_radix(&color[5..7], 16).unwrap_or(255);
-        (r, g, b)
-    } else {
-        (255, 255, 255) // Default to white
+    if args.remove {
+        match setup::remove_status_line(&settings, &binary)? {
+            setup::RemoveOutcome::NotInstalled { other: None } => println!("No statusLine set up by claude-powerline setup in {}", settings.display()),
+            setup::RemoveOutcome::NotInstalled { other: Some(other) } => {
+                println!("Left {} alone: its statusLine runs `{}`", settings.display(), other)
+            }
+            setup::RemoveOutcome::Removed { backup } => {
+                println!("Removed the statusLine from {}", settings.display());
+                println!("Previous settings saved to {}", backup.display());
+            }
+            setup::RemoveOutcome::Restored { command, from, backup } => {
+                println!("Restored statusLine `{}` from {} in {}", command, from.display(), settings.display());
+                println!("Previous settings saved to {}", backup.display());
+            }
+        }
+        return Ok(());
     }
-}
 
-fn format_number(num: u32) -> String {
-    if num >= 1_000_000 {
-        format!("{:.1}M", num as f64 / 1_000_000.0)
-    } else if num >= 1_000 {
-        format!("{:.1}K", num as f64 / 1_000.0)
-    } else {
-        num.to_string()
+    let command = setup::status_line_command(&binary, &flags);
+    match setup::install_status_line(&settings, &command)? {
+        setup::SetupOutcome::Unchanged => println!("{} already runs `{}`", settings.display(), command),
+        setup::SetupOutcome::Installed { backup } => {
+            println!("Added statusLine `{}` to {}", command, settings.display());
+            if let Some(backup) = backup {
+                println!("Previous settings saved to {}", backup.display());
+            }
+        }
+        setup::SetupOutcome::Updated { previous, backup } => {
+            println!("Replaced statusLine `{}` with `{}` in {}", previous, command, settings.display());
+            println!("Previous settings saved to {}", backup.display());
+        }
     }
+    println!("Restart Claude Code, or start a new session, to see it");
+    Ok(())
 }
 
-fn format_tokens(num: u32) -> String {
-    if num >= 1_000_000 {
-        format!("{:.1}M", num as f64 / 1_000_000.0)
-    } else if num >= 1_000 {
-        format!("{:.1}K", num as f64 / 1_000.0)
-    } else {
-        num.to_string()
+/// Look the latest release up now, whatever the update segment's interval, and say whether it's newer
+async fn run_check_update(args: &Args) -> Result<()> {
+    // For the state directory the update segment reads
+    if let Ok(config) = config::load_profile_config(args.config.clone(), args.profile.as_deref()).await {
+        init_data_dirs(&config);
     }
+
+    let state = check_for_update(UpdateState::default_path().as_deref()).await?;
+    match state.newer_than(CURRENT_VERSION) {
+        Some(latest) => println!("claude-powerline v{} is available (you have v{}): {}", latest, CURRENT_VERSION, RELEASES_PAGE),
+        None => println!("claude-powerline v{} is up to date", CURRENT_VERSION),
+    }
+    Ok(())
 }
 
 async fn install_fonts() -> Result<()> {
@@ -480,18 +924,65 @@ fn print_help() {
     println!();
     println!("USAGE:");
     println!("    claude-powerline [OPTIONS]");
+    println!("    claude-powerline usage [--days <N>] [--format <FORMAT>] [--by-project | --team]");
+    println!("    claude-powerline doctor");
+    println!("    claude-powerline cache [info|clear]");
+    println!("    claude-powerline render [--svg <FILE>] [--png <FILE>]");
+    println!("    claude-powerline setup [--theme <THEME>] [--style <STYLE>] [--profile <NAME>] [--settings <FILE>]");
+    println!("    claude-powerline setup --remove [--settings <FILE>]");
+    println!("    claude-powerline compare [--original <COMMAND>] < hook.json");
+    println!();
+    println!("COMMANDS:");
+    println!("    usage                  Print per-day cost, tokens and message counts");
+    println!("    doctor                 Diagnose config discovery, transcripts, statusLine hook and colors");
+    println!("    cache                  Show the cache directory and its size; `cache clear` deletes it");
+    println!("    render                 Draw the themed statusline as an image (README screenshots, theme galleries)");
+    println!("    setup                  Point Claude Code's statusLine at this binary (backs up settings.json first)");
+    println!("    compare                Diff each segment against the original TypeScript claude-powerline");
     println!();
     println!("OPTIONS:");
-    println!("    --theme <THEME>        Theme: dark, light, nord, tokyo-night, rose-pine [default: dark]");
-    println!("    --style <STYLE>        Style: minimal, powerline [default: minimal]");
+    println!("    --theme <THEME>        Theme: dark, light, nord, tokyo-night, rose-pine, auto [default: dark]");
+    println!("    --style <STYLE>        Style: minimal, powerline, transparent [default: minimal]");
+    println!("    --theme-file <FILE>    Theme JSON file to use instead of --theme (for working on a theme)");
     println!("    --config <FILE>        Custom config file path");
+    println!("    --profile <NAME>       Merge the named entry of the config's `profiles` over the rest");
+    println!("    --color <WHEN>         Colors: auto, always, never; overrides TERM and the color variables [default: auto]");
     println!("    --basename             Show only directory name instead of full path");
     println!("    --install-fonts        Install powerline fonts");
+    println!("    --schema               Print the JSON Schema of the config file");
+    println!("    --check-update         Look up the latest release and say whether it is newer");
+    println!("    --days <N>             Number of days to include in usage reports [default: 7]");
+    println!("    --format <FORMAT>      Usage report format: table, csv, json, markdown, html [default: table]");
+    println!("    --by-project           Group usage reports by project directory instead of by day");
+    println!("    --team                 Show each day's cost per person of the config's `team`");
+    println!("    --timing               Print per-segment timings and parse counts to stderr");
+    println!("    --watch                Keep re-rendering the statusline (for a tmux pane or terminal)");
+    println!("    --interval <SECS>      Refresh interval for --watch; transcript changes refresh sooner [default: 5]");
+    println!("    --svg <FILE>           Write the rendered statusline as SVG (render command)");
+    println!("    --png <FILE>           Write the rendered statusline as PNG via rsvg-convert (render command)");
+    println!("    --settings <FILE>      Claude settings file for setup [default: ~/.claude/settings.json]");
+    println!("    --remove               Take the statusLine setup added out again, restoring the one it replaced");
+    println!("    --original <COMMAND>   How compare runs the original, e.g. `npx -y @owloops/claude-powerline`");
+    println!("    -v, --debug            Print debug logging to stderr (-vv for trace)");
+    println!("    --debug-filter <SPEC>  Only log some modules, e.g. `git` or `warn,git=trace,render`");
     println!("    --help                 Show this help message");
     println!();
     println!("ENVIRONMENT VARIABLES:");
     println!("    CLAUDE_POWERLINE_THEME     Override theme");
     println!("    CLAUDE_POWERLINE_STYLE     Override style");
+    println!("    CLAUDE_POWERLINE_<SEGMENT>_<OPTION>");
+    println!("                               Override a segment option, e.g. CLAUDE_POWERLINE_GIT_SHOW_SHA=false");
     println!("    CLAUDE_POWERLINE_CONFIG    Override config path");
-    println!("    CLAUDE_POWERLINE_DEBUG     Enable debug logging");
+    println!("    CLAUDE_POWERLINE_PROFILE   Config profile to use when --profile isn't given");
+    println!("    CLAUDE_POWERLINE_DEBUG     Debug logging to stderr and the state directory log: 1, a level or a filter spec");
+    println!("    CLAUDE_POWERLINE_ENTRY_CACHE  Set to 0 to disable the binary parsed-entry cache");
+    println!("    CLAUDE_POWERLINE_MMAP_THRESHOLD  Memory-map transcripts at least this many bytes (default 1048576)");
+    println!("    CLAUDE_POWERLINE_THREADS   Threads parsing transcripts, over `performance.threads` [default: CPU count]");
+    println!("    CLAUDE_POWERLINE_PARSE_CONCURRENCY  Transcripts parsed at once, over `performance.parseConcurrency`");
+    println!("    CLAUDE_POWERLINE_RUNTIME   Async runtime: current-thread or multi-thread [default: current-thread]");
+    println!("    CLAUDE_POWERLINE_NO_SESSION_INDEX  Set to 1 to stop keeping the session index");
+    println!("    CLAUDE_POWERLINE_ORIGINAL  How compare runs the original when --original isn't given");
+    if cfg!(feature = "sqlite-index") {
+        println!("    CLAUDE_POWERLINE_INDEX     Use the SQLite usage index (`1` or a database file path)");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,137 @@
+pub mod svg;
+
+pub use svg::*;
+
+/// A 24-bit color as (red, green, blue)
+pub type Rgb = (u8, u8, u8);
+
+/// A run of statusline text drawn with one set of colors
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StyledSpan {
+    pub text: String,
+    /// `None` means the terminal's default colors
+    pub fg: Option<Rgb>,
+    pub bg: Option<Rgb>,
+    pub bold: bool,
+}
+
+/// Split rendered statusline text into styled spans by interpreting its SGR escapes
+/// (24-bit, 256-color and basic 16-color foregrounds and backgrounds, bold and resets).
+/// Other escape sequences are dropped.
+pub fn parse_ansi(text: &str) -> Vec<StyledSpan> {
+    let mut spans = Vec::new();
+    let mut current = StyledSpan::default();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            current.text.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // CSI: parameters up to a final byte in @..~; only `m` (SGR) changes the style
+            Some('[') => {
+                let mut params = String::new();
+                let mut final_byte = None;
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        final_byte = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+
+                if final_byte == Some('m') {
+                    let mut style = StyledSpan { text: String::new(), ..current.clone() };
+                    apply_sgr(&mut style, &params);
+                    if !current.text.is_empty() {
+                        spans.push(std::mem::replace(&mut current, style));
+                    } else {
+                        current = style;
+                    }
+                }
+            }
+            // OSC (e.g. hyperlinks): skip to BEL or ESC \
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !current.text.is_empty() {
+        spans.push(current);
+    }
+    spans
+}
+
+fn apply_sgr(style: &mut StyledSpan, params: &str) {
+    let codes: Vec<u16> = if params.is_empty() {
+        vec![0]
+    } else {
+        params.split(';').map(|p| p.parse().unwrap_or(0)).collect()
+    };
+
+    let mut codes = codes.into_iter();
+    while let Some(code) = codes.next() {
+        match code {
+            0 => {
+                style.fg = None;
+                style.bg = None;
+                style.bold = false;
+            }
+            1 => style.bold = true,
+            22 => style.bold = false,
+            30..=37 => style.fg = Some(ansi_256_color(code as u8 - 30)),
+            90..=97 => style.fg = Some(ansi_256_color(code as u8 - 90 + 8)),
+            40..=47 => style.bg = Some(ansi_256_color(code as u8 - 40)),
+            100..=107 => style.bg = Some(ansi_256_color(code as u8 - 100 + 8)),
+            39 => style.fg = None,
+            49 => style.bg = None,
+            38 | 48 => {
+                let color = match codes.next() {
+                    Some(2) => {
+                        let mut channel = || codes.next().unwrap_or(0).min(255) as u8;
+                        Some((channel(), channel(), channel()))
+                    }
+                    Some(5) => codes.next().map(|n| ansi_256_color(n.min(255) as u8)),
+                    _ => None,
+                };
+                if code == 38 {
+                    style.fg = color;
+                } else {
+                    style.bg = color;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// RGB value of an entry in the xterm 256-color palette
+pub fn ansi_256_color(index: u8) -> Rgb {
+    const BASIC: [Rgb; 16] = [
+        (0, 0, 0), (205, 49, 49), (13, 188, 121), (229, 229, 16),
+        (36, 114, 200), (188, 63, 188), (17, 168, 205), (229, 229, 229),
+        (102, 102, 102), (241, 76, 76), (35, 209, 139), (245, 245, 67),
+        (59, 142, 234), (214, 112, 214), (41, 184, 219), (255, 255, 255),
+    ];
+
+    match index {
+        0..=15 => BASIC[index as usize],
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let n = index - 16;
+            (level(n / 36), level((n / 6) % 6), level(n % 6))
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
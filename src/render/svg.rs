@@ -0,0 +1,125 @@
+use crate::render::{parse_ansi, Rgb};
+use crate::utils::display_width;
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// Canvas and font settings for an SVG render
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    pub font_size: f64,
+    pub font_family: String,
+    /// Canvas color behind the statusline, like a terminal background
+    pub background: Rgb,
+    /// Color of text drawn without a theme color (separators, plain segments)
+    pub foreground: Rgb,
+    pub padding: f64,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            font_size: 14.0,
+            font_family: "'JetBrains Mono', 'Fira Code', Menlo, Consolas, 'DejaVu Sans Mono', monospace".to_string(),
+            background: (30, 30, 30),
+            foreground: (212, 212, 212),
+            padding: 8.0,
+        }
+    }
+}
+
+/// Draw rendered statusline text (with its ANSI colors) as an SVG image: one
+/// terminal-cell-sized background rectangle and text run per styled span, one row per line
+pub fn render_svg(statusline: &str, options: &SvgOptions) -> String {
+    let cell_width = options.font_size * 0.6;
+    let line_height = options.font_size * 1.5;
+
+    let lines: Vec<_> = statusline.lines().map(parse_ansi).collect();
+    let max_cells = lines
+        .iter()
+        .map(|spans| spans.iter().map(|span| display_width(&span.text)).sum::<usize>())
+        .max()
+        .unwrap_or(0);
+    let width = options.padding * 2.0 + max_cells as f64 * cell_width;
+    let height = options.padding * 2.0 + lines.len().max(1) as f64 * line_height;
+
+    let mut rects = Vec::new();
+    let mut texts = Vec::new();
+    for (row, spans) in lines.iter().enumerate() {
+        let top = options.padding + row as f64 * line_height;
+        let baseline = top + options.font_size * 1.1;
+        let mut column = 0usize;
+
+        for span in spans {
+            let cells = display_width(&span.text);
+            let x = options.padding + column as f64 * cell_width;
+            let span_width = cells as f64 * cell_width;
+
+            if let Some(bg) = span.bg {
+                rects.push(format!(
+                    r#"  <rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"/>"#,
+                    x, top, span_width, line_height, hex(bg)
+                ));
+            }
+            if !span.text.trim().is_empty() {
+                texts.push(format!(
+                    r#"  <text x="{:.1}" y="{:.1}" fill="{}"{} textLength="{:.1}" lengthAdjust="spacing">{}</text>"#,
+                    x,
+                    baseline,
+                    hex(span.fg.unwrap_or(options.foreground)),
+                    if span.bold { r#" font-weight="bold""# } else { "" },
+                    span_width,
+                    xml_escape(&span.text)
+                ));
+            }
+
+            column += cells;
+        }
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.0}\" height=\"{h:.0}\" viewBox=\"0 0 {w:.1} {h:.1}\">\n  \
+         <rect width=\"100%\" height=\"100%\" rx=\"6\" fill=\"{bg}\"/>\n\
+         <g font-family=\"{font}\" font-size=\"{size}\" xml:space=\"preserve\">\n{rects}\n{texts}\n</g>\n</svg>\n",
+        w = width,
+        h = height,
+        bg = hex(options.background),
+        font = xml_escape(&options.font_family),
+        size = options.font_size,
+        rects = rects.join("\n"),
+        texts = texts.join("\n"),
+    )
+}
+
+/// Rasterize `svg` to a PNG at `path` with `rsvg-convert` (librsvg)
+pub async fn write_png(svg: &str, path: &Path) -> Result<()> {
+    let svg_path = path.with_extension(format!("svg.tmp{}", std::process::id()));
+    tokio::fs::write(&svg_path, svg).await?;
+
+    let output = Command::new("rsvg-convert")
+        .args(["--format", "png", "--output"])
+        .arg(path)
+        .arg(&svg_path)
+        .stdin(Stdio::null())
+        .output()
+        .await;
+    let _ = tokio::fs::remove_file(&svg_path).await;
+
+    let output = output.context("Failed to run rsvg-convert (install librsvg to export PNG)")?;
+    if !output.status.success() {
+        anyhow::bail!("rsvg-convert exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+fn hex((r, g, b): Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
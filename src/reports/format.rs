@@ -0,0 +1,179 @@
+use crate::segments::cost_format;
+use crate::utils::round_cost;
+
+/// Render rows as an aligned plain-text table: first column left-aligned, the rest right-aligned,
+/// with an optional totals row below a rule (header is bolded when `use_colors` is set)
+pub(crate) fn render_table(
+    header: &[&str],
+    rows: &[Vec<String>],
+    total: Option<Vec<String>>,
+    use_colors: bool,
+) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+    for row in rows.iter().chain(total.iter()) {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: &[&str]| {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .enumerate()
+            .map(|(i, (cell, width))| {
+                if i == 0 {
+                    format!("{:<width$}", cell, width = width)
+                } else {
+                    format!("{:>width$}", cell, width = width)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = Vec::new();
+    let header_line = format_row(header);
+    if use_colors {
+        lines.push(format!("\x1b[1m{}\x1b[0m", header_line));
+    } else {
+        lines.push(header_line);
+    }
+
+    let rule = "-".repeat(widths.iter().sum::<usize>() + 2 * (widths.len() - 1));
+    lines.push(rule.clone());
+    for row in rows {
+        lines.push(format_row(&row.iter().map(|s| s.as_str()).collect::<Vec<_>>()));
+    }
+
+    if let Some(total) = total {
+        lines.push(rule);
+        lines.push(format_row(&total.iter().map(|s| s.as_str()).collect::<Vec<_>>()));
+    }
+
+    lines.join("\n")
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or newline
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// A report cost in dollars and cents (`$1.23`), multiplied and rounded as configured
+pub(crate) fn format_dollars(cost: f64) -> String {
+    let format = cost_format();
+    format!("${:.2}", round_cost(cost * format.multiplier, 2, format.rounding))
+}
+
+/// `cost` with the configured multiplier applied (the raw cost when there is none), exported next
+/// to the raw cost on every CSV and JSON row and total so the rows add up to the billed total
+pub(crate) fn billed_cost_usd(cost: f64) -> f64 {
+    cost * cost_format().multiplier
+}
+
+/// Format an integer with thousands separators (1234567 -> 1,234,567)
+pub(crate) fn format_with_separators(num: u64) -> String {
+    let digits = num.to_string();
+    let mut result = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            result.push(',');
+        }
+        result.push(c);
+    }
+
+    result
+}
+
+/// Render rows as a GitHub-flavored Markdown table, numeric columns right-aligned like
+/// `render_table`, with an optional bolded totals row
+pub(crate) fn render_markdown_table(header: &[&str], rows: &[Vec<String>], total: Option<Vec<String>>) -> String {
+    let cell = |value: &str| value.replace('|', "\\|");
+    let row_line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+
+    let mut lines = vec![row_line(header.iter().map(|h| cell(h)).collect())];
+    lines.push(row_line(
+        (0..header.len()).map(|i| if i == 0 { ":---".to_string() } else { "---:".to_string() }).collect(),
+    ));
+
+    for row in rows {
+        lines.push(row_line(row.iter().map(|c| cell(c)).collect()));
+    }
+    if let Some(total) = total {
+        lines.push(row_line(total.iter().map(|c| format!("**{}**", cell(c))).collect()));
+    }
+
+    lines.join("\n")
+}
+
+/// Render rows as an HTML `<table>`, with an optional totals row in `<tfoot>`
+pub(crate) fn render_html_table(header: &[&str], rows: &[Vec<String>], total: Option<Vec<String>>) -> String {
+    let row_html = |cells: &[String], tag: &str| {
+        let cells: String = cells
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let class = if i == 0 { "" } else { " class=\"num\"" };
+                format!("<{tag}{class}>{}</{tag}>", html_escape(c))
+            })
+            .collect();
+        format!("      <tr>{}</tr>", cells)
+    };
+
+    let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+    let mut lines = vec![
+        "  <table>".to_string(),
+        "    <thead>".to_string(),
+        row_html(&header, "th"),
+        "    </thead>".to_string(),
+        "    <tbody>".to_string(),
+    ];
+    lines.extend(rows.iter().map(|row| row_html(row, "td")));
+    lines.push("    </tbody>".to_string());
+    if let Some(total) = total {
+        lines.push("    <tfoot>".to_string());
+        lines.push(row_html(&total, "th"));
+        lines.push("    </tfoot>".to_string());
+    }
+    lines.push("  </table>".to_string());
+
+    lines.join("\n")
+}
+
+/// Wrap report sections in a standalone HTML page with a little inline styling
+pub(crate) fn html_document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n  <meta charset=\"utf-8\">\n  <title>{title}</title>\n  <style>\n    \
+         body {{ font-family: sans-serif; margin: 2em; }}\n    \
+         table {{ border-collapse: collapse; margin-bottom: 2em; }}\n    \
+         th, td {{ border: 1px solid #ccc; padding: 4px 10px; text-align: left; }}\n    \
+         .num {{ text-align: right; }}\n    \
+         tfoot th {{ background: #f4f4f4; }}\n  \
+         </style>\n</head>\n<body>\n  <h1>{title}</h1>\n{body}\n</body>\n</html>",
+        title = html_escape(title),
+        body = body
+    )
+}
+
+/// Escape text for use in HTML element content or attribute values
+pub(crate) fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,88 @@
+pub(crate) mod format;
+pub mod projects;
+pub mod team;
+pub mod usage;
+
+pub use projects::*;
+pub use team::*;
+pub use usage::*;
+
+use anyhow::Result;
+
+/// The `--format` values every report can be written in
+pub const REPORT_FORMATS: [&str; 5] = ["table", "csv", "json", "markdown", "html"];
+
+/// A report that can be written in each of the `REPORT_FORMATS`
+pub trait RenderReport {
+    fn render_table(&self, use_colors: bool) -> String;
+    fn render_csv(&self) -> String;
+    fn render_json(&self) -> Result<String>;
+    fn render_markdown(&self) -> String;
+    fn render_html(&self) -> String;
+}
+
+/// Render `report` as `format`, one of the `REPORT_FORMATS`
+pub fn render_report(report: &impl RenderReport, format: &str, use_colors: bool) -> Result<String> {
+    Ok(match format {
+        "table" => report.render_table(use_colors),
+        "csv" => report.render_csv(),
+        "json" => report.render_json()?,
+        "markdown" => report.render_markdown(),
+        "html" => report.render_html(),
+        _ => anyhow::bail!("Unknown report format: {} (expected {})", format, REPORT_FORMATS.join(", ")),
+    })
+}
+
+impl RenderReport for UsageReport {
+    fn render_table(&self, use_colors: bool) -> String {
+        UsageReport::render_table(self, use_colors)
+    }
+    fn render_csv(&self) -> String {
+        UsageReport::render_csv(self)
+    }
+    fn render_json(&self) -> Result<String> {
+        UsageReport::render_json(self)
+    }
+    fn render_markdown(&self) -> String {
+        UsageReport::render_markdown(self)
+    }
+    fn render_html(&self) -> String {
+        UsageReport::render_html(self)
+    }
+}
+
+impl RenderReport for ProjectUsageReport {
+    fn render_table(&self, use_colors: bool) -> String {
+        ProjectUsageReport::render_table(self, use_colors)
+    }
+    fn render_csv(&self) -> String {
+        ProjectUsageReport::render_csv(self)
+    }
+    fn render_json(&self) -> Result<String> {
+        ProjectUsageReport::render_json(self)
+    }
+    fn render_markdown(&self) -> String {
+        ProjectUsageReport::render_markdown(self)
+    }
+    fn render_html(&self) -> String {
+        ProjectUsageReport::render_html(self)
+    }
+}
+
+impl RenderReport for TeamUsageReport {
+    fn render_table(&self, use_colors: bool) -> String {
+        TeamUsageReport::render_table(self, use_colors)
+    }
+    fn render_csv(&self) -> String {
+        TeamUsageReport::render_csv(self)
+    }
+    fn render_json(&self) -> Result<String> {
+        TeamUsageReport::render_json(self)
+    }
+    fn render_markdown(&self) -> String {
+        TeamUsageReport::render_markdown(self)
+    }
+    fn render_html(&self) -> String {
+        TeamUsageReport::render_html(self)
+    }
+}
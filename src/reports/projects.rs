@@ -0,0 +1,164 @@
+use super::format::{billed_cost_usd, csv_field, format_dollars, format_with_separators, html_document, render_html_table, render_markdown_table, render_table};
+use super::usage::{fold_usage_for_days, UsageTotals};
+use crate::segments::cost_format;
+use crate::utils::entry_project_name;
+use anyhow::Result;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// Usage totals for a single project directory
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectUsage {
+    pub project: String,
+    pub cost: f64,
+    /// `cost` with `display.costMultiplier` applied (equal to `cost` without one)
+    pub billed_cost_usd: f64,
+    pub tokens: u64,
+    pub message_count: u32,
+}
+
+/// Per-project usage report over a date range, sorted by cost (highest first)
+#[derive(Debug, Clone)]
+pub struct ProjectUsageReport {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub projects: Vec<ProjectUsage>,
+}
+
+#[derive(Serialize)]
+struct ProjectUsageReportJson<'a> {
+    start: NaiveDate,
+    end: NaiveDate,
+    projects: &'a [ProjectUsage],
+    total: UsageTotals,
+    cost_multiplier: f64,
+}
+
+const PROJECT_HEADER: [&str; 4] = ["Project", "Cost", "Tokens", "Messages"];
+
+impl ProjectUsageReport {
+    /// Load a report covering the last `days` calendar days, including today
+    pub async fn load(days: u32) -> Result<Self> {
+        let (folds, start, end) = fold_usage_for_days(days, |entry, _| {
+            Some(entry_project_name(entry).unwrap_or_else(|| "unknown".to_string()))
+        }).await?;
+
+        let projects = folds
+            .into_iter()
+            .map(|(project, fold)| ProjectUsage {
+                cost: fold.cost,
+                billed_cost_usd: billed_cost_usd(fold.cost),
+                tokens: fold.tokens.total_tokens(),
+                message_count: fold.entry_count,
+                project,
+            })
+            .collect();
+
+        Ok(Self::sorted(start, end, projects))
+    }
+
+    /// Build a report with projects ordered by cost, most expensive first
+    fn sorted(start: NaiveDate, end: NaiveDate, mut projects: Vec<ProjectUsage>) -> Self {
+        projects.sort_by(|a, b| {
+            b.cost
+                .total_cmp(&a.cost)
+                .then_with(|| a.project.cmp(&b.project))
+        });
+
+        Self { start, end, projects }
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.projects.iter().map(|p| p.cost).sum()
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.projects.iter().map(|p| p.tokens).sum()
+    }
+
+    pub fn total_messages(&self) -> u32 {
+        self.projects.iter().map(|p| p.message_count).sum()
+    }
+
+    /// Render the report as an aligned plain-text table (header is bolded when `use_colors` is set)
+    pub fn render_table(&self, use_colors: bool) -> String {
+        let table = render_table(&PROJECT_HEADER, &self.project_rows(), Some(self.total_row()), use_colors);
+        format!("{}\n\n{}", self.title(), table)
+    }
+
+    /// Render the report as a Markdown table with a totals row
+    pub fn render_markdown(&self) -> String {
+        format!(
+            "# {}\n\n{}\n",
+            self.title(),
+            render_markdown_table(&PROJECT_HEADER, &self.project_rows(), Some(self.total_row()))
+        )
+    }
+
+    /// Render the report as a standalone HTML page
+    pub fn render_html(&self) -> String {
+        let table = render_html_table(&PROJECT_HEADER, &self.project_rows(), Some(self.total_row()));
+        html_document(&self.title(), &table)
+    }
+
+    fn title(&self) -> String {
+        format!("Usage by project, {} to {}", self.start, self.end)
+    }
+
+    fn project_rows(&self) -> Vec<Vec<String>> {
+        self.projects
+            .iter()
+            .map(|p| vec![
+                p.project.clone(),
+                format_dollars(p.cost),
+                format_with_separators(p.tokens),
+                p.message_count.to_string(),
+            ])
+            .collect()
+    }
+
+    fn total_row(&self) -> Vec<String> {
+        vec![
+            "Total".to_string(),
+            format_dollars(self.total_cost()),
+            format_with_separators(self.total_tokens()),
+            self.total_messages().to_string(),
+        ]
+    }
+
+    /// Render the report as CSV (one row per project, no totals row)
+    pub fn render_csv(&self) -> String {
+        let mut lines = vec!["project,cost_usd,billed_cost_usd,tokens,messages".to_string()];
+
+        for project in &self.projects {
+            lines.push(format!(
+                "{},{:.4},{:.4},{},{}",
+                csv_field(&project.project),
+                project.cost,
+                project.billed_cost_usd,
+                project.tokens,
+                project.message_count
+            ));
+        }
+
+        lines.join("\n")
+    }
+
+    /// Render the report as pretty-printed JSON including the date range and a totals object
+    pub fn render_json(&self) -> Result<String> {
+        let report = ProjectUsageReportJson {
+            start: self.start,
+            end: self.end,
+            projects: &self.projects,
+            total: UsageTotals {
+                cost: self.total_cost(),
+                billed_cost_usd: billed_cost_usd(self.total_cost()),
+                tokens: self.total_tokens(),
+                message_count: self.total_messages(),
+            },
+            cost_multiplier: cost_format().multiplier,
+        };
+
+        Ok(serde_json::to_string_pretty(&report)?)
+    }
+}
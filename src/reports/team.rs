@@ -0,0 +1,194 @@
+use super::format::{billed_cost_usd, csv_field, format_dollars, html_document, render_html_table, render_markdown_table, render_table};
+use super::usage::{fold_usage_for_days_in, report_days, DailyUsage, UsageTotals};
+use crate::config::TeamConfig;
+use crate::segments::cost_format;
+use crate::utils::warn_with_context;
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Serialize;
+
+/// One person's usage per day
+#[derive(Debug, Clone, Serialize)]
+pub struct PersonUsage {
+    pub name: String,
+    /// Every day of the report range, including days without usage
+    pub days: Vec<DailyUsage>,
+}
+
+impl PersonUsage {
+    pub fn total_cost(&self) -> f64 {
+        self.days.iter().map(|d| d.cost).sum()
+    }
+
+    fn totals(&self) -> UsageTotals {
+        UsageTotals {
+            cost: self.total_cost(),
+            billed_cost_usd: billed_cost_usd(self.total_cost()),
+            tokens: self.days.iter().map(|d| d.tokens).sum(),
+            message_count: self.days.iter().map(|d| d.message_count).sum(),
+        }
+    }
+}
+
+/// Daily cost per person across a team, the local user first, over a date range
+#[derive(Debug, Clone)]
+pub struct TeamUsageReport {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub people: Vec<PersonUsage>,
+}
+
+#[derive(Serialize)]
+struct PersonUsageJson<'a> {
+    name: &'a str,
+    days: &'a [DailyUsage],
+    total: UsageTotals,
+}
+
+#[derive(Serialize)]
+struct TeamUsageReportJson<'a> {
+    start: NaiveDate,
+    end: NaiveDate,
+    people: Vec<PersonUsageJson<'a>>,
+    total: UsageTotals,
+    cost_multiplier: f64,
+}
+
+impl TeamUsageReport {
+    /// Load a report covering the last `days` calendar days, including today, for everyone in
+    /// `team`. A person whose Claude data can't be read is warned about and counted as idle.
+    pub async fn load(team: &TeamConfig, days: u32) -> Result<Self> {
+        let end = Utc::now().date_naive();
+        let start = end - Duration::days(report_days(days) as i64 - 1);
+
+        let mut people = Vec::new();
+        for (name, claude_paths) in team.people() {
+            let folds = match fold_usage_for_days_in(claude_paths, days, |_, date| Some(date)).await {
+                Ok((folds, _, _)) => folds,
+                Err(e) => {
+                    warn_with_context("team", &format!("Counting no usage for {}: {:#}", name, e));
+                    Default::default()
+                }
+            };
+
+            let mut days = Vec::new();
+            let mut date = start;
+            while date <= end {
+                let fold = folds.get(&date).cloned().unwrap_or_default();
+                days.push(DailyUsage {
+                    date,
+                    cost: fold.cost,
+                    billed_cost_usd: billed_cost_usd(fold.cost),
+                    tokens: fold.tokens.total_tokens(),
+                    message_count: fold.entry_count,
+                });
+                date += Duration::days(1);
+            }
+            people.push(PersonUsage { name, days });
+        }
+
+        Ok(Self { start, end, people })
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.people.iter().map(PersonUsage::total_cost).sum()
+    }
+
+    /// Render the report as an aligned plain-text table, one column per person (header is
+    /// bolded when `use_colors` is set)
+    pub fn render_table(&self, use_colors: bool) -> String {
+        let table = render_table(&self.header(), &self.day_rows(), Some(self.total_row()), use_colors);
+        format!("{}\n\n{}", self.title(), table)
+    }
+
+    /// Render the report as a Markdown table with a totals row
+    pub fn render_markdown(&self) -> String {
+        format!(
+            "# {}\n\n{}\n",
+            self.title(),
+            render_markdown_table(&self.header(), &self.day_rows(), Some(self.total_row()))
+        )
+    }
+
+    /// Render the report as a standalone HTML page
+    pub fn render_html(&self) -> String {
+        let table = render_html_table(&self.header(), &self.day_rows(), Some(self.total_row()));
+        html_document(&self.title(), &table)
+    }
+
+    fn title(&self) -> String {
+        format!("Team usage, {} to {}", self.start, self.end)
+    }
+
+    fn header(&self) -> Vec<&str> {
+        std::iter::once("Date")
+            .chain(self.people.iter().map(|person| person.name.as_str()))
+            .chain(["Total"])
+            .collect()
+    }
+
+    fn day_rows(&self) -> Vec<Vec<String>> {
+        let dates = self.people.first().map(|person| person.days.as_slice()).unwrap_or_default();
+        dates
+            .iter()
+            .enumerate()
+            .map(|(i, day)| {
+                let costs: Vec<f64> = self.people.iter().map(|person| person.days[i].cost).collect();
+                std::iter::once(day.date.format("%Y-%m-%d").to_string())
+                    .chain(costs.iter().map(|&cost| format_dollars(cost)))
+                    .chain([format_dollars(costs.iter().sum())])
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn total_row(&self) -> Vec<String> {
+        std::iter::once("Total".to_string())
+            .chain(self.people.iter().map(|person| format_dollars(person.total_cost())))
+            .chain([format_dollars(self.total_cost())])
+            .collect()
+    }
+
+    /// Render the report as CSV, one row per person and day (no totals row)
+    pub fn render_csv(&self) -> String {
+        let mut lines = vec!["date,person,cost_usd,billed_cost_usd,tokens,messages".to_string()];
+
+        for person in &self.people {
+            for day in &person.days {
+                lines.push(format!(
+                    "{},{},{:.4},{:.4},{},{}",
+                    day.date.format("%Y-%m-%d"),
+                    csv_field(&person.name),
+                    day.cost,
+                    day.billed_cost_usd,
+                    day.tokens,
+                    day.message_count
+                ));
+            }
+        }
+
+        lines.join("\n")
+    }
+
+    /// Render the report as pretty-printed JSON with per-person and team totals
+    pub fn render_json(&self) -> Result<String> {
+        let people: Vec<PersonUsageJson> = self.people
+            .iter()
+            .map(|person| PersonUsageJson { name: &person.name, days: &person.days, total: person.totals() })
+            .collect();
+        let report = TeamUsageReportJson {
+            start: self.start,
+            end: self.end,
+            total: UsageTotals {
+                cost: self.total_cost(),
+                billed_cost_usd: billed_cost_usd(self.total_cost()),
+                tokens: people.iter().map(|person| person.total.tokens).sum(),
+                message_count: people.iter().map(|person| person.total.message_count).sum(),
+            },
+            people,
+            cost_multiplier: cost_format().multiplier,
+        };
+
+        Ok(serde_json::to_string_pretty(&report)?)
+    }
+}
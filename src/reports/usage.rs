@@ -0,0 +1,280 @@
+use super::format::{billed_cost_usd, format_dollars, format_with_separators, html_document, render_html_table, render_markdown_table, render_table};
+use crate::segments::cost_format;
+use crate::utils::{debug_with_context, DataAggregator, ParsedEntry, UsageFold, UNKNOWN_MODEL};
+use anyhow::Result;
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Usage totals for a single calendar day (UTC, matching the today segment)
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    pub cost: f64,
+    /// `cost` with `display.costMultiplier` applied (equal to `cost` without one)
+    pub billed_cost_usd: f64,
+    pub tokens: u64,
+    pub message_count: u32,
+}
+
+/// Usage totals for a single model over the whole report range
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelUsage {
+    pub model: String,
+    pub cost: f64,
+    /// `cost` with `display.costMultiplier` applied (equal to `cost` without one)
+    pub billed_cost_usd: f64,
+    pub tokens: u64,
+    pub message_count: u32,
+}
+
+/// Per-day usage report built on the same aggregation pipeline as the statusline
+#[derive(Debug, Clone)]
+pub struct UsageReport {
+    pub days: Vec<DailyUsage>,
+    /// Per-model breakdown of the same range, sorted by cost (highest first)
+    pub models: Vec<ModelUsage>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct UsageTotals {
+    pub(crate) cost: f64,
+    /// `cost` with `display.costMultiplier` applied, named as on the rows
+    pub(crate) billed_cost_usd: f64,
+    pub(crate) tokens: u64,
+    pub(crate) message_count: u32,
+}
+
+#[derive(Serialize)]
+struct UsageReportJson<'a> {
+    days: &'a [DailyUsage],
+    models: &'a [ModelUsage],
+    total: UsageTotals,
+    cost_multiplier: f64,
+}
+
+/// Fold usage for the last `days` calendar days (UTC, including today) into the groups chosen
+/// by `group`, which receives each entry along with its date; also returns the date range
+pub(crate) async fn fold_usage_for_days<K, G>(days: u32, group: G) -> Result<(BTreeMap<K, UsageFold>, NaiveDate, NaiveDate)>
+where
+    K: Ord + Clone,
+    G: Fn(&ParsedEntry, NaiveDate) -> Option<K>,
+{
+    fold_usage_for_days_in(None, days, group).await
+}
+
+/// The longest range a report covers; a larger `--days` would overflow the hour filter and the
+/// date arithmetic, and no transcripts go back that far anyway
+pub const MAX_REPORT_DAYS: u32 = 100 * 366;
+
+/// `days` as a report range: at least today, at most `MAX_REPORT_DAYS`
+pub fn report_days(days: u32) -> u32 {
+    days.clamp(1, MAX_REPORT_DAYS)
+}
+
+/// `fold_usage_for_days` over `claude_paths` instead of the local Claude directories, when given
+pub(crate) async fn fold_usage_for_days_in<K, G>(
+    claude_paths: Option<Vec<PathBuf>>,
+    days: u32,
+    group: G,
+) -> Result<(BTreeMap<K, UsageFold>, NaiveDate, NaiveDate)>
+where
+    K: Ord + Clone,
+    G: Fn(&ParsedEntry, NaiveDate) -> Option<K>,
+{
+    let days = report_days(days);
+    let end = Utc::now().date_naive();
+    let start = end - Duration::days(days as i64 - 1);
+
+    debug_with_context("usage", &format!("Loading usage from {} to {}", start, end));
+
+    let mut aggregator = DataAggregator::new().with_time_filter(days * 24);
+    if let Some(paths) = claude_paths {
+        aggregator = aggregator.with_claude_paths(paths);
+    }
+    let folds = aggregator
+        .fold_usage_by(|entry| {
+            let date = entry.timestamp.date_naive();
+            if date >= start && date <= end {
+                group(entry, date)
+            } else {
+                None
+            }
+        })
+        .await?;
+
+    debug_with_context("usage", &format!("Folded usage into {} groups", folds.len()));
+
+    Ok((folds, start, end))
+}
+
+const DAY_HEADER: [&str; 4] = ["Date", "Cost", "Tokens", "Messages"];
+const MODEL_HEADER: [&str; 4] = ["Model", "Cost", "Tokens", "Messages"];
+
+/// Model an entry is billed under; entries without one are grouped as `unknown`
+fn entry_model(entry: &ParsedEntry) -> String {
+    entry.message
+        .as_ref()
+        .and_then(|message| message.model.as_deref())
+        .unwrap_or(UNKNOWN_MODEL)
+        .to_string()
+}
+
+fn sorted_by_cost(mut models: Vec<ModelUsage>) -> Vec<ModelUsage> {
+    models.sort_by(|a, b| b.cost.total_cmp(&a.cost).then_with(|| a.model.cmp(&b.model)));
+    models
+}
+
+impl UsageReport {
+    /// Load a report covering the last `days` calendar days, including today
+    pub async fn load(days: u32) -> Result<Self> {
+        let (folds, start, end) = fold_usage_for_days(days, |entry, date| Some((date, entry_model(entry)))).await?;
+
+        let mut by_day: BTreeMap<NaiveDate, UsageFold> = BTreeMap::new();
+        let mut by_model: BTreeMap<String, UsageFold> = BTreeMap::new();
+        for ((date, model), fold) in &folds {
+            by_day.entry(*date).or_default().merge(fold);
+            by_model.entry(model.clone()).or_default().merge(fold);
+        }
+
+        let mut days = Vec::new();
+        let mut date = start;
+        while date <= end {
+            let fold = by_day.get(&date).cloned().unwrap_or_default();
+            days.push(DailyUsage {
+                date,
+                cost: fold.cost,
+                billed_cost_usd: billed_cost_usd(fold.cost),
+                tokens: fold.tokens.total_tokens(),
+                message_count: fold.entry_count,
+            });
+            date += Duration::days(1);
+        }
+
+        let models = by_model
+            .into_iter()
+            .map(|(model, fold)| ModelUsage {
+                model,
+                cost: fold.cost,
+                billed_cost_usd: billed_cost_usd(fold.cost),
+                tokens: fold.tokens.total_tokens(),
+                message_count: fold.entry_count,
+            })
+            .collect();
+
+        Ok(Self { days, models: sorted_by_cost(models) })
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.days.iter().map(|d| d.cost).sum()
+    }
+
+    pub fn total_tokens(&self) -> u64 {
+        self.days.iter().map(|d| d.tokens).sum()
+    }
+
+    pub fn total_messages(&self) -> u32 {
+        self.days.iter().map(|d| d.message_count).sum()
+    }
+
+    /// Render the report as an aligned plain-text table (header is bolded when `use_colors` is set)
+    pub fn render_table(&self, use_colors: bool) -> String {
+        render_table(&DAY_HEADER, &self.day_rows(), Some(self.total_row()), use_colors)
+    }
+
+    /// Render the report as Markdown (per-day table, per-model table, totals) for pasting into a wiki
+    pub fn render_markdown(&self) -> String {
+        format!(
+            "# {}\n\n## By day\n\n{}\n\n## By model\n\n{}\n",
+            self.title(),
+            render_markdown_table(&DAY_HEADER, &self.day_rows(), Some(self.total_row())),
+            render_markdown_table(&MODEL_HEADER, &self.model_rows(), Some(self.total_row())),
+        )
+    }
+
+    /// Render the report as a standalone HTML page with the same sections as the Markdown report
+    pub fn render_html(&self) -> String {
+        let body = format!(
+            "  <h2>By day</h2>\n{}\n  <h2>By model</h2>\n{}",
+            render_html_table(&DAY_HEADER, &self.day_rows(), Some(self.total_row())),
+            render_html_table(&MODEL_HEADER, &self.model_rows(), Some(self.total_row())),
+        );
+        html_document(&self.title(), &body)
+    }
+
+    fn title(&self) -> String {
+        match (self.days.first(), self.days.last()) {
+            (Some(first), Some(last)) => format!("Claude usage, {} to {}", first.date, last.date),
+            _ => "Claude usage".to_string(),
+        }
+    }
+
+    fn day_rows(&self) -> Vec<Vec<String>> {
+        self.days
+            .iter()
+            .map(|d| vec![
+                d.date.format("%Y-%m-%d").to_string(),
+                format_dollars(d.cost),
+                format_with_separators(d.tokens),
+                d.message_count.to_string(),
+            ])
+            .collect()
+    }
+
+    fn model_rows(&self) -> Vec<Vec<String>> {
+        self.models
+            .iter()
+            .map(|m| vec![
+                m.model.clone(),
+                format_dollars(m.cost),
+                format_with_separators(m.tokens),
+                m.message_count.to_string(),
+            ])
+            .collect()
+    }
+
+    fn total_row(&self) -> Vec<String> {
+        vec![
+            "Total".to_string(),
+            format_dollars(self.total_cost()),
+            format_with_separators(self.total_tokens()),
+            self.total_messages().to_string(),
+        ]
+    }
+
+    /// Render the report as CSV (one row per day, no totals row so spreadsheets can sum columns)
+    pub fn render_csv(&self) -> String {
+        let mut lines = vec!["date,cost_usd,billed_cost_usd,tokens,messages".to_string()];
+
+        for day in &self.days {
+            lines.push(format!(
+                "{},{:.4},{:.4},{},{}",
+                day.date.format("%Y-%m-%d"),
+                day.cost,
+                day.billed_cost_usd,
+                day.tokens,
+                day.message_count
+            ));
+        }
+
+        lines.join("\n")
+    }
+
+    /// Render the report as pretty-printed JSON including a totals object
+    pub fn render_json(&self) -> Result<String> {
+        let report = UsageReportJson {
+            days: &self.days,
+            models: &self.models,
+            total: UsageTotals {
+                cost: self.total_cost(),
+                billed_cost_usd: billed_cost_usd(self.total_cost()),
+                tokens: self.total_tokens(),
+                message_count: self.total_messages(),
+            },
+            cost_multiplier: cost_format().multiplier,
+        };
+
+        Ok(serde_json::to_string_pretty(&report)?)
+    }
+}
@@ -1,24 +1,83 @@
-use crate::segments::Segment;
-use crate::utils::{ParsedEntry, debug_with_context, DataAggregator, PricingService};
+use crate::config::{BlockConfig, BudgetAmount};
+use crate::segments::{budget_usage, displayed_tokens, format_budget_or_cost, format_checked_cost, format_number, format_token_breakdown, Segment, SegmentContext};
+use crate::themes::apply_theme_colors;
+use crate::utils::{ParsedEntry, debug_with_context, HookCostLedger, PricingService, SharedEntries, TokenBreakdown};
 use anyhow::Result;
 use chrono::{DateTime, Duration, Utc, Timelike};
+use std::ops::Range;
 
+/// How far back the burn-rate trend looks for the recent rate
+const BURN_TREND_WINDOW_MINUTES: i64 = 30;
 
-#[derive(Debug, Clone)]
+/// How far the recent rate may stray from the block's overall rate and still count as steady
+const BURN_TREND_TOLERANCE: f64 = 0.1;
+
+/// Whether usage is speeding up or slowing down: the last 30 minutes against the whole block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BurnTrend {
+    Rising,
+    Steady,
+    Falling,
+}
+
+impl BurnTrend {
+    /// Compare a recent rate with the overall one; `None` when there's no overall rate to compare with
+    pub fn from_rates(recent: f64, overall: f64) -> Option<Self> {
+        if overall <= 0.0 {
+            return None;
+        }
+
+        Some(if recent > overall * (1.0 + BURN_TREND_TOLERANCE) {
+            Self::Rising
+        } else if recent < overall * (1.0 - BURN_TREND_TOLERANCE) {
+            Self::Falling
+        } else {
+            Self::Steady
+        })
+    }
+
+    pub fn arrow(self) -> &'static str {
+        match self {
+            Self::Rising => "↑",
+            Self::Steady => "→",
+            Self::Falling => "↓",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct BlockInfo {
     pub cost: Option<f64>,
-    pub tokens: Option<u32>,
-    pub weighted_tokens: Option<u32>,
+    pub tokens: Option<u64>,
+    pub weighted_tokens: Option<u64>,
     pub time_remaining: Option<i64>,
     pub reset_time: Option<DateTime<Utc>>,
     pub burn_rate: Option<f64>,
     pub token_burn_rate: Option<f64>,
+    /// Tokens by kind, behind `tokens`
+    pub breakdown: TokenBreakdown,
+    /// `cost` strays from the transcripts' reported `costUSD` by more than `pricing.costDivergencePercent`
+    pub cost_diverges: bool,
+    /// Burn rate of the last 30 minutes against the block's, with `burnTrend` on
+    pub burn_trend: Option<BurnTrend>,
+    /// Requests with usage data in the block
+    pub message_count: Option<u32>,
 }
 
 pub struct BlockSegment {
     pub enabled: bool,
     pub display_type: String,
     pub burn_type: String,
+    /// `calculated` from transcripts, or `hook` to sum the session costs Claude Code reported
+    pub cost_source: String,
+    /// Usage source to read instead of the configured ones
+    pub source: Option<String>,
+    /// Count cache reads and writes in the token totals shown
+    pub include_cache_tokens: bool,
+    /// Show whether the burn rate (of `burn_type`) is rising or falling
+    pub burn_trend: bool,
+    /// Estimate how many more messages fit in `budget.block` at the block's average size
+    pub messages_left: bool,
 }
 
 impl BlockSegment {
@@ -27,20 +86,45 @@ impl BlockSegment {
             enabled: true,
             display_type: "tokens".to_string(),
             burn_type: "cost".to_string(),
+            cost_source: "calculated".to_string(),
+            source: None,
+            include_cache_tokens: true,
+            burn_trend: false,
+            messages_left: false,
+        }
+    }
+
+    /// The segment with the options `config` sets, the defaults for the rest
+    pub fn from_config(config: Option<&BlockConfig>) -> Self {
+        let mut segment = Self::new();
+        if let Some(c) = config {
+            segment.enabled = c.enabled;
+            segment.display_type = c.display_type.clone().unwrap_or_else(|| "tokens".to_string());
+            segment.burn_type = c.burn_type.clone().unwrap_or_else(|| "cost".to_string());
+            segment.burn_trend = c.burn_trend.unwrap_or(false);
+            segment.messages_left = c.messages_left.unwrap_or(false);
+            segment.cost_source = c.cost_source.clone().unwrap_or_else(|| "calculated".to_string());
+            segment.source = c.source.clone();
+            segment.include_cache_tokens = c.include_cache_tokens.unwrap_or(true);
         }
+        segment
     }
 
     /// Get active block information using global data aggregation
     pub async fn get_active_block_info(&self) -> Result<BlockInfo> {
+        self.get_active_block_info_from(&SharedEntries::new()).await
+    }
+
+    /// Get active block information from entries shared with the other segments
+    pub async fn get_active_block_info_from(&self, shared: &SharedEntries) -> Result<BlockInfo> {
         if !self.enabled {
             return Ok(BlockInfo::default());
         }
 
         debug_with_context("block", "Loading entries for 5-hour session blocks");
 
-        // Use new data aggregation pipeline to get all recent entries
-        let aggregator = DataAggregator::new().with_time_filter(24);
-        let entries = aggregator.load_all_entries().await?;
+        // The shared window covers the last 24 hours, enough to find the active block
+        let entries = shared.all().await?;
 
         if entries.is_empty() {
             debug_with_context("block", "No entries found in recent window");
@@ -54,18 +138,42 @@ impl BlockSegment {
         debug_with_context("block", &format!("Found {} session blocks", blocks.len()));
 
         // Find active block
-        if let Some(active_block) = self.find_active_block(&blocks) {
+        if let Some(active_block) = self.find_active_block(&entries, &blocks) {
             debug_with_context("block", &format!("Found active block with {} entries", active_block.len()));
-            Ok(self.calculate_block_info(&active_block))
+            Ok(self.calculate_block_info(active_block))
         } else {
             debug_with_context("block", "No active block found");
             Ok(BlockInfo::default())
         }
     }
 
+    /// The active block's cost, reset time and cost burn rate from the hook-reported session costs
+    /// in `ledger`; the hook reports no tokens
+    pub fn hook_block_info(&self, ledger: &HookCostLedger) -> BlockInfo {
+        let now = Utc::now();
+        let Some(block) = ledger.active_block(now) else {
+            debug_with_context("block", "No active block in hook-reported costs");
+            return BlockInfo::default();
+        };
+        debug_with_context("block", &format!("Hook-reported cost this block: ${:.2}", block.cost));
+
+        let reset_time = block.start + Duration::hours(5);
+        let active_minutes = (block.last - block.first).num_minutes();
+        let burn_rate = (active_minutes > 0 && block.cost > 0.0)
+            .then(|| block.cost / (active_minutes as f64 / 60.0));
+
+        BlockInfo {
+            cost: (block.cost > 0.0).then_some(block.cost),
+            time_remaining: Some((reset_time - now).num_minutes().max(0)),
+            reset_time: Some(reset_time),
+            burn_rate,
+            ..Default::default()
+        }
+    }
 
-    /// Identify 5-hour session blocks using the original TypeScript algorithm
-    fn identify_session_blocks(&self, entries: &[ParsedEntry]) -> Vec<Vec<ParsedEntry>> {
+    /// Identify 5-hour session blocks using the original TypeScript algorithm.
+    /// Blocks are returned as index ranges into `entries` so no entry is copied.
+    fn identify_session_blocks(&self, entries: &[ParsedEntry]) -> Vec<Range<usize>> {
         if entries.is_empty() {
             return Vec::new();
         }
@@ -73,61 +181,40 @@ impl BlockSegment {
         // Entries should already be sorted by timestamp from data aggregation
         let session_duration_ms = 5 * 60 * 60 * 1000; // 5 hours in milliseconds
         let mut blocks = Vec::new();
-        let mut current_block_entries = Vec::new();
-        let mut current_block_start: Option<DateTime<Utc>> = None;
+        let mut current_block_first = 0;
+        let mut current_block_start = self.floor_to_hour(entries[0].timestamp);
 
-        for entry in entries {
+        for (index, pair) in entries.windows(2).enumerate() {
+            let (last, entry) = (&pair[0], &pair[1]);
             let entry_time = entry.timestamp;
 
-            match current_block_start {
-                None => {
-                    // Start first block - floor to the hour
-                    current_block_start = Some(self.floor_to_hour(entry_time));
-                    current_block_entries.push(entry.clone());
-                }
-                Some(block_start) => {
-                    let time_since_block_start = entry_time.signed_duration_since(block_start).num_milliseconds();
-                    
-                    let time_since_last_entry = if let Some(last) = current_block_entries.last() {
-                        entry_time.signed_duration_since(last.timestamp).num_milliseconds()
-                    } else {
-                        0
-                    };
-
-                    // Check if we need to start a new block
-                    // New block starts if: time since block start > 5 hours OR time since last entry > 5 hours
-                    if time_since_block_start > session_duration_ms || time_since_last_entry > session_duration_ms {
-                        // Finalize current block
-                        if !current_block_entries.is_empty() {
-                            blocks.push(current_block_entries.clone());
-                        }
-
-                        // Start new block
-                        current_block_start = Some(self.floor_to_hour(entry_time));
-                        current_block_entries = vec![entry.clone()];
-                    } else {
-                        // Add to current block
-                        current_block_entries.push(entry.clone());
-                    }
-                }
+            let time_since_block_start = entry_time.signed_duration_since(current_block_start).num_milliseconds();
+            let time_since_last_entry = entry_time.signed_duration_since(last.timestamp).num_milliseconds();
+
+            // Check if we need to start a new block
+            // New block starts if: time since block start > 5 hours OR time since last entry > 5 hours
+            if time_since_block_start > session_duration_ms || time_since_last_entry > session_duration_ms {
+                // Finalize current block and start a new one at this entry
+                blocks.push(current_block_first..index + 1);
+                current_block_first = index + 1;
+                current_block_start = self.floor_to_hour(entry_time);
             }
         }
 
         // Don't forget the last block
-        if !current_block_entries.is_empty() {
-            blocks.push(current_block_entries);
-        }
+        blocks.push(current_block_first..entries.len());
 
         blocks
     }
 
     /// Find the currently active block using original algorithm
-    fn find_active_block<'a>(&self, blocks: &'a [Vec<ParsedEntry>]) -> Option<&'a Vec<ParsedEntry>> {
+    fn find_active_block<'a>(&self, entries: &'a [ParsedEntry], blocks: &[Range<usize>]) -> Option<&'a [ParsedEntry]> {
         let now = Utc::now();
         let session_duration_ms = 5 * 60 * 60 * 1000; // 5 hours in milliseconds
 
         // Check blocks in reverse order (most recent first)
-        for block in blocks.iter().rev() {
+        for range in blocks.iter().rev() {
+            let block = &entries[range.clone()];
             if let Some(first_entry) = block.first() {
                 let block_start = self.floor_to_hour(first_entry.timestamp);
                 let block_end_time = block_start + Duration::hours(5);
@@ -167,6 +254,10 @@ impl BlockSegment {
         let token_breakdown = pricing_service.calculate_token_breakdown(entries);
         let total_tokens = token_breakdown.total_tokens();
         
+        let message_count = entries.iter()
+            .filter(|entry| entry.message.as_ref().is_some_and(|message| message.usage.is_some()))
+            .count() as u32;
+
         // Calculate weighted tokens (applies 5x multiplier for Opus models)
         let weighted_tokens = pricing_service.calculate_weighted_tokens(entries);
 
@@ -220,9 +311,41 @@ impl BlockSegment {
             reset_time,
             burn_rate,
             token_burn_rate,
+            breakdown: token_breakdown,
+            cost_diverges: pricing_service.cost_diverges("block", pricing_service.check_reported_cost(entries)),
+            burn_trend: self.burn_trend
+                .then(|| self.calculate_burn_trend(entries, &pricing_service, total_cost, total_tokens))
+                .flatten(),
+            message_count: Some(message_count).filter(|&count| count > 0),
         }
     }
 
+    /// Rate over the last 30 minutes against the rate since the block's first entry, in cost or
+    /// tokens per `burn_type`. Blocks younger than the window have nothing to compare yet.
+    fn calculate_burn_trend(&self, entries: &[ParsedEntry], pricing_service: &PricingService, total_cost: f64, total_tokens: u64) -> Option<BurnTrend> {
+        let now = Utc::now();
+        let window = Duration::minutes(BURN_TREND_WINDOW_MINUTES);
+        let block_age = now - entries.first()?.timestamp;
+        if block_age <= window {
+            return None;
+        }
+
+        // Deltas are cumulative per session, so the recent part is the total minus what came before
+        let split = entries.partition_point(|entry| entry.timestamp < now - window);
+        let earlier = &entries[..split];
+        let (total, recent) = if self.burn_type == "tokens" {
+            let earlier_tokens = pricing_service.calculate_token_breakdown(earlier).total_tokens();
+            (total_tokens as f64, total_tokens.saturating_sub(earlier_tokens) as f64)
+        } else {
+            let earlier_cost = pricing_service.calculate_total_cost(earlier).unwrap_or(0.0);
+            (total_cost, (total_cost - earlier_cost).max(0.0))
+        };
+
+        let overall_rate = total / (block_age.num_seconds() as f64 / 3600.0);
+        let recent_rate = recent / (window.num_seconds() as f64 / 3600.0);
+        BurnTrend::from_rates(recent_rate, overall_rate)
+    }
+
     /// Floor timestamp to the nearest hour
     fn floor_to_hour(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
         timestamp.with_minute(0).unwrap().with_second(0).unwrap().with_nanosecond(0).unwrap()
@@ -230,25 +353,27 @@ impl BlockSegment {
 
 }
 
-impl Default for BlockInfo {
+/// How many more messages of the block's average size (in the budget's unit) fit in `budget`;
+/// `None` before the block has any messages
+pub fn estimate_messages_left(budget: &BudgetAmount, block_info: &BlockInfo) -> Option<u32> {
+    let messages = block_info.message_count.filter(|&count| count > 0)?;
+    let used = budget_usage(budget, block_info.cost, block_info.tokens);
+    if used <= 0.0 {
+        return None;
+    }
+
+    let per_message = used / messages as f64;
+    Some(((budget.amount - used).max(0.0) / per_message).floor() as u32)
+}
+
+impl Default for BlockSegment {
     fn default() -> Self {
-        Self {
-            cost: None,
-            tokens: None,
-            weighted_tokens: None,
-            time_remaining: None,
-            reset_time: None,
-            burn_rate: None,
-            token_burn_rate: None,
-        }
+        Self::new()
     }
 }
 
 impl Segment for BlockSegment {
-    fn render(&self) -> Result<String> {
-        // This will be implemented as part of the display logic
-        Ok("◱ Block".to_string())
-    }
+    type Data = BlockInfo;
 
     fn name(&self) -> &'static str {
         "block"
@@ -257,4 +382,80 @@ impl Segment for BlockSegment {
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    async fn data(&self, ctx: &SegmentContext) -> Result<BlockInfo> {
+        if self.cost_source == "hook" {
+            if let Some(ledger) = &ctx.hook_costs {
+                return Ok(self.hook_block_info(ledger));
+            }
+        }
+        self.get_active_block_info_from(&ctx.entries_for(self.source.as_deref())).await
+    }
+
+    fn render(&self, block_info: BlockInfo, ctx: &SegmentContext) -> Result<String> {
+        if block_info.tokens.is_none() && block_info.cost.is_none() {
+            return Ok(String::new());
+        }
+
+        let mut parts = vec!["🎪".to_string()];
+
+        match self.display_type.as_str() {
+            "cost" => {
+                if let Some(cost) = block_info.cost {
+                    parts.push(format_checked_cost(cost, block_info.cost_diverges));
+                }
+            }
+            "tokens" => {
+                if let Some(tokens) = displayed_tokens(block_info.tokens, &block_info.breakdown, self.include_cache_tokens) {
+                    parts.push(format!("{}T", format_number(tokens)));
+                }
+            }
+            "breakdown" => parts.push(format_token_breakdown(&block_info.breakdown, self.include_cache_tokens)),
+            "percentage" => {
+                let budget = ctx.config.budget.as_ref().and_then(|budget| budget.block.as_ref());
+                parts.extend(format_budget_or_cost(budget, block_info.cost, block_info.tokens, block_info.cost_diverges));
+            }
+            // Opus-heavy usage weighs far more against the limits than its raw count suggests
+            "dual" => {
+                let tokens = displayed_tokens(block_info.tokens, &block_info.breakdown, self.include_cache_tokens);
+                match (tokens, block_info.weighted_tokens) {
+                    (Some(tokens), Some(weighted)) => {
+                        parts.push(format!("{} (w {})", format_number(tokens), format_number(weighted)))
+                    }
+                    (Some(tokens), None) => parts.push(format!("{}T", format_number(tokens))),
+                    (None, Some(weighted)) => parts.push(format!("w {}", format_number(weighted))),
+                    (None, None) => {}
+                }
+            }
+            "weighted" => {
+                if let Some(weighted) = block_info.weighted_tokens {
+                    parts.push(format!("{}T", format_number(weighted)));
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(trend) = block_info.burn_trend {
+            parts.push(trend.arrow().to_string());
+        }
+
+        if self.messages_left {
+            let budget = ctx.config.budget.as_ref().and_then(|budget| budget.block.as_ref());
+            if let Some(left) = budget.and_then(|budget| estimate_messages_left(budget, &block_info)) {
+                parts.push(format!("~{} msgs left", left));
+            }
+        }
+
+        // Show reset time instead of minutes remaining
+        if let Some(reset_time) = block_info.reset_time {
+            let now = chrono::Local::now();
+            let local_reset_time = reset_time.with_timezone(&chrono::Local);
+            parts.push(format!("Reset@:{}->{}",
+                              now.format("%H:%M"),
+                              local_reset_time.format("%H:%M")));
+        }
+
+        let formatted = format!(" {} ", parts.join(" "));
+        Ok(apply_theme_colors(&formatted, self.name(), &ctx.theme))
+    }
 }
\ No newline at end of file
@@ -1,9 +1,14 @@
-use crate::segments::Segment;
-use crate::utils::debug_with_context;
-use crate::utils::claude::{parse_jsonl_content, ParsedEntry};
+use crate::segments::{format_number, Segment, SegmentContext};
+use crate::themes::{apply_critical_colors, apply_theme_colors};
+use crate::utils::{debug_with_context, find_last_line, record_parse};
+use crate::utils::claude::{parse_jsonl_bytes, ParsedEntry};
 use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
 use tokio::fs;
 
+/// How far back the context's growth is measured for the time-to-compaction estimate
+const COMPACT_ETA_WINDOW_MINUTES: i64 = 30;
+
 #[derive(Debug, Clone)]
 pub struct ContextInfo {
     pub input_tokens: u32,
@@ -11,11 +16,19 @@ pub struct ContextInfo {
     pub usable_percentage: u32,
     pub max_tokens: u32,
     pub usable_tokens: u32,
+    /// Minutes until the context reaches the compaction point at its recent growth rate, with `compactEta` on
+    pub minutes_to_compact: Option<u32>,
 }
 
 pub struct ContextSegment {
     pub enabled: bool,
     pub show_percentage_only: bool,
+    /// Context left (in percent) below which the segment is drawn in its critical style
+    pub critical_percentage: Option<u32>,
+    /// Prefix `⚠` while critical
+    pub critical_icon: bool,
+    /// Estimate when auto-compaction kicks in from how fast the context grew lately
+    pub compact_eta: bool,
 }
 
 impl ContextSegment {
@@ -23,6 +36,9 @@ impl ContextSegment {
         Self {
             enabled: true,
             show_percentage_only: false,
+            critical_percentage: None,
+            critical_icon: false,
+            compact_eta: false,
         }
     }
 
@@ -54,7 +70,7 @@ impl ContextSegment {
     }
 
     /// Find the current session transcript file
-    async fn find_current_session_transcript(&self) -> Result<Option<std::path::PathBuf>> {
+    pub(crate) async fn find_current_session_transcript(&self) -> Result<Option<std::path::PathBuf>> {
         // Try to find recent transcript files in Claude projects
         let claude_paths = crate::utils::claude::get_claude_paths()?;
         let project_paths = crate::utils::claude::find_project_paths(&claude_paths).await?;
@@ -83,57 +99,99 @@ impl ContextSegment {
         Ok(most_recent_file)
     }
     
-    /// Calculate context info from transcript file (replicates TypeScript logic)
+    /// Calculate context info from transcript file (replicates TypeScript logic).
+    /// The transcript is read backwards and parsing stops at the first usable entry, or with
+    /// `compact_eta` on, once the entries reach back past the growth window or a compaction.
     async fn calculate_context_from_transcript(&self, transcript_path: &std::path::Path) -> Result<ContextInfo> {
-        // Read and parse the transcript file
-        let content = fs::read_to_string(transcript_path).await?;
-        let entries = parse_jsonl_content(&content)?;
-        
-        // Find most recent non-sidechain entry with usage data (reverse iteration like TypeScript)
-        for entry in entries.iter().rev() {
-            // Skip sidechain entries
-            if entry.is_sidechain == Some(true) {
-                continue;
-            }
-            
-            if let Some(message) = &entry.message {
-                if let Some(usage) = &message.usage {
-                    // Calculate total context length (input + cache tokens)
-                    let context_length = usage.input_tokens.unwrap_or(0)
-                        + usage.cache_read_input_tokens.unwrap_or(0)
-                        + usage.cache_creation_input_tokens.unwrap_or(0);
-                    
-                    if context_length == 0 {
+        let path = transcript_path.to_path_buf();
+        let compact_eta = self.compact_eta;
+        let (samples, lines_parsed) = tokio::task::spawn_blocking(move || {
+            // Context lengths with their timestamps, newest first
+            let mut samples: Vec<(DateTime<Utc>, u32)> = Vec::new();
+            let (_, lines_parsed) = find_last_line(&path, |line| {
+                for sample in parse_jsonl_bytes(line).iter().filter_map(Self::entry_context_sample) {
+                    let (Some(newest), Some(oldest)) = (samples.first(), samples.last()) else {
+                        samples.push(sample);
+                        if !compact_eta {
+                            return Some(());
+                        }
                         continue;
+                    };
+                    // A longer context further back means it was compacted in between
+                    if sample.1 > oldest.1 || newest.0 - sample.0 > Duration::minutes(COMPACT_ETA_WINDOW_MINUTES) {
+                        return Some(());
                     }
-                    
-                    // Constants matching TypeScript version
-                    const CONTEXT_LIMIT: u32 = 200_000;  // 200K context limit
-                    const USABLE_LIMIT: u32 = 154_000;   // 77% of total (200K * 0.77)
-                    
-                    // Calculate percentages
-                    let percentage = ((context_length as f64 / CONTEXT_LIMIT as f64) * 100.0)
-                        .round().min(100.0) as u32;
-                    
-                    let usable_percentage = ((context_length as f64 / USABLE_LIMIT as f64) * 100.0)
-                        .round().min(100.0) as u32;
-                    
-                    // Context left percentage (the key metric!)
-                    let context_left_percentage = 100u32.saturating_sub(usable_percentage);
-                    
-                    return Ok(ContextInfo {
-                        input_tokens: context_length,
-                        context_left_percentage,
-                        usable_percentage,
-                        max_tokens: CONTEXT_LIMIT,
-                        usable_tokens: USABLE_LIMIT,
-                    });
+                    samples.push(sample);
                 }
+                None
+            })?;
+            anyhow::Ok((samples, lines_parsed))
+        }).await??;
+        record_parse(1, lines_parsed);
+
+        match samples.first().map(|&(_, context_length)| context_length) {
+            Some(context_length) => {
+                // Constants matching TypeScript version
+                const CONTEXT_LIMIT: u32 = 200_000;  // 200K context limit
+                const USABLE_LIMIT: u32 = 154_000;   // 77% of total (200K * 0.77)
+
+                // Calculate percentages
+                let usable_percentage = ((context_length as f64 / USABLE_LIMIT as f64) * 100.0)
+                    .round().min(100.0) as u32;
+
+                // Context left percentage (the key metric!)
+                let context_left_percentage = 100u32.saturating_sub(usable_percentage);
+
+                Ok(ContextInfo {
+                    input_tokens: context_length,
+                    context_left_percentage,
+                    usable_percentage,
+                    max_tokens: CONTEXT_LIMIT,
+                    usable_tokens: USABLE_LIMIT,
+                    minutes_to_compact: estimate_minutes_to_compact(&samples, USABLE_LIMIT),
+                })
             }
+            // No valid entries found, return default
+            None => Ok(ContextInfo::default()),
         }
-        
-        // No valid entries found, return default
-        Ok(ContextInfo::default())
+    }
+
+    /// Timestamp and context length (input + cache tokens) of a main-chain entry with usage data
+    fn entry_context_sample(entry: &ParsedEntry) -> Option<(DateTime<Utc>, u32)> {
+        // Skip sidechain entries
+        if entry.is_sidechain == Some(true) {
+            return None;
+        }
+
+        let usage = entry.message.as_ref()?.usage.as_ref()?;
+        let context_length = usage.input_tokens.unwrap_or(0)
+            + usage.cache_read_input_tokens.unwrap_or(0)
+            + usage.cache_creation_input_tokens.unwrap_or(0);
+
+        (context_length > 0).then_some((entry.timestamp, context_length))
+    }
+}
+
+/// Minutes until the context reaches `usable_tokens` if it keeps growing as it did over `samples`
+/// (newest first); `None` when it hasn't grown over at least a minute
+pub fn estimate_minutes_to_compact(samples: &[(DateTime<Utc>, u32)], usable_tokens: u32) -> Option<u32> {
+    let (&(newest_time, newest), &(oldest_time, oldest)) = (samples.first()?, samples.last()?);
+    let elapsed_minutes = (newest_time - oldest_time).num_seconds() as f64 / 60.0;
+    let growth = newest.saturating_sub(oldest);
+    if elapsed_minutes < 1.0 || growth == 0 {
+        return None;
+    }
+
+    let remaining = usable_tokens.saturating_sub(newest);
+    Some((remaining as f64 / (growth as f64 / elapsed_minutes)).round() as u32)
+}
+
+/// `compact in ~25m`, or `compact in ~1h40m` past an hour
+pub fn format_compact_eta(minutes: u32) -> String {
+    if minutes < 60 {
+        format!("compact in ~{}m", minutes)
+    } else {
+        format!("compact in ~{}h{:02}m", minutes / 60, minutes % 60)
     }
 }
 
@@ -145,15 +203,19 @@ impl Default for ContextInfo {
             usable_percentage: 0,
             max_tokens: 200000,
             usable_tokens: 154000,
+            minutes_to_compact: None,
         }
     }
 }
 
-impl Segment for ContextSegment {
-    fn render(&self) -> Result<String> {
-        // This will be implemented as part of the display logic
-        Ok("◔ Context".to_string())
+impl Default for ContextSegment {
+    fn default() -> Self {
+        Self::new()
     }
+}
+
+impl Segment for ContextSegment {
+    type Data = ContextInfo;
 
     fn name(&self) -> &'static str {
         "context"
@@ -162,4 +224,34 @@ impl Segment for ContextSegment {
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    async fn data(&self, _ctx: &SegmentContext) -> Result<ContextInfo> {
+        self.get_context_info().await
+    }
+
+    fn render(&self, context_info: ContextInfo, ctx: &SegmentContext) -> Result<String> {
+        // Always show context info (even default values are useful)
+        // Default shows "◔ 0 (100%)" indicating 100% context remaining
+        let critical = self.critical_percentage.is_some_and(|critical| context_info.context_left_percentage < critical);
+        let mut parts = vec!["🧠".to_string()];
+        if critical && self.critical_icon {
+            parts.insert(0, "⚠".to_string());
+        }
+
+        if self.show_percentage_only {
+            parts.push(format!("{}%", context_info.context_left_percentage));
+        } else {
+            parts.push(format_number(context_info.input_tokens as u64));
+            parts.push(format!("({}%)", context_info.context_left_percentage));
+        }
+        if let Some(minutes) = context_info.minutes_to_compact.filter(|_| context_info.context_left_percentage > 0) {
+            parts.push(format_compact_eta(minutes));
+        }
+
+        let formatted = format!(" {} ", parts.join(" "));
+        if critical {
+            return Ok(apply_critical_colors(&formatted, self.name(), &ctx.theme));
+        }
+        Ok(apply_theme_colors(&formatted, self.name(), &ctx.theme))
+    }
 }
\ No newline at end of file
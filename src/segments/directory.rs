@@ -0,0 +1,55 @@
+use crate::segments::{Segment, SegmentContext};
+use crate::themes::apply_theme_colors;
+use anyhow::Result;
+use std::env;
+use std::path::PathBuf;
+
+pub struct DirectorySegment {
+    pub enabled: bool,
+    pub show_basename: bool,
+}
+
+impl DirectorySegment {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            show_basename: false,
+        }
+    }
+}
+
+impl Default for DirectorySegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Segment for DirectorySegment {
+    type Data = PathBuf;
+
+    fn name(&self) -> &'static str {
+        "directory"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    async fn data(&self, _ctx: &SegmentContext) -> Result<PathBuf> {
+        Ok(env::current_dir()?)
+    }
+
+    fn render(&self, current_dir: PathBuf, ctx: &SegmentContext) -> Result<String> {
+        let dir_name = if self.show_basename {
+            current_dir.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("?")
+                .to_string()
+        } else {
+            current_dir.to_string_lossy().to_string()
+        };
+
+        let formatted = format!(" {} ", dir_name);
+        Ok(apply_theme_colors(&formatted, self.name(), &ctx.theme))
+    }
+}
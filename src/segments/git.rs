@@ -1,14 +1,14 @@
-use crate::segments::Segment;
+use crate::segments::{Segment, SegmentContext};
+use crate::themes::apply_theme_colors;
 use crate::utils::{debug_with_context, Cache};
 use anyhow::{Context, Result};
-use gix::{Repository, ThreadSafeRepository};
+use gix::Repository;
 use std::env;
 use std::path::Path;
-use std::sync::Arc;
 use std::time::Duration;
 use tokio::process::Command;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct GitInfo {
     pub branch: Option<String>,
     pub sha: Option<String>,
@@ -44,6 +44,12 @@ impl GitSegment {
         }
     }
 
+    /// Keep git info for `ttl` instead of the default 5 seconds
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache = Cache::new(ttl);
+        self
+    }
+
     /// Get git information for current directory with optimized performance
     pub async fn get_git_info(&self) -> Result<GitInfo> {
         if !self.enabled {
@@ -86,11 +92,9 @@ impl GitSegment {
         let mut info = GitInfo::default();
 
         // Get current branch
-        if let Ok(head_ref) = repo.head_ref() {
-            if let Some(reference) = head_ref {
-                let name = reference.name().shorten();
-                info.branch = Some(name.to_string());
-            }
+        if let Ok(Some(reference)) = repo.head_ref() {
+            let name = reference.name().shorten();
+            info.branch = Some(name.to_string());
         }
 
         // Get current commit SHA
@@ -118,17 +122,20 @@ impl GitSegment {
             info.is_dirty = false;
         } else {
             // Quick dirty check without full status
-            info.is_dirty = self.quick_dirty_check(&repo).await.unwrap_or(false);
+            info.is_dirty = self.quick_dirty_check(&repo).unwrap_or(false);
         }
 
         // Get ahead/behind information (if requested)
         if self.show_upstream {
-            info.ahead_behind = self.get_ahead_behind(&repo).await.ok();
+            info.ahead_behind = self.get_ahead_behind(&repo).ok();
         }
 
+        // `Repository` isn't Sync, so release it before awaiting to keep this future Send
+        drop(repo);
+
         // Get stash count (if requested)
         if self.show_stash_count {
-            info.stash_count = self.get_stash_count(&repo).await.ok();
+            info.stash_count = self.get_stash_count().await.ok();
         }
 
         debug_with_context("git", &format!(
@@ -140,7 +147,7 @@ impl GitSegment {
     }
 
     /// Quick dirty check without full status scan
-    async fn quick_dirty_check(&self, _repo: &Repository) -> Result<bool> {
+    fn quick_dirty_check(&self, _repo: &Repository) -> Result<bool> {
         // Quick dirty check without full status scan
         // This is a simplified implementation for performance
         // In practice, you'd check index vs HEAD
@@ -148,17 +155,17 @@ impl GitSegment {
     }
 
     /// Get ahead/behind count compared to upstream
-    async fn get_ahead_behind(&self, _repo: &Repository) -> Result<(u32, u32)> {
+    fn get_ahead_behind(&self, _repo: &Repository) -> Result<(u32, u32)> {
         // This is a simplified implementation
         // In practice, you'd need to compare local branch with its upstream
         Ok((0, 0))
     }
 
     /// Get stash count
-    async fn get_stash_count(&self, _repo: &Repository) -> Result<u32> {
+    async fn get_stash_count(&self) -> Result<u32> {
         // gix doesn't have direct stash support yet, so we fallback to git command
         match Command::new("git")
-            .args(&["stash", "list", "--porcelain"])
+            .args(["stash", "list", "--porcelain"])
             .output()
             .await
         {
@@ -177,27 +184,14 @@ impl GitSegment {
     }
 }
 
-impl Default for GitInfo {
+impl Default for GitSegment {
     fn default() -> Self {
-        Self {
-            branch: None,
-            sha: None,
-            is_dirty: false,
-            ahead_behind: None,
-            staged_count: 0,
-            unstaged_count: 0,
-            untracked_count: 0,
-            stash_count: None,
-            repo_name: None,
-        }
+        Self::new()
     }
 }
 
 impl Segment for GitSegment {
-    fn render(&self) -> Result<String> {
-        // This will be implemented as part of the display logic
-        Ok("⎇ Git".to_string())
-    }
+    type Data = GitInfo;
 
     fn name(&self) -> &'static str {
         "git"
@@ -206,4 +200,31 @@ impl Segment for GitSegment {
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    async fn data(&self, _ctx: &SegmentContext) -> Result<GitInfo> {
+        self.get_git_info().await
+    }
+
+    fn render(&self, git_info: GitInfo, ctx: &SegmentContext) -> Result<String> {
+        let Some(branch) = git_info.branch else {
+            return Ok(String::new());
+        };
+
+        let mut parts = vec!["⎇".to_string(), branch];
+
+        if self.show_sha {
+            if let Some(sha) = git_info.sha {
+                parts.push(format!("♯{}", sha));
+            }
+        }
+
+        if git_info.is_dirty {
+            parts.push("●".to_string());
+        } else {
+            parts.push("✓".to_string());
+        }
+
+        let formatted = format!(" {} ", parts.join(" "));
+        Ok(apply_theme_colors(&formatted, self.name(), &ctx.theme))
+    }
 }
\ No newline at end of file
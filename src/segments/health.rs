@@ -0,0 +1,157 @@
+use crate::segments::{ContextSegment, Segment, SegmentContext};
+use crate::themes::apply_theme_colors;
+use crate::utils::debug_with_context;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Anomalies found in the current session's transcript, which explain figures that look off
+/// (a session cost of zero, tokens that stop adding up)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TranscriptHealth {
+    pub transcript: Option<PathBuf>,
+    /// The file doesn't end in a newline and its last line isn't valid JSON: a write was cut short
+    pub truncated: bool,
+    /// Other lines that aren't valid JSON
+    pub invalid_lines: usize,
+    /// Entries timestamped before an entry above them
+    pub out_of_order: usize,
+    /// Assistant messages without a `usage` block, which count for nothing
+    pub missing_usage: usize,
+}
+
+impl TranscriptHealth {
+    /// Check the transcript `content`, one JSON entry per line
+    pub fn check(content: &[u8]) -> Self {
+        let mut health = Self::default();
+        let text = String::from_utf8_lossy(content);
+        let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+        let mut latest: Option<DateTime<Utc>> = None;
+
+        for (index, line) in lines.iter().enumerate() {
+            let Ok(entry) = serde_json::from_str::<Value>(line) else {
+                if index + 1 == lines.len() && !content.ends_with(b"\n") {
+                    health.truncated = true;
+                } else {
+                    health.invalid_lines += 1;
+                }
+                continue;
+            };
+
+            let timestamp = entry.get("timestamp")
+                .and_then(Value::as_str)
+                .and_then(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+                .map(|timestamp| timestamp.with_timezone(&Utc));
+            if let Some(timestamp) = timestamp {
+                if latest.is_some_and(|latest| timestamp < latest) {
+                    health.out_of_order += 1;
+                }
+                latest = latest.max(Some(timestamp));
+            }
+
+            let is_assistant = entry.get("type").and_then(Value::as_str) == Some("assistant");
+            if is_assistant && entry.pointer("/message/usage").is_none_or(Value::is_null) {
+                health.missing_usage += 1;
+            }
+        }
+
+        health
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.issues().is_empty()
+    }
+
+    /// A short description of every anomaly found
+    pub fn issues(&self) -> Vec<String> {
+        let mut issues = Vec::new();
+        if self.truncated {
+            issues.push("truncated".to_string());
+        }
+        if self.invalid_lines > 0 {
+            let plural = if self.invalid_lines == 1 { "" } else { "s" };
+            issues.push(format!("{} bad line{}", self.invalid_lines, plural));
+        }
+        if self.out_of_order > 0 {
+            issues.push(format!("{} out of order", self.out_of_order));
+        }
+        if self.missing_usage > 0 {
+            issues.push(format!("{} without usage", self.missing_usage));
+        }
+        issues
+    }
+}
+
+pub struct HealthSegment {
+    pub enabled: bool,
+    /// Show `🩺 ok` for a healthy transcript instead of hiding the segment
+    pub show_healthy: bool,
+}
+
+impl HealthSegment {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            show_healthy: false,
+        }
+    }
+
+    /// Check `transcript`, read whole since anomalies can sit anywhere in it
+    pub async fn check_transcript(&self, transcript: &Path) -> Result<TranscriptHealth> {
+        let content = tokio::fs::read(transcript).await?;
+        let mut health = TranscriptHealth::check(&content);
+        health.transcript = Some(transcript.to_path_buf());
+
+        if !health.is_healthy() {
+            debug_with_context("health", &format!(
+                "{}: {}",
+                transcript.display(),
+                health.issues().join(", ")
+            ));
+        }
+        Ok(health)
+    }
+}
+
+impl Default for HealthSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Segment for HealthSegment {
+    type Data = TranscriptHealth;
+
+    fn name(&self) -> &'static str {
+        "health"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    async fn data(&self, ctx: &SegmentContext) -> Result<TranscriptHealth> {
+        // The hook names the session's transcript; without it, the most recently written one stands in
+        let transcript = match &ctx.hook {
+            Some(hook) => Some(PathBuf::from(&hook.transcript_path)),
+            None => ContextSegment::new().find_current_session_transcript().await?,
+        };
+
+        match transcript {
+            Some(transcript) => self.check_transcript(&transcript).await,
+            None => Ok(TranscriptHealth::default()),
+        }
+    }
+
+    fn render(&self, health: TranscriptHealth, ctx: &SegmentContext) -> Result<String> {
+        let issues = health.issues();
+        if issues.is_empty() && !(self.show_healthy && health.transcript.is_some()) {
+            return Ok(String::new());
+        }
+
+        let summary = if issues.is_empty() { "ok".to_string() } else { issues.join(" · ") };
+        let formatted = format!(" 🩺 {} ", summary);
+        Ok(apply_theme_colors(&formatted, self.name(), &ctx.theme))
+    }
+}
@@ -1,9 +1,10 @@
-use crate::segments::Segment;
-use crate::utils::{get_transcript_parser, debug_with_context};
+use crate::segments::{Segment, SegmentContext};
+use crate::themes::apply_theme_colors;
+use crate::utils::{debug_with_context, SharedEntries};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct MetricsInfo {
     pub avg_response_time: Option<f64>,
     pub last_response_time: Option<f64>,
@@ -38,6 +39,11 @@ impl MetricsSegment {
 
     /// Get performance metrics information
     pub async fn get_metrics_info(&self) -> Result<MetricsInfo> {
+        self.get_metrics_info_from(&SharedEntries::new()).await
+    }
+
+    /// Get performance metrics from entries shared with the other segments
+    pub async fn get_metrics_info_from(&self, shared: &SharedEntries) -> Result<MetricsInfo> {
         if !self.enabled {
             return Ok(MetricsInfo::default());
         }
@@ -45,8 +51,7 @@ impl MetricsSegment {
         debug_with_context("metrics", "Loading performance metrics");
 
         // Get recent entries for analysis
-        let parser = get_transcript_parser();
-        let entries = parser.get_recent_entries(24).await?;
+        let entries = shared.all().await?;
 
         if entries.is_empty() {
             debug_with_context("metrics", "No entries found for metrics");
@@ -149,24 +154,14 @@ impl MetricsSegment {
     }
 }
 
-impl Default for MetricsInfo {
+impl Default for MetricsSegment {
     fn default() -> Self {
-        Self {
-            avg_response_time: None,
-            last_response_time: None,
-            session_duration: None,
-            message_count: None,
-            lines_added: None,
-            lines_removed: None,
-        }
+        Self::new()
     }
 }
 
 impl Segment for MetricsSegment {
-    fn render(&self) -> Result<String> {
-        // This will be implemented as part of the display logic
-        Ok("⧖ Metrics".to_string())
-    }
+    type Data = MetricsInfo;
 
     fn name(&self) -> &'static str {
         "metrics"
@@ -175,4 +170,38 @@ impl Segment for MetricsSegment {
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    async fn data(&self, ctx: &SegmentContext) -> Result<MetricsInfo> {
+        self.get_metrics_info_from(&ctx.shared).await
+    }
+
+    fn render(&self, info: MetricsInfo, ctx: &SegmentContext) -> Result<String> {
+        let mut parts = Vec::new();
+
+        if let Some(avg) = info.avg_response_time {
+            parts.push(format!("{:.1}s", avg / 1000.0));
+        }
+        if let Some(last) = info.last_response_time {
+            parts.push(format!("last {:.1}s", last / 1000.0));
+        }
+        if let Some(minutes) = info.session_duration {
+            parts.push(format!("{}m", minutes));
+        }
+        if let Some(count) = info.message_count {
+            parts.push(format!("{}msg", count));
+        }
+        if let Some(added) = info.lines_added {
+            parts.push(format!("+{}", added));
+        }
+        if let Some(removed) = info.lines_removed {
+            parts.push(format!("-{}", removed));
+        }
+
+        if parts.is_empty() {
+            return Ok(String::new());
+        }
+
+        let formatted = format!(" ⧖ {} ", parts.join(" "));
+        Ok(apply_theme_colors(&formatted, self.name(), &ctx.theme))
+    }
 }
\ No newline at end of file
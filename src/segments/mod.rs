@@ -5,6 +5,11 @@ pub mod git;
 pub mod context;
 pub mod metrics;
 pub mod model;
+pub mod directory;
+pub mod health;
+pub mod update;
+pub mod team;
+pub mod registry;
 
 pub use block::*;
 pub use today::*;
@@ -13,20 +18,514 @@ pub use git::*;
 pub use context::*;
 pub use metrics::*;
 pub use model::*;
+pub use directory::*;
+pub use health::*;
+pub use update::*;
+pub use team::*;
+pub use registry::*;
 
+use crate::reports::format::format_with_separators;
+use crate::config::{BudgetAmount, Config, LayoutConfig};
+use crate::sources::{source_by_name, ClaudeTranscriptSource, UsageSource};
+use crate::themes::Theme;
+use crate::utils::{debug_with_context, display_width, round_cost, RoundingMode, truncate_to_width, ClaudeHookData, HookCostLedger, SharedEntries, TokenBreakdown};
 use anyhow::Result;
 use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
 
-/// Trait for all statusline segments
-pub trait Segment {
-    /// Render the segment as a string
-    fn render(&self) -> Result<String>;
-    
-    /// Get segment name for debugging
+/// Everything a segment may read while rendering, shared by all segments of one render
+pub struct SegmentContext {
+    pub config: Arc<Config>,
+    pub theme: Arc<Theme>,
+    /// Recent entries, loaded once for every segment that reads them
+    pub shared: Arc<SharedEntries>,
+    /// Entries of sources picked by individual segments, loaded once per source
+    by_source: Mutex<HashMap<String, Arc<SharedEntries>>>,
+    /// What Claude Code piped to the statusline on stdin, when it did
+    pub hook: Option<Arc<ClaudeHookData>>,
+    /// Hook-reported session costs with this render's report recorded, for `costSource: "hook"`
+    pub hook_costs: Option<Arc<HookCostLedger>>,
+}
+
+impl SegmentContext {
+    /// Context reading usage from Claude Code's transcripts
+    pub fn new(config: Arc<Config>, theme: Arc<Theme>) -> Self {
+        Self::with_source(config, theme, Arc::new(ClaudeTranscriptSource))
+    }
+
+    pub fn with_source(config: Arc<Config>, theme: Arc<Theme>, source: Arc<dyn UsageSource>) -> Self {
+        Self {
+            config,
+            theme,
+            shared: Arc::new(SharedEntries::with_source(source)),
+            by_source: Mutex::new(HashMap::new()),
+            hook: None,
+            hook_costs: None,
+        }
+    }
+
+    /// Attach the hook data of this render and the cost ledger it was recorded in
+    pub fn with_hook(mut self, hook: Option<ClaudeHookData>, hook_costs: Option<HookCostLedger>) -> Self {
+        self.hook = hook.map(Arc::new);
+        self.hook_costs = hook_costs.map(Arc::new);
+        self
+    }
+
+    /// Entries of the source a segment selected by name, or the shared entries of the
+    /// configured sources when it selected none (or one that can't be read)
+    pub fn entries_for(&self, source: Option<&str>) -> Arc<SharedEntries> {
+        let Some(name) = source else {
+            return self.shared.clone();
+        };
+
+        let mut by_source = self.by_source.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(shared) = by_source.get(name) {
+            return shared.clone();
+        }
+
+        let sources_config = self.config.sources.clone().unwrap_or_default();
+        let Some(source) = source_by_name(name, &sources_config) else {
+            debug_with_context("sources", &format!("Falling back to configured sources for '{}'", name));
+            return self.shared.clone();
+        };
+        let shared = Arc::new(SharedEntries::with_source(source));
+        by_source.insert(name.to_string(), shared.clone());
+        shared
+    }
+}
+
+/// A statusline segment, rendered in two phases: `data` gathers what the segment shows
+/// (transcript and git I/O, run concurrently with the other segments) and `render` formats it
+pub trait Segment: Send + Sync + 'static {
+    type Data: Send;
+
+    /// Segment name, used for theme colors, caching and timings
     fn name(&self) -> &'static str;
-    
+
     /// Check if segment should be displayed
     fn is_enabled(&self) -> bool {
         true
     }
-}
\ No newline at end of file
+
+    /// Gather the data this segment displays
+    fn data(&self, ctx: &SegmentContext) -> impl Future<Output = Result<Self::Data>> + Send;
+
+    /// Format `data` for display; an empty string hides the segment
+    fn render(&self, data: Self::Data, ctx: &SegmentContext) -> Result<String>;
+}
+
+/// Pad a rendered segment to its configured `minWidth` and `padding`, inside its colors so the
+/// background covers the padding, keeping the line from shifting as values change width.
+/// Segments without layout options are returned untouched.
+pub fn apply_layout(text: &str, layout: &LayoutConfig) -> String {
+    const RESET: &str = "\x1b[0m";
+
+    if text.is_empty() || (layout.min_width.is_none() && layout.padding.is_none()) {
+        return text.to_string();
+    }
+
+    // Leading color escapes, the content, and the closing reset
+    let mut body_start = 0;
+    while text[body_start..].starts_with("\x1b[") {
+        match text[body_start..].find('m') {
+            Some(end) => body_start += end + 1,
+            None => break,
+        }
+    }
+    let (prefix, rest) = text.split_at(body_start);
+    let (body, reset) = match rest.strip_suffix(RESET) {
+        Some(body) => (body, RESET),
+        None => (rest, ""),
+    };
+
+    let content = body.trim_matches(' ');
+    let fill = layout.min_width.unwrap_or(0).saturating_sub(display_width(content));
+    let (left, right) = match layout.align.as_deref() {
+        Some("right") => (fill, 0),
+        Some("center") => (fill / 2, fill - fill / 2),
+        _ => (0, fill),
+    };
+    let padding = " ".repeat(layout.padding.unwrap_or(1));
+
+    format!(
+        "{}{}{}{}{}{}{}",
+        prefix,
+        padding,
+        " ".repeat(left),
+        content,
+        " ".repeat(right),
+        padding,
+        reset
+    )
+}
+
+/// Join rendered segments (text, priority) with `separator`, dropping the lowest-priority
+/// segments (the rightmost among equals) until the line fits in `max_width` cells. A single
+/// segment that still doesn't fit is truncated.
+pub fn fit_segments(segments: Vec<(String, u32)>, separator: &str, max_width: Option<usize>) -> String {
+    compose_line(segments, Vec::new(), &Separators::plain(separator), max_width, None)
+}
+
+/// How the segments of a group are joined: the separator between two segments, and the caps
+/// opening and closing the group, which take the color of the segment they touch
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Separators {
+    pub between: String,
+    pub start_cap: String,
+    pub end_cap: String,
+}
+
+impl Separators {
+    /// Just `between`, without caps
+    pub fn plain(between: &str) -> Self {
+        Self { between: between.to_string(), ..Self::default() }
+    }
+
+    /// Separators for `style`: in `powerline` the named `separator` glyph (the hard arrow when
+    /// unset) between segments and the named caps at either end; `transparent` has no backgrounds
+    /// to tell segments apart, so it gets a thin bar; `minimal` keeps its plain gap
+    pub fn for_style(style: &str, separator: Option<&str>, start_cap: Option<&str>, end_cap: Option<&str>) -> Self {
+        match style {
+            "powerline" => {}
+            "transparent" => return Self::plain(" │ "),
+            _ => return Self::plain("  "),
+        }
+
+        let between = separator_glyphs(separator.unwrap_or("hard")).0;
+        Self {
+            between: if between.is_empty() { String::new() } else { format!(" {} ", between) },
+            start_cap: start_cap.map(|cap| separator_glyphs(cap).1).unwrap_or_default(),
+            end_cap: end_cap.map(|cap| separator_glyphs(cap).0).unwrap_or_default(),
+        }
+    }
+
+    /// `segments` joined, with the caps colored after the first and last segment's background
+    fn join(&self, segments: &[(String, u32)]) -> String {
+        let (Some((first, _)), Some((last, _))) = (segments.first(), segments.last()) else {
+            return String::new();
+        };
+        let texts: Vec<&str> = segments.iter().map(|(text, _)| text.as_str()).collect();
+        format!("{}{}{}", cap(&self.start_cap, first), texts.join(&self.between), cap(&self.end_cap, last))
+    }
+
+    /// Cells `segments` take once joined
+    fn width(&self, segments: &[(String, u32)]) -> usize {
+        if segments.is_empty() {
+            return 0;
+        }
+        segments.iter().map(|(text, _)| display_width(text)).sum::<usize>()
+            + display_width(&self.between) * (segments.len() - 1)
+            + display_width(&self.start_cap)
+            + display_width(&self.end_cap)
+    }
+}
+
+/// The (right-pointing, left-pointing) glyphs of a named separator style: `hard`, `soft`,
+/// `rounded`, `slanted`, `flame` or `none`. Anything else is taken as a literal glyph.
+/// All but `hard` and `soft` need a Nerd Font.
+pub fn separator_glyphs(name: &str) -> (String, String) {
+    let (right, left) = match name {
+        "hard" => ("⮀", "⮂"),
+        "soft" => ("⮁", "⮃"),
+        "rounded" => ("\u{e0b4}", "\u{e0b6}"),
+        "slanted" => ("\u{e0bc}", "\u{e0ba}"),
+        "flame" => ("\u{e0c0}", "\u{e0c2}"),
+        "none" => ("", ""),
+        glyph => (glyph, glyph),
+    };
+    (right.to_string(), left.to_string())
+}
+
+/// `glyph` drawn in the background color of `segment`, so the cap looks like part of it
+fn cap(glyph: &str, segment: &str) -> String {
+    if glyph.is_empty() {
+        return String::new();
+    }
+    match background_as_foreground(segment) {
+        Some(color) => format!("{}{}\x1b[0m", color, glyph),
+        None => glyph.to_string(),
+    }
+}
+
+/// The first background color escape of `text` (`ESC[48;...m`), as a foreground escape
+fn background_as_foreground(text: &str) -> Option<String> {
+    let start = text.find("\x1b[48;")?;
+    let end = start + text[start..].find('m')?;
+    Some(format!("\x1b[38;{}", &text[start + 5..=end]))
+}
+
+/// Where a line sits in a framed layout, which decides the corners drawn around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramePosition {
+    Single,
+    First,
+    Middle,
+    Last,
+}
+
+impl FramePosition {
+    /// Position of line `index` out of `count`
+    pub fn of(index: usize, count: usize) -> Self {
+        match index {
+            _ if count <= 1 => Self::Single,
+            0 => Self::First,
+            _ if index + 1 == count => Self::Last,
+            _ => Self::Middle,
+        }
+    }
+
+    /// The piece opening the line and the one closing it at the right edge
+    fn corners(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Single => ("╶─ ", "─╴"),
+            Self::First => ("╭─ ", "─╮"),
+            Self::Middle => ("├─ ", "─┤"),
+            Self::Last => ("╰─ ", "─╯"),
+        }
+    }
+}
+
+/// Lay out one line of the statusline: `left` segments from the start and `right` segments
+/// pushed to the edge at `max_width`, dropping the lowest-priority segments of either group
+/// (the rightmost among equals) until both fit; a single segment that still doesn't fit is
+/// truncated. In a frame the line gets its corners and the gap is drawn as `─`. Without a
+/// width the right group simply follows the left one.
+pub fn compose_line(
+    left: Vec<(String, u32)>,
+    right: Vec<(String, u32)>,
+    separators: &Separators,
+    max_width: Option<usize>,
+    frame: Option<FramePosition>,
+) -> String {
+    let (open, close) = frame.map(FramePosition::corners).unwrap_or(("", ""));
+    let framed = frame.is_some();
+
+    let Some(max_width) = max_width else {
+        let mut line = format!("{}{}", open, separators.join(&left));
+        if !right.is_empty() {
+            line.push_str(if framed { " ─ " } else { &separators.between });
+            line.push_str(&separators.join(&right));
+        }
+        return line;
+    };
+
+    // Room the frame takes (a space sets the right group off the closing corner), and the
+    // least gap keeping the groups apart (` ─ ` inside a frame)
+    let decoration = |has_right: bool| display_width(open) + display_width(close) + usize::from(framed && has_right);
+    let min_gap = |has_right: bool| match (framed, has_right) {
+        (true, true) => 3,
+        (true, false) | (false, true) => 1,
+        (false, false) => 0,
+    };
+    let line_width = |left: &[(String, u32)], right: &[(String, u32)]| {
+        decoration(!right.is_empty()) + separators.width(left) + separators.width(right) + min_gap(!right.is_empty())
+    };
+
+    // Drop across both groups by priority; the right group counts as further right
+    let (mut left, mut right) = (left, right);
+    while left.len() + right.len() > 1 && line_width(&left, &right) > max_width {
+        let lowest = left
+            .iter()
+            .chain(right.iter())
+            .enumerate()
+            .min_by_key(|(index, (_, priority))| (*priority, std::cmp::Reverse(*index)))
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+        if lowest < left.len() {
+            left.remove(lowest);
+        } else {
+            right.remove(lowest - left.len());
+        }
+    }
+
+    let has_right = !right.is_empty();
+    let gap = max_width
+        .saturating_sub(decoration(has_right) + separators.width(&left) + separators.width(&right))
+        .max(min_gap(has_right));
+    let fill = match (framed, has_right) {
+        (true, true) => format!(" {} ", "─".repeat(gap - 2)),
+        (true, false) => format!(" {}", "─".repeat(gap - 1)),
+        (false, true) => " ".repeat(gap),
+        (false, false) => String::new(),
+    };
+    let right_padding = if framed && has_right { " " } else { "" };
+    let line = format!(
+        "{}{}{}{}{}{}",
+        open,
+        separators.join(&left),
+        fill,
+        separators.join(&right),
+        right_padding,
+        close
+    );
+    truncate_to_width(&line, max_width)
+}
+
+/// The part of `budget` used: cost in dollars (with `display.costMultiplier` applied, as shown),
+/// or tokens for `"type": "tokens"`
+pub(crate) fn budget_usage(budget: &BudgetAmount, cost: Option<f64>, tokens: Option<u64>) -> f64 {
+    match budget.budget_type.as_deref() {
+        Some("tokens") => tokens.unwrap_or(0) as f64,
+        _ => cost.unwrap_or(0.0) * cost_format().multiplier,
+    }
+}
+
+/// Usage as a share of `budget` (`63%`), by cost or, for `"type": "tokens"`, by tokens;
+/// `None` without a budget to measure against
+pub fn format_budget_percentage(budget: Option<&BudgetAmount>, cost: Option<f64>, tokens: Option<u64>) -> Option<String> {
+    let budget = budget.filter(|budget| budget.amount > 0.0)?;
+    Some(format!("{:.0}%", budget_usage(budget, cost, tokens) / budget.amount * 100.0))
+}
+
+/// The `percentage` display of a cost segment: usage as a share of `budget`, or without a budget,
+/// when there's nothing to take a share of, the cost (marked when it `diverges`)
+pub fn format_budget_or_cost(budget: Option<&BudgetAmount>, cost: Option<f64>, tokens: Option<u64>, diverges: bool) -> Option<String> {
+    format_budget_percentage(budget, cost, tokens).or_else(|| cost.map(|cost| format_checked_cost(cost, diverges)))
+}
+
+/// The token count a segment shows: all of them, or with `include_cache_tokens` off only input
+/// and output, since cache reads dwarf everything else (costs keep counting them either way)
+pub fn displayed_tokens(tokens: Option<u64>, breakdown: &TokenBreakdown, include_cache_tokens: bool) -> Option<u64> {
+    if include_cache_tokens {
+        tokens
+    } else {
+        Some(breakdown.uncached_tokens()).filter(|&tokens| tokens > 0)
+    }
+}
+
+/// Tokens by kind, `in 12.0K · out 48.0K · cache 1.2M`, for tuning prompt caching; the cache
+/// part (reads and writes together) is left out with `include_cache_tokens` off
+pub fn format_token_breakdown(breakdown: &TokenBreakdown, include_cache_tokens: bool) -> String {
+    let mut parts = vec![
+        format!("in {}", format_number(breakdown.input_tokens)),
+        format!("out {}", format_number(breakdown.output_tokens)),
+    ];
+    if include_cache_tokens {
+        let cache = breakdown.cache_creation_input_tokens + breakdown.cache_read_input_tokens;
+        parts.push(format!("cache {}", format_number(cache)));
+    }
+    parts.join(" · ")
+}
+
+/// How counts are written across segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// `1.2M`, with this many decimals
+    Compact(usize),
+    /// `1,234,567`
+    Exact,
+    /// `1.2e6`, with this many decimals
+    Scientific(usize),
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self::Compact(1)
+    }
+}
+
+impl NumberFormat {
+    /// The format named by `display.numberFormat` (`compact`, `exact` or `scientific`) with
+    /// `display.numberDecimals`; unknown names fall back to compact
+    pub fn from_config(style: Option<&str>, decimals: Option<usize>) -> Self {
+        let decimals = decimals.unwrap_or(1);
+        match style {
+            Some("exact") => Self::Exact,
+            Some("scientific") => Self::Scientific(decimals),
+            _ => Self::Compact(decimals),
+        }
+    }
+}
+
+/// Number format configured via `display`, set once at startup before anything is rendered
+static NUMBER_FORMAT: OnceLock<NumberFormat> = OnceLock::new();
+
+/// Write counts in `format` from now on; only the first call has an effect
+pub fn set_number_format(format: NumberFormat) {
+    let _ = NUMBER_FORMAT.set(format);
+}
+
+/// How costs are written across segments
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostFormat {
+    /// Decimals of a dollar amount; amounts too small to show at this precision get up to 4
+    pub precision: usize,
+    /// Write amounts in cents (`42¢`) instead of dollars
+    pub cents: bool,
+    /// How amounts are rounded to the digits shown
+    pub rounding: RoundingMode,
+    /// Factor applied to every cost shown, such as 1.21 for VAT
+    pub multiplier: f64,
+}
+
+impl Default for CostFormat {
+    fn default() -> Self {
+        Self { precision: 2, cents: false, rounding: RoundingMode::default(), multiplier: 1.0 }
+    }
+}
+
+/// Cost format configured via `display`, set once at startup before anything is rendered
+static COST_FORMAT: OnceLock<CostFormat> = OnceLock::new();
+
+/// Write costs in `format` from now on; only the first call has an effect
+pub fn set_cost_format(format: CostFormat) {
+    let _ = COST_FORMAT.set(format);
+}
+
+/// The configured cost format
+pub fn cost_format() -> CostFormat {
+    COST_FORMAT.get().copied().unwrap_or_default()
+}
+
+/// A cost in the configured format, `$1.23` by default
+pub fn format_cost(cost: f64) -> String {
+    format_cost_as(cost, cost_format())
+}
+
+/// Marker after a cost whose calculation disagrees with the `costUSD` recorded in the transcripts
+pub const COST_DIVERGENCE_MARKER: &str = "≠";
+
+/// A cost in the configured format, marked with `≠` when it `diverges` from the reported cost
+pub fn format_checked_cost(cost: f64, diverges: bool) -> String {
+    let mut text = format_cost(cost);
+    if diverges {
+        text.push_str(COST_DIVERGENCE_MARKER);
+    }
+    text
+}
+
+/// A cost in `format`, multiplied by its multiplier and rounded with its rounding mode. Sub-cent
+/// amounts that would round to nothing keep 4 decimals of a dollar (`$0.0042`) or 2 of a cent
+/// (`0.42¢`) rather than showing as zero.
+pub fn format_cost_as(cost: f64, format: CostFormat) -> String {
+    let cost = cost * format.multiplier;
+    if format.cents {
+        let cents = cost * 100.0;
+        let decimals = if cents > 0.0 && cents < 1.0 { 2 } else { 0 };
+        return format!("{:.*}¢", decimals, round_cost(cents, decimals, format.rounding));
+    }
+
+    let precision = if cost > 0.0 && round_cost(cost, format.precision, format.rounding) == 0.0 {
+        format.precision.max(4)
+    } else {
+        format.precision
+    };
+    format!("${:.*}", precision, round_cost(cost, precision, format.rounding))
+}
+
+/// A count in the configured format, compact (`1.2K`, `3.4M`) by default
+pub fn format_number(num: u64) -> String {
+    format_number_as(num, NUMBER_FORMAT.get().copied().unwrap_or_default())
+}
+
+/// A count in `format`; counts under a thousand are always written out
+pub fn format_number_as(num: u64, format: NumberFormat) -> String {
+    match format {
+        NumberFormat::Exact => format_with_separators(num),
+        _ if num < 1_000 => num.to_string(),
+        NumberFormat::Scientific(decimals) => format!("{:.*e}", decimals, num as f64),
+        NumberFormat::Compact(decimals) if num >= 1_000_000 => format!("{:.*}M", decimals, num as f64 / 1_000_000.0),
+        NumberFormat::Compact(decimals) => format!("{:.*}K", decimals, num as f64 / 1_000.0),
+    }
+}
@@ -1,9 +1,10 @@
-use crate::segments::Segment;
-use crate::utils::{debug_with_context, DataAggregator};
+use crate::segments::{Segment, SegmentContext};
+use crate::themes::apply_theme_colors;
+use crate::utils::{debug_with_context, SharedEntries};
 use anyhow::Result;
 use chrono::{Duration, Utc};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ModelInfo {
     pub current_model: Option<String>,
     pub display_name: Option<String>,
@@ -11,26 +12,33 @@ pub struct ModelInfo {
 
 pub struct ModelSegment {
     pub enabled: bool,
+    /// Usage source to read instead of the configured ones
+    pub source: Option<String>,
 }
 
 impl ModelSegment {
     pub fn new() -> Self {
         Self {
             enabled: true,
+            source: None,
         }
     }
 
     /// Get the most recently used model from transcript data
     pub async fn get_current_model_info(&self) -> Result<ModelInfo> {
+        self.get_current_model_info_from(&SharedEntries::new()).await
+    }
+
+    /// Get the most recently used model from entries shared with the other segments
+    pub async fn get_current_model_info_from(&self, shared: &SharedEntries) -> Result<ModelInfo> {
         if !self.enabled {
             return Ok(ModelInfo::default());
         }
 
         debug_with_context("model", "Looking for current model in recent entries");
 
-        // Load entries from the last hour to find the most recent model
-        let aggregator = DataAggregator::new().with_time_filter(1);
-        let entries = aggregator.load_all_entries().await?;
+        // Only the last hour counts as "current"
+        let entries = shared.since(Utc::now() - Duration::hours(1)).await?;
 
         if entries.is_empty() {
             debug_with_context("model", "No recent entries found");
@@ -38,18 +46,15 @@ impl ModelSegment {
         }
 
         // Find the most recent entry with a model
+        // (entries are sorted by timestamp, so the last match is the newest)
         let mut latest_model: Option<String> = None;
-        let mut latest_timestamp = Utc::now() - Duration::days(365); // Very old date
 
         for entry in entries.iter().rev() {
             if let Some(message) = &entry.message {
                 if let Some(model) = &message.model {
-                    if entry.timestamp > latest_timestamp {
-                        latest_timestamp = entry.timestamp;
-                        latest_model = Some(model.clone());
-                        debug_with_context("model", &format!("Found model: {}", model));
-                        break; // We found the most recent one
-                    }
+                    latest_model = Some(model.to_string());
+                    debug_with_context("model", &format!("Found model: {}", model));
+                    break; // We found the most recent one
                 }
             }
         }
@@ -101,19 +106,14 @@ fn get_display_name(model_id: &str) -> String {
     }
 }
 
-impl Default for ModelInfo {
+impl Default for ModelSegment {
     fn default() -> Self {
-        Self {
-            current_model: None,
-            display_name: None,
-        }
+        Self::new()
     }
 }
 
 impl Segment for ModelSegment {
-    fn render(&self) -> Result<String> {
-        Ok("⚡ Model".to_string())
-    }
+    type Data = ModelInfo;
 
     fn name(&self) -> &'static str {
         "model"
@@ -122,4 +122,17 @@ impl Segment for ModelSegment {
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    async fn data(&self, ctx: &SegmentContext) -> Result<ModelInfo> {
+        self.get_current_model_info_from(&ctx.entries_for(self.source.as_deref())).await
+    }
+
+    fn render(&self, model_info: ModelInfo, ctx: &SegmentContext) -> Result<String> {
+        let Some(name) = model_info.display_name else {
+            return Ok(String::new());
+        };
+
+        let text = format!("🤖 {}", name);
+        Ok(apply_theme_colors(&text, self.name(), &ctx.theme))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,200 @@
+use crate::config::{Config, LayoutConfig, SegmentConfig};
+use crate::segments::*;
+use crate::utils::timing::record_stage;
+use anyhow::Result;
+use futures::future::BoxFuture;
+use std::sync::Arc;
+use std::time::Instant;
+
+type RunFn = dyn Fn(Arc<SegmentContext>) -> BoxFuture<'static, Result<String>> + Send + Sync;
+
+/// A segment with its type erased so differently typed segments can share one registry
+pub struct RegisteredSegment {
+    name: &'static str,
+    enabled: bool,
+    priority: u32,
+    run: Box<RunFn>,
+}
+
+impl RegisteredSegment {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Lower priorities are dropped first when the line doesn't fit
+    pub fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    /// Run both phases of the segment; the future owns everything it needs so it can be spawned
+    pub fn render(&self, ctx: Arc<SegmentContext>) -> BoxFuture<'static, Result<String>> {
+        (self.run)(ctx)
+    }
+}
+
+/// How readily a segment gives up its place on a line that's too wide: usage and context figures
+/// outlast the model and directory, and git details go first
+pub fn default_priority(segment: &str) -> u32 {
+    match segment {
+        "context" => 90,
+        "today" => 80,
+        "block" => 70,
+        "session" => 60,
+        "model" => 50,
+        "directory" => 40,
+        "git" => 30,
+        "health" => 20,
+        _ => 10,
+    }
+}
+
+/// The statusline's segments in display order
+#[derive(Default)]
+pub struct SegmentRegistry {
+    segments: Vec<RegisteredSegment>,
+}
+
+impl SegmentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in segments configured from `config`, in their fixed display order
+    pub fn from_config(config: &Config) -> Self {
+        Self::build(config, &config.segments)
+    }
+
+    /// Only the segments `segments` configures (one line or group of a multi-line layout),
+    /// in the same fixed display order; anything else the config sets up is left out
+    pub fn from_segments(config: &Config, segments: &SegmentConfig) -> Self {
+        let mut registry = Self::build(config, segments);
+        registry.segments.retain(|segment| segments.is_configured(segment.name()));
+        registry
+    }
+
+    fn build(config: &Config, segments: &SegmentConfig) -> Self {
+        let mut registry = Self::new();
+
+        let mut directory = DirectorySegment::new();
+        if let Some(c) = &segments.directory {
+            directory.enabled = c.enabled;
+            directory.show_basename = c.show_basename.unwrap_or(false);
+        }
+        registry.register_with_layout(directory, segments.directory.as_ref().map(|c| c.layout.clone()).unwrap_or_default());
+
+        let cache_ttl = config.cache.clone().unwrap_or_default().ttl_for("git");
+        let mut git = GitSegment::new().with_cache_ttl(cache_ttl);
+        if let Some(c) = &segments.git {
+            git.enabled = c.enabled;
+            git.show_sha = c.show_sha.unwrap_or(true);
+            git.show_working_tree = c.show_working_tree.unwrap_or(false);
+            git.show_upstream = c.show_upstream.unwrap_or(false);
+            git.show_stash_count = c.show_stash_count.unwrap_or(false);
+            git.show_repo_name = c.show_repo_name.unwrap_or(false);
+        }
+        registry.register_with_layout(git, segments.git.as_ref().map(|c| c.layout.clone()).unwrap_or_default());
+
+        let session = SessionSegment::from_config(segments.session.as_ref());
+        registry.register_with_layout(session, segments.session.as_ref().map(|c| c.layout.clone()).unwrap_or_default());
+
+        let today = TodaySegment::from_config(segments.today.as_ref());
+        registry.register_with_layout(today, segments.today.as_ref().map(|c| c.layout.clone()).unwrap_or_default());
+
+        let block = BlockSegment::from_config(segments.block.as_ref());
+        registry.register_with_layout(block, segments.block.as_ref().map(|c| c.layout.clone()).unwrap_or_default());
+
+        let mut context = ContextSegment::new();
+        if let Some(c) = &segments.context {
+            context.enabled = c.enabled;
+            context.show_percentage_only = c.show_percentage_only.unwrap_or(false);
+            context.critical_percentage = c.critical_percentage;
+            context.critical_icon = c.critical_icon.unwrap_or(false);
+            context.compact_eta = c.compact_eta.unwrap_or(false);
+        }
+        registry.register_with_layout(context, segments.context.as_ref().map(|c| c.layout.clone()).unwrap_or_default());
+
+        let mut model = ModelSegment::new();
+        if let Some(c) = &segments.model {
+            model.enabled = c.enabled;
+            model.source = c.source.clone();
+        }
+        registry.register_with_layout(model, segments.model.as_ref().map(|c| c.layout.clone()).unwrap_or_default());
+
+        // Only part of the line when configured: it reads the whole session transcript on every render
+        if let Some(c) = &segments.health {
+            let mut health = HealthSegment::new();
+            health.enabled = c.enabled;
+            health.show_healthy = c.show_healthy.unwrap_or(false);
+            registry.register_with_layout(health, c.layout.clone());
+        }
+
+        // Only part of the line when configured: it looks releases up over the network
+        if let Some(c) = &segments.update {
+            let mut update = UpdateSegment::new();
+            update.enabled = c.enabled;
+            update.interval = chrono::Duration::hours(c.interval_hours.unwrap_or(24).max(1) as i64);
+            registry.register_with_layout(update, c.layout.clone());
+        }
+
+        // Only part of the line when configured: it reads every teammate's transcripts
+        if let Some(c) = &segments.team {
+            let mut team = TeamSegment::new();
+            team.enabled = c.enabled;
+            team.per_person = c.per_person.unwrap_or(false);
+            registry.register_with_layout(team, c.layout.clone());
+        }
+
+        // Metrics implements `Segment` but has never been part of the displayed line,
+        // so it stays out of the default registry until it gets a layout of its own
+
+        registry
+    }
+
+    /// Append `segment` to the end of the line
+    pub fn register<S: Segment>(&mut self, segment: S) -> &mut Self {
+        self.register_with_layout(segment, LayoutConfig::default())
+    }
+
+    /// Append `segment` to the end of the line, padding its output as `layout` says
+    pub fn register_with_layout<S: Segment>(&mut self, segment: S, layout: LayoutConfig) -> &mut Self {
+        let name = segment.name();
+        let enabled = segment.is_enabled();
+        let priority = layout.priority.unwrap_or_else(|| default_priority(name));
+        let segment = Arc::new(segment);
+        let layout = Arc::new(layout);
+
+        let run = move |ctx: Arc<SegmentContext>| -> BoxFuture<'static, Result<String>> {
+            let segment = segment.clone();
+            let layout = layout.clone();
+            Box::pin(async move {
+                let started = Instant::now();
+                let data = segment.data(&ctx).await;
+                record_stage("compute", started.elapsed(), None, None);
+
+                let started = Instant::now();
+                let rendered = segment.render(data?, &ctx).map(|text| apply_layout(&text, &layout));
+                record_stage("render", started.elapsed(), None, None);
+                rendered
+            })
+        };
+
+        self.segments.push(RegisteredSegment { name, enabled, priority, run: Box::new(run) });
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredSegment> {
+        self.segments.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
@@ -1,21 +1,36 @@
-use crate::segments::Segment;
-use crate::utils::{find_transcript_file, debug_with_context, DataAggregator, PricingService, ParsedEntry};
+use crate::config::SessionConfig;
+use crate::segments::{displayed_tokens, format_budget_or_cost, format_checked_cost, format_number, format_token_breakdown, Segment, SegmentContext};
+use crate::themes::apply_theme_colors;
+use crate::sources::{ClaudeTranscriptSource, SessionChain, UsageSource};
+use crate::utils::{debug_with_context, ClaudeHookData, PricingService, ParsedEntry, TokenBreakdown};
 use anyhow::Result;
 use std::env;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct SessionInfo {
     pub cost: Option<f64>,
-    pub tokens: Option<u32>,
+    pub tokens: Option<u64>,
     pub message_count: Option<u32>,
     pub duration_minutes: Option<i64>,
     pub session_id: Option<String>,
+    /// Tokens by kind, behind `tokens`
+    pub breakdown: TokenBreakdown,
+    /// `cost` strays from the transcripts' reported `costUSD` by more than `pricing.costDivergencePercent`
+    pub cost_diverges: bool,
+    /// Sessions added up, counting those this one was resumed or branched from (`includeResumed`)
+    pub chain_sessions: usize,
 }
 
 pub struct SessionSegment {
     pub enabled: bool,
     pub display_type: String,
     pub cost_source: String,
+    /// Usage source to read instead of the configured ones
+    pub source: Option<String>,
+    /// Count cache reads and writes in the token totals shown
+    pub include_cache_tokens: bool,
+    /// Add up the sessions this one was resumed or branched from
+    pub include_resumed: bool,
 }
 
 impl SessionSegment {
@@ -24,11 +39,33 @@ impl SessionSegment {
             enabled: true,
             display_type: "tokens".to_string(),
             cost_source: "calculated".to_string(),
+            source: None,
+            include_cache_tokens: true,
+            include_resumed: false,
         }
     }
 
-    /// Get current session information with optimized performance
+    /// The segment with the options `config` sets, the defaults for the rest
+    pub fn from_config(config: Option<&SessionConfig>) -> Self {
+        let mut segment = Self::new();
+        if let Some(c) = config {
+            segment.enabled = c.enabled;
+            segment.display_type = c.display_type.clone().unwrap_or_else(|| "tokens".to_string());
+            segment.cost_source = c.cost_source.clone().unwrap_or_else(|| "calculated".to_string());
+            segment.source = c.source.clone();
+            segment.include_cache_tokens = c.include_cache_tokens.unwrap_or(true);
+            segment.include_resumed = c.include_resumed.unwrap_or(false);
+        }
+        segment
+    }
+
+    /// Get current session information from Claude Code's transcripts
     pub async fn get_session_info(&self) -> Result<SessionInfo> {
+        self.get_session_info_from(&ClaudeTranscriptSource).await
+    }
+
+    /// Get current session information from `source`
+    pub async fn get_session_info_from(&self, source: &dyn UsageSource) -> Result<SessionInfo> {
         if !self.enabled {
             return Ok(SessionInfo::default());
         }
@@ -38,17 +75,23 @@ impl SessionSegment {
         
         if let Some(ref sid) = session_id {
             debug_with_context("session", &format!("Loading session entries for: {}", sid));
-            
-            // Load entries for this specific session using new architecture
-            if let Some(transcript_path) = find_transcript_file(sid).await? {
-                // Use DataAggregator to load entries from specific session file
-                let aggregator = DataAggregator::new();
-                let entries = aggregator.load_session_entries(&transcript_path).await?;
 
-                debug_with_context("session", &format!("Found {} entries in current session", entries.len()));
+            let loaded = if self.include_resumed {
+                source.session_chain_entries(sid).await?
+            } else {
+                source.session_entries(sid).await?.map(|entries| SessionChain { entries, sessions: 1 })
+            };
+
+            if let Some(chain) = loaded {
+                debug_with_context("session", &format!(
+                    "Found {} entries in current session ({} transcript(s))",
+                    chain.entries.len(),
+                    chain.sessions
+                ));
 
-                let mut info = self.calculate_session_info(&entries);
+                let mut info = self.calculate_session_info(&chain.entries);
                 info.session_id = session_id;
+                info.chain_sessions = chain.sessions;
                 return Ok(info);
             }
         }
@@ -57,6 +100,19 @@ impl SessionSegment {
         Ok(SessionInfo::default())
     }
 
+    /// Session cost and duration as Claude Code reports them in the hook data, or `None` when it reports no cost
+    pub fn hook_session_info(&self, hook: &ClaudeHookData) -> Option<SessionInfo> {
+        let cost = hook.cost.as_ref()?;
+        debug_with_context("session", &format!("Using hook-reported cost ${:.2}", cost.total_cost_usd));
+
+        Some(SessionInfo {
+            cost: Some(cost.total_cost_usd).filter(|&cost| cost > 0.0),
+            duration_minutes: Some((cost.total_duration_ms / 60_000) as i64),
+            session_id: Some(hook.session_id.clone()),
+            ..Default::default()
+        })
+    }
+
     /// Try to determine the current session ID
     async fn get_current_session_id(&self) -> Result<Option<String>> {
         // Try environment variables first
@@ -114,27 +170,21 @@ impl SessionSegment {
             message_count: if message_count > 0 { Some(message_count) } else { None },
             duration_minutes,
             session_id: None, // Will be set by caller
+            breakdown: token_breakdown,
+            cost_diverges: pricing_service.cost_diverges("session", pricing_service.check_reported_cost(entries)),
+            chain_sessions: 1,
         }
     }
 }
 
-impl Default for SessionInfo {
+impl Default for SessionSegment {
     fn default() -> Self {
-        Self {
-            cost: None,
-            tokens: None,
-            message_count: None,
-            duration_minutes: None,
-            session_id: None,
-        }
+        Self::new()
     }
 }
 
 impl Segment for SessionSegment {
-    fn render(&self) -> Result<String> {
-        // This will be implemented as part of the display logic
-        Ok("§ Session".to_string())
-    }
+    type Data = SessionInfo;
 
     fn name(&self) -> &'static str {
         "session"
@@ -143,4 +193,55 @@ impl Segment for SessionSegment {
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    async fn data(&self, ctx: &SegmentContext) -> Result<SessionInfo> {
+        if self.cost_source == "hook" {
+            if let Some(info) = ctx.hook.as_deref().and_then(|hook| self.hook_session_info(hook)) {
+                return Ok(info);
+            }
+        }
+        self.get_session_info_from(ctx.entries_for(self.source.as_deref()).source().as_ref()).await
+    }
+
+    fn render(&self, session_info: SessionInfo, ctx: &SegmentContext) -> Result<String> {
+        if session_info.tokens.is_none() && session_info.cost.is_none() {
+            return Ok(String::new());
+        }
+
+        let mut parts = vec!["§".to_string()];
+
+        match self.display_type.as_str() {
+            "cost" => {
+                if let Some(cost) = session_info.cost {
+                    parts.push(format_checked_cost(cost, session_info.cost_diverges));
+                }
+            }
+            "tokens" => {
+                if let Some(tokens) = displayed_tokens(session_info.tokens, &session_info.breakdown, self.include_cache_tokens) {
+                    parts.push(format!("{}T", format_number(tokens)));
+                }
+            }
+            "breakdown" => parts.push(format_token_breakdown(&session_info.breakdown, self.include_cache_tokens)),
+            "percentage" => {
+                let budget = ctx.config.budget.as_ref().and_then(|budget| budget.session.as_ref());
+                parts.extend(format_budget_or_cost(budget, session_info.cost, session_info.tokens, session_info.cost_diverges));
+            }
+            "both" => {
+                if let Some(cost) = session_info.cost {
+                    parts.push(format_checked_cost(cost, session_info.cost_diverges));
+                }
+                if let Some(tokens) = displayed_tokens(session_info.tokens, &session_info.breakdown, self.include_cache_tokens) {
+                    parts.push(format!("{}T", format_number(tokens)));
+                }
+            }
+            _ => {}
+        }
+
+        if session_info.chain_sessions > 1 {
+            parts.push(format!("({} sessions)", session_info.chain_sessions));
+        }
+
+        let formatted = format!(" {} ", parts.join(" "));
+        Ok(apply_theme_colors(&formatted, self.name(), &ctx.theme))
+    }
 }
\ No newline at end of file
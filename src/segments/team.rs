@@ -0,0 +1,83 @@
+use crate::reports::TeamUsageReport;
+use crate::segments::{format_budget_percentage, format_cost, Segment, SegmentContext};
+use crate::themes::apply_theme_colors;
+use anyhow::Result;
+
+/// Today's cost of each person on the configured `team`, the local user first
+#[derive(Debug, Clone, Default)]
+pub struct TeamInfo {
+    pub costs: Vec<(String, f64)>,
+}
+
+impl TeamInfo {
+    pub fn total_cost(&self) -> f64 {
+        self.costs.iter().map(|(_, cost)| cost).sum()
+    }
+}
+
+/// Shows the team's combined cost today (`👥 $12.40`), its share of `budget.team` and, with
+/// `per_person`, what each person spent
+pub struct TeamSegment {
+    pub enabled: bool,
+    /// List each person's cost after the total
+    pub per_person: bool,
+}
+
+impl TeamSegment {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            per_person: false,
+        }
+    }
+}
+
+impl Default for TeamSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Segment for TeamSegment {
+    type Data = TeamInfo;
+
+    fn name(&self) -> &'static str {
+        "team"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    async fn data(&self, ctx: &SegmentContext) -> Result<TeamInfo> {
+        if !self.enabled {
+            return Ok(TeamInfo::default());
+        }
+
+        let team = ctx.config.team.clone().unwrap_or_default();
+        let report = TeamUsageReport::load(&team, 1).await?;
+        Ok(TeamInfo {
+            costs: report.people.iter().map(|person| (person.name.clone(), person.total_cost())).collect(),
+        })
+    }
+
+    fn render(&self, info: TeamInfo, ctx: &SegmentContext) -> Result<String> {
+        let total = info.total_cost();
+        if total <= 0.0 {
+            return Ok(String::new());
+        }
+
+        let mut parts = vec!["👥".to_string(), format_cost(total)];
+        let budget = ctx.config.budget.as_ref().and_then(|budget| budget.team.as_ref());
+        if let Some(percentage) = format_budget_percentage(budget, Some(total), None) {
+            parts.push(format!("({})", percentage));
+        }
+        if self.per_person {
+            parts.push("·".to_string());
+            parts.extend(info.costs.iter().map(|(name, cost)| format!("{} {}", name, format_cost(*cost))));
+        }
+
+        let formatted = format!(" {} ", parts.join(" "));
+        Ok(apply_theme_colors(&formatted, self.name(), &ctx.theme))
+    }
+}
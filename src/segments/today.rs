@@ -1,17 +1,45 @@
-use crate::segments::Segment;
-use crate::utils::{debug_with_context, DataAggregator, PricingService, ParsedEntry};
+use crate::config::TodayConfig;
+use crate::segments::{budget_usage, displayed_tokens, format_budget_or_cost, format_checked_cost, format_cost, format_number, format_token_breakdown, Segment, SegmentContext};
+use crate::themes::{apply_heat_colors, apply_theme_colors};
+use crate::sources::UsageSource;
+use crate::utils::{debug_with_context, DataAggregator, HookCostLedger, PricingService, ParsedEntry, SharedEntries, TokenBreakdown};
 use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use std::ops::Range;
 
-#[derive(Debug, Clone)]
+/// Days before today that `weeklyAverage` averages over
+const AVERAGE_DAYS: i64 = 7;
+
+#[derive(Debug, Clone, Default)]
 pub struct TodayInfo {
     pub cost: Option<f64>,
-    pub tokens: Option<u32>,
+    pub tokens: Option<u64>,
     pub message_count: Option<u32>,
+    /// Tokens by kind, behind `tokens`
+    pub breakdown: TokenBreakdown,
+    /// `cost` strays from the transcripts' reported `costUSD` by more than `pricing.costDivergencePercent`
+    pub cost_diverges: bool,
+    /// What yesterday had cost by this time of day, with `compareYesterday` on
+    pub yesterday_cost: Option<f64>,
+    /// Average daily cost of the 7 days before today, with `weeklyAverage` on
+    pub weekly_average: Option<f64>,
 }
 
 pub struct TodaySegment {
     pub enabled: bool,
     pub display_type: String,
+    /// `calculated` from transcripts, or `hook` to sum the session costs Claude Code reported
+    pub cost_source: String,
+    /// Usage source to read instead of the configured ones
+    pub source: Option<String>,
+    /// Count cache reads and writes in the token totals shown
+    pub include_cache_tokens: bool,
+    /// Shade the background by how much of the daily budget is spent
+    pub heat_map: bool,
+    /// Show the change in cost against yesterday up to the same time of day
+    pub compare_yesterday: bool,
+    /// Show the average daily cost of the previous 7 days
+    pub weekly_average: bool,
 }
 
 impl TodaySegment {
@@ -19,20 +47,104 @@ impl TodaySegment {
         Self {
             enabled: true,
             display_type: "cost".to_string(),
+            cost_source: "calculated".to_string(),
+            source: None,
+            include_cache_tokens: true,
+            heat_map: false,
+            compare_yesterday: false,
+            weekly_average: false,
+        }
+    }
+
+    /// The segment with the options `config` sets, the defaults for the rest
+    pub fn from_config(config: Option<&TodayConfig>) -> Self {
+        let mut segment = Self::new();
+        if let Some(c) = config {
+            segment.enabled = c.enabled;
+            segment.display_type = c.display_type.clone().unwrap_or_else(|| "cost".to_string());
+            segment.cost_source = c.cost_source.clone().unwrap_or_else(|| "calculated".to_string());
+            segment.source = c.source.clone();
+            segment.include_cache_tokens = c.include_cache_tokens.unwrap_or(true);
+            segment.heat_map = c.heat_map.unwrap_or(false);
+            segment.compare_yesterday = c.compare_yesterday.unwrap_or(false);
+            segment.weekly_average = c.weekly_average.unwrap_or(false);
         }
+        segment
     }
 
-    /// Get today's usage information using global data aggregation
+    /// Get today's usage information by folding running totals over today's transcripts,
+    /// without keeping the entries in memory
     pub async fn get_today_info(&self) -> Result<TodayInfo> {
         if !self.enabled {
             return Ok(TodayInfo::default());
         }
 
+        let today_start = Utc::now().date_naive().and_hms_opt(0, 0, 0)
+            .unwrap().and_utc();
+        let hours_since_midnight = (Utc::now() - today_start).num_hours() as u32 + 1;
+
+        let fold = DataAggregator::new()
+            .with_time_filter(hours_since_midnight)
+            .fold_usage_since(today_start)
+            .await?;
+
+        Ok(TodayInfo {
+            cost: (fold.cost > 0.0).then_some(fold.cost),
+            tokens: Some(fold.tokens.total_tokens()).filter(|&tokens| tokens > 0),
+            message_count: Some(fold.entry_count).filter(|&count| count > 0),
+            breakdown: fold.tokens,
+            cost_diverges: PricingService::new().cost_diverges("today", fold.cost_check),
+            yesterday_cost: None,
+            weekly_average: None,
+        })
+    }
+
+    /// Today's cost summed from the hook-reported session costs in `ledger`; the hook reports no tokens
+    pub fn hook_today_info(&self, ledger: &HookCostLedger) -> TodayInfo {
+        let today_start = Utc::now().date_naive().and_hms_opt(0, 0, 0)
+            .unwrap().and_utc();
+        let cost = ledger.cost_since(today_start);
+        debug_with_context("today", &format!("Hook-reported cost today: ${:.2}", cost));
+
+        TodayInfo {
+            cost: (cost > 0.0).then_some(cost),
+            ..Default::default()
+        }
+    }
+
+    /// The costs today is compared against, from one read of `source` (the same source today's
+    /// cost comes from): of the previous 7 days with `weeklyAverage` on, else only of yesterday
+    pub async fn past_costs(&self, source: &dyn UsageSource) -> Result<PastCosts> {
+        let now = Utc::now();
+        let range = if self.weekly_average { previous_week(now) } else { yesterday_so_far(now) };
+        let entries = source.entries_in(range).await?;
+        Ok(PastCosts::from_entries(&entries, now))
+    }
+
+    fn compares_with_past(&self) -> bool {
+        self.compare_yesterday || self.weekly_average
+    }
+
+    fn apply_past_costs(&self, info: &mut TodayInfo, past: PastCosts) {
+        if self.compare_yesterday {
+            info.yesterday_cost = Some(past.yesterday);
+        }
+        if self.weekly_average {
+            info.weekly_average = Some(past.weekly_average);
+        }
+    }
+
+    /// Get today's usage information from entries shared with the other segments
+    pub async fn get_today_info_from(&self, shared: &SharedEntries) -> Result<TodayInfo> {
+        if !self.enabled {
+            return Ok(TodayInfo::default());
+        }
+
         debug_with_context("today", "Loading today's entries");
 
-        // Use the new global data aggregation pipeline
-        let aggregator = DataAggregator::new();
-        let entries = aggregator.load_today_entries().await?;
+        let today_start = Utc::now().date_naive().and_hms_opt(0, 0, 0)
+            .unwrap().and_utc();
+        let entries = shared.since(today_start).await?;
 
         if entries.is_empty() {
             debug_with_context("today", "No entries found for today");
@@ -67,24 +179,89 @@ impl TodaySegment {
             cost: if total_cost > 0.0 { Some(total_cost) } else { None },
             tokens: if total_tokens > 0 { Some(total_tokens) } else { None },
             message_count: if message_count > 0 { Some(message_count) } else { None },
+            breakdown: token_breakdown,
+            cost_diverges: pricing_service.cost_diverges("today", pricing_service.check_reported_cost(entries)),
+            yesterday_cost: None,
+            weekly_average: None,
         }
     }
 }
 
-impl Default for TodayInfo {
-    fn default() -> Self {
+/// What today's cost is compared against
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PastCosts {
+    /// Cost from yesterday's midnight up to this time yesterday
+    pub yesterday: f64,
+    /// Average daily cost of the 7 days before today; days without usage count as zero
+    pub weekly_average: f64,
+}
+
+impl PastCosts {
+    /// Both comparisons from `entries`, sorted by timestamp; the weekly average is only right
+    /// if they cover the previous 7 days
+    pub fn from_entries(entries: &[ParsedEntry], now: DateTime<Utc>) -> Self {
+        let pricing = PricingService::new();
+        let cost_in = |range: Range<DateTime<Utc>>| {
+            let start = entries.partition_point(|entry| entry.timestamp < range.start);
+            let end = entries.partition_point(|entry| entry.timestamp < range.end);
+            pricing.calculate_total_cost(&entries[start..end.max(start)]).unwrap_or(0.0)
+        };
+
+        Self {
+            yesterday: cost_in(yesterday_so_far(now)),
+            weekly_average: cost_in(previous_week(now)) / AVERAGE_DAYS as f64,
+        }
+    }
+
+    /// Both comparisons from the hook-reported costs in `ledger`
+    pub fn from_hook(ledger: &HookCostLedger, now: DateTime<Utc>) -> Self {
         Self {
-            cost: None,
-            tokens: None,
-            message_count: None,
+            yesterday: ledger.cost_in(yesterday_so_far(now)),
+            weekly_average: ledger.cost_in(previous_week(now)) / AVERAGE_DAYS as f64,
         }
     }
 }
 
-impl Segment for TodaySegment {
-    fn render(&self) -> Result<String> {
-        Ok("☉ Today".to_string())
+/// The 7 days before the day of `now`, up to its midnight
+pub fn previous_week(now: DateTime<Utc>) -> Range<DateTime<Utc>> {
+    let today_start = now.date_naive().and_hms_opt(0, 0, 0)
+        .unwrap().and_utc();
+    today_start - Duration::days(AVERAGE_DAYS)..today_start
+}
+
+/// From yesterday's midnight up to the time of day of `now`
+pub fn yesterday_so_far(now: DateTime<Utc>) -> Range<DateTime<Utc>> {
+    let same_time_yesterday = now - Duration::days(1);
+    let yesterday_start = same_time_yesterday.date_naive().and_hms_opt(0, 0, 0)
+        .unwrap().and_utc();
+    yesterday_start..same_time_yesterday
+}
+
+/// Change from `yesterday` to `today` as `↑34%`, `↓12%` or `→0%`; nothing when yesterday cost nothing
+pub fn format_cost_change(today: f64, yesterday: f64) -> Option<String> {
+    if yesterday <= 0.0 {
+        return None;
+    }
+
+    let change = ((today - yesterday) / yesterday * 100.0).round();
+    let arrow = if change > 0.0 {
+        '↑'
+    } else if change < 0.0 {
+        '↓'
+    } else {
+        '→'
+    };
+    Some(format!("{}{}%", arrow, change.abs()))
+}
+
+impl Default for TodaySegment {
+    fn default() -> Self {
+        Self::new()
     }
+}
+
+impl Segment for TodaySegment {
+    type Data = TodayInfo;
 
     fn name(&self) -> &'static str {
         "today"
@@ -93,4 +270,78 @@ impl Segment for TodaySegment {
     fn is_enabled(&self) -> bool {
         self.enabled
     }
+
+    async fn data(&self, ctx: &SegmentContext) -> Result<TodayInfo> {
+        if self.cost_source == "hook" {
+            if let Some(ledger) = &ctx.hook_costs {
+                let mut info = self.hook_today_info(ledger);
+                if self.compares_with_past() && info.cost.is_some() {
+                    self.apply_past_costs(&mut info, PastCosts::from_hook(ledger, Utc::now()));
+                }
+                return Ok(info);
+            }
+        }
+        let shared = ctx.entries_for(self.source.as_deref());
+        let mut info = self.get_today_info_from(&shared).await?;
+
+        if self.compares_with_past() && info.cost.is_some() {
+            match self.past_costs(shared.source().as_ref()).await {
+                Ok(past) => self.apply_past_costs(&mut info, past),
+                Err(e) => debug_with_context("today", &format!("Failed to load past costs: {:#}", e)),
+            }
+        }
+        Ok(info)
+    }
+
+    fn render(&self, today_info: TodayInfo, ctx: &SegmentContext) -> Result<String> {
+        if today_info.tokens.is_none() && today_info.cost.is_none() {
+            return Ok(String::new());
+        }
+
+        let mut parts = vec!["💰".to_string()];
+
+        match self.display_type.as_str() {
+            "cost" => {
+                if let Some(cost) = today_info.cost {
+                    parts.push(format_checked_cost(cost, today_info.cost_diverges));
+                }
+            }
+            "tokens" => {
+                if let Some(tokens) = displayed_tokens(today_info.tokens, &today_info.breakdown, self.include_cache_tokens) {
+                    parts.push(format!("{}T", format_number(tokens)));
+                }
+            }
+            "breakdown" => parts.push(format_token_breakdown(&today_info.breakdown, self.include_cache_tokens)),
+            "percentage" => {
+                let budget = ctx.config.budget.as_ref().and_then(|budget| budget.today.as_ref());
+                parts.extend(format_budget_or_cost(budget, today_info.cost, today_info.tokens, today_info.cost_diverges));
+            }
+            "both" => {
+                if let Some(cost) = today_info.cost {
+                    parts.push(format_checked_cost(cost, today_info.cost_diverges));
+                }
+                if let Some(tokens) = displayed_tokens(today_info.tokens, &today_info.breakdown, self.include_cache_tokens) {
+                    parts.push(format!("{}T", format_number(tokens)));
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(change) = today_info.cost.zip(today_info.yesterday_cost)
+            .and_then(|(today, yesterday)| format_cost_change(today, yesterday))
+        {
+            parts.push(change);
+        }
+        if let Some(average) = today_info.weekly_average.filter(|_| today_info.cost.is_some()) {
+            parts.push(format!("({}d avg {})", AVERAGE_DAYS, format_cost(average)));
+        }
+
+        let formatted = format!(" {} ", parts.join(" "));
+        let budget = ctx.config.budget.as_ref().and_then(|budget| budget.today.as_ref());
+        if let Some(budget) = budget.filter(|budget| self.heat_map && budget.amount > 0.0) {
+            let spent = budget_usage(budget, today_info.cost, today_info.tokens) / budget.amount;
+            return Ok(apply_heat_colors(&formatted, self.name(), spent, &ctx.theme));
+        }
+        Ok(apply_theme_colors(&formatted, self.name(), &ctx.theme))
+    }
 }
\ No newline at end of file
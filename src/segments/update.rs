@@ -0,0 +1,70 @@
+use crate::segments::{Segment, SegmentContext};
+use crate::themes::apply_theme_colors;
+use crate::utils::{spawn_update_check, UpdateState, CURRENT_VERSION};
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use std::path::PathBuf;
+
+/// Shows `⬆ v1.4.0` when a newer release than this binary is out. Only the result of the last
+/// lookup is read; a due lookup runs in the background and shows up on a later render.
+pub struct UpdateSegment {
+    pub enabled: bool,
+    /// How long a lookup's answer is trusted before the releases are asked again
+    pub interval: Duration,
+    /// Where lookups are recorded; `None` uses the state directory
+    state_path: Option<PathBuf>,
+}
+
+impl UpdateSegment {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            interval: Duration::hours(24),
+            state_path: None,
+        }
+    }
+
+    pub fn with_state_path(mut self, path: PathBuf) -> Self {
+        self.state_path = Some(path);
+        self
+    }
+}
+
+impl Default for UpdateSegment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Segment for UpdateSegment {
+    type Data = Option<String>;
+
+    fn name(&self) -> &'static str {
+        "update"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    async fn data(&self, _ctx: &SegmentContext) -> Result<Option<String>> {
+        let Some(path) = self.state_path.clone().or_else(UpdateState::default_path) else {
+            return Ok(None);
+        };
+
+        let state = UpdateState::load(&path);
+        let newer = state.newer_than(CURRENT_VERSION).map(str::to_string);
+        if state.is_due(Utc::now(), self.interval) {
+            spawn_update_check(&path, state);
+        }
+        Ok(newer)
+    }
+
+    fn render(&self, newer: Option<String>, ctx: &SegmentContext) -> Result<String> {
+        let Some(version) = newer else {
+            return Ok(String::new());
+        };
+        let formatted = format!(" ⬆ v{} ", version);
+        Ok(apply_theme_colors(&formatted, self.name(), &ctx.theme))
+    }
+}
@@ -0,0 +1,179 @@
+use anyhow::{Context, Result};
+use crate::utils::write_atomic;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// What `install_status_line` did to the settings file
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetupOutcome {
+    /// There was no statusLine before
+    Installed { backup: Option<PathBuf> },
+    /// A statusLine running `previous` was replaced
+    Updated { previous: String, backup: PathBuf },
+    /// The statusLine already ran this command; nothing was written
+    Unchanged,
+}
+
+/// What `remove_status_line` did to the settings file
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoveOutcome {
+    /// The statusLine was deleted; no backup held an earlier one
+    Removed { backup: PathBuf },
+    /// The statusLine found in `from`, from before setup replaced it, was put back
+    Restored { command: String, from: PathBuf, backup: PathBuf },
+    /// No statusLine running this binary; `other` is whatever runs instead
+    NotInstalled { other: Option<String> },
+}
+
+/// The user settings file Claude Code reads the statusLine from: `settings.json` in the first
+/// `CLAUDE_CONFIG_DIR` entry, or `~/.claude/settings.json`
+pub fn default_settings_path() -> Option<PathBuf> {
+    if let Ok(dirs) = std::env::var("CLAUDE_CONFIG_DIR") {
+        let separator = if cfg!(windows) { ';' } else { ',' };
+        if let Some(dir) = dirs.split(separator).map(str::trim).find(|dir| !dir.is_empty()) {
+            return Some(PathBuf::from(dir).join("settings.json"));
+        }
+    }
+    dirs::home_dir().map(|home| home.join(".claude").join("settings.json"))
+}
+
+/// The statusLine command running `binary` with `flags`, each quoted if it needs to be
+pub fn status_line_command(binary: &Path, flags: &[String]) -> String {
+    std::iter::once(binary.to_string_lossy().into_owned())
+        .chain(flags.iter().cloned())
+        .map(|word| shell_quote(&word))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn shell_quote(word: &str) -> String {
+    if !word.is_empty() && !word.contains(|c: char| c.is_whitespace() || "\"'$`\\&;|<>()*?#~".contains(c)) {
+        return word.to_string();
+    }
+    format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\"").replace('$', "\\$").replace('`', "\\`"))
+}
+
+/// Point the statusLine of the settings file at `path` to `command`, keeping every other
+/// setting (and any other statusLine option, such as `padding`). The file is created if
+/// missing; an existing one is first copied to the next free `settings.json.claude-powerline.bak[.N]`,
+/// so the original is never overwritten. A file that isn't valid JSON is left alone.
+pub fn install_status_line(path: &Path, command: &str) -> Result<SetupOutcome> {
+    let mut settings = read_settings(path)?;
+    let Some(object) = settings.as_object_mut() else {
+        anyhow::bail!("{} does not hold a JSON object", path.display());
+    };
+
+    let previous = status_line_command_of(object.get("statusLine"));
+    let is_command = object.get("statusLine").and_then(|status_line| status_line.get("type")) == Some(&json!("command"));
+    if is_command && previous.as_deref() == Some(command) {
+        return Ok(SetupOutcome::Unchanged);
+    }
+
+    let status_line = object.entry("statusLine").or_insert_with(|| json!({}));
+    if !status_line.is_object() {
+        *status_line = json!({});
+    }
+    status_line["type"] = json!("command");
+    status_line["command"] = json!(command);
+
+    let backup = if path.exists() { Some(backup_file(path)?) } else { None };
+    write_settings(path, &settings)?;
+
+    Ok(match (previous, backup) {
+        (Some(previous), Some(backup)) => SetupOutcome::Updated { previous, backup },
+        (_, backup) => SetupOutcome::Installed { backup },
+    })
+}
+
+/// Undo `install_status_line`: take out the statusLine if it runs `binary`, as setup installs
+/// it, and nothing else; another claude-powerline (such as the npm package) isn't touched. If a
+/// backup made by setup holds a statusLine running something else, the newest such one is put
+/// back instead. The file is backed up before it changes, like on install.
+pub fn remove_status_line(path: &Path, binary: &Path) -> Result<RemoveOutcome> {
+    if !path.exists() {
+        return Ok(RemoveOutcome::NotInstalled { other: None });
+    }
+    let mut settings = read_settings(path)?;
+    let Some(object) = settings.as_object_mut() else {
+        anyhow::bail!("{} does not hold a JSON object", path.display());
+    };
+
+    let command = status_line_command_of(object.get("statusLine"));
+    if !command.as_deref().is_some_and(|command| is_own_command(command, binary)) {
+        return Ok(RemoveOutcome::NotInstalled { other: command });
+    }
+
+    // The newest backup whose statusLine was someone else's
+    let earlier = backup_files(path).into_iter().rev().find_map(|backup| {
+        let status_line = read_settings(&backup).ok()?.get("statusLine")?.clone();
+        let command = status_line_command_of(Some(&status_line))?;
+        (!is_own_command(&command, binary)).then_some((backup, status_line, command))
+    });
+
+    let backup = backup_file(path)?;
+    let outcome = match earlier {
+        Some((from, status_line, command)) => {
+            object.insert("statusLine".to_string(), status_line);
+            RemoveOutcome::Restored { command, from, backup }
+        }
+        None => {
+            // shift_remove keeps the other keys where they were
+            object.shift_remove("statusLine");
+            RemoveOutcome::Removed { backup }
+        }
+    };
+    write_settings(path, &settings)?;
+    Ok(outcome)
+}
+
+fn status_line_command_of(status_line: Option<&Value>) -> Option<String> {
+    status_line?.get("command")?.as_str().map(str::to_string)
+}
+
+/// Whether `command` runs `binary`, with or without flags, as `setup` writes it
+fn is_own_command(command: &str, binary: &Path) -> bool {
+    let own = status_line_command(binary, &[]);
+    command.strip_prefix(&own).is_some_and(|flags| flags.is_empty() || flags.starts_with(' '))
+}
+
+/// A settings file as JSON; a missing or empty file is an empty object
+fn read_settings(path: &Path) -> Result<Value> {
+    match std::fs::read_to_string(path) {
+        Ok(content) if content.trim().is_empty() => Ok(json!({})),
+        Ok(content) => serde_json::from_str::<Value>(&content)
+            .with_context(|| format!("{} is not valid JSON; fix it or move it aside first", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(json!({})),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+}
+
+fn write_settings(path: &Path, settings: &Value) -> Result<()> {
+    // Written aside and renamed into place, so Claude Code never reads half a file
+    write_atomic(path, format!("{}\n", serde_json::to_string_pretty(settings)?).as_bytes())
+}
+
+/// `<path>.claude-powerline.bak`, `<path>.claude-powerline.bak.1`, ...: the backups setup made
+/// so far, oldest first. Their own name keeps any `<path>.bak` of the user's out of them.
+fn backup_files(path: &Path) -> Vec<PathBuf> {
+    (0..).map(|n| backup_name(path, n)).take_while(|backup| backup.exists()).collect()
+}
+
+fn backup_name(path: &Path, n: usize) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    match n {
+        0 => path.with_file_name(format!("{}.claude-powerline.bak", name)),
+        n => path.with_file_name(format!("{}.claude-powerline.bak.{}", name, n)),
+    }
+}
+
+/// Copy `path` to the first of `<path>.claude-powerline.bak`, `.bak.1`, ... that doesn't exist yet
+fn backup_file(path: &Path) -> Result<PathBuf> {
+    let backup = (0..)
+        .map(|n| backup_name(path, n))
+        .find(|candidate| !candidate.exists())
+        .expect("some backup name is free");
+
+    std::fs::copy(path, &backup)
+        .with_context(|| format!("Failed to back up {} to {}", path.display(), backup.display()))?;
+    Ok(backup)
+}
@@ -0,0 +1,166 @@
+use crate::sources::{SessionChain, UsageSource};
+use crate::utils::{debug_with_context, deduplicate_entries, find_transcript_file, is_transcript_path, DataAggregator, ParsedEntry, ProjectUuidIndex};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Most transcripts followed back from a session, so a malformed link can't read the whole history
+const MAX_CHAIN_TRANSCRIPTS: usize = 32;
+
+/// Claude Code's JSONL transcripts under every Claude config directory
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClaudeTranscriptSource;
+
+impl ClaudeTranscriptSource {
+    /// Follow a session's transcript back through the sessions it was resumed or branched from.
+    /// A resumed session starts a new transcript whose entries still carry the earlier session's
+    /// `sessionId`, or whose first `parentUuid` names an entry of the earlier transcript; the
+    /// latter is looked up among the transcripts of the same project.
+    async fn load_session_chain(&self, session_id: &str, transcript_path: PathBuf) -> Result<SessionChain> {
+        let aggregator = DataAggregator::new();
+        let mut sessions = HashSet::from([session_id.to_string()]);
+        let mut transcripts = vec![transcript_path.clone()];
+        let mut entries = aggregator.load_session_entries(&transcript_path).await?;
+        let mut uuid_index: Option<ProjectUuidIndex> = None;
+
+        while transcripts.len() < MAX_CHAIN_TRANSCRIPTS {
+            let mut next = None;
+
+            // Earlier sessions named outright
+            for sid in entries.iter().filter_map(|entry| entry.raw.get("sessionId")?.as_str()) {
+                if sessions.insert(sid.to_string()) {
+                    if let Some(path) = find_transcript_file(sid).await?.filter(|path| !transcripts.contains(path)) {
+                        next = Some((path.clone(), aggregator.load_session_entries(&path).await?));
+                        break;
+                    }
+                }
+            }
+
+            // Parents outside every transcript read so far
+            if next.is_none() {
+                let uuids: HashSet<&str> = entries.iter().filter_map(|entry| entry.raw.get("uuid")?.as_str()).collect();
+                let dangling: HashSet<&str> = entries
+                    .iter()
+                    .filter_map(|entry| entry.raw.get("parentUuid")?.as_str())
+                    .filter(|parent| !uuids.contains(parent))
+                    .collect();
+
+                if !dangling.is_empty() {
+                    // Brought up to date once, and only when some parent is missing
+                    let index = match uuid_index {
+                        Some(ref index) => index,
+                        None => uuid_index.insert(project_uuid_index(&aggregator, &transcript_path).await),
+                    };
+                    if let Some(path) = index.find(dangling.iter().copied(), &transcripts).map(Path::to_path_buf) {
+                        let earlier = aggregator.load_session_entries(&path).await?;
+                        // A hash collision would name a transcript without the parent
+                        let holds_parent = earlier
+                            .iter()
+                            .any(|entry| entry.raw.get("uuid").and_then(|v| v.as_str()).is_some_and(|uuid| dangling.contains(uuid)));
+                        next = holds_parent.then_some((path, earlier));
+                    }
+                }
+            }
+
+            let Some((path, earlier)) = next else {
+                break;
+            };
+            debug_with_context("claude_source", &format!("Session resumed from transcript: {}", path.display()));
+            transcripts.push(path);
+            entries.extend(earlier);
+        }
+
+        // Resumed transcripts repeat the history they continue from
+        entries.sort_by_key(|entry| entry.timestamp);
+        let (entries, _) = deduplicate_entries(entries);
+        Ok(SessionChain { entries, sessions: transcripts.len() })
+    }
+}
+
+/// The uuid index of the project `transcript_path` is in, with every transcript of the project
+/// that changed since it was last indexed read again (and the index saved, if there's a cache).
+/// The index file is read and written on the blocking pool, the directory through `tokio::fs`.
+async fn project_uuid_index(aggregator: &DataAggregator, transcript_path: &Path) -> ProjectUuidIndex {
+    let Some(project_dir) = transcript_path.parent() else {
+        return ProjectUuidIndex::default();
+    };
+    let index_path = ProjectUuidIndex::path_for(project_dir);
+    let load_path = index_path.clone();
+    let mut index = tokio::task::spawn_blocking(move || load_path.as_deref().map(ProjectUuidIndex::load).unwrap_or_default())
+        .await
+        .unwrap_or_default();
+    let Ok(mut dir) = tokio::fs::read_dir(project_dir).await else {
+        return index;
+    };
+
+    let mut present = Vec::new();
+    while let Ok(Some(entry)) = dir.next_entry().await {
+        let path = entry.path();
+        if !is_transcript_path(&path) {
+            continue;
+        }
+        let Ok(metadata) = tokio::fs::metadata(&path).await else {
+            continue;
+        };
+        if !index.is_current(&path, &metadata) {
+            if let Ok(entries) = aggregator.load_session_entries(&path).await {
+                index.insert(&path, &metadata, &entries);
+            }
+        }
+        present.push(path);
+    }
+    index.retain(&present);
+
+    let Some(path) = index_path else {
+        return index;
+    };
+    let saved = tokio::task::spawn_blocking(move || {
+        if let Err(e) = index.save(&path) {
+            debug_with_context("claude_source", &format!("Failed to save the uuid index: {:#}", e));
+        }
+        index
+    });
+    saved.await.unwrap_or_default()
+}
+
+impl UsageSource for ClaudeTranscriptSource {
+    fn name(&self) -> &'static str {
+        "claude"
+    }
+
+    fn entries_since(&self, since: DateTime<Utc>) -> BoxFuture<'_, Result<Vec<ParsedEntry>>> {
+        Box::pin(async move {
+            // The aggregator filters by whole hours; round up so nothing inside the window is skipped
+            let minutes = (Utc::now() - since).num_minutes().max(0);
+            let hours = ((minutes + 59) / 60) as u32;
+
+            let mut entries = DataAggregator::new().with_time_filter(hours).load_all_entries().await?;
+            let start = entries.partition_point(|entry| entry.timestamp < since);
+            entries.drain(..start);
+            Ok(entries)
+        })
+    }
+
+    fn session_entries<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, Result<Option<Vec<ParsedEntry>>>> {
+        Box::pin(async move {
+            let Some(transcript_path) = find_transcript_file(session_id).await? else {
+                return Ok(None);
+            };
+
+            debug_with_context("claude_source", &format!("Loading session transcript: {}", transcript_path.display()));
+            let entries = DataAggregator::new().load_session_entries(&transcript_path).await?;
+            Ok(Some(entries))
+        })
+    }
+
+    fn session_chain_entries<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, Result<Option<SessionChain>>> {
+        Box::pin(async move {
+            let Some(transcript_path) = find_transcript_file(session_id).await? else {
+                return Ok(None);
+            };
+            Ok(Some(self.load_session_chain(session_id, transcript_path).await?))
+        })
+    }
+}
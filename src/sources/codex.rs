@@ -0,0 +1,205 @@
+use crate::sources::UsageSource;
+use crate::utils::claude::{MessageInfo, UsageInfo};
+use crate::utils::{debug_with_context, intern, record_parse, split_lines_mut, ParsedEntry};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use walkdir::WalkDir;
+
+/// OpenAI Codex CLI's session logs: `sessions/YYYY/MM/DD/rollout-<time>-<session id>.jsonl`.
+///
+/// Each `token_count` event carries the session's cumulative usage, which is exactly what
+/// entries hold, so every change in the running total becomes one entry.
+pub struct CodexSource {
+    sessions_dir: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct RolloutLine {
+    timestamp: String,
+    #[serde(rename = "type")]
+    line_type: String,
+    #[serde(default)]
+    payload: serde_json::Value,
+}
+
+/// `total_token_usage` of a `token_count` event. OpenAI counts cached input as part of the input.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+struct TokenUsage {
+    #[serde(default)]
+    input_tokens: u32,
+    #[serde(default)]
+    cached_input_tokens: u32,
+    #[serde(default)]
+    output_tokens: u32,
+}
+
+impl CodexSource {
+    /// Read from a Codex home directory (the one holding `sessions/`)
+    pub fn new(codex_home: PathBuf) -> Self {
+        Self {
+            sessions_dir: codex_home.join("sessions"),
+        }
+    }
+
+    /// `$CODEX_HOME`, or `~/.codex`
+    pub fn default_home() -> Option<PathBuf> {
+        std::env::var_os("CODEX_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".codex")))
+    }
+
+    /// Rollout files modified at or after `since`
+    fn rollout_files(&self, since: Option<DateTime<Utc>>) -> Vec<PathBuf> {
+        WalkDir::new(&self.sessions_dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                name.starts_with("rollout-") && name.ends_with(".jsonl")
+            })
+            .filter(|entry| since.is_none_or(|since| {
+                entry.metadata()
+                    .ok()
+                    .and_then(|metadata| metadata.modified().ok())
+                    .is_none_or(|modified| DateTime::<Utc>::from(modified) >= since)
+            }))
+            .map(|entry| entry.into_path())
+            .collect()
+    }
+
+    fn load_files(&self, files: &[PathBuf]) -> Vec<ParsedEntry> {
+        let mut entries: Vec<ParsedEntry> = files
+            .par_iter()
+            .flat_map(|path| match read_rollout(path) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    debug_with_context("codex", &format!("Failed to read {}: {:#}", path.display(), e));
+                    Vec::new()
+                }
+            })
+            .collect();
+        record_parse(files.len(), entries.len());
+
+        entries.sort_by_key(|entry| entry.timestamp);
+        entries
+    }
+}
+
+impl UsageSource for CodexSource {
+    fn name(&self) -> &'static str {
+        "codex"
+    }
+
+    fn entries_since(&self, since: DateTime<Utc>) -> BoxFuture<'_, Result<Vec<ParsedEntry>>> {
+        Box::pin(async move {
+            let mut entries = self.load_files(&self.rollout_files(Some(since)));
+            let start = entries.partition_point(|entry| entry.timestamp < since);
+            entries.drain(..start);
+
+            debug_with_context("codex", &format!("Loaded {} entries from {}", entries.len(), self.sessions_dir.display()));
+            Ok(entries)
+        })
+    }
+
+    fn session_entries<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, Result<Option<Vec<ParsedEntry>>>> {
+        Box::pin(async move {
+            let suffix = format!("{}.jsonl", session_id);
+            let files: Vec<PathBuf> = self.rollout_files(None)
+                .into_iter()
+                .filter(|path| path.to_string_lossy().ends_with(&suffix))
+                .collect();
+
+            if files.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(self.load_files(&files)))
+        })
+    }
+}
+
+/// Entries for every change of the cumulative token total in one rollout file
+fn read_rollout(path: &Path) -> Result<Vec<ParsedEntry>> {
+    let mut content = std::fs::read(path)
+        .with_context(|| format!("Failed to read rollout: {}", path.display()))?;
+
+    let source_file = intern(&path.to_string_lossy());
+    let mut session_id = String::new();
+    let mut cwd: Option<String> = None;
+    let mut model: Option<Arc<str>> = None;
+    let mut previous = TokenUsage::default();
+    let mut entries = Vec::new();
+
+    for (index, line) in split_lines_mut(&mut content).enumerate() {
+        let Ok(line) = serde_json::from_slice::<RolloutLine>(line) else {
+            continue;
+        };
+        let payload = &line.payload;
+
+        match line.line_type.as_str() {
+            "session_meta" => {
+                session_id = payload.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                cwd = payload.get("cwd").and_then(|v| v.as_str()).map(str::to_string).or(cwd);
+            }
+            "turn_context" => {
+                model = payload.get("model").and_then(|v| v.as_str()).map(intern).or(model);
+                cwd = payload.get("cwd").and_then(|v| v.as_str()).map(str::to_string).or(cwd);
+            }
+            "event_msg" if payload.get("type").and_then(|v| v.as_str()) == Some("token_count") => {
+                let Some(usage) = payload
+                    .get("info")
+                    .and_then(|info| info.get("total_token_usage"))
+                    .and_then(|usage| serde_json::from_value::<TokenUsage>(usage.clone()).ok())
+                else {
+                    continue;
+                };
+                // Codex repeats the last total on some events; only changes are usage
+                if usage == previous {
+                    continue;
+                }
+                let Ok(timestamp) = DateTime::parse_from_rfc3339(&line.timestamp) else {
+                    continue;
+                };
+
+                let id = format!("{}:{}", session_id, index);
+                let mut raw = HashMap::new();
+                raw.insert("sessionId".to_string(), serde_json::Value::from(session_id.clone()));
+                raw.insert("requestId".to_string(), serde_json::Value::from(id.clone()));
+                if let Some(cwd) = &cwd {
+                    raw.insert("cwd".to_string(), serde_json::Value::from(cwd.clone()));
+                }
+
+                entries.push(ParsedEntry {
+                    timestamp: timestamp.with_timezone(&Utc),
+                    message: Some(MessageInfo {
+                        id: Some(id),
+                        usage: Some(UsageInfo {
+                            input_tokens: Some(usage.input_tokens.saturating_sub(usage.cached_input_tokens)),
+                            output_tokens: Some(usage.output_tokens),
+                            cache_creation_input_tokens: Some(0),
+                            cache_read_input_tokens: Some(usage.cached_input_tokens),
+                            cache_creation: None,
+                            service_tier: None,
+                        }),
+                        model: model.clone(),
+                    }),
+                    cost_usd: None,
+                    source_file: Some(source_file.clone()),
+                    is_sidechain: None,
+                    raw,
+                });
+                previous = usage;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
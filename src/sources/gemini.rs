@@ -0,0 +1,186 @@
+use crate::sources::UsageSource;
+use crate::utils::claude::{MessageInfo, UsageInfo};
+use crate::utils::{debug_with_context, intern, record_parse, ParsedEntry};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Gemini CLI's recorded chats: one JSON file per session under
+/// `tmp/<project hash>/chats/session-<time>-<session id prefix>.json`.
+///
+/// Like OpenCode, each message carries only its own tokens, so a session's messages
+/// are turned into running totals.
+pub struct GeminiSource {
+    tmp_dir: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConversationRecord {
+    #[serde(rename = "sessionId")]
+    session_id: String,
+    #[serde(default)]
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    id: String,
+    timestamp: String,
+    #[serde(rename = "type")]
+    message_type: String,
+    tokens: Option<MessageTokens>,
+    model: Option<String>,
+}
+
+/// Token counts of one reply. Gemini counts cached prompt tokens as part of the input.
+#[derive(Debug, Default, Deserialize)]
+struct MessageTokens {
+    #[serde(default)]
+    input: u32,
+    #[serde(default)]
+    output: u32,
+    #[serde(default)]
+    cached: u32,
+    #[serde(default)]
+    thoughts: u32,
+}
+
+impl GeminiSource {
+    /// Read from a Gemini CLI home directory (the one holding `tmp/`)
+    pub fn new(gemini_home: PathBuf) -> Self {
+        Self {
+            tmp_dir: gemini_home.join("tmp"),
+        }
+    }
+
+    /// `~/.gemini`
+    pub fn default_home() -> Option<PathBuf> {
+        dirs::home_dir().map(|home| home.join(".gemini"))
+    }
+
+    /// Chat files modified at or after `since` whose name contains `name_filter`
+    fn chat_files(&self, since: Option<DateTime<Utc>>, name_filter: &str) -> Vec<PathBuf> {
+        WalkDir::new(&self.tmp_dir)
+            .min_depth(3)
+            .max_depth(3)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                name.starts_with("session-") && name.ends_with(".json") && name.contains(name_filter)
+            })
+            .filter(|entry| since.is_none_or(|since| {
+                entry.metadata()
+                    .ok()
+                    .and_then(|metadata| metadata.modified().ok())
+                    .is_none_or(|modified| DateTime::<Utc>::from(modified) >= since)
+            }))
+            .map(|entry| entry.into_path())
+            .collect()
+    }
+
+    /// Entries of every conversation in `files` accepted by `keep`, sorted by timestamp
+    fn load_chats(&self, files: &[PathBuf], keep: impl Fn(&ConversationRecord) -> bool + Sync) -> Vec<ParsedEntry> {
+        let mut entries: Vec<ParsedEntry> = files
+            .par_iter()
+            .flat_map(|path| match read_chat(path) {
+                Ok(record) if keep(&record) => conversation_entries(path, record),
+                Ok(_) => Vec::new(),
+                Err(e) => {
+                    debug_with_context("gemini", &format!("Failed to read {}: {:#}", path.display(), e));
+                    Vec::new()
+                }
+            })
+            .collect();
+        record_parse(files.len(), entries.len());
+
+        entries.sort_by_key(|entry| entry.timestamp);
+        entries
+    }
+}
+
+impl UsageSource for GeminiSource {
+    fn name(&self) -> &'static str {
+        "gemini"
+    }
+
+    fn entries_since(&self, since: DateTime<Utc>) -> BoxFuture<'_, Result<Vec<ParsedEntry>>> {
+        Box::pin(async move {
+            let mut entries = self.load_chats(&self.chat_files(Some(since), ""), |_| true);
+            let start = entries.partition_point(|entry| entry.timestamp < since);
+            entries.drain(..start);
+
+            debug_with_context("gemini", &format!("Loaded {} entries from {}", entries.len(), self.tmp_dir.display()));
+            Ok(entries)
+        })
+    }
+
+    fn session_entries<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, Result<Option<Vec<ParsedEntry>>>> {
+        Box::pin(async move {
+            // File names only carry the first 8 characters of the session id
+            let prefix = session_id.get(..8).unwrap_or(session_id);
+            let files = self.chat_files(None, prefix);
+            let entries = self.load_chats(&files, |record| record.session_id == session_id);
+
+            Ok((!entries.is_empty()).then_some(entries))
+        })
+    }
+}
+
+fn read_chat(path: &Path) -> Result<ConversationRecord> {
+    let content = std::fs::read(path)
+        .with_context(|| format!("Failed to read chat: {}", path.display()))?;
+    serde_json::from_slice(&content)
+        .with_context(|| format!("Failed to parse chat: {}", path.display()))
+}
+
+/// Gemini replies of one conversation as entries with cumulative usage
+fn conversation_entries(path: &Path, record: ConversationRecord) -> Vec<ParsedEntry> {
+    let source_file = intern(&path.to_string_lossy());
+    let mut total = MessageTokens::default();
+
+    record.messages
+        .into_iter()
+        .filter(|message| message.message_type == "gemini")
+        .filter_map(|message| {
+            let tokens = message.tokens?;
+            let timestamp = DateTime::parse_from_rfc3339(&message.timestamp).ok()?.with_timezone(&Utc);
+
+            // Thinking tokens are billed as output. Running totals saturate rather than wrap in
+            // a conversation long (or corrupt) enough to pass u32::MAX
+            total.input = total.input.saturating_add(tokens.input.saturating_sub(tokens.cached));
+            total.output = total.output.saturating_add(tokens.output.saturating_add(tokens.thoughts));
+            total.cached = total.cached.saturating_add(tokens.cached);
+
+            let mut raw = HashMap::new();
+            raw.insert("sessionId".to_string(), serde_json::Value::from(record.session_id.clone()));
+            raw.insert("requestId".to_string(), serde_json::Value::from(message.id.clone()));
+
+            Some(ParsedEntry {
+                timestamp,
+                message: Some(MessageInfo {
+                    id: Some(message.id),
+                    usage: Some(UsageInfo {
+                        input_tokens: Some(total.input),
+                        output_tokens: Some(total.output),
+                        cache_creation_input_tokens: Some(0),
+                        cache_read_input_tokens: Some(total.cached),
+                        cache_creation: None,
+                        service_tier: None,
+                    }),
+                    model: message.model.as_deref().map(intern),
+                }),
+                cost_usd: None,
+                source_file: Some(source_file.clone()),
+                is_sidechain: None,
+                raw,
+            })
+        })
+        .collect()
+}
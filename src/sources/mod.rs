@@ -0,0 +1,154 @@
+pub mod claude;
+pub mod codex;
+pub mod gemini;
+pub mod opencode;
+
+pub use claude::*;
+pub use codex::*;
+pub use gemini::*;
+pub use opencode::*;
+
+use crate::config::{Config, SourcesConfig};
+use crate::utils::{debug_with_context, ParsedEntry};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// A backend that usage entries are read from. Segments only see entries, so another
+/// backend (a usage index, a remote API, another CLI's logs) plugs in by implementing this.
+pub trait UsageSource: Send + Sync {
+    /// Short identifier used in config and debug logs
+    fn name(&self) -> &'static str;
+
+    /// Deduplicated entries timestamped at or after `since`, sorted by timestamp
+    fn entries_since(&self, since: DateTime<Utc>) -> BoxFuture<'_, Result<Vec<ParsedEntry>>>;
+
+    /// Entries of the session `session_id`, or `None` when this source has no such session
+    fn session_entries<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, Result<Option<Vec<ParsedEntry>>>>;
+
+    /// Entries of the session `session_id` and of the sessions it was resumed or branched from.
+    /// Sources that don't record resumes return the session alone.
+    fn session_chain_entries<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, Result<Option<SessionChain>>> {
+        Box::pin(async move {
+            Ok(self.session_entries(session_id).await?.map(|entries| SessionChain { entries, sessions: 1 }))
+        })
+    }
+
+    /// Deduplicated entries timestamped within `range`, sorted by timestamp
+    fn entries_in(&self, range: Range<DateTime<Utc>>) -> BoxFuture<'_, Result<Vec<ParsedEntry>>> {
+        Box::pin(async move {
+            let mut entries = self.entries_since(range.start).await?;
+            let end = entries.partition_point(|entry| entry.timestamp < range.end);
+            entries.truncate(end);
+            Ok(entries)
+        })
+    }
+}
+
+/// A session together with the sessions it continues
+#[derive(Debug, Clone, Default)]
+pub struct SessionChain {
+    /// Entries of every session in the chain, deduplicated and sorted by timestamp
+    pub entries: Vec<ParsedEntry>,
+    /// Sessions in the chain, the requested one included
+    pub sessions: usize,
+}
+
+/// Several sources read as one, so totals cover every tool in use. A source that fails
+/// is logged and skipped rather than hiding the others.
+pub struct MergedSource {
+    sources: Vec<Arc<dyn UsageSource>>,
+}
+
+impl MergedSource {
+    pub fn new(sources: Vec<Arc<dyn UsageSource>>) -> Self {
+        Self { sources }
+    }
+}
+
+impl UsageSource for MergedSource {
+    fn name(&self) -> &'static str {
+        "merged"
+    }
+
+    fn entries_since(&self, since: DateTime<Utc>) -> BoxFuture<'_, Result<Vec<ParsedEntry>>> {
+        Box::pin(async move {
+            let loads = self.sources.iter().map(|source| source.entries_since(since));
+            let mut entries = Vec::new();
+            for (source, result) in self.sources.iter().zip(futures::future::join_all(loads).await) {
+                match result {
+                    Ok(loaded) => entries.extend(loaded),
+                    Err(e) => debug_with_context("sources", &format!("Skipping '{}' source: {:#}", source.name(), e)),
+                }
+            }
+
+            entries.sort_by_key(|entry| entry.timestamp);
+            Ok(entries)
+        })
+    }
+
+    fn session_entries<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, Result<Option<Vec<ParsedEntry>>>> {
+        Box::pin(async move {
+            for source in &self.sources {
+                if let Some(entries) = source.session_entries(session_id).await? {
+                    return Ok(Some(entries));
+                }
+            }
+            Ok(None)
+        })
+    }
+
+    fn session_chain_entries<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, Result<Option<SessionChain>>> {
+        Box::pin(async move {
+            for source in &self.sources {
+                if let Some(chain) = source.session_chain_entries(session_id).await? {
+                    return Ok(Some(chain));
+                }
+            }
+            Ok(None)
+        })
+    }
+}
+
+/// The usage source selected by `config.sources`: Claude Code's transcripts unless
+/// other sources are enabled, and all enabled sources merged when there are several
+pub fn source_from_config(config: &Config) -> Arc<dyn UsageSource> {
+    let sources_config = config.sources.clone().unwrap_or_default();
+    let mut sources: Vec<Arc<dyn UsageSource>> = sources_config
+        .enabled_sources()
+        .iter()
+        .filter_map(|name| source_by_name(name, &sources_config))
+        .collect();
+
+    match sources.len() {
+        0 => Arc::new(ClaudeTranscriptSource),
+        1 => sources.remove(0),
+        _ => Arc::new(MergedSource::new(sources)),
+    }
+}
+
+/// The source called `name`, reading from the directory configured for it, or `None`
+/// when the name is unknown or its directory can't be determined
+pub fn source_by_name(name: &str, sources_config: &SourcesConfig) -> Option<Arc<dyn UsageSource>> {
+    match name {
+        "claude" => Some(Arc::new(ClaudeTranscriptSource)),
+        "opencode" => {
+            let dir = sources_config.opencode_dir.clone().or_else(OpenCodeSource::default_data_dir)?;
+            Some(Arc::new(OpenCodeSource::new(dir)))
+        }
+        "codex" => {
+            let dir = sources_config.codex_dir.clone().or_else(CodexSource::default_home)?;
+            Some(Arc::new(CodexSource::new(dir)))
+        }
+        "gemini" => {
+            let dir = sources_config.gemini_dir.clone().or_else(GeminiSource::default_home)?;
+            Some(Arc::new(GeminiSource::new(dir)))
+        }
+        other => {
+            debug_with_context("sources", &format!("Unknown usage source '{}'", other));
+            None
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use crate::sources::UsageSource;
+use crate::utils::claude::{MessageInfo, UsageInfo};
+use crate::utils::{debug_with_context, intern, record_parse, ParsedEntry};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// OpenCode's session storage: one JSON file per message under
+/// `storage/message/<sessionID>/`, and one per session under `storage/session/<projectID>/`.
+///
+/// OpenCode records the tokens of each message on its own, while entries here carry
+/// cumulative per-session usage like Claude Code's transcripts, so each session's
+/// messages are turned into running totals.
+pub struct OpenCodeSource {
+    storage_dir: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoredMessage {
+    id: String,
+    #[serde(rename = "sessionID")]
+    session_id: String,
+    role: String,
+    time: MessageTime,
+    #[serde(rename = "modelID")]
+    model_id: Option<String>,
+    tokens: Option<MessageTokens>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageTime {
+    /// Milliseconds since the Unix epoch
+    created: i64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MessageTokens {
+    #[serde(default)]
+    input: u32,
+    #[serde(default)]
+    output: u32,
+    #[serde(default)]
+    reasoning: u32,
+    #[serde(default)]
+    cache: CacheTokens,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CacheTokens {
+    #[serde(default)]
+    read: u32,
+    #[serde(default)]
+    write: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoredSession {
+    id: String,
+    directory: Option<String>,
+}
+
+impl OpenCodeSource {
+    /// Read from OpenCode's data directory (the one holding `storage/`)
+    pub fn new(data_dir: PathBuf) -> Self {
+        Self {
+            storage_dir: data_dir.join("storage"),
+        }
+    }
+
+    /// `$XDG_DATA_HOME/opencode`, falling back to `~/.local/share/opencode` on every platform as OpenCode does
+    pub fn default_data_dir() -> Option<PathBuf> {
+        std::env::var_os("XDG_DATA_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("share")))
+            .map(|dir| dir.join("opencode"))
+    }
+
+    /// Every session directory under `storage/message` modified at or after `since`
+    fn session_dirs(&self, since: Option<DateTime<Utc>>) -> Vec<PathBuf> {
+        WalkDir::new(self.storage_dir.join("message"))
+            .min_depth(1)
+            .max_depth(1)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_dir())
+            .filter(|entry| since.is_none_or(|since| modified_since(entry.path(), since)))
+            .map(|entry| entry.into_path())
+            .collect()
+    }
+
+    /// Working directory of each session, keyed by session id
+    fn session_directories(&self) -> HashMap<String, String> {
+        WalkDir::new(self.storage_dir.join("session"))
+            .min_depth(2)
+            .max_depth(2)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|entry| serde_json::from_slice::<StoredSession>(&std::fs::read(entry.path()).ok()?).ok())
+            .filter_map(|session| Some((session.id, session.directory?)))
+            .collect()
+    }
+
+    /// Entries of every session in `session_dirs`, sorted by timestamp
+    fn load_sessions(&self, session_dirs: &[PathBuf]) -> Vec<ParsedEntry> {
+        let directories = self.session_directories();
+
+        let mut entries: Vec<ParsedEntry> = session_dirs
+            .par_iter()
+            .flat_map(|dir| read_session(dir, &directories))
+            .collect();
+        record_parse(session_dirs.len(), entries.len());
+
+        entries.sort_by_key(|entry| entry.timestamp);
+        entries
+    }
+}
+
+impl UsageSource for OpenCodeSource {
+    fn name(&self) -> &'static str {
+        "opencode"
+    }
+
+    fn entries_since(&self, since: DateTime<Utc>) -> BoxFuture<'_, Result<Vec<ParsedEntry>>> {
+        Box::pin(async move {
+            // Whole sessions are loaded so the running totals start from the session's first message
+            let mut entries = self.load_sessions(&self.session_dirs(Some(since)));
+            let start = entries.partition_point(|entry| entry.timestamp < since);
+            entries.drain(..start);
+
+            debug_with_context("opencode", &format!("Loaded {} entries from {}", entries.len(), self.storage_dir.display()));
+            Ok(entries)
+        })
+    }
+
+    fn session_entries<'a>(&'a self, session_id: &'a str) -> BoxFuture<'a, Result<Option<Vec<ParsedEntry>>>> {
+        Box::pin(async move {
+            let dir = self.storage_dir.join("message").join(session_id);
+            if !dir.is_dir() {
+                return Ok(None);
+            }
+            Ok(Some(self.load_sessions(&[dir])))
+        })
+    }
+}
+
+/// Assistant messages of one session as entries with cumulative usage
+fn read_session(dir: &Path, directories: &HashMap<String, String>) -> Vec<ParsedEntry> {
+    let mut messages: Vec<StoredMessage> = WalkDir::new(dir)
+        .min_depth(1)
+        .max_depth(1)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|entry| serde_json::from_slice(&std::fs::read(entry.path()).ok()?).ok())
+        .filter(|message: &StoredMessage| message.role == "assistant" && message.tokens.is_some())
+        .collect();
+    messages.sort_by_key(|message| message.time.created);
+
+    let source_file = intern(&dir.to_string_lossy());
+    let mut total = MessageTokens::default();
+
+    messages
+        .into_iter()
+        .filter_map(|message| {
+            let tokens = message.tokens.unwrap_or_default();
+            // Reasoning tokens are billed as output. Running totals saturate rather than wrap in
+            // a session long (or corrupt) enough to pass u32::MAX
+            total.input = total.input.saturating_add(tokens.input);
+            total.output = total.output.saturating_add(tokens.output.saturating_add(tokens.reasoning));
+            total.cache.read = total.cache.read.saturating_add(tokens.cache.read);
+            total.cache.write = total.cache.write.saturating_add(tokens.cache.write);
+
+            let mut raw = HashMap::new();
+            raw.insert("sessionId".to_string(), serde_json::Value::from(message.session_id.clone()));
+            raw.insert("requestId".to_string(), serde_json::Value::from(message.id.clone()));
+            if let Some(directory) = directories.get(&message.session_id) {
+                raw.insert("cwd".to_string(), serde_json::Value::from(directory.clone()));
+            }
+
+            Some(ParsedEntry {
+                timestamp: DateTime::<Utc>::from_timestamp_millis(message.time.created)?,
+                message: Some(MessageInfo {
+                    id: Some(message.id),
+                    usage: Some(UsageInfo {
+                        input_tokens: Some(total.input),
+                        output_tokens: Some(total.output),
+                        cache_creation_input_tokens: Some(total.cache.write),
+                        cache_read_input_tokens: Some(total.cache.read),
+                        cache_creation: None,
+                        service_tier: None,
+                    }),
+                    model: message.model_id.as_deref().map(intern),
+                }),
+                cost_usd: None,
+                source_file: Some(source_file.clone()),
+                is_sidechain: None,
+                raw,
+            })
+        })
+        .collect()
+}
+
+fn modified_since(path: &Path, since: DateTime<Utc>) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .map(|modified| DateTime::<Utc>::from(modified) >= since)
+        .unwrap_or(true)
+}
@@ -0,0 +1,120 @@
+use crate::config::StatsdConfig;
+use crate::segments::{BlockSegment, SegmentContext, TodaySegment};
+use crate::utils::{debug_with_context, state_root};
+use anyhow::{Context, Result};
+use std::net::UdpSocket;
+use std::time::Duration;
+
+pub const DEFAULT_STATSD_ADDRESS: &str = "127.0.0.1:8125";
+pub const DEFAULT_STATSD_PREFIX: &str = "claude_powerline";
+pub const DEFAULT_STATSD_INTERVAL_SECS: u64 = 10;
+
+/// Pushes are throttled by the mtime of this stamp file in the state root
+const PUSH_STAMP: &str = ".last-statsd-push";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+/// One StatsD sample; `name` is appended to the configured prefix
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    pub name: &'static str,
+    pub value: f64,
+    pub kind: MetricKind,
+}
+
+impl Metric {
+    pub fn gauge(name: &'static str, value: f64) -> Self {
+        Self { name, value, kind: MetricKind::Gauge }
+    }
+
+    pub fn counter(name: &'static str, value: f64) -> Self {
+        Self { name, value, kind: MetricKind::Counter }
+    }
+}
+
+/// StatsD lines for `metrics`, one per line, with a DogStatsD `|#tags` suffix when `tags` is non-empty
+pub fn format_metrics(prefix: &str, metrics: &[Metric], tags: &[String]) -> String {
+    let tags = if tags.is_empty() {
+        String::new()
+    } else {
+        format!("|#{}", tags.join(","))
+    };
+
+    metrics
+        .iter()
+        .map(|metric| {
+            let kind = match metric.kind {
+                MetricKind::Counter => "c",
+                MetricKind::Gauge => "g",
+            };
+            format!("{}.{}:{}|{}{}", prefix, metric.name, metric.value, kind, tags)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Today's and the active block's usage, read from the entries the render already loaded
+pub async fn collect_metrics(ctx: &SegmentContext) -> Result<Vec<Metric>> {
+    let today = TodaySegment::new().get_today_info_from(&ctx.shared).await?;
+    let block = BlockSegment::new().get_active_block_info_from(&ctx.shared).await?;
+
+    Ok(vec![
+        Metric::counter("pushes", 1.0),
+        Metric::gauge("today.cost_usd", today.cost.unwrap_or(0.0)),
+        Metric::gauge("today.tokens", today.tokens.unwrap_or(0) as f64),
+        Metric::gauge("block.cost_usd", block.cost.unwrap_or(0.0)),
+        Metric::gauge("block.tokens", block.tokens.unwrap_or(0) as f64),
+        Metric::gauge("block.burn_rate_usd_per_hour", block.burn_rate.unwrap_or(0.0)),
+        Metric::gauge("block.tokens_per_hour", block.token_burn_rate.unwrap_or(0.0)),
+        Metric::gauge("block.minutes_remaining", block.time_remaining.unwrap_or(0) as f64),
+    ])
+}
+
+/// Send `payload` to the agent at `address` in a single datagram
+pub fn send_payload(address: &str, payload: &str) -> Result<()> {
+    let socket = UdpSocket::bind("0.0.0.0:0").context("Failed to bind a UDP socket")?;
+    socket.send_to(payload.as_bytes(), address)
+        .with_context(|| format!("Failed to send metrics to {}", address))?;
+    Ok(())
+}
+
+/// Push usage metrics unless the previous push was less than `intervalSecs` ago, since
+/// the statusline renders on every prompt. Returns whether metrics were sent.
+pub async fn push_if_due(config: &StatsdConfig, ctx: &SegmentContext) -> Result<bool> {
+    if !config.enabled {
+        return Ok(false);
+    }
+
+    let interval = Duration::from_secs(config.interval_secs.unwrap_or(DEFAULT_STATSD_INTERVAL_SECS));
+    let stamp = state_root().map(|root| root.join(PUSH_STAMP));
+    if let Some(stamp) = &stamp {
+        let due = std::fs::metadata(stamp)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_none_or(|age| age >= interval);
+        if !due {
+            return Ok(false);
+        }
+    }
+
+    let metrics = collect_metrics(ctx).await?;
+    let prefix = config.prefix.as_deref().unwrap_or(DEFAULT_STATSD_PREFIX);
+    let payload = format_metrics(prefix, &metrics, config.tags.as_deref().unwrap_or_default());
+    let address = config.address.as_deref().unwrap_or(DEFAULT_STATSD_ADDRESS);
+    send_payload(address, &payload)?;
+    debug_with_context("statsd", &format!("Pushed {} metrics to {}", metrics.len(), address));
+
+    if let Some(stamp) = stamp {
+        if let Some(parent) = stamp.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&stamp, b"")
+            .with_context(|| format!("Failed to write {}", stamp.display()))?;
+    }
+    Ok(true)
+}
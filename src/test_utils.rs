@@ -0,0 +1,292 @@
+//! Fixture builders for tests that need Claude Code transcripts on disk (the `test-utils`
+//! feature). A `ClaudeDirFixture` is a temporary Claude directory; `TranscriptEntry` and
+//! `Transcript` write the JSONL lines Claude Code would:
+//!
+//! ```ignore
+//! let claude = ClaudeDirFixture::new()?;
+//! let now = Utc::now();
+//! let transcript = Transcript::new("session-1")
+//!     .entry(TranscriptEntry::assistant(now - Duration::minutes(5)).usage(1000, 500))
+//!     .entry(TranscriptEntry::assistant(now).model("claude-3-opus").usage(200, 80).sidechain(true));
+//! claude.write_transcript("my-project", &transcript)?;
+//! std::env::set_var("CLAUDE_CONFIG_DIR", claude.path());
+//! ```
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tempfile::TempDir;
+
+/// Model assistant entries use unless one is set
+pub const DEFAULT_MODEL: &str = "claude-3-5-sonnet";
+
+/// Numbers message and request ids, so separately built entries never look like duplicates
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+fn next_id() -> u64 {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// The timestamp format of Claude Code's transcripts
+pub fn transcript_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+/// One transcript line. Assistant entries get a unique message id, request id and `uuid`, the
+/// default model and no usage until set; anything not covered goes in with `field`.
+#[derive(Debug, Clone)]
+pub struct TranscriptEntry {
+    kind: &'static str,
+    timestamp: DateTime<Utc>,
+    session_id: Option<String>,
+    model: Option<String>,
+    message_id: Option<String>,
+    request_id: Option<String>,
+    usage: Map<String, Value>,
+    cost_usd: Option<f64>,
+    sidechain: Option<bool>,
+    uuid: String,
+    parent_uuid: Option<String>,
+    extra: Map<String, Value>,
+}
+
+impl TranscriptEntry {
+    fn new(kind: &'static str, timestamp: DateTime<Utc>) -> Self {
+        let id = next_id();
+        Self {
+            kind,
+            timestamp,
+            session_id: None,
+            model: None,
+            message_id: None,
+            request_id: None,
+            usage: Map::new(),
+            cost_usd: None,
+            sidechain: None,
+            uuid: format!("uuid-{}", id),
+            parent_uuid: None,
+            extra: Map::new(),
+        }
+    }
+
+    /// A model response at `timestamp`
+    pub fn assistant(timestamp: DateTime<Utc>) -> Self {
+        let id = next_id();
+        Self {
+            model: Some(DEFAULT_MODEL.to_string()),
+            message_id: Some(format!("msg-{}", id)),
+            request_id: Some(format!("req-{}", id)),
+            ..Self::new("assistant", timestamp)
+        }
+    }
+
+    /// A user prompt at `timestamp`, which carries no usage
+    pub fn user(timestamp: DateTime<Utc>) -> Self {
+        Self::new("user", timestamp)
+    }
+
+    pub fn model(mut self, model: &str) -> Self {
+        self.model = Some(model.to_string());
+        self
+    }
+
+    /// Input and output tokens
+    pub fn usage(mut self, input_tokens: u64, output_tokens: u64) -> Self {
+        self.usage.insert("input_tokens".to_string(), json!(input_tokens));
+        self.usage.insert("output_tokens".to_string(), json!(output_tokens));
+        self
+    }
+
+    /// Cache write and cache read tokens
+    pub fn cache(mut self, creation_tokens: u64, read_tokens: u64) -> Self {
+        self.usage.insert("cache_creation_input_tokens".to_string(), json!(creation_tokens));
+        self.usage.insert("cache_read_input_tokens".to_string(), json!(read_tokens));
+        self
+    }
+
+    /// The `costUSD` Claude Code recorded, used instead of a price calculation where present
+    pub fn cost(mut self, cost_usd: f64) -> Self {
+        self.cost_usd = Some(cost_usd);
+        self
+    }
+
+    /// Overrides the `sessionId` the transcript gives its entries
+    pub fn session(mut self, session_id: &str) -> Self {
+        self.session_id = Some(session_id.to_string());
+        self
+    }
+
+    /// Reuse a message id, e.g. to write the streamed copies of one response
+    pub fn message_id(mut self, message_id: &str) -> Self {
+        self.message_id = Some(message_id.to_string());
+        self
+    }
+
+    pub fn request_id(mut self, request_id: &str) -> Self {
+        self.request_id = Some(request_id.to_string());
+        self
+    }
+
+    /// Mark the entry as a subagent's (`isSidechain`)
+    pub fn sidechain(mut self, sidechain: bool) -> Self {
+        self.sidechain = Some(sidechain);
+        self
+    }
+
+    pub fn uuid(mut self, uuid: &str) -> Self {
+        self.uuid = uuid.to_string();
+        self
+    }
+
+    /// The `uuid` of the entry this one follows
+    pub fn parent(mut self, parent_uuid: &str) -> Self {
+        self.parent_uuid = Some(parent_uuid.to_string());
+        self
+    }
+
+    /// Any other top-level field, e.g. `cwd`
+    pub fn field(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.extra.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// The entry as Claude Code writes it
+    pub fn to_json(&self) -> Value {
+        let mut entry = Map::new();
+        entry.insert("type".to_string(), json!(self.kind));
+        entry.insert("timestamp".to_string(), json!(transcript_timestamp(self.timestamp)));
+        entry.insert("uuid".to_string(), json!(self.uuid));
+        if let Some(parent) = &self.parent_uuid {
+            entry.insert("parentUuid".to_string(), json!(parent));
+        }
+        if let Some(session_id) = &self.session_id {
+            entry.insert("sessionId".to_string(), json!(session_id));
+        }
+        if let Some(sidechain) = self.sidechain {
+            entry.insert("isSidechain".to_string(), json!(sidechain));
+        }
+        if let Some(request_id) = &self.request_id {
+            entry.insert("requestId".to_string(), json!(request_id));
+        }
+        if let Some(cost) = self.cost_usd {
+            entry.insert("costUSD".to_string(), json!(cost));
+        }
+
+        let mut message = Map::new();
+        if self.kind == "user" {
+            message.insert("role".to_string(), json!("user"));
+        }
+        if let Some(id) = &self.message_id {
+            message.insert("id".to_string(), json!(id));
+        }
+        if let Some(model) = &self.model {
+            message.insert("model".to_string(), json!(model));
+        }
+        if !self.usage.is_empty() {
+            message.insert("usage".to_string(), Value::Object(self.usage.clone()));
+        }
+        entry.insert("message".to_string(), Value::Object(message));
+
+        for (key, value) in &self.extra {
+            entry.insert(key.clone(), value.clone());
+        }
+        Value::Object(entry)
+    }
+}
+
+/// One session's transcript: its entries, each given the session's id unless it has its own
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    session_id: String,
+    entries: Vec<TranscriptEntry>,
+    /// Chain each entry's `parentUuid` to the one before, as Claude Code does
+    chained: bool,
+}
+
+impl Transcript {
+    pub fn new(session_id: &str) -> Self {
+        Self { session_id: session_id.to_string(), entries: Vec::new(), chained: true }
+    }
+
+    pub fn entry(mut self, entry: TranscriptEntry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /// Leave `parentUuid` to what each entry sets
+    pub fn unchained(mut self) -> Self {
+        self.chained = false;
+        self
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// The file name Claude Code gives the transcript
+    pub fn file_name(&self) -> String {
+        format!("{}.jsonl", self.session_id)
+    }
+
+    /// The transcript as JSONL, one entry per line
+    pub fn to_jsonl(&self) -> String {
+        let mut previous: Option<&str> = None;
+        let mut jsonl = String::new();
+        for entry in &self.entries {
+            let mut line = entry.clone();
+            line.session_id.get_or_insert_with(|| self.session_id.clone());
+            if self.chained && line.parent_uuid.is_none() {
+                line.parent_uuid = previous.map(str::to_string);
+            }
+            jsonl.push_str(&line.to_json().to_string());
+            jsonl.push('\n');
+            previous = Some(&entry.uuid);
+        }
+        jsonl
+    }
+}
+
+/// A temporary Claude directory with a `projects` folder, removed when dropped. Point the code
+/// under test at it with `CLAUDE_CONFIG_DIR` or `set_claude_paths`.
+pub struct ClaudeDirFixture {
+    dir: TempDir,
+}
+
+impl ClaudeDirFixture {
+    pub fn new() -> Result<Self> {
+        let dir = TempDir::new().context("Failed to create a temporary Claude directory")?;
+        std::fs::create_dir_all(dir.path().join("projects"))?;
+        Ok(Self { dir })
+    }
+
+    /// The Claude directory itself
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// The directory of `project` under `projects`, created if missing
+    pub fn project_dir(&self, project: &str) -> Result<PathBuf> {
+        let dir = self.path().join("projects").join(project);
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        Ok(dir)
+    }
+
+    /// Write `transcript` into `project`, returning its path
+    pub fn write_transcript(&self, project: &str, transcript: &Transcript) -> Result<PathBuf> {
+        let path = self.project_dir(project)?.join(transcript.file_name());
+        std::fs::write(&path, transcript.to_jsonl())
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Write raw JSONL (e.g. deliberately malformed lines) as `file_name` in `project`
+    pub fn write_raw(&self, project: &str, file_name: &str, content: &str) -> Result<PathBuf> {
+        let path = self.project_dir(project)?.join(file_name);
+        std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
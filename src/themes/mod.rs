@@ -1,14 +1,105 @@
-use colored::{ColoredString, Colorize};
 use std::collections::HashMap;
 
+use crate::config::{strip_json_comments, AutoThemeConfig, ThemeColors};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+use crate::utils::{should_use_colors, supports_rgb_colors, system_appearance, terminal_background, Background};
+
 pub struct Theme {
     pub colors: HashMap<String, (String, String)>, // (bg, fg)
+    /// Text attributes per segment; segments without an entry are drawn plain
+    pub attributes: HashMap<String, TextAttributes>,
+    /// Leave the terminal's background alone and color only the text (the `transparent` style)
+    pub transparent: bool,
 }
 
 impl Theme {
     pub fn get_colors(&self, segment: &str) -> Option<&(String, String)> {
         self.colors.get(segment)
     }
+
+    /// Layer the config's `colors` over the theme, segment by segment
+    pub fn apply_overrides(&mut self, overrides: &HashMap<String, ThemeColors>) {
+        for (segment, entry) in overrides {
+            if entry.bg.is_some() || entry.fg.is_some() {
+                let (bg, fg) = self.colors.get(segment).cloned().unwrap_or_default();
+                let bg = entry.bg.clone().unwrap_or(bg);
+                let fg = entry.fg.clone().unwrap_or(fg);
+                self.colors.insert(segment.clone(), (bg, fg));
+            }
+
+            let attributes = self.attributes.entry(segment.clone()).or_default();
+            attributes.bold = entry.bold.unwrap_or(attributes.bold);
+            attributes.dim = entry.dim.unwrap_or(attributes.dim);
+            attributes.italic = entry.italic.unwrap_or(attributes.italic);
+            attributes.underline = entry.underline.unwrap_or(attributes.underline);
+        }
+    }
+}
+
+/// Bold, dim, italic and underline on top of a segment's colors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextAttributes {
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl TextAttributes {
+    /// The SGR escape switching these attributes on, or nothing when none are set
+    pub fn sgr(&self) -> String {
+        let codes: Vec<&str> = [(self.bold, "1"), (self.dim, "2"), (self.italic, "3"), (self.underline, "4")]
+            .into_iter()
+            .filter_map(|(on, code)| on.then_some(code))
+            .collect();
+        if codes.is_empty() {
+            String::new()
+        } else {
+            format!("\x1b[{}m", codes.join(";"))
+        }
+    }
+}
+
+/// The theme to load for the configured `name`: `auto` becomes the dark or the light theme of
+/// `auto_theme` (`dark` / `light` unless set) to match the terminal's background, or the system
+/// appearance with `follow: "system"`, and the dark one when that can't be told
+pub fn resolve_theme_name(name: &str, auto_theme: Option<&AutoThemeConfig>) -> String {
+    if name != "auto" {
+        return name.to_string();
+    }
+
+    let auto_theme = auto_theme.cloned().unwrap_or_default();
+    let background = match auto_theme.follow.as_deref() {
+        Some("system") => system_appearance(),
+        _ => terminal_background(),
+    };
+    match background {
+        Some(Background::Light) => auto_theme.light.unwrap_or_else(|| "light".to_string()),
+        _ => auto_theme.dark.unwrap_or_else(|| "dark".to_string()),
+    }
+}
+
+/// A theme kept in its own JSON file: the built-in theme it `extends` (the configured theme
+/// if unset) with its `colors` entries, shaped like the config's `colors`, layered on top
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThemeFile {
+    pub extends: Option<String>,
+    #[serde(default)]
+    pub colors: HashMap<String, ThemeColors>,
+}
+
+/// Load the theme file at `path`, on top of the built-in theme `base` unless it `extends` another
+pub fn load_theme_file(path: &Path, base: &str) -> Result<Theme> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read theme file: {}", path.display()))?;
+    let file: ThemeFile = serde_json::from_str(&strip_json_comments(&content))
+        .with_context(|| format!("Failed to parse theme file: {}", path.display()))?;
+
+    let mut theme = get_theme(file.extends.as_deref().unwrap_or(base));
+    theme.apply_overrides(&file.colors);
+    Ok(theme)
 }
 
 pub fn get_theme(name: &str) -> Theme {
@@ -31,9 +122,15 @@ fn dark_theme() -> Theme {
     colors.insert("session".to_string(), ("#805ad5".to_string(), "#f7fafc".to_string()));
     colors.insert("context".to_string(), ("#e53e3e".to_string(), "#f7fafc".to_string()));
     colors.insert("metrics".to_string(), ("#38b2ac".to_string(), "#f7fafc".to_string()));
+    colors.insert("health".to_string(), ("#b7791f".to_string(), "#fffff0".to_string()));
+    colors.insert("update".to_string(), ("#4a5568".to_string(), "#e2e8f0".to_string()));
+    colors.insert("team".to_string(), ("#2c7a7b".to_string(), "#f7fafc".to_string()));
     colors.insert("model".to_string(), ("#ed8936".to_string(), "#f7fafc".to_string()));
+    colors.insert("heat_low".to_string(), ("#38a169".to_string(), "#f7fafc".to_string()));
+    colors.insert("heat_mid".to_string(), ("#d69e2e".to_string(), "#1a202c".to_string()));
+    colors.insert("heat_high".to_string(), ("#e53e3e".to_string(), "#f7fafc".to_string()));
     
-    Theme { colors }
+    Theme { colors, attributes: HashMap::new(), transparent: false }
 }
 
 fn light_theme() -> Theme {
@@ -45,9 +142,15 @@ fn light_theme() -> Theme {
     colors.insert("session".to_string(), ("#d6bcfa".to_string(), "#1a202c".to_string()));
     colors.insert("context".to_string(), ("#feb2b2".to_string(), "#1a202c".to_string()));
     colors.insert("metrics".to_string(), ("#b2f5ea".to_string(), "#1a202c".to_string()));
+    colors.insert("health".to_string(), ("#fefcbf".to_string(), "#744210".to_string()));
+    colors.insert("update".to_string(), ("#e2e8f0".to_string(), "#2d3748".to_string()));
+    colors.insert("team".to_string(), ("#c6f6d5".to_string(), "#1a202c".to_string()));
     colors.insert("model".to_string(), ("#fed7aa".to_string(), "#1a202c".to_string()));
+    colors.insert("heat_low".to_string(), ("#c6f6d5".to_string(), "#1a202c".to_string()));
+    colors.insert("heat_mid".to_string(), ("#faf089".to_string(), "#1a202c".to_string()));
+    colors.insert("heat_high".to_string(), ("#feb2b2".to_string(), "#1a202c".to_string()));
     
-    Theme { colors }
+    Theme { colors, attributes: HashMap::new(), transparent: false }
 }
 
 fn nord_theme() -> Theme {
@@ -59,9 +162,15 @@ fn nord_theme() -> Theme {
     colors.insert("session".to_string(), ("#b48ead".to_string(), "#eceff4".to_string()));
     colors.insert("context".to_string(), ("#bf616a".to_string(), "#eceff4".to_string()));
     colors.insert("metrics".to_string(), ("#88c0d0".to_string(), "#eceff4".to_string()));
+    colors.insert("health".to_string(), ("#ebcb8b".to_string(), "#2e3440".to_string()));
+    colors.insert("update".to_string(), ("#4c566a".to_string(), "#eceff4".to_string()));
+    colors.insert("team".to_string(), ("#8fbcbb".to_string(), "#2e3440".to_string()));
     colors.insert("model".to_string(), ("#d08770".to_string(), "#eceff4".to_string()));
+    colors.insert("heat_low".to_string(), ("#a3be8c".to_string(), "#2e3440".to_string()));
+    colors.insert("heat_mid".to_string(), ("#ebcb8b".to_string(), "#2e3440".to_string()));
+    colors.insert("heat_high".to_string(), ("#bf616a".to_string(), "#eceff4".to_string()));
     
-    Theme { colors }
+    Theme { colors, attributes: HashMap::new(), transparent: false }
 }
 
 fn tokyo_night_theme() -> Theme {
@@ -73,9 +182,15 @@ fn tokyo_night_theme() -> Theme {
     colors.insert("session".to_string(), ("#bb9af7".to_string(), "#1a1b26".to_string()));
     colors.insert("context".to_string(), ("#f7768e".to_string(), "#1a1b26".to_string()));
     colors.insert("metrics".to_string(), ("#2ac3de".to_string(), "#1a1b26".to_string()));
+    colors.insert("health".to_string(), ("#e0af68".to_string(), "#1a1b26".to_string()));
+    colors.insert("update".to_string(), ("#414868".to_string(), "#c0caf5".to_string()));
+    colors.insert("team".to_string(), ("#73daca".to_string(), "#1a1b26".to_string()));
     colors.insert("model".to_string(), ("#ff9e64".to_string(), "#1a1b26".to_string()));
+    colors.insert("heat_low".to_string(), ("#9ece6a".to_string(), "#1a1b26".to_string()));
+    colors.insert("heat_mid".to_string(), ("#e0af68".to_string(), "#1a1b26".to_string()));
+    colors.insert("heat_high".to_string(), ("#f7768e".to_string(), "#1a1b26".to_string()));
     
-    Theme { colors }
+    Theme { colors, attributes: HashMap::new(), transparent: false }
 }
 
 fn rose_pine_theme() -> Theme {
@@ -87,7 +202,135 @@ fn rose_pine_theme() -> Theme {
     colors.insert("session".to_string(), ("#eb6f92".to_string(), "#e0def4".to_string()));
     colors.insert("context".to_string(), ("#ebbcba".to_string(), "#191724".to_string()));
     colors.insert("metrics".to_string(), ("#9ccfd8".to_string(), "#191724".to_string()));
+    colors.insert("health".to_string(), ("#f6c177".to_string(), "#191724".to_string()));
+    colors.insert("update".to_string(), ("#26233a".to_string(), "#e0def4".to_string()));
+    colors.insert("team".to_string(), ("#31748f".to_string(), "#e0def4".to_string()));
     colors.insert("model".to_string(), ("#ebbcba".to_string(), "#191724".to_string()));
+    colors.insert("heat_low".to_string(), ("#9ccfd8".to_string(), "#191724".to_string()));
+    colors.insert("heat_mid".to_string(), ("#f6c177".to_string(), "#191724".to_string()));
+    colors.insert("heat_high".to_string(), ("#eb6f92".to_string(), "#e0def4".to_string()));
     
-    Theme { colors }
-}
\ No newline at end of file
+    Theme { colors, attributes: HashMap::new(), transparent: false }
+}
+
+/// Wrap `text` in the theme's colors for `segment`, or return it plain when colors are off
+pub fn apply_theme_colors(text: &str, segment: &str, theme: &Theme) -> String {
+    // Check if we should use colors
+    if !should_use_colors() {
+        return text.to_string();
+    }
+
+    let attributes = theme.attributes.get(segment).map(TextAttributes::sgr).unwrap_or_default();
+    if let Some((bg_color, fg_color)) = theme.get_colors(segment) {
+        let bg_rgb = parse_color(bg_color);
+        let fg_rgb = parse_color(fg_color);
+        
+        // Try 24-bit RGB first, fallback to 8-bit if not supported
+        if theme.transparent && supports_rgb_colors() {
+            format!("\x1b[38;2;{};{};{}m{}{}\x1b[0m", fg_rgb.0, fg_rgb.1, fg_rgb.2, attributes, text)
+        } else if theme.transparent {
+            format!("\x1b[38;5;{}m{}{}\x1b[0m", rgb_to_8bit(fg_rgb), attributes, text)
+        } else if supports_rgb_colors() {
+            format!("\x1b[48;2;{};{};{}m\x1b[38;2;{};{};{}m{}{}\x1b[0m", 
+                    bg_rgb.0, bg_rgb.1, bg_rgb.2,
+                    fg_rgb.0, fg_rgb.1, fg_rgb.2,
+                    attributes, text)
+        } else {
+            // Fallback to basic 8-bit colors
+            let bg_code = rgb_to_8bit(bg_rgb);
+            let fg_code = rgb_to_8bit(fg_rgb);
+            format!("\x1b[48;5;{}m\x1b[38;5;{}m{}{}\x1b[0m", bg_code, fg_code, attributes, text)
+        }
+    } else if !attributes.is_empty() {
+        format!("{}{}\x1b[0m", attributes, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Wrap `text` for a segment in a critical state so it can't be missed: the theme's
+/// `<segment>_critical` entry when there is one (set it under `colors`), otherwise the
+/// segment's own colors swapped, in bold
+pub fn apply_critical_colors(text: &str, segment: &str, theme: &Theme) -> String {
+    let critical = format!("{}_critical", segment);
+    if theme.colors.contains_key(&critical) {
+        return apply_theme_colors(text, &critical, theme);
+    }
+    let Some((bg, fg)) = theme.get_colors(segment) else {
+        return apply_theme_colors(text, segment, theme);
+    };
+
+    let mut attributes = theme.attributes.get(segment).copied().unwrap_or_default();
+    attributes.bold = true;
+    let inverted = Theme {
+        colors: HashMap::from([(critical.clone(), (fg.clone(), bg.clone()))]),
+        attributes: HashMap::from([(critical.clone(), attributes)]),
+        transparent: theme.transparent,
+    };
+    apply_theme_colors(text, &critical, &inverted)
+}
+
+/// Wrap `text` for a segment with its background shaded by `level` (0 to 1, clamped) from
+/// green through yellow to red, interpolating the backgrounds of the theme's `heat_low`,
+/// `heat_mid` and `heat_high` entries (in the transparent style the text is shaded instead)
+pub fn apply_heat_colors(text: &str, segment: &str, level: f64, theme: &Theme) -> String {
+    let stop = |name: &str, fallback: (u8, u8, u8)| {
+        theme.get_colors(name).map(|(bg, _)| parse_color(bg)).unwrap_or(fallback)
+    };
+    let (green, yellow, red) = (stop("heat_low", (56, 161, 105)), stop("heat_mid", (214, 158, 46)), stop("heat_high", (229, 62, 62)));
+
+    let level = level.clamp(0.0, 1.0);
+    let bg = if level < 0.5 {
+        mix(green, yellow, level * 2.0)
+    } else {
+        mix(yellow, red, level * 2.0 - 1.0)
+    };
+    let bg = format!("#{:02x}{:02x}{:02x}", bg.0, bg.1, bg.2);
+    // Without backgrounds the text itself carries the heat
+    let fg = match theme.get_colors(segment) {
+        _ if theme.transparent => bg.clone(),
+        Some((_, fg)) => fg.clone(),
+        None => "#ffffff".to_string(),
+    };
+
+    let heat = format!("{}_heat", segment);
+    let shaded = Theme {
+        colors: HashMap::from([(heat.clone(), (bg, fg))]),
+        attributes: theme.attributes.get(segment).map(|attributes| (heat.clone(), *attributes)).into_iter().collect(),
+        transparent: theme.transparent,
+    };
+    apply_theme_colors(text, &heat, &shaded)
+}
+
+/// The color `t` (0 to 1) of the way from `from` to `to`
+fn mix(from: (u8, u8, u8), to: (u8, u8, u8), t: f64) -> (u8, u8, u8) {
+    let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    (channel(from.0, to.0), channel(from.1, to.1), channel(from.2, to.2))
+}
+
+fn rgb_to_8bit((r, g, b): (u8, u8, u8)) -> u8 {
+    // Convert RGB to closest 8-bit color (216 color cube + grayscale)
+    if r == g && g == b {
+        // Grayscale
+        if r < 8 { 16 }
+        else if r > 248 { 231 }
+        else { ((r - 8) / 10) + 232 }
+    } else {
+        // Color cube: 16 + 36*r + 6*g + b
+        let r6 = r * 5 / 255;
+        let g6 = g * 5 / 255;
+        let b6 = b * 5 / 255;
+        16 + 36 * r6 + 6 * g6 + b6
+    }
+}
+
+fn parse_color(color: &str) -> (u8, u8, u8) {
+    if color.starts_with('#') && color.len() == 7 {
+        let r = u8::from_str_radix(&color[1..3], 16).unwrap_or(255);
+        let g = u8::from_str_radix(&color[3..5], 16).unwrap_or(255);
+        let b = u8::from_str_radix(&color[5..7], 16).unwrap_or(255);
+        (r, g, b)
+    } else {
+        (255, 255, 255) // Default to white
+    }
+}
@@ -0,0 +1,131 @@
+use crate::utils::cache_dir::cache_root;
+use crate::utils::terminal::Background;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, SystemTime};
+
+/// How long a looked-up appearance is reused before the platform is asked again
+const APPEARANCE_TTL: Duration = Duration::from_secs(60);
+
+/// How long a render waits for the platform's answer before going on with the previous one
+const APPEARANCE_WAIT: Duration = Duration::from_millis(200);
+
+/// Cache file holding the last looked-up appearance
+const APPEARANCE_CACHE: &str = "system_appearance";
+
+/// A lookup is running, so another render (in watch mode) doesn't start a second one
+static LOOKING_UP: AtomicBool = AtomicBool::new(false);
+
+/// Whether the desktop is in dark or light mode, as `query_system_appearance` tells. The answer
+/// is kept in the cache directory for `APPEARANCE_TTL`, so a render after the system switches
+/// between day and night picks up the change within a minute without running a command each
+/// time. When it's out of date the command runs on a thread of its own, and a render that
+/// can't wait for it goes on with the previous answer.
+pub fn system_appearance() -> Option<Background> {
+    let cache = cache_root().map(|root| root.join(APPEARANCE_CACHE));
+    let cached = cache.as_deref().and_then(read_cached_appearance);
+    if let Some((appearance, true)) = cached {
+        return appearance;
+    }
+    let previous = cached.and_then(|(appearance, _)| appearance);
+
+    if LOOKING_UP.swap(true, Ordering::SeqCst) {
+        return previous;
+    }
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let appearance = query_system_appearance();
+        if let Some(path) = &cache {
+            write_cached_appearance(path, appearance);
+        }
+        LOOKING_UP.store(false, Ordering::SeqCst);
+        let _ = sender.send(appearance);
+    });
+    receiver.recv_timeout(APPEARANCE_WAIT).unwrap_or(previous)
+}
+
+/// The cached appearance and whether it is still fresh
+fn read_cached_appearance(path: &Path) -> Option<(Option<Background>, bool)> {
+    let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()?;
+    let fresh = SystemTime::now().duration_since(modified).is_ok_and(|age| age < APPEARANCE_TTL);
+    let appearance = match std::fs::read_to_string(path).ok()?.trim() {
+        "dark" => Some(Background::Dark),
+        "light" => Some(Background::Light),
+        "unknown" => None,
+        _ => return None,
+    };
+    Some((appearance, fresh))
+}
+
+fn write_cached_appearance(path: &Path, appearance: Option<Background>) {
+    let text = match appearance {
+        Some(Background::Dark) => "dark",
+        Some(Background::Light) => "light",
+        None => "unknown",
+    };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let _ = std::fs::write(path, text);
+}
+
+/// Whether the desktop is in dark or light mode: the macOS appearance, the Windows app theme,
+/// or GNOME's color scheme (falling back to a `-dark` GTK theme). `None` when the platform
+/// has no such setting or it can't be read. Runs the platform's settings command every call.
+pub fn query_system_appearance() -> Option<Background> {
+    if cfg!(target_os = "macos") {
+        // The key only exists in dark mode; `defaults` fails when it's missing
+        let output = Command::new("defaults").args(["read", "-g", "AppleInterfaceStyle"]).output().ok()?;
+        let dark = output.status.success() && String::from_utf8_lossy(&output.stdout).trim().eq_ignore_ascii_case("dark");
+        Some(if dark { Background::Dark } else { Background::Light })
+    } else if cfg!(windows) {
+        let output = Command::new("reg")
+            .args([
+                "query",
+                r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+                "/v",
+                "AppsUseLightTheme",
+            ])
+            .output()
+            .ok()?;
+        parse_apps_use_light_theme(&String::from_utf8_lossy(&output.stdout))
+    } else {
+        gsettings("color-scheme")
+            .and_then(|scheme| parse_color_scheme(&scheme))
+            .or_else(|| gsettings("gtk-theme").map(|theme| parse_gtk_theme(&theme)))
+    }
+}
+
+/// GNOME's `org.gnome.desktop.interface color-scheme`: `'prefer-dark'`, `'prefer-light'` or
+/// `'default'` (light)
+pub fn parse_color_scheme(scheme: &str) -> Option<Background> {
+    match scheme.trim().trim_matches('\'') {
+        "prefer-dark" => Some(Background::Dark),
+        "prefer-light" | "default" => Some(Background::Light),
+        _ => None,
+    }
+}
+
+/// A GTK theme name such as `'Adwaita-dark'`
+pub fn parse_gtk_theme(theme: &str) -> Background {
+    if theme.to_lowercase().contains("dark") {
+        Background::Dark
+    } else {
+        Background::Light
+    }
+}
+
+/// `reg query` output for `AppsUseLightTheme`: `AppsUseLightTheme    REG_DWORD    0x0` is dark
+pub fn parse_apps_use_light_theme(output: &str) -> Option<Background> {
+    let line = output.lines().find(|line| line.contains("AppsUseLightTheme"))?;
+    let value = line.split_whitespace().last()?;
+    let value = u32::from_str_radix(value.trim_start_matches("0x"), 16).ok()?;
+    Some(if value == 0 { Background::Dark } else { Background::Light })
+}
+
+fn gsettings(key: &str) -> Option<String> {
+    let output = Command::new("gsettings").args(["get", "org.gnome.desktop.interface", key]).output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use flate2::read::MultiGzDecoder;
+use std::io::Read;
+use std::path::Path;
+
+/// Whether `name` is a transcript file name: `<session>.jsonl`, optionally followed by a rotation
+/// suffix (`.1`, `-20240101`, `.2024-01-01`) and/or `.gz` for archived transcripts
+pub fn is_transcript_file_name(name: &str) -> bool {
+    let name = name.strip_suffix(".gz").unwrap_or(name);
+    let Some(index) = name.rfind(".jsonl") else {
+        return false;
+    };
+
+    let rotation = &name[index + ".jsonl".len()..];
+    match rotation.strip_prefix(['.', '-']) {
+        Some(suffix) => !suffix.is_empty() && suffix.bytes().all(|b| b.is_ascii_digit() || b == b'-'),
+        None => rotation.is_empty(),
+    }
+}
+
+/// Whether `path` names a live, rotated or archived transcript
+pub fn is_transcript_path(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(is_transcript_file_name)
+}
+
+/// Whether `path` is a gzip-compressed transcript, which can't be memory-mapped or tailed
+pub fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+/// Read a gzip-compressed transcript whole, decompressed. Archives concatenated with
+/// `cat a.gz b.gz` hold several gzip members, all of which are read.
+pub fn read_compressed(path: &Path) -> Result<Vec<u8>> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open file: {}", path.display()))?;
+
+    let mut content = Vec::new();
+    MultiGzDecoder::new(std::io::BufReader::new(file))
+        .read_to_end(&mut content)
+        .with_context(|| format!("Failed to decompress transcript: {}", path.display()))?;
+    Ok(content)
+}
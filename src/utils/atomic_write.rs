@@ -0,0 +1,21 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Replace `path` with `bytes` by writing `<path stem>.tmp<pid>` beside it and renaming that
+/// into place, so a concurrent reader sees either the old or the new contents, never half a file.
+/// Missing parent directories are created; the temporary file is removed if the write fails.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    let tmp_path = path.with_extension(format!("tmp{}", std::process::id()));
+    let written = std::fs::write(&tmp_path, bytes)
+        .and_then(|_| std::fs::rename(&tmp_path, path))
+        .with_context(|| format!("Failed to write {}", path.display()));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    written
+}
@@ -1,4 +1,3 @@
-use anyhow::Result;
 use dashmap::DashMap;
 use std::hash::Hash;
 use std::sync::Arc;
@@ -28,13 +27,15 @@ where
     }
 
     pub fn get(&self, key: &K) -> Option<V> {
-        let entry = self.data.get(key)?;
-        if Instant::now() > entry.expires_at {
-            self.data.remove(key);
-            None
-        } else {
-            Some(entry.value.clone())
+        {
+            let entry = self.data.get(key)?;
+            if Instant::now() <= entry.expires_at {
+                return Some(entry.value.clone());
+            }
         }
+        // Removed only once the read guard is gone: removing while holding it deadlocks on the shard lock
+        self.data.remove_if(key, |_, entry| Instant::now() > entry.expires_at);
+        None
     }
 
     pub fn insert(&self, key: K, value: V) {
@@ -0,0 +1,163 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+use crate::utils::logger::{debug_with_context, warn_with_context};
+
+/// Default cap on the total size of the cache directory
+pub const DEFAULT_MAX_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Size-capped cleanup runs at most this often, tracked by the mtime of a stamp file
+const EVICTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const EVICTION_STAMP: &str = ".last-eviction";
+
+/// What this tool keeps directly under the cache root, each with its `.` suffixed variants
+/// (extensions, temp files, SQLite journals). `cacheDir` may be a directory other programs use too, so
+/// anything else there is neither counted, evicted nor cleared.
+const OWN_ENTRIES: &[&str] = &[
+    "entries",
+    "uuids",
+    "segments",
+    "session_index",
+    "usage-index",
+    "system_appearance",
+    "wsl_user_profile",
+    EVICTION_STAMP,
+];
+
+/// Whether `name`, directly under the cache root, is one of `OWN_ENTRIES`
+fn is_own_entry(name: &str) -> bool {
+    OWN_ENTRIES.iter().any(|own| {
+        name.strip_prefix(own)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+    })
+}
+
+/// Cache root configured via `cacheDir`, set once at startup before any cache is touched
+static CACHE_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `dir` as the root of every on-disk cache; only the first call has an effect
+pub fn set_cache_root(dir: PathBuf) {
+    let _ = CACHE_ROOT.set(dir);
+}
+
+/// Root of every on-disk cache: the configured `cacheDir`, or `<XDG cache dir>/claude-powerline`
+pub fn cache_root() -> Option<PathBuf> {
+    CACHE_ROOT
+        .get()
+        .cloned()
+        .or_else(|| dirs::cache_dir().map(|dir| dir.join("claude-powerline")))
+}
+
+/// Total size in bytes of this tool's files under `root`
+pub fn cache_size(root: &Path) -> u64 {
+    cache_files(root).iter().map(|file| file.size).sum()
+}
+
+/// Delete least recently used files under `root` until it fits in `max_bytes`. A file that
+/// can't be removed is warned about and passed over; one already gone just stops counting.
+/// Returns the number of bytes freed.
+pub fn evict_to_size(root: &Path, max_bytes: u64) -> Result<u64> {
+    let mut files = cache_files(root);
+    let mut total: u64 = files.iter().map(|file| file.size).sum();
+    if total <= max_bytes {
+        return Ok(0);
+    }
+
+    files.sort_by_key(|file| file.last_used);
+
+    let mut freed = 0;
+    for file in files {
+        if total <= max_bytes {
+            break;
+        }
+
+        match std::fs::remove_file(&file.path) {
+            Ok(()) => freed += file.size,
+            // Removed since it was listed, by another process evicting or clearing at the same time
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                warn_with_context("cache_dir", &format!("Failed to remove cache file {}: {}", file.path.display(), e));
+                continue;
+            }
+        }
+        total -= file.size;
+    }
+
+    debug_with_context("cache_dir", &format!("Evicted {} bytes from {}", freed, root.display()));
+    Ok(freed)
+}
+
+/// Run `evict_to_size` on `root` unless it already ran within the last hour
+pub fn evict_if_due(root: &Path, max_bytes: u64) -> Result<u64> {
+    let stamp = root.join(EVICTION_STAMP);
+    let due = std::fs::metadata(&stamp)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_none_or(|age| age >= EVICTION_INTERVAL);
+    if !due || !root.exists() {
+        return Ok(0);
+    }
+
+    std::fs::write(&stamp, b"")
+        .with_context(|| format!("Failed to write {}", stamp.display()))?;
+    evict_to_size(root, max_bytes)
+}
+
+/// Delete this tool's files under `root`, and `root` itself if nothing else is left in it.
+/// Returns the number of bytes freed.
+pub fn clear_cache(root: &Path) -> Result<u64> {
+    let Ok(dir) = std::fs::read_dir(root) else {
+        return Ok(0);
+    };
+
+    let freed = cache_size(root);
+    for entry in dir.filter_map(|entry| entry.ok()) {
+        if !is_own_entry(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+        let path = entry.path();
+        let removed = if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        removed.with_context(|| format!("Failed to remove {}", path.display()))?;
+    }
+
+    // Only succeeds once the directory is empty
+    let _ = std::fs::remove_dir(root);
+    Ok(freed)
+}
+
+struct CacheFile {
+    path: PathBuf,
+    size: u64,
+    last_used: SystemTime,
+}
+
+/// Every regular file of this tool under `root` except the eviction stamp and the SQLite usage
+/// index, which is open while rendering and rebuilt from scratch when deleted
+fn cache_files(root: &Path) -> Vec<CacheFile> {
+    WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() != 1 || is_own_entry(&entry.file_name().to_string_lossy()))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            name != EVICTION_STAMP && !name.starts_with("usage-index.sqlite")
+        })
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let modified = metadata.modified().ok()?;
+            // Access times are only as precise as the mount allows, so never rank below the last write
+            let last_used = metadata.accessed().map_or(modified, |accessed| accessed.max(modified));
+            Some(CacheFile { path: entry.into_path(), size: metadata.len(), last_used })
+        })
+        .collect()
+}
@@ -1,15 +1,28 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use dashmap::DashMap;
-use futures::future::try_join_all;
+use futures::{StreamExt, TryStreamExt};
 use std::sync::OnceLock;
-use memmap2::Mmap;
+use memmap2::MmapOptions;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::fs;
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::utils::cache::Cache;
+use crate::utils::archive::{is_compressed, is_transcript_path, read_compressed};
+use crate::utils::intern::deserialize_interned;
+use crate::utils::lines::split_lines_mut;
+use crate::utils::parallelism::parse_concurrency;
+use crate::utils::parse_errors::{record_parse_stats, ParseStats};
+use crate::utils::session_index::with_session_index;
+use crate::utils::timing::record_stage;
+use crate::utils::wsl::{bridge_path, is_wsl, windows_claude_dirs};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaudeHookData {
@@ -58,7 +71,7 @@ pub struct ParsedEntry {
     #[serde(rename = "costUSD", skip_serializing_if = "Option::is_none")]
     pub cost_usd: Option<f64>,
     #[serde(skip)]
-    pub source_file: Option<String>,  // Track which transcript file this entry came from
+    pub source_file: Option<Arc<str>>,  // Track which transcript file this entry came from (interned)
     #[serde(rename = "isSidechain", skip_serializing_if = "Option::is_none")]
     pub is_sidechain: Option<bool>,
     #[serde(flatten)]
@@ -69,7 +82,8 @@ pub struct ParsedEntry {
 pub struct MessageInfo {
     pub id: Option<String>,
     pub usage: Option<UsageInfo>,
-    pub model: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_interned")]
+    pub model: Option<Arc<str>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,14 +92,33 @@ pub struct UsageInfo {
     pub output_tokens: Option<u32>,
     pub cache_creation_input_tokens: Option<u32>,
     pub cache_read_input_tokens: Option<u32>,
+    /// `cache_creation_input_tokens` broken down by cache TTL, when reported
+    #[serde(default)]
+    pub cache_creation: Option<CacheCreation>,
+    /// `standard`, `priority` or `batch`; batch requests are billed at half price
+    #[serde(default, deserialize_with = "deserialize_interned")]
+    pub service_tier: Option<Arc<str>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheCreation {
+    pub ephemeral_5m_input_tokens: Option<u32>,
+    pub ephemeral_1h_input_tokens: Option<u32>,
 }
 
 /// High-performance shared transcript parser with memory mapping and caching
 pub struct TranscriptParser {
-    cache: Arc<DashMap<PathBuf, Arc<Vec<ParsedEntry>>>>,
+    cache: Arc<DashMap<PathBuf, CachedTranscript>>,
     claude_paths: Vec<PathBuf>,
 }
 
+/// A transcript's parsed entries, with the size and mtime of the file they were parsed from
+struct CachedTranscript {
+    size: u64,
+    mtime: SystemTime,
+    entries: Arc<Vec<Arc<ParsedEntry>>>,
+}
+
 impl TranscriptParser {
     pub fn new() -> Result<Self> {
         let claude_paths = get_claude_paths()?;
@@ -100,7 +133,8 @@ impl TranscriptParser {
         &self,
         time_filter: Option<impl Fn(&ParsedEntry) -> bool + Send + Sync>,
         file_filter: Option<impl Fn(&Path, DateTime<Utc>) -> bool + Send + Sync>,
-    ) -> Result<Vec<ParsedEntry>> {
+    ) -> Result<Vec<Arc<ParsedEntry>>> {
+        let started = Instant::now();
         let project_paths = find_project_paths(&self.claude_paths).await?;
         let mut all_entries = Vec::new();
 
@@ -112,21 +146,28 @@ impl TranscriptParser {
 
             while let Some(entry) = entries.next_entry().await? {
                 let path = entry.path();
-                if path.extension().and_then(|s| s.to_str()) == Some("jsonl") {
+                if is_transcript_path(&path) {
                     if let Ok(metadata) = entry.metadata().await {
-                        let mtime = metadata.modified()?.into();
-                        if file_filter.as_ref().map_or(true, |f| f(&path, mtime)) {
-                            file_tasks.push(self.parse_file_cached(path));
+                        let modified = metadata.modified()?;
+                        if file_filter.as_ref().is_none_or(|f| f(&path, modified.into())) {
+                            file_tasks.push(self.parse_file_cached(path, metadata.len(), modified));
                         }
                     }
                 }
             }
         }
 
-        // Parse all files in parallel
-        let results = try_join_all(file_tasks).await?;
+        let files = file_tasks.len();
+        record_stage("discovery", started.elapsed(), Some(files), None);
+
+        // Parse the files concurrently, at most `parse_concurrency` at a time
+        let started = Instant::now();
+        let results: Vec<_> = futures::stream::iter(file_tasks)
+            .buffered(parse_concurrency())
+            .try_collect()
+            .await?;
         
-        // Flatten results and apply time filter
+        // Flatten results and apply time filter (cloning only the Arc handles)
         for entries in results {
             if let Some(ref filter) = time_filter {
                 all_entries.extend(entries.iter().filter(|e| filter(e)).cloned());
@@ -135,40 +176,38 @@ impl TranscriptParser {
             }
         }
 
+        record_stage("parse", started.elapsed(), Some(files), Some(all_entries.len()));
+
         // Sort by timestamp for consistent deduplication
+        let started = Instant::now();
         all_entries.sort_by_key(|e| e.timestamp);
 
         // Deduplicate entries
-        let mut seen_hashes = std::collections::HashSet::new();
-        let mut dedup_entries = Vec::new();
-
-        for entry in all_entries {
-            if let Some(hash) = create_unique_hash(&entry) {
-                if seen_hashes.insert(hash) {
-                    dedup_entries.push(entry);
-                }
-            } else {
-                dedup_entries.push(entry);
-            }
-        }
+        let (dedup_entries, stats) = deduplicate_entries(all_entries);
+        record_stage("dedupe", started.elapsed(), None, Some(dedup_entries.len()));
+        crate::utils::logger::debug_with_context("transcript", &format!("Deduplicated {}", stats));
 
         Ok(dedup_entries)
     }
 
-    /// Parse a single file with caching and memory mapping
-    async fn parse_file_cached(&self, path: PathBuf) -> Result<Arc<Vec<ParsedEntry>>> {
-        // Check cache first
+    /// Parse a single file with caching and memory mapping. Cached entries are only served
+    /// while the file still has the `size` and `mtime` they were parsed at, so a transcript
+    /// that has grown since is parsed again.
+    async fn parse_file_cached(&self, path: PathBuf, size: u64, mtime: SystemTime) -> Result<Arc<Vec<Arc<ParsedEntry>>>> {
         if let Some(cached) = self.cache.get(&path) {
-            return Ok(cached.clone());
+            if cached.size == size && cached.mtime == mtime {
+                return Ok(cached.entries.clone());
+            }
         }
 
         // Parse file with memory mapping for large files
         let entries = parse_jsonl_file_mmap(&path).await?;
-        let entries_arc = Arc::new(entries);
-        
-        // Cache the result
-        self.cache.insert(path, entries_arc.clone());
-        
+        let entries_arc = Arc::new(entries.into_iter().map(Arc::new).collect::<Vec<_>>());
+
+        // Cache the result under the stamp read before parsing: if the file grew meanwhile,
+        // the next load sees a different stamp and parses it again
+        self.cache.insert(path, CachedTranscript { size, mtime, entries: entries_arc.clone() });
+
         Ok(entries_arc)
     }
 
@@ -176,7 +215,7 @@ impl TranscriptParser {
     pub async fn get_recent_entries(
         &self,
         hours_back: u32,
-    ) -> Result<Vec<ParsedEntry>> {
+    ) -> Result<Vec<Arc<ParsedEntry>>> {
         let cutoff = Utc::now() - chrono::Duration::hours(hours_back as i64);
         
         self.load_entries(
@@ -186,7 +225,7 @@ impl TranscriptParser {
     }
 
     /// Get entries for today only (optimized for daily segments)
-    pub async fn get_today_entries(&self) -> Result<Vec<ParsedEntry>> {
+    pub async fn get_today_entries(&self) -> Result<Vec<Arc<ParsedEntry>>> {
         let today_start = Utc::now().date_naive().and_hms_opt(0, 0, 0)
             .unwrap().and_utc();
         
@@ -197,8 +236,24 @@ impl TranscriptParser {
     }
 }
 
+/// Files at least this large are memory-mapped instead of read into memory
+pub const DEFAULT_MMAP_THRESHOLD: u64 = 1024 * 1024;
+
+/// Size cutoff for memory-mapping transcripts, overridable in bytes via `CLAUDE_POWERLINE_MMAP_THRESHOLD`
+pub fn mmap_threshold() -> u64 {
+    std::env::var("CLAUDE_POWERLINE_MMAP_THRESHOLD")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_MMAP_THRESHOLD)
+}
+
 /// Memory-mapped JSONL parsing for maximum performance
 async fn parse_jsonl_file_mmap(path: &Path) -> Result<Vec<ParsedEntry>> {
+    if is_compressed(path) {
+        let mut content = read_compressed(path)?;
+        return Ok(parse_counting_errors(path, &mut content));
+    }
+
     let file = File::open(path)
         .with_context(|| format!("Failed to open file: {}", path.display()))?;
     
@@ -206,64 +261,215 @@ async fn parse_jsonl_file_mmap(path: &Path) -> Result<Vec<ParsedEntry>> {
     let file_size = metadata.len();
     
     // For small files, use regular parsing
-    if file_size < 1024 * 1024 {
+    if file_size < mmap_threshold() {
         return parse_jsonl_file_regular(path).await;
     }
 
-    // Use memory mapping for large files
-    let mmap = unsafe {
-        Mmap::map(&file)
+    // Private copy-on-write mapping: simd-json parses each line in place, and only
+    // the pages it actually writes to are copied
+    let mut mmap = unsafe {
+        MmapOptions::new()
+            .map_copy(&file)
             .with_context(|| format!("Failed to mmap file: {}", path.display()))?
     };
 
-    let content = std::str::from_utf8(&mmap)
-        .with_context(|| format!("Invalid UTF-8 in file: {}", path.display()))?;
-
-    parse_jsonl_content(content)
+    Ok(parse_counting_errors(path, &mut mmap))
 }
 
 /// Regular file parsing for smaller files
 async fn parse_jsonl_file_regular(path: &Path) -> Result<Vec<ParsedEntry>> {
-    let content = fs::read_to_string(path).await
+    let mut content = fs::read(path).await
         .with_context(|| format!("Failed to read file: {}", path.display()))?;
     
-    parse_jsonl_content(&content)
+    Ok(parse_counting_errors(path, &mut content))
+}
+
+/// Parse a transcript's contents, recording what was skipped and recovered
+fn parse_counting_errors(path: &Path, buffer: &mut [u8]) -> Vec<ParsedEntry> {
+    let (entries, stats) = parse_jsonl_bytes_with_stats(buffer);
+    record_parse_stats(path, &stats);
+    entries
 }
 
 /// Parse JSONL content with SIMD JSON for maximum performance
 pub fn parse_jsonl_content(content: &str) -> Result<Vec<ParsedEntry>> {
+    // simd-json needs a mutable buffer, so copy the content once rather than once per line
+    let mut buffer = content.as_bytes().to_vec();
+    Ok(parse_jsonl_bytes(&mut buffer))
+}
+
+/// Parse JSONL from a mutable buffer, handing each line to simd-json in place.
+/// The buffer is clobbered in the process; invalid lines are skipped silently.
+pub fn parse_jsonl_bytes(buffer: &mut [u8]) -> Vec<ParsedEntry> {
+    parse_jsonl_bytes_with_stats(buffer).0
+}
+
+/// `parse_jsonl_bytes`, also returning how many lines weren't valid JSON. Valid lines without
+/// a timestamp (summaries, snapshots) are skipped without counting as errors.
+pub fn parse_jsonl_bytes_counting(buffer: &mut [u8]) -> (Vec<ParsedEntry>, usize) {
+    let (entries, stats) = parse_jsonl_bytes_with_stats(buffer);
+    (entries, stats.skipped_lines)
+}
+
+/// `parse_jsonl_bytes`, also returning what was skipped and recovered (see `parse_jsonl_lines`)
+pub fn parse_jsonl_bytes_with_stats(buffer: &mut [u8]) -> (Vec<ParsedEntry>, ParseStats) {
+    parse_jsonl_lines(buffer, parse_jsonl_line)
+}
+
+const BYTE_ORDER_MARK: &[u8] = b"\xEF\xBB\xBF";
+
+/// Run `parse` over every line of a JSONL buffer. A line that isn't valid JSON as it stands
+/// goes through recovery rather than being dropped: a leading byte order mark is stripped,
+/// objects written back to back on one line are read one by one, and after an object cut
+/// short by an interrupted write, reading resumes at the next object on the line. An
+/// unterminated last line that ends mid-object is Claude Code still appending to it; it is
+/// counted as pending rather than skipped.
+pub(crate) fn parse_jsonl_lines(
+    buffer: &mut [u8],
+    mut parse: impl FnMut(&mut [u8]) -> Result<Option<ParsedEntry>>,
+) -> (Vec<ParsedEntry>, ParseStats) {
     let mut entries = Vec::new();
-    
-    for (line_num, line) in content.lines().enumerate() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
+    let mut stats = ParseStats::default();
+    // simd-json parses in place and may clobber a line it rejects, so a line that doesn't look
+    // like one whole object is parsed from a copy, keeping the original for recovery
+    let mut scratch = Vec::new();
+
+    let mut lines = split_lines_mut(buffer);
+    while let Some(line) = lines.next() {
+        let unterminated = !lines.last_terminated();
+        let line = trim_ascii_mut(line);
+        let had_mark = line.starts_with(BYTE_ORDER_MARK);
+        let line = if had_mark { trim_ascii_mut(&mut line[BYTE_ORDER_MARK.len()..]) } else { line };
+        if line.is_empty() {
             continue;
         }
 
-        match parse_jsonl_line(trimmed) {
-            Ok(Some(entry)) => entries.push(entry),
-            Ok(None) => continue, // Skip entries without timestamp
-            Err(_e) => {
-                // Silently skip invalid lines instead of showing warnings
-                continue;
+        // Objects written back to back would be rejected only after the first one's strings were
+        // unescaped over the line; anything cut short fails before the object that follows it
+        let whole = line.first() == Some(&b'{')
+            && line.last() == Some(&b'}')
+            && memchr::memmem::find(line, b"}{").is_none();
+        let parsed = if whole {
+            parse(line)
+        } else {
+            scratch.clear();
+            scratch.extend_from_slice(line);
+            parse(&mut scratch)
+        };
+        match parsed {
+            Ok(entry) => {
+                entries.extend(entry);
+                if had_mark {
+                    stats.recovered_lines += 1;
+                    stats.recovered_bytes += line.len();
+                }
             }
+            Err(e) if is_json_error(&e) => {
+                recover_line(line, unterminated, &mut scratch, &mut parse, &mut entries, &mut stats);
+            }
+            // Lines without a timestamp are skipped
+            Err(_) => {}
         }
     }
 
-    Ok(entries)
+    (entries, stats)
 }
 
-/// Parse a single JSONL line with error handling
-fn parse_jsonl_line(line: &str) -> Result<Option<ParsedEntry>> {
-    // Try SIMD JSON first for performance
-    let mut line_bytes = line.as_bytes().to_vec();
-    let raw_value = match simd_json::to_borrowed_value(&mut line_bytes) {
-        Ok(val) => serde_json::to_value(&val).unwrap(),
-        Err(_) => {
-            // Fallback to regular serde_json
-            serde_json::from_str(line)?
+/// Read what can be read from a line that isn't one valid JSON object, object by object
+fn recover_line(
+    line: &[u8],
+    unterminated: bool,
+    scratch: &mut Vec<u8>,
+    parse: &mut impl FnMut(&mut [u8]) -> Result<Option<ParsedEntry>>,
+    entries: &mut Vec<ParsedEntry>,
+    stats: &mut ParseStats,
+) {
+    let (mut recovered, mut skipped, mut pending) = (0, 0, 0);
+    let mut produced = false;
+    // Whether the next object starts where the last good one ended, rather than where reading
+    // resumed after garbage: an object found by resuming may be nested in a cut-off one, so it
+    // only counts if it is an entry
+    let mut aligned = true;
+    let mut pos = 0;
+
+    while pos < line.len() {
+        if line[pos].is_ascii_whitespace() {
+            pos += 1;
+            continue;
         }
-    };
+
+        let start = pos;
+        let end = if line[start] == b'{' {
+            let mut stream = serde_json::Deserializer::from_slice(&line[start..]).into_iter::<serde::de::IgnoredAny>();
+            match stream.next() {
+                Some(Ok(_)) => Some(start + stream.byte_offset()),
+                Some(Err(e)) if e.is_eof() && unterminated => {
+                    pending = line.len() - start;
+                    break;
+                }
+                _ => None,
+            }
+        } else {
+            None
+        };
+
+        let Some(end) = end else {
+            // Resume at the next object, leaving out everything before it
+            pos = line[start + 1..].iter().position(|&b| b == b'{').map_or(line.len(), |i| start + 1 + i);
+            skipped += pos - start;
+            aligned = false;
+            continue;
+        };
+
+        scratch.clear();
+        scratch.extend_from_slice(&line[start..end]);
+        let counts = match parse(scratch) {
+            Ok(Some(entry)) => {
+                entries.push(entry);
+                produced = true;
+                true
+            }
+            Err(e) if is_json_error(&e) => false,
+            // Valid JSON without a timestamp, like a summary written next to an entry
+            _ => aligned,
+        };
+        if counts {
+            recovered += end - start;
+        } else {
+            skipped += end - start;
+        }
+        aligned = counts;
+        pos = end;
+    }
+
+    if produced || (recovered > 0 && skipped == 0) {
+        stats.recovered_lines += 1;
+        stats.recovered_bytes += recovered;
+        stats.skipped_bytes += skipped;
+        stats.pending_bytes += pending;
+    } else if skipped == 0 && pending > 0 {
+        stats.pending_bytes += pending;
+    } else {
+        stats.skipped_lines += 1;
+        stats.skipped_bytes += line.len();
+    }
+}
+
+/// Whether a line failed because it isn't JSON at all, rather than lacking fields
+pub(crate) fn is_json_error(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<simd_json::Error>().is_some()
+}
+
+pub(crate) fn trim_ascii_mut(bytes: &mut [u8]) -> &mut [u8] {
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+    let end = bytes.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(start, |i| i + 1);
+    &mut bytes[start..end]
+}
+
+/// Parse a single JSONL line in place with error handling
+fn parse_jsonl_line(line: &mut [u8]) -> Result<Option<ParsedEntry>> {
+    let borrowed = simd_json::to_borrowed_value(line)?;
+    let raw_value = serde_json::to_value(&borrowed)?;
 
     // Extract timestamp - skip entries without valid timestamp
     let timestamp_str = raw_value
@@ -276,9 +482,9 @@ fn parse_jsonl_line(line: &str) -> Result<Option<ParsedEntry>> {
         .with_context(|| format!("Invalid timestamp format: {}", timestamp_str))?
         .with_timezone(&Utc);
 
-    // Parse message info if present
+    // Parse message info if present, deserializing from the value by reference
     let message = raw_value.get("message")
-        .and_then(|v| serde_json::from_value(v.clone()).ok());
+        .and_then(|v| MessageInfo::deserialize(v).ok());
 
     // Extract cost if present
     let cost_usd = raw_value.get("costUSD")
@@ -288,8 +494,11 @@ fn parse_jsonl_line(line: &str) -> Result<Option<ParsedEntry>> {
     let is_sidechain = raw_value.get("isSidechain")
         .and_then(|v| v.as_bool());
 
-    // Convert to HashMap for raw storage
-    let raw: HashMap<String, serde_json::Value> = serde_json::from_value(raw_value)?;
+    // Move the top-level fields into raw storage without another round of deserialization
+    let raw: HashMap<String, serde_json::Value> = match raw_value {
+        serde_json::Value::Object(map) => map.into_iter().collect(),
+        _ => anyhow::bail!("Transcript line is not a JSON object"),
+    };
 
     Ok(Some(ParsedEntry {
         timestamp,
@@ -318,7 +527,122 @@ pub fn create_unique_hash(entry: &ParsedEntry) -> Option<String> {
     Some(format!("{}:{}", message_id, request_id))
 }
 
-/// Get Claude configuration paths with cross-platform support
+/// 128-bit deduplication key (xxh3) of an entry's message and request ids,
+/// avoiding a formatted String per entry
+pub fn unique_entry_key(entry: &ParsedEntry) -> Option<u128> {
+    let message_id = entry.message.as_ref()
+        .and_then(|m| m.id.as_ref())
+        .map(|s| s.as_str())
+        .or_else(|| {
+            entry.raw.get("message")
+                .and_then(|v| v.get("id"))
+                .and_then(|v| v.as_str())
+        })?;
+
+    let request_id = entry.raw.get("requestId")
+        .and_then(|v| v.as_str())?;
+
+    let mut hasher = Xxh3::new();
+    hasher.update(message_id.as_bytes());
+    hasher.update(b":");
+    hasher.update(request_id.as_bytes());
+    Some(hasher.digest128())
+}
+
+/// 128-bit deduplication key of an entry's content, for entries without a message/request id
+/// pair. Claude Code gives every transcript line a `uuid`, which identifies copies of the same
+/// line (in overlapping config directories, say); entries without one are hashed whole.
+pub fn entry_content_key(entry: &ParsedEntry) -> u128 {
+    let mut hasher = Xxh3::new();
+    hasher.update(b"content:");
+    hasher.update(&entry.timestamp.timestamp_micros().to_le_bytes());
+
+    if let Some(uuid) = entry.raw.get("uuid").and_then(|v| v.as_str()) {
+        hasher.update(uuid.as_bytes());
+        return hasher.digest128();
+    }
+
+    if let Ok(message) = serde_json::to_vec(&entry.message) {
+        hasher.update(&message);
+    }
+    hasher.update(&entry.cost_usd.unwrap_or_default().to_le_bytes());
+    hasher.update(&[entry.is_sidechain.map_or(2, u8::from)]);
+
+    // `raw` iterates in no particular order, so its fields are hashed sorted by name
+    let mut fields: Vec<_> = entry.raw.iter().collect();
+    fields.sort_unstable_by_key(|(name, _)| name.as_str());
+    for (name, value) in fields {
+        hasher.update(name.as_bytes());
+        if let Ok(value) = serde_json::to_vec(value) {
+            hasher.update(&value);
+        }
+    }
+
+    hasher.digest128()
+}
+
+/// Key deduplicating `entry`: its message/request id pair, falling back to its content
+pub fn dedupe_key(entry: &ParsedEntry) -> u128 {
+    unique_entry_key(entry).unwrap_or_else(|| entry_content_key(entry))
+}
+
+/// Counts from a deduplication pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupeStats {
+    pub total: usize,
+    pub unique: usize,
+    pub duplicates: usize,
+    /// Kept entries without a message/request id pair, deduplicated by content instead
+    pub unkeyed: usize,
+}
+
+impl std::fmt::Display for DedupeStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} entries, {} unique, {} duplicates dropped, {} without ids",
+            self.total, self.unique, self.duplicates, self.unkeyed
+        )
+    }
+}
+
+/// Drop entries whose message/request pair (or, lacking one, content) was already seen, keeping
+/// the first occurrence.
+/// Works on owned entries as well as shared `Arc<ParsedEntry>` handles.
+pub fn deduplicate_entries<E: Borrow<ParsedEntry>>(entries: Vec<E>) -> (Vec<E>, DedupeStats) {
+    let mut stats = DedupeStats { total: entries.len(), ..Default::default() };
+    let mut seen_keys = HashSet::with_capacity(entries.len());
+    let mut deduplicated = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let keyed = unique_entry_key(entry.borrow());
+        let key = keyed.unwrap_or_else(|| entry_content_key(entry.borrow()));
+        if !seen_keys.insert(key) {
+            stats.duplicates += 1;
+            continue;
+        }
+
+        if keyed.is_none() {
+            stats.unkeyed += 1;
+        }
+        deduplicated.push(entry);
+    }
+
+    stats.unique = deduplicated.len() - stats.unkeyed;
+    (deduplicated, stats)
+}
+
+/// Claude directories configured via `claudePaths`, set once at startup
+static CONFIGURED_CLAUDE_PATHS: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+/// Scan `paths` instead of the platform's default Claude directories; only the first call has an effect
+pub fn set_claude_paths(paths: Vec<PathBuf>) {
+    let _ = CONFIGURED_CLAUDE_PATHS.set(paths);
+}
+
+/// Get Claude configuration paths with cross-platform support: `CLAUDE_CONFIG_DIR`, then the
+/// configured `claudePaths`, then the platform defaults. Paths written for the other side of
+/// WSL (`C:\...` inside WSL, `/mnt/c/...` on Windows) are translated.
 pub fn get_claude_paths() -> Result<Vec<PathBuf>> {
     let mut paths = Vec::new();
 
@@ -326,13 +650,22 @@ pub fn get_claude_paths() -> Result<Vec<PathBuf>> {
     if let Ok(env_paths) = std::env::var("CLAUDE_CONFIG_DIR") {
         let separator = if cfg!(windows) { ';' } else { ',' };
         for path_str in env_paths.split(separator) {
-            let path = PathBuf::from(path_str.trim());
+            let path = bridge_path(Path::new(path_str.trim()));
             if path.exists() {
                 paths.push(path);
             }
         }
     }
 
+    if paths.is_empty() {
+        for path in CONFIGURED_CLAUDE_PATHS.get().into_iter().flatten() {
+            let path = bridge_path(path);
+            if path.exists() && !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+
     // Fallback to platform-specific default locations
     if paths.is_empty() {
         if let Some(home) = dirs::home_dir() {
@@ -372,6 +705,11 @@ pub fn get_claude_paths() -> Result<Vec<PathBuf>> {
                 } else if claude_path.exists() {
                     paths.push(claude_path);
                 }
+
+                // Inside WSL, Claude Code may also run on the Windows side
+                if is_wsl() {
+                    paths.extend(windows_claude_dirs());
+                }
             }
         }
     }
@@ -383,37 +721,234 @@ pub fn get_claude_paths() -> Result<Vec<PathBuf>> {
     Ok(paths)
 }
 
-/// Find all project paths within Claude directories
+/// Find all project paths within Claude directories, each directory once
 pub async fn find_project_paths(claude_paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
-    let mut project_paths = Vec::new();
+    cached_project_paths(claude_paths)
+}
+
+/// How long a listing of the project directories is reused, as long as no `projects` directory
+/// has changed in the meantime
+pub const PROJECT_PATHS_TTL: Duration = Duration::from_secs(5);
+
+/// A listing of the project directories, with the `projects` directory mtimes it was made at
+#[derive(Clone)]
+struct ProjectListing {
+    mtimes: Vec<Option<SystemTime>>,
+    paths: Vec<PathBuf>,
+}
+
+/// Project listings by the Claude directories they cover, shared by every segment in the process
+static PROJECT_PATHS: OnceLock<Cache<Vec<PathBuf>, ProjectListing>> = OnceLock::new();
+
+fn projects_dir_mtimes(claude_paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    claude_paths
+        .iter()
+        .map(|claude_path| std::fs::metadata(claude_path.join("projects")).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// The project directories under each Claude directory's `projects`, each once. A listing is
+/// reused for `PROJECT_PATHS_TTL`, or until a `projects` directory's mtime changes (a project
+/// was added or removed), instead of walking and canonicalizing every directory per segment.
+pub fn cached_project_paths(claude_paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let cache = PROJECT_PATHS.get_or_init(|| Cache::new(PROJECT_PATHS_TTL));
+    let mtimes = projects_dir_mtimes(claude_paths);
+    let key = claude_paths.to_vec();
+
+    if let Some(listing) = cache.get(&key) {
+        if listing.mtimes == mtimes {
+            return Ok(listing.paths);
+        }
+    }
 
+    let mut project_paths = Vec::new();
     for claude_path in claude_paths {
         let projects_dir = claude_path.join("projects");
-        if projects_dir.exists() {
-            let mut entries = fs::read_dir(&projects_dir).await?;
-            while let Some(entry) = entries.next_entry().await? {
-                if entry.file_type().await?.is_dir() {
-                    project_paths.push(entry.path());
-                }
+        if !projects_dir.exists() {
+            continue;
+        }
+        for entry in std::fs::read_dir(&projects_dir)
+            .with_context(|| format!("Failed to read {}", projects_dir.display()))?
+        {
+            let entry = entry.context("Failed to read project directory")?;
+            if entry.file_type()?.is_dir() {
+                project_paths.push(entry.path());
+            }
+        }
+    }
+
+    let paths = dedupe_canonical_paths(project_paths);
+    cache.insert(key, ProjectListing { mtimes, paths: paths.clone() });
+    Ok(paths)
+}
+
+/// Drop paths leading to the same file or directory as an earlier one, as when both
+/// `~/.config/claude` and `~/.claude` hold symlinks to (or mounts of) the same projects.
+/// Paths that can't be resolved are compared as given.
+pub fn dedupe_canonical_paths(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = HashSet::with_capacity(paths.len());
+    paths
+        .into_iter()
+        .filter(|path| seen.insert(std::fs::canonicalize(path).unwrap_or_else(|_| path.clone())))
+        .collect()
+}
+
+/// Extra transcript locations configured via `transcriptGlobs`, set once at startup
+static TRANSCRIPT_GLOBS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Also read transcripts matching `globs`, on top of the Claude directories; only the first call has an effect
+pub fn set_transcript_globs(globs: Vec<String>) {
+    let _ = TRANSCRIPT_GLOBS.set(globs);
+}
+
+/// Transcript files matched by the configured `transcriptGlobs`
+pub fn configured_transcript_files() -> Vec<PathBuf> {
+    TRANSCRIPT_GLOBS.get().map(|globs| expand_transcript_globs(globs)).unwrap_or_default()
+}
+
+/// Expand transcript locations outside the Claude directories. A plain path names a transcript
+/// or a directory searched recursively; otherwise `*`, `?` and `[...]` match within one path
+/// component and `**` across any number of them. A leading `~/` stands for the home directory.
+/// Only transcript files (see `is_transcript_path`) are returned, each once.
+pub fn expand_transcript_globs(globs: &[String]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+
+    for glob in globs {
+        let glob = match (glob.strip_prefix("~/"), dirs::home_dir()) {
+            (Some(rest), Some(home)) => home.join(rest).to_string_lossy().into_owned(),
+            _ => glob.clone(),
+        };
+
+        // Walk from the deepest directory that precedes any wildcard
+        let path = Path::new(&glob);
+        let base: PathBuf = path.components()
+            .take_while(|component| !component.as_os_str().to_string_lossy().contains(['*', '?', '[']))
+            .collect();
+        let is_pattern = base.as_path() != path;
+        // Without `**` a pattern can't match below its own depth
+        let max_depth = if is_pattern && !glob.contains("**") {
+            path.components().count() - base.components().count()
+        } else {
+            usize::MAX
+        };
+
+        let walker = walkdir::WalkDir::new(&base).follow_links(true).max_depth(max_depth);
+        for entry in walker.into_iter().filter_map(|entry| entry.ok()) {
+            let candidate = entry.path();
+            if !entry.file_type().is_file() || !is_transcript_path(candidate) {
+                continue;
             }
+            let matches = !is_pattern || gix::glob::wildmatch(
+                glob.as_str().into(),
+                candidate.to_string_lossy().as_ref().into(),
+                gix::glob::wildmatch::Mode::NO_MATCH_SLASH_LITERAL,
+            );
+            if matches && seen.insert(candidate.to_path_buf()) {
+                files.push(candidate.to_path_buf());
+            }
+        }
+    }
+
+    files
+}
+
+/// Decode a project directory name back into the working directory it was created for.
+/// Claude replaces path separators with dashes (`/home/me/app` -> `-home-me-app`), so
+/// dashes inside the original directory names cannot be recovered.
+pub fn decode_project_dir_name(name: &str) -> String {
+    if let Some(rest) = name.strip_prefix('-') {
+        format!("/{}", rest.replace('-', "/"))
+    } else if let Some((drive, rest)) = name.split_once("--") {
+        // Windows: C:\Users\me -> C--Users-me
+        format!("{}:\\{}", drive, rest.replace('-', "\\"))
+    } else {
+        name.to_string()
+    }
+}
+
+/// Determine the project an entry belongs to, preferring the recorded `cwd` over the
+/// (lossy) decoded transcript directory name
+pub fn entry_project_name(entry: &ParsedEntry) -> Option<String> {
+    if let Some(cwd) = entry.raw.get("cwd").and_then(|v| v.as_str()) {
+        if !cwd.is_empty() {
+            return Some(cwd.to_string());
         }
     }
 
-    Ok(project_paths)
+    let source_file = entry.source_file.as_ref()?;
+    let dir_name = Path::new(source_file.as_ref()).parent()?.file_name()?.to_str()?;
+    Some(decode_project_dir_name(dir_name))
 }
 
-/// Find transcript file for a specific session
+/// Most recent modification time across all transcript files, used to detect new usage cheaply
+pub async fn latest_transcript_mtime(claude_paths: &[PathBuf]) -> Option<std::time::SystemTime> {
+    let project_paths = find_project_paths(claude_paths).await.ok()?;
+    let mut latest = None;
+
+    for project_path in project_paths {
+        let Ok(mut entries) = fs::read_dir(&project_path).await else {
+            continue;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if !is_transcript_path(&entry.path()) {
+                continue;
+            }
+            if let Ok(modified) = entry.metadata().await.and_then(|m| m.modified()) {
+                latest = latest.max(Some(modified));
+            }
+        }
+    }
+
+    latest
+}
+
+/// How long a session whose transcript wasn't found is taken to still have none, so segments
+/// of a brand-new session don't each scan every project directory
+pub const MISSING_TRANSCRIPT_TTL: Duration = Duration::from_secs(2);
+
+/// How long a found transcript's location is remembered; it is checked to still exist on every use
+const FOUND_TRANSCRIPT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// A session id and the Claude directories its transcript was looked for in
+type SessionLookup = (Vec<PathBuf>, String);
+
+/// Where each session's transcript was found, or `None` for sessions found to have none yet
+static SESSION_TRANSCRIPTS: OnceLock<Cache<SessionLookup, Option<PathBuf>>> = OnceLock::new();
+
+/// Find transcript file for a specific session. Where a session's transcript was found is
+/// remembered for the life of the process, and that it wasn't found for `MISSING_TRANSCRIPT_TTL`.
 pub async fn find_transcript_file(session_id: &str) -> Result<Option<PathBuf>> {
     let claude_paths = get_claude_paths()?;
+    let cache = SESSION_TRANSCRIPTS.get_or_init(|| Cache::new(FOUND_TRANSCRIPT_TTL));
+    let key = (claude_paths.clone(), session_id.to_string());
+    match cache.get(&key) {
+        Some(Some(path)) if path.exists() => return Ok(Some(path)),
+        Some(None) => return Ok(None),
+        // Moved or deleted since; look again
+        _ => {}
+    }
+
+    // Where an earlier run read it, without walking the project directories
+    if let Some(path) = with_session_index(|index| index.find_session(session_id, &claude_paths).map(Path::to_path_buf)).flatten() {
+        if path.exists() {
+            cache.insert(key, Some(path.clone()));
+            return Ok(Some(path));
+        }
+    }
+
     let project_paths = find_project_paths(&claude_paths).await?;
 
     for project_path in project_paths {
         let transcript_path = project_path.join(format!("{}.jsonl", session_id));
         if transcript_path.exists() {
+            cache.insert(key, Some(transcript_path.clone()));
             return Ok(Some(transcript_path));
         }
     }
 
+    cache.insert_with_ttl(key, None, MISSING_TRANSCRIPT_TTL);
     Ok(None)
 }
 
@@ -1,25 +1,88 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use rayon::prelude::*;
-use serde_json::{Deserializer, Value};
-use std::collections::{HashMap, HashSet};
-use std::fs::File;
-use std::io::BufReader;
+use memmap2::MmapOptions;
+use simd_json::prelude::*;
+use simd_json::BorrowedValue;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{File, Metadata};
+use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
 use walkdir::WalkDir;
 
-use crate::utils::claude::{ParsedEntry, MessageInfo, UsageInfo, get_claude_paths};
+use crate::sources::{ClaudeTranscriptSource, UsageSource};
+use crate::utils::claude::{ParsedEntry, MessageInfo, UsageInfo, CacheCreation, cached_project_paths, configured_transcript_files, dedupe_key, deduplicate_entries, get_claude_paths, mmap_threshold, parse_jsonl_lines};
+use crate::utils::pricing::{PricingService, SessionDeltas, UsageFold};
+use crate::utils::archive::{is_compressed, is_transcript_path, read_compressed};
+use crate::utils::intern::intern;
+use crate::utils::logger::debug_with_context;
+use crate::utils::parallelism::parse_concurrency;
+use crate::utils::parse_errors::{record_parse_stats, ParseStats};
+use crate::utils::session_index::{save_session_index, with_session_index};
+use crate::utils::timing::{record_parse, record_stage};
+use crate::utils::watcher::prewarmed_entries_for;
+use crate::utils::wsl::bridge_path;
+use crate::utils::entry_cache::{entry_cache_dir, load_cached_entries_for, store_cached_entries_for};
+
+/// A transcript found by discovery, with the metadata read for it there. The metadata is
+/// carried through filtering, deduplication and parsing, so each file is stat'ed once.
+#[derive(Debug, Clone)]
+pub struct TranscriptFile {
+    pub path: PathBuf,
+    pub metadata: Metadata,
+}
+
+impl TranscriptFile {
+    /// Read the metadata of a transcript that wasn't found by discovery
+    pub fn stat(path: &Path) -> Result<Self> {
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("Failed to open file: {}", path.display()))?;
+        Ok(Self { path: path.to_path_buf(), metadata })
+    }
+
+    pub fn modified(&self) -> Option<DateTime<Utc>> {
+        self.metadata.modified().ok().map(DateTime::<Utc>::from)
+    }
+
+    /// What identifies the file itself, whichever path leads to it: its device and inode where
+    /// the metadata has them, its canonical path elsewhere
+    fn identity(&self) -> FileIdentity {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            FileIdentity::Inode(self.metadata.dev(), self.metadata.ino())
+        }
+        #[cfg(not(unix))]
+        {
+            FileIdentity::Path(std::fs::canonicalize(&self.path).unwrap_or_else(|_| self.path.clone()))
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum FileIdentity {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Inode(u64, u64),
+    #[cfg_attr(unix, allow(dead_code))]
+    Path(PathBuf),
+}
 
 /// High-performance data aggregation pipeline that discovers all Claude projects,
 /// loads transcript files in parallel, and performs global deduplication
 pub struct DataAggregator {
     time_filter_hours: Option<u32>,
+    /// Claude directories to scan instead of the local ones, such as a teammate's export
+    claude_paths: Option<Vec<PathBuf>>,
 }
 
 impl DataAggregator {
     pub fn new() -> Self {
         Self {
             time_filter_hours: None,
+            claude_paths: None,
         }
     }
 
@@ -28,102 +91,266 @@ impl DataAggregator {
         self
     }
 
+    /// Scan only `paths` (translated for WSL like `claudePaths`) instead of the local Claude
+    /// directories and `transcriptGlobs`; paths that don't exist are skipped
+    pub fn with_claude_paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.claude_paths = Some(paths.iter().map(|path| bridge_path(path)).filter(|path| path.exists()).collect());
+        self
+    }
+
+    /// The Claude directories this aggregator reads
+    fn claude_paths(&self) -> Result<Vec<PathBuf>> {
+        match &self.claude_paths {
+            Some(paths) => Ok(paths.clone()),
+            None => get_claude_paths(),
+        }
+    }
+
     /// Load all entries from all projects with optional time filtering
     pub async fn load_all_entries(&self) -> Result<Vec<ParsedEntry>> {
         // Phase 1: Discover all project directories
-        let claude_paths = get_claude_paths()?;
+        let started = Instant::now();
+        let claude_paths = self.claude_paths()?;
         let project_paths = self.discover_all_projects(&claude_paths)?;
+
+        // The index only covers the local Claude directories
+        #[cfg(feature = "sqlite-index")]
+        if let Some(index_path) = crate::utils::usage_index::index_path_from_env().filter(|_| self.claude_paths.is_none()) {
+            match self.load_from_index(&index_path, &project_paths) {
+                Ok(entries) => return Ok(entries),
+                Err(e) => crate::utils::debug_with_context(
+                    "usage_index",
+                    &format!("Index unavailable, parsing transcripts instead: {:#}", e),
+                ),
+            }
+        }
         
         // Phase 2: Discover all transcript files with time filtering
         let transcript_files = self.discover_transcript_files(&project_paths)?;
+        record_stage("discovery", started.elapsed(), Some(transcript_files.len()), None);
         
         // Phase 3: Parse files in parallel using streaming
+        let started = Instant::now();
         let all_entries = self.parse_files_parallel(&transcript_files)?;
+        save_session_index();
+        record_parse(transcript_files.len(), all_entries.len());
+        record_stage("parse", started.elapsed(), Some(transcript_files.len()), Some(all_entries.len()));
         
         // Phase 4: Global deduplication and sorting
+        let started = Instant::now();
         let deduplicated_entries = self.deduplicate_and_sort(all_entries)?;
+        record_stage("dedupe", started.elapsed(), None, Some(deduplicated_entries.len()));
         
         Ok(deduplicated_entries)
     }
 
-    /// Discover all project directories across all Claude paths
-    fn discover_all_projects(&self, claude_paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
-        let mut project_paths = Vec::new();
-        
-        for claude_path in claude_paths {
-            let projects_dir = claude_path.join("projects");
-            if !projects_dir.exists() {
+    /// Fold usage into per-group running totals without holding the whole history in memory.
+    /// Transcripts are parsed one parallel batch at a time, folded in file order and dropped;
+    /// only the deduplication keys and each transcript's totals outlive a batch. `group` picks
+    /// the bucket for an entry (`None` skips it), and token deltas are tracked per transcript
+    /// and bucket, as `PricingService` does for grouped entries.
+    ///
+    /// Of duplicated entries the earliest is counted (the first transcript's on a tie), as
+    /// `load_all_entries` keeps it. A copy found in a later batch that predates the one already
+    /// folded takes its place, and the transcripts of both are folded again at the end.
+    pub async fn fold_usage_by<K, G>(&self, group: G) -> Result<BTreeMap<K, UsageFold>>
+    where
+        K: Ord + Clone,
+        G: Fn(&ParsedEntry) -> Option<K>,
+    {
+        let started = Instant::now();
+        let claude_paths = self.claude_paths()?;
+        let project_paths = self.discover_all_projects(&claude_paths)?;
+        let transcript_files = self.discover_transcript_files(&project_paths)?;
+        record_stage("discovery", started.elapsed(), Some(transcript_files.len()), None);
+
+        let pricing_service = PricingService::new();
+        // The copy of each deduplication key that counts: its timestamp and transcript
+        let mut winners: HashMap<u128, (DateTime<Utc>, usize)> = HashMap::new();
+        let mut transcript_totals = Vec::new();
+        let mut refold = HashSet::new();
+        let mut entries_parsed = 0;
+        // Parsing and folding (with its inline deduplication) interleave per batch
+        let mut parse_time = std::time::Duration::ZERO;
+        let fold_started = Instant::now();
+
+        for batch in transcript_files.chunks(parse_concurrency()) {
+            let started = Instant::now();
+            let mut parsed = self.parse_files_parallel(batch)?;
+            parse_time += started.elapsed();
+            entries_parsed += parsed.len();
+
+            // Entries arrive grouped by file; each file is one session
+            for file_entries in parsed.chunk_by_mut(|a, b| a.source_file == b.source_file) {
+                file_entries.sort_by_key(|e| e.timestamp);
+                let transcript = transcript_totals.len();
+
+                let totals = fold_transcript(file_entries, &group, &pricing_service, |entry| {
+                    match winners.entry(dedupe_key(entry)) {
+                        Entry::Vacant(slot) => {
+                            slot.insert((entry.timestamp, transcript));
+                            true
+                        }
+                        Entry::Occupied(mut slot) => {
+                            let (timestamp, owner) = *slot.get();
+                            if entry.timestamp < timestamp {
+                                slot.insert((entry.timestamp, transcript));
+                                refold.insert(owner);
+                                refold.insert(transcript);
+                            }
+                            false
+                        }
+                    }
+                });
+                transcript_totals.push((file_entries[0].source_file.clone(), totals));
+            }
+        }
+
+        // Fold the transcripts whose counted copies changed again, now that every winner is known
+        for transcript in refold {
+            let Some(source) = transcript_totals[transcript].0.clone() else {
                 continue;
+            };
+            let Some(file) = transcript_files.iter().find(|file| *file.path.to_string_lossy() == *source) else {
+                continue;
+            };
+
+            let mut entries = self.parse_transcript_file_streaming(file)?;
+            entries.sort_by_key(|e| e.timestamp);
+            let mut claimed = HashSet::new();
+            transcript_totals[transcript].1 = fold_transcript(&entries, &group, &pricing_service, |entry| {
+                let key = dedupe_key(entry);
+                winners.get(&key) == Some(&(entry.timestamp, transcript)) && claimed.insert(key)
+            });
+        }
+
+        let mut totals: BTreeMap<K, UsageFold> = BTreeMap::new();
+        for (_, transcript) in &transcript_totals {
+            for (key, fold) in transcript {
+                totals.entry(key.clone()).or_default().merge(fold);
             }
-            
-            for entry in WalkDir::new(&projects_dir)
-                .min_depth(1)
-                .max_depth(1)
-                .into_iter()
-                .filter_entry(|e| e.file_type().is_dir()) 
-            {
-                let entry = entry.context("Failed to read project directory")?;
-                project_paths.push(entry.into_path());
+        }
+
+        save_session_index();
+        record_parse(transcript_files.len(), entries_parsed);
+        record_stage("parse", parse_time, Some(transcript_files.len()), Some(entries_parsed));
+        record_stage("dedupe", fold_started.elapsed().saturating_sub(parse_time), None, Some(entries_parsed));
+        debug_with_context("aggregation", &format!(
+            "Folded {} entries from {} files into {} groups",
+            entries_parsed, transcript_files.len(), totals.len()
+        ));
+
+        Ok(totals)
+    }
+
+    /// Fold usage of every entry at or after `since` into a single total
+    pub async fn fold_usage_since(&self, since: DateTime<Utc>) -> Result<UsageFold> {
+        // With the index, the window is summed in SQL instead of parsed and folded
+        #[cfg(feature = "sqlite-index")]
+        if let Some(index_path) = crate::utils::usage_index::index_path_from_env().filter(|_| self.claude_paths.is_none()) {
+            let usage = self.discover_all_projects(&self.claude_paths()?)
+                .and_then(|project_paths| self.synced_index(&index_path, &project_paths))
+                .and_then(|index| index.usage_since(since, &PricingService::new()));
+            match usage {
+                Ok(usage) => return Ok(usage),
+                Err(e) => crate::utils::debug_with_context(
+                    "usage_index",
+                    &format!("Index unavailable, parsing transcripts instead: {:#}", e),
+                ),
             }
         }
-        
-        Ok(project_paths)
+
+        let mut totals = self.fold_usage_by(|entry| (entry.timestamp >= since).then_some(())).await?;
+        Ok(totals.remove(&()).unwrap_or_default())
     }
 
-    /// Discover all transcript files with optional time-based filtering
-    fn discover_transcript_files(&self, project_paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
-        let mut transcript_files = Vec::new();
+    /// Sync the SQLite usage index with every transcript and query the time window from it
+    #[cfg(feature = "sqlite-index")]
+    fn load_from_index(&self, index_path: &Path, project_paths: &[PathBuf]) -> Result<Vec<ParsedEntry>> {
+        let index = self.synced_index(index_path, project_paths)?;
         let cutoff_time = self.time_filter_hours
             .map(|hours| Utc::now() - chrono::Duration::hours(hours as i64));
-        
+        index.entries_since(cutoff_time)
+    }
+
+    /// Open the SQLite usage index and ingest whatever the transcripts gained since the last sync
+    #[cfg(feature = "sqlite-index")]
+    fn synced_index(&self, index_path: &Path, project_paths: &[PathBuf]) -> Result<crate::utils::usage_index::UsageIndex> {
+        let mut index = crate::utils::usage_index::UsageIndex::open(index_path)?;
+
+        // Discovery is unfiltered so that older history lands in the index too
+        let transcript_files = DataAggregator::new().discover_transcript_files(project_paths)?;
+        let added = index.sync_files(&transcript_files)?;
+        record_parse(transcript_files.len(), added);
+
+        Ok(index)
+    }
+
+    /// Discover all project directories across all Claude paths
+    fn discover_all_projects(&self, claude_paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        cached_project_paths(claude_paths)
+    }
+
+    /// Discover all transcript files (those in the project directories, then any matched by
+    /// `transcriptGlobs`) with optional time-based filtering. Each file is stat'ed once, and
+    /// deduplication and the time filter work from that metadata.
+    pub fn discover_transcript_files(&self, project_paths: &[PathBuf]) -> Result<Vec<TranscriptFile>> {
+        let mut candidates = Vec::new();
+
         for project_path in project_paths {
+            // Symlinks are resolved while walking, so a linked transcript has its target's type
             for entry in WalkDir::new(project_path)
                 .max_depth(1) // Only look in project directory, not subdirectories
+                .follow_links(true)
                 .into_iter()
             {
                 let entry = match entry {
                     Ok(e) => e,
                     Err(_) => continue, // Skip files we can't read
                 };
-                
-                let path = entry.path();
-                
-                // Only process .jsonl files
-                if !path.is_file() || !path.file_name()
-                    .map(|name| name.to_string_lossy().ends_with(".jsonl"))
-                    .unwrap_or(false) {
+
+                // Only process transcripts, including rotated and gzip-archived ones
+                if !entry.file_type().is_file() || !is_transcript_path(entry.path()) {
                     continue;
                 }
-                
-                // Apply time-based filtering if specified
-                if let Some(cutoff) = cutoff_time {
-                    if let Ok(metadata) = std::fs::metadata(path) {
-                        if let Ok(modified) = metadata.modified() {
-                            let modified_utc: DateTime<Utc> = modified.into();
-                            if modified_utc < cutoff {
-                                continue; // Skip old files
-                            }
-                        }
-                    }
+
+                if let Ok(metadata) = entry.metadata() {
+                    candidates.push(TranscriptFile { path: entry.into_path(), metadata });
                 }
-                
-                transcript_files.push(path.to_path_buf());
             }
         }
-        
-        Ok(transcript_files)
+
+        if self.claude_paths.is_none() {
+            candidates.extend(configured_transcript_files().iter().filter_map(|path| TranscriptFile::stat(path).ok()));
+        }
+
+        // A transcript reachable through several paths is read once
+        let mut seen = HashSet::with_capacity(candidates.len());
+        candidates.retain(|file| seen.insert(file.identity()));
+
+        // Apply time-based filtering if specified: files not modified since the cutoff are
+        // passed over, and so are files the session index knows end before it
+        if let Some(hours) = self.time_filter_hours {
+            let cutoff = Utc::now() - chrono::Duration::hours(hours as i64);
+            candidates.retain(|file| file.modified().is_none_or(|modified| modified >= cutoff));
+            with_session_index(|index| {
+                candidates.retain(|file| index.may_have_entries_since(&file.path, &file.metadata, cutoff));
+            });
+        }
+
+        Ok(candidates)
     }
 
     /// Parse multiple files in parallel using streaming JSON parsing
-    fn parse_files_parallel(&self, file_paths: &[PathBuf]) -> Result<Vec<ParsedEntry>> {
-        let all_entries: Vec<ParsedEntry> = file_paths
+    fn parse_files_parallel(&self, files: &[TranscriptFile]) -> Result<Vec<ParsedEntry>> {
+        let all_entries: Vec<ParsedEntry> = files
             .par_iter()
-            .flat_map(|path| {
-                match self.parse_transcript_file_streaming(path) {
+            .flat_map(|file| {
+                match self.parse_transcript_file_streaming(file) {
                     Ok(entries) => entries,
                     Err(e) => {
                         // Log error but continue processing other files
-                        eprintln!("Warning: Failed to parse {}: {}", path.display(), e);
+                        eprintln!("Warning: Failed to parse {}: {}", file.path.display(), e);
                         Vec::new()
                     }
                 }
@@ -133,49 +360,111 @@ impl DataAggregator {
         Ok(all_entries)
     }
 
-    /// Parse a single transcript file using streaming JSON parsing
-    fn parse_transcript_file_streaming(&self, file_path: &Path) -> Result<Vec<ParsedEntry>> {
+    /// Load a single transcript file, preferring pre-warmed entries (watch mode), then the
+    /// binary sidecar cache, and only parsing the JSON when neither is current
+    fn parse_transcript_file_streaming(&self, file: &TranscriptFile) -> Result<Vec<ParsedEntry>> {
+        // Pre-warmed entries are shared with the watcher, so only those in the time window are copied
+        if let Some(entries) = prewarmed_entries_for(&file.path, file.metadata.len()) {
+            with_session_index(|index| index.observe(&file.path, &file.metadata, &entries));
+            let cutoff = self.time_cutoff();
+            return Ok(entries.iter().filter(|entry| cutoff.is_none_or(|cutoff| entry.timestamp >= cutoff)).cloned().collect());
+        }
+
+        let entries = self.read_transcript_entries(file)?;
+        with_session_index(|index| index.observe(&file.path, &file.metadata, &entries));
+        Ok(self.apply_time_filter(entries))
+    }
+
+    /// Every entry of a transcript, from the sidecar cache when it is current
+    fn read_transcript_entries(&self, file: &TranscriptFile) -> Result<Vec<ParsedEntry>> {
+        let file_path = file.path.as_path();
+        let cache_dir = entry_cache_dir();
+        if let Some(entries) = cache_dir.as_deref().and_then(|dir| load_cached_entries_for(dir, file_path, &file.metadata)) {
+            return Ok(entries);
+        }
+
+        // Sidecars hold every entry so any time window can be served from them later
+        let (entries, stats) = DataAggregator::new().read_transcript_file(file)?;
+        record_parse_stats(file_path, &stats);
+        if let Some(dir) = &cache_dir {
+            if let Err(e) = store_cached_entries_for(dir, file_path, &file.metadata, &entries, &stats) {
+                crate::utils::debug_with_context("entry_cache", &format!("Failed to write cache: {:#}", e));
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn apply_time_filter(&self, mut entries: Vec<ParsedEntry>) -> Vec<ParsedEntry> {
+        if let Some(cutoff_time) = self.time_cutoff() {
+            entries.retain(|entry| entry.timestamp >= cutoff_time);
+        }
+        entries
+    }
+
+    /// Earliest timestamp kept by the time filter, if one is set
+    fn time_cutoff(&self) -> Option<DateTime<Utc>> {
+        self.time_filter_hours.map(|hours| Utc::now() - chrono::Duration::hours(hours as i64))
+    }
+
+    /// Parse a single transcript file with simd-json, borrowing each line from the
+    /// file buffer (memory-mapped above the mmap threshold). Also returns what was
+    /// skipped and recovered.
+    fn read_transcript_file(&self, transcript: &TranscriptFile) -> Result<(Vec<ParsedEntry>, ParseStats)> {
+        let file_path = transcript.path.as_path();
         let file = File::open(file_path)
             .with_context(|| format!("Failed to open file: {}", file_path.display()))?;
-        
-        let reader = BufReader::new(file);
-        let mut entries = Vec::new();
-        
+        let file_size = transcript.metadata.len();
+
         // Get the file path as string for source tracking
-        let source_file = file_path.to_string_lossy().to_string();
-        
-        // Stream through JSONL file line by line
-        for line in std::io::BufRead::lines(reader) {
-            let line = line.context("Failed to read line from transcript file")?;
-            let line = line.trim();
-            
-            if line.is_empty() {
-                continue;
-            }
-            
-            match self.parse_jsonl_line(line) {
-                Ok(Some(mut entry)) => {
-                    // Set the source file for this entry
-                    entry.source_file = Some(source_file.clone());
-                    entries.push(entry);
-                },
-                Ok(None) => continue, // Skip entries without timestamp
-                Err(_) => continue, // Skip invalid lines silently
-            }
+        let source_file = intern(&file_path.to_string_lossy());
+
+        let (mut entries, stats) = if is_compressed(file_path) {
+            self.parse_jsonl_buffer(&mut read_compressed(file_path)?)
+        } else if file_size >= mmap_threshold() {
+            let mut mmap = unsafe {
+                MmapOptions::new()
+                    .map_copy(&file)
+                    .with_context(|| format!("Failed to mmap file: {}", file_path.display()))?
+            };
+            self.parse_jsonl_buffer(&mut mmap)
+        } else {
+            let mut buffer = Vec::with_capacity(file_size as usize);
+            BufReader::new(file).read_to_end(&mut buffer)
+                .context("Failed to read transcript file")?;
+            self.parse_jsonl_buffer(&mut buffer)
+        };
+
+        for entry in &mut entries {
+            entry.source_file = Some(source_file.clone());
         }
-        
-        Ok(entries)
+
+        Ok((entries, stats))
+    }
+
+    /// Parse every line of a JSONL buffer in place, skipping blank lines and recovering
+    /// invalid ones where possible; returns the entries and what was skipped and recovered.
+    /// Lines without a timestamp or outside the time window yield nothing.
+    pub(crate) fn parse_jsonl_buffer(&self, buffer: &mut [u8]) -> (Vec<ParsedEntry>, ParseStats) {
+        parse_jsonl_lines(buffer, |line| self.parse_jsonl_bytes(line))
     }
 
     /// Parse a single JSONL line into a ParsedEntry
-    fn parse_jsonl_line(&self, line: &str) -> Result<Option<ParsedEntry>> {
-        let raw_value: Value = serde_json::from_str(line)
+    #[cfg(feature = "sqlite-index")]
+    pub(crate) fn parse_jsonl_line(&self, line: &str) -> Result<Option<ParsedEntry>> {
+        let mut bytes = line.as_bytes().to_vec();
+        self.parse_jsonl_bytes(&mut bytes)
+    }
+
+    /// Parse a single JSONL line in place. The timestamp is read from the borrowed value
+    /// first, so entries outside the time window are dropped before anything is allocated.
+    fn parse_jsonl_bytes(&self, line: &mut [u8]) -> Result<Option<ParsedEntry>> {
+        let raw_value = simd_json::to_borrowed_value(line)
             .context("Failed to parse JSON line")?;
-        
+
         // Extract timestamp - skip entries without valid timestamp
         let timestamp_str = raw_value
-            .get("timestamp")
-            .and_then(|v| v.as_str())
+            .get_str("timestamp")
             .ok_or_else(|| anyhow::anyhow!("Missing or invalid timestamp"))?;
         
         let timestamp = DateTime::parse_from_rfc3339(timestamp_str)
@@ -188,7 +477,6 @@ impl DataAggregator {
             let cutoff_time = Utc::now() - chrono::Duration::hours(hours as i64);
             if timestamp < cutoff_time {
                 return Ok(None); // Skip entries outside time window
-            } else {
             }
         }
 
@@ -197,16 +485,18 @@ impl DataAggregator {
             .and_then(|v| self.parse_message_info(v));
 
         // Extract cost if present
-        let cost_usd = raw_value.get("costUSD")
-            .and_then(|v| v.as_f64());
+        let cost_usd = raw_value.get_f64("costUSD");
 
         // Extract sidechain flag
-        let is_sidechain = raw_value.get("isSidechain")
-            .and_then(|v| v.as_bool());
+        let is_sidechain = raw_value.get_bool("isSidechain");
 
-        // Convert to HashMap for raw storage
-        let raw: HashMap<String, Value> = serde_json::from_value(raw_value)
-            .context("Failed to convert to HashMap")?;
+        // Convert the top-level fields to owned values for raw storage
+        let object = raw_value.as_object()
+            .ok_or_else(|| anyhow::anyhow!("Transcript line is not a JSON object"))?;
+        let mut raw = HashMap::with_capacity(object.len());
+        for (key, value) in object.iter() {
+            raw.insert(key.to_string(), serde_json::to_value(value)?);
+        }
 
         Ok(Some(ParsedEntry {
             timestamp,
@@ -218,15 +508,13 @@ impl DataAggregator {
         }))
     }
 
-    /// Parse message information from raw JSON value
-    fn parse_message_info(&self, message_value: &Value) -> Option<MessageInfo> {
-        let id = message_value.get("id")
-            .and_then(|v| v.as_str())
+    /// Parse message information from a borrowed JSON value
+    fn parse_message_info(&self, message_value: &BorrowedValue) -> Option<MessageInfo> {
+        let id = message_value.get_str("id")
             .map(|s| s.to_string());
         
-        let model = message_value.get("model")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+        let model = message_value.get_str("model")
+            .map(intern);
         
         let usage = message_value.get("usage")
             .and_then(|v| self.parse_usage_info(v));
@@ -234,21 +522,24 @@ impl DataAggregator {
         Some(MessageInfo { id, usage, model })
     }
 
-    /// Parse usage information from raw JSON value
-    fn parse_usage_info(&self, usage_value: &Value) -> Option<UsageInfo> {
+    /// Parse usage information from a borrowed JSON value
+    fn parse_usage_info(&self, usage_value: &BorrowedValue) -> Option<UsageInfo> {
         Some(UsageInfo {
-            input_tokens: usage_value.get("input_tokens")
-                .and_then(|v| v.as_u64())
+            input_tokens: usage_value.get_u64("input_tokens")
                 .map(|v| v as u32),
-            output_tokens: usage_value.get("output_tokens")
-                .and_then(|v| v.as_u64())
+            output_tokens: usage_value.get_u64("output_tokens")
                 .map(|v| v as u32),
-            cache_creation_input_tokens: usage_value.get("cache_creation_input_tokens")
-                .and_then(|v| v.as_u64())
+            cache_creation_input_tokens: usage_value.get_u64("cache_creation_input_tokens")
                 .map(|v| v as u32),
-            cache_read_input_tokens: usage_value.get("cache_read_input_tokens")
-                .and_then(|v| v.as_u64())
+            cache_read_input_tokens: usage_value.get_u64("cache_read_input_tokens")
                 .map(|v| v as u32),
+            cache_creation: usage_value.get("cache_creation")
+                .map(|v| CacheCreation {
+                    ephemeral_5m_input_tokens: v.get_u64("ephemeral_5m_input_tokens").map(|v| v as u32),
+                    ephemeral_1h_input_tokens: v.get_u64("ephemeral_1h_input_tokens").map(|v| v as u32),
+                }),
+            service_tier: usage_value.get_str("service_tier")
+                .map(intern),
         })
     }
 
@@ -256,45 +547,42 @@ impl DataAggregator {
     fn deduplicate_and_sort(&self, mut entries: Vec<ParsedEntry>) -> Result<Vec<ParsedEntry>> {
         // First, sort all entries by timestamp for deterministic deduplication
         entries.sort_by_key(|e| e.timestamp);
-        
-        // Create set to track seen message/request ID combinations
-        let mut seen_hashes = HashSet::new();
-        let mut deduplicated = Vec::new();
-        
-        for entry in entries {
-            if let Some(hash) = self.create_unique_hash(&entry) {
-                if seen_hashes.insert(hash) {
-                    deduplicated.push(entry);
-                }
-                // Skip duplicates silently
-            } else {
-                // Include entries without proper IDs (shouldn't happen normally)
-                deduplicated.push(entry);
-            }
-        }
-        
+
+        let (deduplicated, stats) = deduplicate_entries(entries);
+        debug_with_context("aggregation", &format!("Deduplicated {}", stats));
+
         Ok(deduplicated)
     }
+}
 
-    /// Create unique hash for deduplication (messageId:requestId)
-    fn create_unique_hash(&self, entry: &ParsedEntry) -> Option<String> {
-        // Try to get message ID from the message structure
-        let message_id = entry.message.as_ref()
-            .and_then(|m| m.id.as_ref())
-            .map(|s| s.as_str())
-            .or_else(|| {
-                // Fallback: try to get it from raw JSON
-                entry.raw.get("message")
-                    .and_then(|v| v.get("id"))
-                    .and_then(|v| v.as_str())
-            })?;
+/// Fold one transcript's entries, sorted by timestamp, into per-group totals, counting the
+/// entries `counts` accepts
+fn fold_transcript<K, G>(
+    entries: &[ParsedEntry],
+    group: &G,
+    pricing_service: &PricingService,
+    mut counts: impl FnMut(&ParsedEntry) -> bool,
+) -> BTreeMap<K, UsageFold>
+where
+    K: Ord + Clone,
+    G: Fn(&ParsedEntry) -> Option<K>,
+{
+    let mut totals: BTreeMap<K, UsageFold> = BTreeMap::new();
+    let mut sessions: BTreeMap<K, SessionDeltas> = BTreeMap::new();
 
-        // Get request ID from raw JSON
-        let request_id = entry.raw.get("requestId")
-            .and_then(|v| v.as_str())?;
+    for entry in entries.iter().filter(|entry| pricing_service.counts_entry(entry)) {
+        if !counts(entry) {
+            continue;
+        }
+        let Some(key) = group(entry) else {
+            continue;
+        };
 
-        Some(format!("{}:{}", message_id, request_id))
+        let deltas = sessions.entry(key.clone()).or_default();
+        totals.entry(key).or_default().add_entry(entry, deltas, pricing_service);
     }
+
+    totals
 }
 
 impl Default for DataAggregator {
@@ -329,6 +617,76 @@ impl DataAggregator {
 
     /// Load entries from a specific session transcript file
     pub async fn load_session_entries(&self, transcript_path: &std::path::Path) -> Result<Vec<ParsedEntry>> {
-        self.parse_transcript_file_streaming(transcript_path)
+        let entries = self.parse_transcript_file_streaming(&TranscriptFile::stat(transcript_path)?)?;
+        save_session_index();
+        record_parse(1, entries.len());
+        Ok(entries)
+    }
+}
+/// Window loaded by `SharedEntries`; covers today (UTC), the 5-hour block lookback and recent-model lookups
+pub const SHARED_WINDOW_HOURS: u32 = 24;
+
+/// Recent entries loaded lazily at most once per render and shared by every segment,
+/// so each transcript is read once instead of once per segment
+pub struct SharedEntries {
+    source: Arc<dyn UsageSource>,
+    entries: tokio::sync::OnceCell<Arc<Vec<ParsedEntry>>>,
+}
+
+impl SharedEntries {
+    /// Shared entries read from Claude Code's transcripts
+    pub fn new() -> Self {
+        Self::with_source(Arc::new(ClaudeTranscriptSource))
+    }
+
+    pub fn with_source(source: Arc<dyn UsageSource>) -> Self {
+        Self {
+            source,
+            entries: tokio::sync::OnceCell::new(),
+        }
     }
-}
\ No newline at end of file
+
+    /// The source the entries are read from
+    pub fn source(&self) -> &Arc<dyn UsageSource> {
+        &self.source
+    }
+
+    /// All deduplicated entries from the last `SHARED_WINDOW_HOURS`, sorted by timestamp
+    pub async fn all(&self) -> Result<Arc<Vec<ParsedEntry>>> {
+        self.entries
+            .get_or_try_init(|| async {
+                let since = Utc::now() - chrono::Duration::hours(SHARED_WINDOW_HOURS as i64);
+                self.source.entries_since(since).await.map(Arc::new)
+            })
+            .await
+            .cloned()
+    }
+
+    /// Entries at or after `cutoff` (which should fall inside the shared window),
+    /// as a view into the shared entries rather than a copy
+    pub async fn since(&self, cutoff: DateTime<Utc>) -> Result<EntryWindow> {
+        let entries = self.all().await?;
+        let start = entries.partition_point(|entry| entry.timestamp < cutoff);
+        Ok(EntryWindow { entries, start })
+    }
+}
+
+impl Default for SharedEntries {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Borrowed tail of the shared entries, dereferencing to a slice
+pub struct EntryWindow {
+    entries: Arc<Vec<ParsedEntry>>,
+    start: usize,
+}
+
+impl std::ops::Deref for EntryWindow {
+    type Target = [ParsedEntry];
+
+    fn deref(&self) -> &[ParsedEntry] {
+        &self.entries[self.start..]
+    }
+}
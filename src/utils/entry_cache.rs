@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use crate::utils::atomic_write::write_atomic;
+use crate::utils::cache_dir::cache_root;
+use crate::utils::claude::{MessageInfo, ParsedEntry};
+use crate::utils::intern::intern;
+use crate::utils::logger::debug_with_context;
+use crate::utils::parse_errors::{record_parse_stats, ParseStats};
+
+/// Bumped whenever the sidecar layout changes so stale caches are ignored rather than misread
+const CACHE_VERSION: u32 = 6;
+
+/// A top-level field of the raw transcript line. Arrays and objects (`cost`, `message`) are
+/// kept as serialized JSON, since bincode can't round-trip `serde_json::Value` directly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum CachedValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(String),
+    Json(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedEntry {
+    timestamp_secs: i64,
+    timestamp_nanos: u32,
+    message: Option<MessageInfo>,
+    cost_usd: Option<f64>,
+    is_sidechain: Option<bool>,
+    raw: Vec<(String, CachedValue)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Sidecar {
+    version: u32,
+    source: String,
+    size: u64,
+    mtime_nanos: u128,
+    /// What parsing the transcript skipped and recovered
+    parse_stats: ParseStats,
+    entries: Vec<CachedEntry>,
+}
+
+/// Directory holding binary sidecars of parsed transcripts, or `None` when disabled
+/// via `CLAUDE_POWERLINE_ENTRY_CACHE=0`
+pub fn entry_cache_dir() -> Option<PathBuf> {
+    if std::env::var("CLAUDE_POWERLINE_ENTRY_CACHE").is_ok_and(|v| v == "0" || v == "false") {
+        return None;
+    }
+
+    cache_root().map(|dir| dir.join("entries"))
+}
+
+/// Load the cached entries for `transcript` from `cache_dir` if the sidecar matches its current mtime and size.
+/// The transcript's parse statistics are recorded again, as if it had been parsed.
+pub fn load_cached_entries(cache_dir: &Path, transcript: &Path) -> Option<Vec<ParsedEntry>> {
+    load_cached_entries_for(cache_dir, transcript, &std::fs::metadata(transcript).ok()?)
+}
+
+/// `load_cached_entries` for a transcript whose metadata was already read
+pub fn load_cached_entries_for(cache_dir: &Path, transcript: &Path, metadata: &Metadata) -> Option<Vec<ParsedEntry>> {
+    let (size, mtime_nanos) = metadata_fingerprint(metadata)?;
+    let bytes = std::fs::read(sidecar_path(cache_dir, transcript)).ok()?;
+    let sidecar: Sidecar = bincode::deserialize(&bytes).ok()?;
+
+    let source = transcript.to_string_lossy();
+    if sidecar.version != CACHE_VERSION || sidecar.source != source || sidecar.size != size || sidecar.mtime_nanos != mtime_nanos {
+        return None;
+    }
+
+    record_parse_stats(transcript, &sidecar.parse_stats);
+    let source = intern(&source);
+    Some(sidecar.entries.into_iter().map(|entry| from_cached(entry, &source)).collect())
+}
+
+/// Write a sidecar for `transcript` holding `entries` and its parse statistics
+/// (written atomically via rename)
+pub fn store_cached_entries(cache_dir: &Path, transcript: &Path, entries: &[ParsedEntry], parse_stats: &ParseStats) -> Result<()> {
+    let metadata = std::fs::metadata(transcript)
+        .with_context(|| format!("Failed to stat {}", transcript.display()))?;
+    store_cached_entries_for(cache_dir, transcript, &metadata, entries, parse_stats)
+}
+
+/// `store_cached_entries` stamped with metadata read before the transcript was parsed, so a
+/// transcript that grew during the parse doesn't get a sidecar claiming to cover the growth
+pub fn store_cached_entries_for(
+    cache_dir: &Path,
+    transcript: &Path,
+    metadata: &Metadata,
+    entries: &[ParsedEntry],
+    parse_stats: &ParseStats,
+) -> Result<()> {
+    let (size, mtime_nanos) = metadata_fingerprint(metadata)
+        .with_context(|| format!("No modification time for {}", transcript.display()))?;
+
+    let sidecar = Sidecar {
+        version: CACHE_VERSION,
+        source: transcript.to_string_lossy().to_string(),
+        size,
+        mtime_nanos,
+        parse_stats: *parse_stats,
+        entries: entries.iter().map(to_cached).collect(),
+    };
+
+    write_atomic(&sidecar_path(cache_dir, transcript), &bincode::serialize(&sidecar)?)?;
+
+    debug_with_context("entry_cache", &format!("Cached {} entries for {}", entries.len(), transcript.display()));
+    Ok(())
+}
+
+fn metadata_fingerprint(metadata: &Metadata) -> Option<(u64, u128)> {
+    let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((metadata.len(), mtime.as_nanos()))
+}
+
+/// Sidecar file name derived from a stable FNV-1a hash of the transcript path
+fn sidecar_path(cache_dir: &Path, transcript: &Path) -> PathBuf {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in transcript.to_string_lossy().bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    cache_dir.join(format!("{:016x}.bin", hash))
+}
+
+fn to_cached(entry: &ParsedEntry) -> CachedEntry {
+    let raw = entry.raw
+        .iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::Null => CachedValue::Null,
+                serde_json::Value::Bool(b) => CachedValue::Bool(*b),
+                serde_json::Value::Number(n) => {
+                    if let Some(u) = n.as_u64() {
+                        CachedValue::UInt(u)
+                    } else if let Some(i) = n.as_i64() {
+                        CachedValue::Int(i)
+                    } else {
+                        CachedValue::Float(n.as_f64()?)
+                    }
+                }
+                serde_json::Value::String(s) => CachedValue::Str(s.clone()),
+                serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                    CachedValue::Json(serde_json::to_string(value).ok()?)
+                }
+            };
+            Some((key.clone(), value))
+        })
+        .collect();
+
+    CachedEntry {
+        timestamp_secs: entry.timestamp.timestamp(),
+        timestamp_nanos: entry.timestamp.timestamp_subsec_nanos(),
+        message: entry.message.clone(),
+        cost_usd: entry.cost_usd,
+        is_sidechain: entry.is_sidechain,
+        raw,
+    }
+}
+
+fn from_cached(entry: CachedEntry, source_file: &Arc<str>) -> ParsedEntry {
+    let raw: HashMap<String, serde_json::Value> = entry.raw
+        .into_iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                CachedValue::Null => serde_json::Value::Null,
+                CachedValue::Bool(b) => serde_json::Value::Bool(b),
+                CachedValue::Int(i) => serde_json::Value::from(i),
+                CachedValue::UInt(u) => serde_json::Value::from(u),
+                CachedValue::Float(f) => serde_json::Value::from(f),
+                CachedValue::Str(s) => serde_json::Value::String(s),
+                CachedValue::Json(json) => serde_json::from_str(&json).ok()?,
+            };
+            Some((key, value))
+        })
+        .collect();
+
+    ParsedEntry {
+        timestamp: DateTime::<Utc>::from_timestamp(entry.timestamp_secs, entry.timestamp_nanos).unwrap_or_default(),
+        message: entry.message,
+        cost_usd: entry.cost_usd,
+        source_file: Some(source_file.clone()),
+        is_sidechain: entry.is_sidechain,
+        raw,
+    }
+}
@@ -0,0 +1,32 @@
+use crate::utils::logger::debug_with_context;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+/// Run `f` while holding an exclusive lock on `<path>.lock`, so concurrent renders doing a
+/// load-modify-save of `path` don't overwrite each other's changes. If the lock file can't be
+/// created, `f` still runs, unlocked.
+pub fn with_file_lock<T>(path: &Path, f: impl FnOnce() -> T) -> T {
+    let _lock = lock_file(&lock_path(path));
+    f()
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// Held until the returned file is dropped
+fn lock_file(path: &Path) -> Option<File> {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(path);
+    match file.and_then(|file| file.lock().map(|_| file)) {
+        Ok(file) => Some(file),
+        Err(e) => {
+            debug_with_context("file_lock", &format!("Couldn't lock {}: {}", path.display(), e));
+            None
+        }
+    }
+}
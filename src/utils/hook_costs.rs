@@ -0,0 +1,197 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::utils::atomic_write::write_atomic;
+use crate::utils::file_lock::with_file_lock;
+use crate::utils::logger::debug_with_context;
+use crate::utils::state_dir::state_root;
+
+/// Cost increments older than this are dropped; the today segment's weekly average never
+/// reaches further back
+const SAMPLE_MAX_AGE_HOURS: i64 = 8 * 24;
+
+/// Cost increments older than this are merged into one per hour, which is all the weekly average
+/// needs; today, yesterday up to the same time and the active block only read newer ones
+const EXACT_SAMPLE_HOURS: i64 = 48;
+
+/// Sessions not reported for this long are forgotten; one resumed afterwards starts from a new
+/// baseline rather than having its whole cumulative cost counted again
+const SESSION_MAX_AGE_DAYS: i64 = 9;
+
+/// Length of a usage block, as in the block segment
+const BLOCK_HOURS: i64 = 5;
+
+/// Last cumulative cost Claude Code reported for a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SessionTotal {
+    cost: f64,
+    seen: DateTime<Utc>,
+}
+
+/// Cost a session added between two renders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookCostSample {
+    pub timestamp: DateTime<Utc>,
+    pub cost: f64,
+}
+
+/// The cost and time window of the active 5-hour block, from hook-reported costs
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookBlock {
+    pub start: DateTime<Utc>,
+    pub cost: f64,
+    /// When the first and last cost increment of the block were recorded
+    pub first: DateTime<Utc>,
+    pub last: DateTime<Utc>,
+}
+
+/// Cumulative session costs from the statusline hook (`cost.total_cost_usd`), persisted in
+/// `<state root>/hook_costs.json`. Each render records how much a session's total grew since the
+/// last one, so today and the active block can be summed across sessions without reading transcripts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HookCostLedger {
+    sessions: HashMap<String, SessionTotal>,
+    samples: Vec<HookCostSample>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl HookCostLedger {
+    pub fn default_path() -> Option<PathBuf> {
+        state_root().map(|dir| dir.join("hook_costs.json"))
+    }
+
+    /// Load the ledger from `path`; a missing or unreadable file yields an empty ledger
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let mut ledger = path
+            .as_deref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice::<HookCostLedger>(&bytes).ok())
+            .unwrap_or_default();
+
+        ledger.path = path;
+        ledger
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        write_atomic(path, &serde_json::to_vec(self)?)
+    }
+
+    /// Record `total_cost` as the cumulative cost of `session_id` at `now`. The growth since the
+    /// session's previous total becomes a sample; a total that went down (a cleared session) counts
+    /// in full. A session's first report is only its baseline: what it cost before then was spent
+    /// at times the ledger can't know, so booking it all at `now` would inflate today and the block.
+    pub fn record(&mut self, session_id: &str, total_cost: f64, now: DateTime<Utc>) {
+        let added = match self.sessions.get(session_id).map(|session| session.cost) {
+            Some(previous) if total_cost >= previous => total_cost - previous,
+            Some(_) => total_cost,
+            None => 0.0,
+        };
+
+        if added > 0.0 {
+            self.samples.push(HookCostSample { timestamp: now, cost: added });
+        }
+        self.sessions.insert(session_id.to_string(), SessionTotal { cost: total_cost, seen: now });
+
+        self.samples.retain(|sample| now - sample.timestamp < Duration::hours(SAMPLE_MAX_AGE_HOURS));
+        self.merge_old_samples(now - Duration::hours(EXACT_SAMPLE_HOURS));
+        self.sessions.retain(|_, session| now - session.seen < Duration::days(SESSION_MAX_AGE_DAYS));
+    }
+
+    /// Merge samples from before `cutoff` into one per hour, stamped with the start of the hour
+    fn merge_old_samples(&mut self, cutoff: DateTime<Utc>) {
+        let (old, recent): (Vec<_>, Vec<_>) = self.samples.drain(..).partition(|sample| sample.timestamp < cutoff);
+
+        let mut hours: BTreeMap<DateTime<Utc>, f64> = BTreeMap::new();
+        for sample in old {
+            *hours.entry(floor_to_hour(sample.timestamp)).or_default() += sample.cost;
+        }
+        self.samples = hours
+            .into_iter()
+            .map(|(timestamp, cost)| HookCostSample { timestamp, cost })
+            .chain(recent)
+            .collect();
+    }
+
+    /// Cost recorded at or after `start`, across all sessions
+    pub fn cost_since(&self, start: DateTime<Utc>) -> f64 {
+        self.samples
+            .iter()
+            .filter(|sample| sample.timestamp >= start)
+            .map(|sample| sample.cost)
+            .sum()
+    }
+
+    /// Cost recorded within `range`, across all sessions
+    pub fn cost_in(&self, range: Range<DateTime<Utc>>) -> f64 {
+        self.samples
+            .iter()
+            .filter(|sample| range.contains(&sample.timestamp))
+            .map(|sample| sample.cost)
+            .sum()
+    }
+
+    /// The block active at `now`, split the way the block segment splits transcript entries:
+    /// a block starts at the hour of its first sample and ends 5 hours later or after a 5-hour gap
+    pub fn active_block(&self, now: DateTime<Utc>) -> Option<HookBlock> {
+        let mut samples: Vec<&HookCostSample> = self.samples.iter().collect();
+        samples.sort_by_key(|sample| sample.timestamp);
+
+        let mut block: Option<HookBlock> = None;
+        for sample in samples {
+            let continues = block.as_ref().is_some_and(|block| {
+                sample.timestamp - block.start <= Duration::hours(BLOCK_HOURS)
+                    && sample.timestamp - block.last <= Duration::hours(BLOCK_HOURS)
+            });
+
+            match &mut block {
+                Some(block) if continues => {
+                    block.cost += sample.cost;
+                    block.last = sample.timestamp;
+                }
+                _ => {
+                    block = Some(HookBlock {
+                        start: floor_to_hour(sample.timestamp),
+                        cost: sample.cost,
+                        first: sample.timestamp,
+                        last: sample.timestamp,
+                    });
+                }
+            }
+        }
+
+        block.filter(|block| {
+            now < block.start + Duration::hours(BLOCK_HOURS) && now - block.last < Duration::hours(BLOCK_HOURS)
+        })
+    }
+}
+
+/// Record one hook report in the ledger at the default path, locked so renders of concurrent
+/// sessions don't drop each other's reports
+pub fn record_hook_cost(session_id: &str, total_cost: f64) -> HookCostLedger {
+    let path = HookCostLedger::default_path();
+    let record = || {
+        let mut ledger = HookCostLedger::load(path.clone());
+        ledger.record(session_id, total_cost, Utc::now());
+
+        if let Err(e) = ledger.save() {
+            debug_with_context("hook_costs", &format!("Failed to save hook costs: {:#}", e));
+        }
+        ledger
+    };
+    match &path {
+        Some(path) => with_file_lock(path, record),
+        None => record(),
+    }
+}
+
+fn floor_to_hour(timestamp: DateTime<Utc>) -> DateTime<Utc> {
+    timestamp.with_minute(0).unwrap().with_second(0).unwrap().with_nanosecond(0).unwrap()
+}
@@ -0,0 +1,28 @@
+use dashmap::DashSet;
+use serde::{Deserialize, Deserializer};
+use std::sync::{Arc, OnceLock};
+
+/// Process-wide pool of strings that repeat across transcript entries (model ids,
+/// transcript paths, session ids). The set of distinct values stays small, so entries
+/// share one allocation each instead of cloning a String per entry.
+static INTERNED: OnceLock<DashSet<Arc<str>>> = OnceLock::new();
+
+/// Return the shared copy of `value`, adding it to the pool on first use
+pub fn intern(value: &str) -> Arc<str> {
+    let pool = INTERNED.get_or_init(DashSet::new);
+    if let Some(existing) = pool.get(value) {
+        return existing.clone();
+    }
+
+    let interned: Arc<str> = Arc::from(value);
+    pool.insert(interned.clone());
+    interned
+}
+
+/// Serde helper interning an optional string field as it is deserialized
+pub fn deserialize_interned<'de, D>(deserializer: D) -> Result<Option<Arc<str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<std::borrow::Cow<'de, str>>::deserialize(deserializer)?.map(|value| intern(&value)))
+}
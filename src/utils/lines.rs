@@ -0,0 +1,52 @@
+/// Lines of a byte buffer, split where memchr finds each newline. Lines are handed out as
+/// mutable slices of the buffer itself (a file read or memory-mapped whole), so simd-json can
+/// parse them in place: splitting copies nothing and checks nothing for UTF-8 up front. (A
+/// line that may need recovery is still copied before parsing, see `parse_jsonl_lines`.)
+pub struct LinesMut<'a> {
+    rest: &'a mut [u8],
+    terminated: bool,
+}
+
+/// Split `buffer` into its lines, without their newlines. A trailing newline doesn't add an
+/// empty last line; text after the last newline is a line of its own.
+pub fn split_lines_mut(buffer: &mut [u8]) -> LinesMut<'_> {
+    LinesMut { rest: buffer, terminated: true }
+}
+
+impl LinesMut<'_> {
+    /// Whether the line returned last ended with a newline; only the last line of a buffer
+    /// that doesn't end with one (a line still being written) is unterminated
+    pub fn last_terminated(&self) -> bool {
+        self.terminated
+    }
+}
+
+impl<'a> Iterator for LinesMut<'a> {
+    type Item = &'a mut [u8];
+
+    fn next(&mut self) -> Option<&'a mut [u8]> {
+        if self.rest.is_empty() {
+            return None;
+        }
+
+        let rest = std::mem::take(&mut self.rest);
+        match memchr::memchr(b'\n', rest) {
+            Some(newline) => {
+                let (line, tail) = rest.split_at_mut(newline);
+                self.rest = &mut tail[1..];
+                self.terminated = true;
+                Some(line)
+            }
+            None => {
+                self.terminated = false;
+                Some(rest)
+            }
+        }
+    }
+}
+
+/// The length of the complete lines at the start of `bytes`: up to and including its last
+/// newline, or 0 if it has none
+pub fn complete_lines_len(bytes: &[u8]) -> usize {
+    memchr::memrchr(b'\n', bytes).map_or(0, |newline| newline + 1)
+}
@@ -1,13 +1,359 @@
+use crate::utils::state_dir::log_file_path;
 use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 
-pub fn debug(message: &str) {
-    if env::var("CLAUDE_POWERLINE_DEBUG").is_ok() {
-        eprintln!("[DEBUG] {}", message);
+/// Default size at which the log file is rotated
+pub const DEFAULT_LOG_MAX_BYTES: u64 = 1024 * 1024;
+
+/// Default number of rotated files (`.1`, `.2`, ...) kept next to the log
+pub const DEFAULT_LOG_MAX_FILES: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.to_ascii_lowercase().as_str() {
+            "trace" => Some(Self::Trace),
+            "debug" => Some(Self::Debug),
+            "info" => Some(Self::Info),
+            "warn" | "warning" => Some(Self::Warn),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Trace => "TRACE",
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warn => "WARN",
+            Self::Error => "ERROR",
+        }
+    }
+}
+
+/// Which messages to print: a default level plus per-module overrides, where a module is the
+/// context a message is logged under (`git`, `render`, `segment_cache`, ...)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LogFilter {
+    /// Level for modules without an override; `None` silences them
+    pub default: Option<LogLevel>,
+    pub modules: Vec<(String, LogLevel)>,
+}
+
+impl LogFilter {
+    pub fn all(level: LogLevel) -> Self {
+        Self { default: Some(level), modules: Vec::new() }
+    }
+
+    /// Parse a comma-separated spec such as `debug`, `git`, `trace` or `warn,git=trace,render`:
+    /// a bare level sets the default, a bare module enables it at debug, `module=level` sets its level.
+    /// `1`, `true`, `on` or an empty spec mean debug for everything; `0`, `false` or `off` mean nothing.
+    pub fn parse(spec: &str) -> Option<Self> {
+        let spec = spec.trim();
+        match spec.to_ascii_lowercase().as_str() {
+            "" | "1" | "true" | "on" | "yes" => return Some(Self::all(LogLevel::Debug)),
+            "0" | "false" | "off" | "no" => return None,
+            _ => {}
+        }
+
+        let mut filter = Self::default();
+        for item in spec.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            match item.split_once('=') {
+                Some((module, level)) => {
+                    let level = LogLevel::parse(level).unwrap_or(LogLevel::Debug);
+                    filter.modules.push((module.trim().to_string(), level));
+                }
+                None => match LogLevel::parse(item) {
+                    Some(level) => filter.default = Some(level),
+                    None => filter.modules.push((item.to_string(), LogLevel::Debug)),
+                },
+            }
+        }
+        Some(filter)
+    }
+
+    /// Whether a message at `level` logged under `context` passes. A module matches its own
+    /// context and contexts it prefixes up to `_` (`segment` matches `segment_cache`).
+    pub fn enabled(&self, level: LogLevel, context: Option<&str>) -> bool {
+        let module_level = context.and_then(|context| {
+            self.modules
+                .iter()
+                .find(|(module, _)| {
+                    context == module
+                        || context.strip_prefix(module.as_str()).is_some_and(|rest| rest.starts_with('_'))
+                })
+                .map(|(_, level)| *level)
+        });
+
+        module_level.or(self.default).is_some_and(|threshold| level >= threshold)
+    }
+}
+
+/// Where and how much to log to a file; messages below `level` are dropped
+#[derive(Debug, Clone)]
+pub struct FileLogSettings {
+    pub path: PathBuf,
+    pub level: LogLevel,
+    pub max_bytes: u64,
+    pub max_files: u32,
+}
+
+impl FileLogSettings {
+    /// Settings used while debugging to stderr when no log file is configured: whatever
+    /// stderr prints also goes to `<state root>/logs/claude-powerline.log`
+    pub fn debug_default() -> Option<Self> {
+        Some(Self {
+            path: log_file_path()?,
+            level: LogLevel::Trace,
+            max_bytes: DEFAULT_LOG_MAX_BYTES,
+            max_files: DEFAULT_LOG_MAX_FILES,
+        })
     }
 }
 
+/// What goes to stderr: set from the CLI, or parsed from `CLAUDE_POWERLINE_DEBUG` on first use
+static STDERR_FILTER: OnceLock<Option<LogFilter>> = OnceLock::new();
+
+/// File logging configured via `logging`, set once at startup
+static FILE_LOG_SETTINGS: OnceLock<FileLogSettings> = OnceLock::new();
+
+/// Messages logged before the log file is known (config loading logs before its `logging`
+/// section is applied) are held, up to this many, and written once it is
+const MAX_PENDING_LOG_ENTRIES: usize = 256;
+
+/// Where file logging stands
+enum FileLogState {
+    /// The log file isn't settled yet: entries wait with their level and whether stderr took them
+    Pending(Vec<(LogLevel, bool, String)>),
+    /// The log file, if any, opened on the first message that reaches it
+    Ready(Option<LogFile>),
+}
+
+static FILE_LOG: Mutex<FileLogState> = Mutex::new(FileLogState::Pending(Vec::new()));
+
+/// Print messages passing `filter` to stderr instead of following `CLAUDE_POWERLINE_DEBUG`;
+/// only takes effect before anything is logged
+pub fn set_stderr_filter(filter: LogFilter) {
+    let _ = STDERR_FILTER.set(Some(filter));
+}
+
+/// Log to the file described by `settings`, including what was logged before this call; only
+/// the first call has an effect
+pub fn set_file_logging(settings: FileLogSettings) {
+    let _ = FILE_LOG_SETTINGS.set(settings);
+    finish_file_logging_setup();
+}
+
+/// Settle the log file once the config is applied: the one `set_file_logging` gave, else the
+/// debug default while debugging to stderr, else none. Messages held until now are written to it.
+pub fn finish_file_logging_setup() {
+    let settings = FILE_LOG_SETTINGS.get().cloned().or_else(|| {
+        stderr_filter().as_ref().and_then(|_| FileLogSettings::debug_default())
+    });
+
+    let Ok(mut state) = FILE_LOG.lock() else {
+        return;
+    };
+    let FileLogState::Pending(pending) = &mut *state else {
+        return;
+    };
+    let pending = std::mem::take(pending);
+
+    let mut log_file = settings.map(LogFile::new);
+    if let Some(log_file) = &mut log_file {
+        for (level, to_stderr, entry) in pending {
+            if file_wants(level, to_stderr) {
+                log_file.append(&entry);
+            }
+        }
+    }
+    *state = FileLogState::Ready(log_file);
+}
+
+pub fn trace_with_context(context: &str, message: &str) {
+    log(LogLevel::Trace, Some(context), message);
+}
+
+pub fn debug(message: &str) {
+    log(LogLevel::Debug, None, message);
+}
+
 pub fn debug_with_context(context: &str, message: &str) {
-    if env::var("CLAUDE_POWERLINE_DEBUG").is_ok() {
-        eprintln!("[DEBUG] {}: {}", context, message);
+    log(LogLevel::Debug, Some(context), message);
+}
+
+pub fn info_with_context(context: &str, message: &str) {
+    log(LogLevel::Info, Some(context), message);
+}
+
+pub fn warn_with_context(context: &str, message: &str) {
+    log(LogLevel::Warn, Some(context), message);
+}
+
+pub fn error_with_context(context: &str, message: &str) {
+    log(LogLevel::Error, Some(context), message);
+}
+
+/// Whether a message at `level` under `context` would reach stderr or the log file, so callers
+/// can skip building expensive messages (such as structured events) nobody will see
+pub fn log_enabled(level: LogLevel, context: &str) -> bool {
+    let (to_stderr, to_file) = targets(level, Some(context));
+    to_stderr || to_file
+}
+
+/// Write `event` (one JSON object) as a bare line, without the `[LEVEL] context:` prefix or the
+/// file's timestamp, so the output can be fed to `jq` or another JSON-lines reader as-is
+pub fn log_event(level: LogLevel, context: &str, event: &str) {
+    let (to_stderr, to_file) = targets(level, Some(context));
+    if to_stderr {
+        eprintln!("{}", event);
+    }
+    if to_file {
+        write_to_file(level, to_stderr, &format!("{}\n", event));
+    }
+}
+
+/// Print to stderr when the stderr filter (`--debug`, `-v`, `CLAUDE_POWERLINE_DEBUG`) passes the
+/// message, and append to the log file when it's at or above the file's level. Stderr is invisible
+/// when Claude Code runs the statusline, so the file is what survives for diagnosing failures.
+fn log(level: LogLevel, context: Option<&str>, message: &str) {
+    let (to_stderr, to_file) = targets(level, context);
+    if !to_stderr && !to_file {
+        return;
+    }
+
+    let line = match context {
+        Some(context) => format!("[{}] {}: {}", level.label(), context, message),
+        None => format!("[{}] {}", level.label(), message),
+    };
+    if to_stderr {
+        eprintln!("{}", line);
+    }
+    if to_file {
+        write_to_file(level, to_stderr, &format!(
+            "{} [{}] {}\n",
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+            std::process::id(),
+            line
+        ));
+    }
+}
+
+fn stderr_filter() -> &'static Option<LogFilter> {
+    STDERR_FILTER.get_or_init(|| {
+        env::var("CLAUDE_POWERLINE_DEBUG").ok().and_then(|spec| LogFilter::parse(&spec))
+    })
+}
+
+/// Whether a message goes to (stderr, the log file); while the log file isn't settled every
+/// message might, so all are passed on to be held
+fn targets(level: LogLevel, context: Option<&str>) -> (bool, bool) {
+    let to_stderr = stderr_filter().as_ref().is_some_and(|filter| filter.enabled(level, context));
+
+    let to_file = match FILE_LOG.lock().as_deref() {
+        Ok(FileLogState::Pending(_)) => true,
+        Ok(FileLogState::Ready(log_file)) => log_file.is_some() && file_wants(level, to_stderr),
+        Err(_) => false,
+    };
+
+    (to_stderr, to_file)
+}
+
+/// Whether the settled log file takes a message: the debug default file mirrors stderr, a
+/// configured file has a level of its own
+fn file_wants(level: LogLevel, to_stderr: bool) -> bool {
+    match FILE_LOG_SETTINGS.get() {
+        Some(settings) => level >= settings.level,
+        None => to_stderr,
     }
-}
\ No newline at end of file
+}
+
+fn write_to_file(level: LogLevel, to_stderr: bool, entry: &str) {
+    let Ok(mut state) = FILE_LOG.lock() else {
+        return;
+    };
+    match &mut *state {
+        FileLogState::Pending(pending) => {
+            if pending.len() < MAX_PENDING_LOG_ENTRIES {
+                pending.push((level, to_stderr, entry.to_string()));
+            }
+        }
+        FileLogState::Ready(Some(log_file)) => log_file.append(entry),
+        FileLogState::Ready(None) => {}
+    }
+}
+
+/// An append-only log file that is rotated to `<path>.1` .. `<path>.<max_files>` once it grows past `max_bytes`
+struct LogFile {
+    settings: FileLogSettings,
+    file: Option<File>,
+    size: u64,
+}
+
+impl LogFile {
+    fn new(settings: FileLogSettings) -> Self {
+        Self { settings, file: None, size: 0 }
+    }
+
+    /// Append `entry` (newline included); failures are ignored: logging must never break a render
+    fn append(&mut self, entry: &str) {
+        if self.file.is_none() || self.size >= self.settings.max_bytes {
+            self.reopen();
+        }
+        let Some(file) = &mut self.file else {
+            return;
+        };
+
+        if file.write_all(entry.as_bytes()).is_ok() {
+            self.size += entry.len() as u64;
+        }
+    }
+
+    /// Open the log for appending, rotating it first if it's already full
+    /// (another invocation may have filled it since this one last looked)
+    fn reopen(&mut self) {
+        let path = self.settings.path.clone();
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        if std::fs::metadata(&path).is_ok_and(|metadata| metadata.len() >= self.settings.max_bytes) {
+            rotate(&path, self.settings.max_files);
+        }
+
+        self.file = OpenOptions::new().create(true).append(true).open(&path).ok();
+        self.size = std::fs::metadata(&path).map(|metadata| metadata.len()).unwrap_or(0);
+    }
+}
+
+/// Shift `path.N-1` to `path.N` (dropping the oldest) and `path` to `path.1`
+fn rotate(path: &Path, max_files: u32) {
+    let numbered = |n: u32| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    };
+
+    if max_files == 0 {
+        let _ = std::fs::remove_file(path);
+        return;
+    }
+
+    let _ = std::fs::remove_file(numbered(max_files));
+    for n in (1..max_files).rev() {
+        let _ = std::fs::rename(numbered(n), numbered(n + 1));
+    }
+    let _ = std::fs::rename(path, numbered(1));
+}
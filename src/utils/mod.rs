@@ -1,11 +1,55 @@
 pub mod claude;
 pub mod cache;
+pub mod cache_dir;
+pub mod state_dir;
+pub mod file_lock;
+pub mod atomic_write;
+pub mod intern;
 pub mod logger;
 pub mod data_aggregation;
 pub mod pricing;
+pub mod terminal;
+pub mod timing;
+pub mod watcher;
+pub mod entry_cache;
+pub mod segment_cache;
+pub mod tail;
+pub mod lines;
+pub mod parallelism;
+pub mod parse_errors;
+pub mod session_index;
+pub mod wsl;
+pub mod appearance;
+pub mod hook_costs;
+pub mod archive;
+pub mod update_check;
+#[cfg(feature = "sqlite-index")]
+pub mod usage_index;
 
 pub use claude::*;
 pub use cache::*;
+pub use cache_dir::*;
+pub use state_dir::*;
+pub use file_lock::*;
+pub use atomic_write::*;
+pub use intern::*;
 pub use logger::*;
 pub use data_aggregation::*;
-pub use pricing::*;
\ No newline at end of file
+pub use pricing::*;
+pub use terminal::*;
+pub use timing::*;
+pub use watcher::*;
+pub use entry_cache::*;
+pub use segment_cache::*;
+pub use tail::*;
+pub use lines::*;
+pub use parallelism::*;
+pub use parse_errors::*;
+pub use session_index::*;
+pub use wsl::*;
+pub use appearance::*;
+pub use hook_costs::*;
+pub use archive::*;
+pub use update_check::*;
+#[cfg(feature = "sqlite-index")]
+pub use usage_index::*;
\ No newline at end of file
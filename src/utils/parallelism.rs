@@ -0,0 +1,100 @@
+use std::sync::OnceLock;
+
+use crate::utils::logger::debug_with_context;
+
+/// Rayon threads from `performance.threads`
+static THREADS: OnceLock<usize> = OnceLock::new();
+
+/// Transcripts parsed at once from `performance.parseConcurrency`
+static PARSE_CONCURRENCY: OnceLock<usize> = OnceLock::new();
+
+/// Apply the configured `performance.threads` and `performance.parseConcurrency`; only the first
+/// call takes effect. `CLAUDE_POWERLINE_THREADS` and `CLAUDE_POWERLINE_PARSE_CONCURRENCY` take
+/// precedence over both.
+pub fn set_parallelism(threads: Option<usize>, parse_concurrency: Option<usize>) {
+    if let Some(threads) = threads.filter(|&n| n > 0) {
+        let _ = THREADS.set(threads);
+    }
+    if let Some(limit) = parse_concurrency.filter(|&n| n > 0) {
+        let _ = PARSE_CONCURRENCY.set(limit);
+    }
+}
+
+fn env_count(name: &str) -> Option<usize> {
+    std::env::var(name).ok()?.trim().parse().ok().filter(|&n| n > 0)
+}
+
+/// The thread count asked for, if any; without one rayon sizes its pool to the CPU count
+/// (or `RAYON_NUM_THREADS`)
+pub fn configured_threads() -> Option<usize> {
+    env_count("CLAUDE_POWERLINE_THREADS").or_else(|| THREADS.get().copied())
+}
+
+/// How many transcripts are parsed at once, by the parallel parse and by the async shared
+/// parser alike; defaults to the size of the rayon pool
+pub fn parse_concurrency() -> usize {
+    env_count("CLAUDE_POWERLINE_PARSE_CONCURRENCY")
+        .or_else(|| PARSE_CONCURRENCY.get().copied())
+        .unwrap_or_else(rayon::current_num_threads)
+        .max(1)
+}
+
+/// Size the global rayon pool to the configured thread count. The pool can only be sized before
+/// it is first used, so this must run before any parsing; later calls leave it as it is.
+pub fn init_thread_pool() {
+    let Some(threads) = configured_threads() else {
+        return;
+    };
+
+    let built = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|index| format!("powerline-parse-{}", index))
+        .build_global();
+    match built {
+        Ok(()) => debug_with_context("parallelism", &format!("Parsing on {} thread(s)", threads)),
+        Err(e) => debug_with_context("parallelism", &format!("Thread pool already running: {}", e)),
+    }
+}
+
+/// Which tokio runtime the process runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RuntimeFlavor {
+    /// Everything on the main thread: a render mostly waits on a few file reads, and CPU-heavy
+    /// parsing runs on the rayon pool anyway, so worker threads only add startup time
+    #[default]
+    CurrentThread,
+    /// Segments spread across a pool of worker threads
+    MultiThread,
+}
+
+impl std::str::FromStr for RuntimeFlavor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "current-thread" | "current" | "single" => Ok(RuntimeFlavor::CurrentThread),
+            "multi-thread" | "multi" => Ok(RuntimeFlavor::MultiThread),
+            other => anyhow::bail!("Unknown runtime `{}` (expected current-thread or multi-thread)", other),
+        }
+    }
+}
+
+/// The runtime flavor `CLAUDE_POWERLINE_RUNTIME` asks for; unset or unknown is current-thread
+pub fn runtime_flavor() -> RuntimeFlavor {
+    let Ok(value) = std::env::var("CLAUDE_POWERLINE_RUNTIME") else {
+        return RuntimeFlavor::default();
+    };
+    value.parse().unwrap_or_else(|e| {
+        debug_with_context("parallelism", &format!("{:#}; using current-thread", e));
+        RuntimeFlavor::default()
+    })
+}
+
+/// Start a tokio runtime of the given flavor, with I/O and timers enabled
+pub fn build_runtime(flavor: RuntimeFlavor) -> std::io::Result<tokio::runtime::Runtime> {
+    let mut builder = match flavor {
+        RuntimeFlavor::CurrentThread => tokio::runtime::Builder::new_current_thread(),
+        RuntimeFlavor::MultiThread => tokio::runtime::Builder::new_multi_thread(),
+    };
+    builder.enable_all().build()
+}
@@ -0,0 +1,124 @@
+use crate::utils::logger::{debug_with_context, warn_with_context};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// What reading one transcript made of its lines beyond the ones that parsed as they were
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseStats {
+    /// Lines nothing could be read from
+    pub skipped_lines: usize,
+    /// Bytes left out: whole skipped lines, and garbage around the objects of recovered ones
+    pub skipped_bytes: usize,
+    /// Lines that only parsed after repair: a byte order mark stripped, several objects written
+    /// on one line split apart, or an interrupted write cut away
+    pub recovered_lines: usize,
+    /// Bytes of the objects read from recovered lines
+    pub recovered_bytes: usize,
+    /// An unterminated last line that is still being written; neither skipped nor recovered,
+    /// since the next read sees it whole
+    pub pending_bytes: usize,
+}
+
+impl ParseStats {
+    /// Whether every line parsed as it was
+    pub fn is_clean(&self) -> bool {
+        self.skipped_lines == 0 && self.recovered_lines == 0 && self.skipped_bytes == 0
+    }
+
+    pub fn add(&mut self, other: &ParseStats) {
+        self.skipped_lines += other.skipped_lines;
+        self.skipped_bytes += other.skipped_bytes;
+        self.recovered_lines += other.recovered_lines;
+        self.recovered_bytes += other.recovered_bytes;
+        self.pending_bytes += other.pending_bytes;
+    }
+}
+
+/// Parse statistics per transcript read by this process, for those that weren't clean
+static PARSE_STATS: OnceLock<Mutex<BTreeMap<PathBuf, ParseStats>>> = OnceLock::new();
+
+fn parse_stats_map() -> &'static Mutex<BTreeMap<PathBuf, ParseStats>> {
+    PARSE_STATS.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// Record how many lines of `transcript` failed to parse as JSON, replacing any earlier count
+/// for it (a file is re-read whole, so the latest count is the current one)
+pub fn record_parse_errors(transcript: &Path, invalid_lines: usize) {
+    record_parse_stats(transcript, &ParseStats { skipped_lines: invalid_lines, ..ParseStats::default() });
+}
+
+/// Record what reading `transcript` skipped and recovered, replacing anything recorded for it before
+pub fn record_parse_stats(transcript: &Path, stats: &ParseStats) {
+    let Ok(mut all) = parse_stats_map().lock() else {
+        return;
+    };
+
+    if stats.is_clean() {
+        all.remove(transcript);
+        return;
+    }
+
+    let previous = all.insert(transcript.to_path_buf(), *stats);
+    if stats.recovered_lines > 0 {
+        debug_with_context("transcript", &format!(
+            "Recovered {} line(s) ({} bytes) in {}",
+            stats.recovered_lines,
+            stats.recovered_bytes,
+            transcript.display()
+        ));
+    }
+    if stats.skipped_lines == 0 {
+        return;
+    }
+
+    if previous.map(|previous| previous.skipped_lines) != Some(stats.skipped_lines) {
+        warn_with_context("transcript", &format!(
+            "Skipped {} unparseable line(s) ({} bytes) in {}",
+            stats.skipped_lines,
+            stats.skipped_bytes,
+            transcript.display()
+        ));
+    } else {
+        debug_with_context("transcript", &format!(
+            "{} unparseable line(s) in {}",
+            stats.skipped_lines,
+            transcript.display()
+        ));
+    }
+}
+
+/// Transcripts with unparseable lines and how many, worst first
+pub fn parse_error_counts() -> Vec<(PathBuf, usize)> {
+    let mut counts: Vec<(PathBuf, usize)> = parse_stats()
+        .into_iter()
+        .filter(|(_, stats)| stats.skipped_lines > 0)
+        .map(|(path, stats)| (path, stats.skipped_lines))
+        .collect();
+
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// Every transcript that had lines skipped or recovered, with its statistics, by path
+pub fn parse_stats() -> Vec<(PathBuf, ParseStats)> {
+    parse_stats_map()
+        .lock()
+        .map(|all| all.iter().map(|(path, stats)| (path.clone(), *stats)).collect())
+        .unwrap_or_default()
+}
+
+/// Unparseable lines across every transcript read so far
+pub fn total_parse_errors() -> usize {
+    total_parse_stats().skipped_lines
+}
+
+/// Statistics summed across every transcript read so far
+pub fn total_parse_stats() -> ParseStats {
+    let mut total = ParseStats::default();
+    for (_, stats) in parse_stats() {
+        total.add(&stats);
+    }
+    total
+}
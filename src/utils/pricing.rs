@@ -1,7 +1,26 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
 
-use crate::utils::claude::{ParsedEntry, UsageInfo};
+use crate::config::strip_json_comments;
+
+use crate::utils::claude::{MessageInfo, ParsedEntry, UsageInfo};
+use crate::utils::intern::intern;
+use crate::utils::logger::{debug_with_context, warn_with_context};
+
+/// Key grouping entries into sessions for cumulative token deltas: the transcript file,
+/// falling back to the recorded session id
+fn session_key(entry: &ParsedEntry) -> Arc<str> {
+    entry.source_file.clone()
+        .or_else(|| entry.raw.get("sessionId").and_then(|v| v.as_str()).map(intern))
+        .unwrap_or_else(|| intern("unknown"))
+}
+
+/// Model that entries without one are grouped under
+pub const UNKNOWN_MODEL: &str = "unknown";
 
 /// Current Claude API pricing (2025) per million tokens
 #[derive(Debug, Clone)]
@@ -23,15 +42,267 @@ impl ModelPricing {
             cache_read: input * 0.1,
         }
     }
+
+    /// Override the cache-read price, for vendors that don't discount it to 0.1x input
+    pub fn with_cache_read(mut self, cache_read: f64) -> Self {
+        self.cache_read = cache_read;
+        self
+    }
+}
+
+/// One model's rates in a pricing file, in dollars per million tokens. Cache rates left out
+/// follow the input rate as the built-in table does: 1.25x and 2x for 5-minute and 1-hour
+/// cache writes, 0.1x for cache reads.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelRates {
+    pub input: f64,
+    pub output: f64,
+    #[serde(rename = "cacheWrite5m")]
+    pub cache_write_5m: Option<f64>,
+    #[serde(rename = "cacheWrite1h")]
+    pub cache_write_1h: Option<f64>,
+    #[serde(rename = "cacheRead")]
+    pub cache_read: Option<f64>,
+}
+
+impl ModelRates {
+    pub fn to_pricing(&self) -> ModelPricing {
+        let defaults = ModelPricing::new(self.input, self.output);
+        ModelPricing {
+            cache_write_5m: self.cache_write_5m.unwrap_or(defaults.cache_write_5m),
+            cache_write_1h: self.cache_write_1h.unwrap_or(defaults.cache_write_1h),
+            cache_read: self.cache_read.unwrap_or(defaults.cache_read),
+            ..defaults
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let rates = [Some(self.input), Some(self.output), self.cache_write_5m, self.cache_write_1h, self.cache_read];
+        if rates.into_iter().flatten().any(|rate| !rate.is_finite() || rate < 0.0) {
+            anyhow::bail!("rates must be non-negative numbers");
+        }
+        Ok(())
+    }
+}
+
+/// Read a pricing file: a JSON object (comments allowed) mapping model ids to `ModelRates`, e.g.
+/// `{ "claude-sonnet-4": { "input": 3, "output": 15 } }`
+pub fn load_pricing_file(path: &Path) -> Result<HashMap<String, ModelPricing>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read pricing file: {}", path.display()))?;
+    let rates: HashMap<String, ModelRates> = serde_json::from_str(&strip_json_comments(&content))
+        .with_context(|| format!("Failed to parse pricing file: {}", path.display()))?;
+
+    rates
+        .into_iter()
+        .map(|(model, rates)| {
+            rates.validate().with_context(|| format!("Invalid rates for `{}` in {}", model, path.display()))?;
+            Ok((model, rates.to_pricing()))
+        })
+        .collect()
+}
+
+/// How a cost is rounded to the decimals shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Halves round up: $0.125 is $0.13
+    #[default]
+    HalfUp,
+    /// Halves round to the even neighbour (bankers' rounding): $0.125 is $0.12, $0.135 is $0.14
+    HalfEven,
+    /// Digits past the last one shown are dropped: $0.129 is $0.12
+    Truncate,
+}
+
+impl RoundingMode {
+    /// The mode named by `display.costRounding` (`half-up`, `bankers` or `truncate`); unknown
+    /// names are warned about and fall back to half-up
+    pub fn from_config(name: Option<&str>) -> Self {
+        match name {
+            None | Some("half-up") => Self::HalfUp,
+            Some("bankers") => Self::HalfEven,
+            Some("truncate") => Self::Truncate,
+            Some(other) => {
+                warn_with_context("config", &format!(
+                    "Unknown display.costRounding `{}`; expected half-up, bankers or truncate, using half-up",
+                    other
+                ));
+                Self::HalfUp
+            }
+        }
+    }
+}
+
+/// `value` rounded to `decimals` decimals with `mode`. Floating-point noise far below the last
+/// decimal is ignored first, so $1.005 counts as the half it was meant to be.
+pub fn round_cost(value: f64, decimals: usize, mode: RoundingMode) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    let scaled = (value * factor * 1e6).round() / 1e6;
+    let rounded = match mode {
+        RoundingMode::HalfUp => scaled.round(),
+        RoundingMode::HalfEven => scaled.round_ties_even(),
+        RoundingMode::Truncate => scaled.trunc(),
+    };
+    rounded / factor
+}
+
+/// Rounding applied to each entry's cost before it is added up, so totals are sums of the
+/// amounts an invoice would list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CostRounding {
+    pub mode: RoundingMode,
+    /// Decimals of a dollar each cost is rounded to
+    pub decimals: usize,
+}
+
+impl CostRounding {
+    pub fn apply(&self, cost: f64) -> f64 {
+        round_cost(cost, self.decimals, self.mode)
+    }
+}
+
+/// Share of the regular price billed for requests made through the Message Batches API
+pub const BATCH_PRICE_FACTOR: f64 = 0.5;
+
+/// How batch-priced requests are detected and billed
+#[derive(Debug, Clone, Copy)]
+pub struct BatchPricing {
+    /// Apply the batch discount at all
+    pub discount: bool,
+    /// Treat every request as batch-priced, not just those whose usage reports the `batch` service tier
+    pub assume_batch: bool,
+}
+
+impl Default for BatchPricing {
+    fn default() -> Self {
+        Self {
+            discount: true,
+            assume_batch: false,
+        }
+    }
+}
+
+/// Lifetime of a prompt-cache write, which sets its price
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CacheTtl {
+    /// Billed at 1.25x the input price
+    #[default]
+    FiveMinutes,
+    /// Billed at 2x the input price
+    OneHour,
+}
+
+/// How far (in percent) a calculated cost may stray from the transcript's `costUSD` before it's flagged
+pub const DEFAULT_COST_DIVERGENCE_PERCENT: f64 = 5.0;
+
+/// Batch pricing configured via `pricing`, set once at startup before any cost is calculated
+static BATCH_PRICING: OnceLock<BatchPricing> = OnceLock::new();
+
+/// Cache-write TTL assumed for usage that doesn't break cache writes down by TTL
+static DEFAULT_CACHE_TTL: OnceLock<CacheTtl> = OnceLock::new();
+
+/// Divergence threshold configured via `pricing.costDivergencePercent`
+static COST_DIVERGENCE_PERCENT: OnceLock<f64> = OnceLock::new();
+
+/// Whether subagent (sidechain) usage counts, configured via `pricing.includeSidechains`
+static INCLUDE_SIDECHAINS: OnceLock<bool> = OnceLock::new();
+
+/// Per-entry rounding configured via `display.costRoundingPoint`
+static ENTRY_COST_ROUNDING: OnceLock<CostRounding> = OnceLock::new();
+
+/// Rates loaded from `pricingFile`, consulted before the built-in table
+static CUSTOM_PRICING: OnceLock<Arc<HashMap<String, ModelPricing>>> = OnceLock::new();
+
+/// Use `batch` for every `PricingService` created afterwards; only the first call has an effect
+pub fn set_batch_pricing(batch: BatchPricing) {
+    let _ = BATCH_PRICING.set(batch);
+}
+
+/// Use `ttl` for cache writes of unknown TTL in every `PricingService` created afterwards;
+/// only the first call has an effect
+pub fn set_default_cache_ttl(ttl: CacheTtl) {
+    let _ = DEFAULT_CACHE_TTL.set(ttl);
+}
+
+/// Flag calculated costs more than `percent` away from the reported `costUSD` in every
+/// `PricingService` created afterwards (0 turns the check off); only the first call has an effect
+pub fn set_cost_divergence_percent(percent: f64) {
+    let _ = COST_DIVERGENCE_PERCENT.set(percent);
+}
+
+/// Count (or leave out) sidechain entries in every `PricingService` created afterwards;
+/// only the first call has an effect
+pub fn set_include_sidechains(include: bool) {
+    let _ = INCLUDE_SIDECHAINS.set(include);
+}
+
+/// Round each entry's cost with `rounding` before it is summed in every `PricingService` created
+/// afterwards; only the first call has an effect
+pub fn set_entry_cost_rounding(rounding: CostRounding) {
+    let _ = ENTRY_COST_ROUNDING.set(rounding);
+}
+
+/// Price models from `table` before the built-in table in every `PricingService` created
+/// afterwards; only the first call has an effect
+pub fn set_custom_pricing(table: HashMap<String, ModelPricing>) {
+    let _ = CUSTOM_PRICING.set(Arc::new(table));
+}
+
+/// The reported (`costUSD`) and calculated cost of the same entries
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CostCheck {
+    pub reported: f64,
+    pub calculated: f64,
+}
+
+impl CostCheck {
+    /// How far the calculated cost is from the reported one, as a percentage of the reported cost
+    pub fn divergence_percent(&self) -> f64 {
+        if self.reported == 0.0 {
+            return if self.calculated == 0.0 { 0.0 } else { 100.0 };
+        }
+        (self.calculated - self.reported).abs() / self.reported * 100.0
+    }
+
+    /// Whether the two differ by more than `threshold` percent; a threshold of 0 never flags
+    pub fn diverges(&self, threshold: f64) -> bool {
+        threshold > 0.0 && self.divergence_percent() > threshold
+    }
 }
 
 /// Pricing service with current 2025 Claude model pricing
 pub struct PricingService {
     pricing_table: HashMap<String, ModelPricing>,
+    /// A user rate card, matched before `pricing_table`
+    custom_table: Arc<HashMap<String, ModelPricing>>,
+    batch: BatchPricing,
+    default_cache_ttl: CacheTtl,
+    divergence_percent: f64,
+    include_sidechains: bool,
+    /// Rounding of each entry's cost; totals are rounded only when shown without it
+    entry_rounding: Option<CostRounding>,
 }
 
 impl PricingService {
+    /// The built-in pricing table with the options set at startup (`set_batch_pricing` and the
+    /// like), which is what the segments and reports price with
     pub fn new() -> Self {
+        let defaults = Self::with_defaults();
+        Self {
+            batch: BATCH_PRICING.get().copied().unwrap_or(defaults.batch),
+            default_cache_ttl: DEFAULT_CACHE_TTL.get().copied().unwrap_or(defaults.default_cache_ttl),
+            divergence_percent: COST_DIVERGENCE_PERCENT.get().copied().unwrap_or(defaults.divergence_percent),
+            include_sidechains: INCLUDE_SIDECHAINS.get().copied().unwrap_or(defaults.include_sidechains),
+            custom_table: CUSTOM_PRICING.get().cloned().unwrap_or(defaults.custom_table),
+            entry_rounding: ENTRY_COST_ROUNDING.get().copied(),
+            ..defaults
+        }
+    }
+
+    /// The built-in pricing table with the default options, whatever was set at startup, so
+    /// its results depend on nothing but the entries priced; the `with_*` methods adjust it
+    pub fn with_defaults() -> Self {
         let mut pricing_table = HashMap::new();
         
         // Claude 3.5 Sonnet / Claude 3.7 Sonnet pricing
@@ -60,15 +331,112 @@ impl PricingService {
         // Legacy models (approximate pricing)
         pricing_table.insert("claude-3-sonnet".to_string(), ModelPricing::new(3.0, 15.0));
         pricing_table.insert("claude-3-haiku".to_string(), ModelPricing::new(0.25, 1.25));
+
+        // OpenAI models used by Codex CLI; cached input is billed as a cache read, cache writes are free
+        pricing_table.insert("gpt-5".to_string(), ModelPricing::new(1.25, 10.0));
+        pricing_table.insert("gpt-5-codex".to_string(), ModelPricing::new(1.25, 10.0));
+        pricing_table.insert("gpt-5-mini".to_string(), ModelPricing::new(0.25, 2.0));
+        pricing_table.insert("gpt-5-nano".to_string(), ModelPricing::new(0.05, 0.40));
+        pricing_table.insert("gpt-4.1".to_string(), ModelPricing::new(2.0, 8.0).with_cache_read(0.50));
+        pricing_table.insert("gpt-4.1-mini".to_string(), ModelPricing::new(0.40, 1.60).with_cache_read(0.10));
+        pricing_table.insert("gpt-4o".to_string(), ModelPricing::new(2.50, 10.0).with_cache_read(1.25));
+        pricing_table.insert("gpt-4o-mini".to_string(), ModelPricing::new(0.15, 0.60).with_cache_read(0.075));
+        pricing_table.insert("o3".to_string(), ModelPricing::new(2.0, 8.0).with_cache_read(0.50));
+        pricing_table.insert("o4-mini".to_string(), ModelPricing::new(1.10, 4.40).with_cache_read(0.275));
+
+        // Google models used by Gemini CLI (prompts up to 200K tokens); cached input is billed as a cache read
+        pricing_table.insert("gemini-2.5-pro".to_string(), ModelPricing::new(1.25, 10.0).with_cache_read(0.125));
+        pricing_table.insert("gemini-2.5-flash".to_string(), ModelPricing::new(0.30, 2.50).with_cache_read(0.03));
+        pricing_table.insert("gemini-2.5-flash-lite".to_string(), ModelPricing::new(0.10, 0.40).with_cache_read(0.01));
+        pricing_table.insert("gemini-2.0-flash".to_string(), ModelPricing::new(0.10, 0.40).with_cache_read(0.025));
         
-        Self { pricing_table }
+        Self {
+            pricing_table,
+            custom_table: Arc::default(),
+            batch: BatchPricing::default(),
+            default_cache_ttl: CacheTtl::default(),
+            divergence_percent: DEFAULT_COST_DIVERGENCE_PERCENT,
+            include_sidechains: true,
+            entry_rounding: None,
+        }
+    }
+
+    /// `with_defaults` with the rates of the pricing file at `path` matched before the built-in table
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Ok(Self::with_defaults().with_model_pricing(load_pricing_file(path)?))
+    }
+
+    /// Match models against `table` before the built-in table, instead of the configured `pricingFile`
+    pub fn with_model_pricing(mut self, table: HashMap<String, ModelPricing>) -> Self {
+        self.custom_table = Arc::new(table);
+        self
+    }
+
+    /// Override the configured cache-write TTL for usage that doesn't report one
+    pub fn with_default_cache_ttl(mut self, ttl: CacheTtl) -> Self {
+        self.default_cache_ttl = ttl;
+        self
+    }
+
+    /// Override the configured cost divergence threshold
+    pub fn with_divergence_percent(mut self, percent: f64) -> Self {
+        self.divergence_percent = percent;
+        self
+    }
+
+    /// Override the configured batch pricing
+    pub fn with_batch_pricing(mut self, batch: BatchPricing) -> Self {
+        self.batch = batch;
+        self
+    }
+
+    /// Override whether sidechain entries count
+    pub fn with_include_sidechains(mut self, include: bool) -> Self {
+        self.include_sidechains = include;
+        self
+    }
+
+    /// Override the configured per-entry rounding; `None` leaves entry costs unrounded
+    pub fn with_entry_rounding(mut self, rounding: Option<CostRounding>) -> Self {
+        self.entry_rounding = rounding;
+        self
+    }
+
+    /// `cost` of one entry, rounded when entries are rounded before summing
+    fn entry_cost(&self, cost: f64) -> f64 {
+        self.entry_rounding.map_or(cost, |rounding| rounding.apply(cost))
+    }
+
+    /// Whether entry costs are rounded before summing, so entries can't be priced in bulk
+    pub fn rounds_entry_costs(&self) -> bool {
+        self.entry_rounding.is_some()
+    }
+
+    /// Whether `entry` counts towards totals: every entry, unless sidechains are left out
+    pub fn counts_entry(&self, entry: &ParsedEntry) -> bool {
+        self.include_sidechains || entry.is_sidechain != Some(true)
+    }
+
+    /// Whether sidechain entries count towards totals
+    pub fn includes_sidechains(&self) -> bool {
+        self.include_sidechains
+    }
+
+    /// Price factor for a request with `usage`: `BATCH_PRICE_FACTOR` for batch-priced requests, else 1
+    pub fn price_factor(&self, usage: &UsageInfo) -> f64 {
+        let is_batch = self.batch.assume_batch || usage.service_tier.as_deref() == Some("batch");
+        if self.batch.discount && is_batch {
+            BATCH_PRICE_FACTOR
+        } else {
+            1.0
+        }
     }
 
     /// Calculate cost for a single transcript entry
     pub fn calculate_cost_for_entry(&self, entry: &ParsedEntry) -> Result<f64> {
         // Use cached cost if available
         if let Some(cost) = entry.cost_usd {
-            return Ok(cost);
+            return Ok(self.entry_cost(cost));
         }
 
         // Extract model and usage information
@@ -87,35 +455,18 @@ impl PricingService {
     /// Calculate cost for specific usage and model
     pub fn calculate_cost_for_usage(&self, model_id: &str, usage: &UsageInfo) -> Result<f64> {
         let pricing = self.get_model_pricing(model_id)?;
-        
-        let input_tokens = usage.input_tokens.unwrap_or(0) as f64;
-        let output_tokens = usage.output_tokens.unwrap_or(0) as f64;
-        let cache_creation_tokens = usage.cache_creation_input_tokens.unwrap_or(0) as f64;
-        let cache_read_tokens = usage.cache_read_input_tokens.unwrap_or(0) as f64;
-        
-        // Calculate costs per token type
-        let input_cost = (input_tokens / 1_000_000.0) * pricing.input;
-        let output_cost = (output_tokens / 1_000_000.0) * pricing.output;
-        let cache_creation_cost = (cache_creation_tokens / 1_000_000.0) * pricing.cache_write_5m; // Default to 5-minute cache
-        let cache_read_cost = (cache_read_tokens / 1_000_000.0) * pricing.cache_read;
-        
-        Ok(input_cost + output_cost + cache_creation_cost + cache_read_cost)
+        let tokens = SessionDeltas::default().next(usage);
+
+        Ok(self.entry_cost(self.tokens_cost(pricing, &tokens) * self.price_factor(usage)))
     }
 
-    /// Get pricing for a specific model with fuzzy matching
+    /// Get pricing for a specific model with fuzzy matching, from the user rate card first
     pub fn get_model_pricing(&self, model_id: &str) -> Result<&ModelPricing> {
-        // Try exact match first
-        if let Some(pricing) = self.pricing_table.get(model_id) {
+        if let Some(pricing) = self.match_model(&self.custom_table, model_id) {
             return Ok(pricing);
         }
-        
-        // Try fuzzy matching for various model name formats
-        let normalized_model = self.normalize_model_name(model_id);
-        
-        for (key, pricing) in &self.pricing_table {
-            if key.contains(&normalized_model) || normalized_model.contains(key) {
-                return Ok(pricing);
-            }
+        if let Some(pricing) = self.match_model(&self.pricing_table, model_id) {
+            return Ok(pricing);
         }
         
         // Fallback to reasonable defaults based on model family
@@ -129,6 +480,33 @@ impl PricingService {
         }
     }
 
+    /// The entry of `table` for `model_id`: an exact match, else the longest key contained in it,
+    /// else a key containing it
+    fn match_model<'a>(&self, table: &'a HashMap<String, ModelPricing>, model_id: &str) -> Option<&'a ModelPricing> {
+        // Try exact match first
+        if let Some(pricing) = table.get(model_id) {
+            return Some(pricing);
+        }
+        
+        // Try fuzzy matching for various model name formats. The longest contained key wins,
+        // so dated or suffixed ids (`gpt-5-mini-2025-08-07`) pick the most specific entry.
+        let normalized_model = self.normalize_model_name(model_id);
+        let fuzzy_match = table
+            .iter()
+            .map(|(key, pricing)| (self.normalize_model_name(key), pricing))
+            .filter(|(key, _)| normalized_model.contains(key.as_str()))
+            .max_by_key(|(key, _)| key.len());
+        if let Some((_, pricing)) = fuzzy_match {
+            return Some(pricing);
+        }
+
+        // Abbreviated ids (`sonnet-4`) match a key that contains them
+        table
+            .iter()
+            .find(|(key, _)| self.normalize_model_name(key).contains(&normalized_model))
+            .map(|(_, pricing)| pricing)
+    }
+
     /// Normalize model names for fuzzy matching
     fn normalize_model_name(&self, model_id: &str) -> String {
         model_id
@@ -148,215 +526,214 @@ impl PricingService {
         }
     }
 
-    /// Calculate total cost for a list of entries (handles cumulative token counts per session)
-    pub fn calculate_total_cost(&self, entries: &[ParsedEntry]) -> Result<f64> {
-        use std::collections::HashMap;
-        
-        let mut total_cost = 0.0;
-        
-        // Group entries by session to handle cumulative counts properly
-        let mut sessions: HashMap<String, Vec<&ParsedEntry>> = HashMap::new();
-        
-        for entry in entries {
-            let session_key = entry.source_file.clone()
-                .or_else(|| entry.raw.get("sessionId").and_then(|v| v.as_str()).map(String::from))
-                .unwrap_or_else(|| "unknown".to_string());
-            
-            sessions.entry(session_key).or_insert_with(Vec::new).push(entry);
+    /// Cost of the tokens `usage` added since the session's previous entry, or `None` for unknown models
+    pub fn cost_for_delta(&self, model_id: &str, usage: &UsageInfo, delta: &TokenBreakdown) -> Option<f64> {
+        self.cost_for_tokens(model_id, delta).map(|cost| self.entry_cost(cost * self.price_factor(usage)))
+    }
+
+    /// Cost of a block of tokens for `model_id` at regular prices, or `None` for unknown models
+    pub fn cost_for_tokens(&self, model_id: &str, tokens: &TokenBreakdown) -> Option<f64> {
+        let pricing = self.get_model_pricing(model_id).ok()?;
+        Some(self.tokens_cost(pricing, tokens))
+    }
+
+    fn tokens_cost(&self, pricing: &ModelPricing, tokens: &TokenBreakdown) -> f64 {
+        // Cache writes are billed by TTL; without a reported split all use the default TTL
+        let cache_create_1h = tokens.cache_creation_1h_input_tokens
+            .unwrap_or(match self.default_cache_ttl {
+                CacheTtl::FiveMinutes => 0,
+                CacheTtl::OneHour => tokens.cache_creation_input_tokens,
+            })
+            .min(tokens.cache_creation_input_tokens);
+        let cache_create_5m = tokens.cache_creation_input_tokens - cache_create_1h;
+
+        let input_cost = (tokens.input_tokens as f64 / 1_000_000.0) * pricing.input;
+        let output_cost = (tokens.output_tokens as f64 / 1_000_000.0) * pricing.output;
+        let cache_create_cost = (cache_create_5m as f64 / 1_000_000.0) * pricing.cache_write_5m
+            + (cache_create_1h as f64 / 1_000_000.0) * pricing.cache_write_1h;
+        let cache_read_cost = (tokens.cache_read_input_tokens as f64 / 1_000_000.0) * pricing.cache_read;
+
+        input_cost + output_cost + cache_create_cost + cache_read_cost
+    }
+
+    /// Cost, tokens, weighted tokens and entry count of `entries` in one pass, as
+    /// `DataAggregator::fold_usage_by` folds them: the pure form of the usage math, for tools
+    /// that bring their own entries. Cumulative usage is turned into per-entry deltas per session.
+    pub fn usage_totals<E: Borrow<ParsedEntry>>(&self, entries: &[E]) -> UsageFold {
+        let mut totals = UsageFold::default();
+
+        for session in self.sessions(entries) {
+            let mut deltas = SessionDeltas::default();
+            for entry in session {
+                totals.add_entry(entry, &mut deltas, self);
+            }
         }
-        
-        // Process each session separately
-        for (_session_key, session_entries) in sessions {
-            // Sort by timestamp to ensure proper delta calculation
-            let mut sorted_entries = session_entries;
-            sorted_entries.sort_by_key(|e| e.timestamp);
-            
-            // Track previous cumulative values for this session
-            let mut prev_input = 0u32;
-            let mut prev_output = 0u32;
-            let mut prev_cache_create = 0u32;
-            let mut prev_cache_read = 0u32;
-            
-            for entry in sorted_entries {
-                if let Some(message) = &entry.message {
-                    if let Some(usage) = &message.usage {
-                        let input_now = usage.input_tokens.unwrap_or(0);
-                        let output_now = usage.output_tokens.unwrap_or(0);
-                        let cache_create_now = usage.cache_creation_input_tokens.unwrap_or(0);
-                        let cache_read_now = usage.cache_read_input_tokens.unwrap_or(0);
-                        
-                        // Calculate deltas (new tokens since last message in this session)
-                        let delta_input = input_now.saturating_sub(prev_input);
-                        let delta_output = output_now.saturating_sub(prev_output);
-                        let delta_cache_create = cache_create_now.saturating_sub(prev_cache_create);
-                        let delta_cache_read = cache_read_now.saturating_sub(prev_cache_read);
-                        
-                        // Calculate cost for this entry's delta tokens
-                        if let Some(model) = &message.model {
-                            if let Ok(pricing) = self.get_model_pricing(model) {
-                                let input_cost = (delta_input as f64 / 1_000_000.0) * pricing.input;
-                                let output_cost = (delta_output as f64 / 1_000_000.0) * pricing.output;
-                                let cache_create_cost = (delta_cache_create as f64 / 1_000_000.0) * pricing.cache_write_5m;
-                                let cache_read_cost = (delta_cache_read as f64 / 1_000_000.0) * pricing.cache_read;
-                                
-                                total_cost += input_cost + output_cost + cache_create_cost + cache_read_cost;
-                            }
-                        }
-                        
-                        // Update previous values for next iteration
-                        prev_input = input_now;
-                        prev_output = output_now;
-                        prev_cache_create = cache_create_now;
-                        prev_cache_read = cache_read_now;
-                    } else {
-                        // No usage data - keep previous counters unchanged
-                        // DO NOT reset them as this would cause the next entry to be counted in full
-                    }
-                }
+
+        totals
+    }
+
+    /// `usage_totals` split by the model each entry names (`UNKNOWN_MODEL` for entries without
+    /// one, such as user prompts). Deltas are still taken across the whole session, so a session
+    /// that switches models is split at the switch and the parts add up to `usage_totals`.
+    pub fn usage_by_model<E: Borrow<ParsedEntry>>(&self, entries: &[E]) -> HashMap<String, UsageFold> {
+        let mut by_model: HashMap<String, UsageFold> = HashMap::new();
+
+        for session in self.sessions(entries) {
+            let mut deltas = SessionDeltas::default();
+            for entry in session {
+                let model = entry.message.as_ref().and_then(|message| message.model.as_deref()).unwrap_or(UNKNOWN_MODEL);
+                by_model.entry(model.to_string()).or_default().add_entry(entry, &mut deltas, self);
             }
         }
-        
+
+        by_model
+    }
+
+    /// Calculate total cost for a list of entries (handles cumulative token counts per session)
+    pub fn calculate_total_cost<E: Borrow<ParsedEntry>>(&self, entries: &[E]) -> Result<f64> {
+        let mut total_cost = 0.0;
+
+        self.for_each_session_delta(entries, |_, message, usage, delta| {
+            if let Some(cost) = message.model.as_ref().and_then(|model| self.cost_for_delta(model, usage, &delta)) {
+                total_cost += cost;
+            }
+        });
+
         Ok(total_cost)
     }
 
     /// Calculate token breakdown for a list of entries (handles cumulative token counts per session)
-    pub fn calculate_token_breakdown(&self, entries: &[ParsedEntry]) -> TokenBreakdown {
-        use std::collections::HashMap;
-        
+    pub fn calculate_token_breakdown<E: Borrow<ParsedEntry>>(&self, entries: &[E]) -> TokenBreakdown {
         let mut breakdown = TokenBreakdown::default();
-        
-        if entries.is_empty() {
-            return breakdown;
-        }
-        
-        // Group entries by session (source file)
-        let mut sessions: HashMap<String, Vec<&ParsedEntry>> = HashMap::new();
-        
-        for entry in entries {
-            let session_key = entry.source_file.clone()
-                .or_else(|| entry.raw.get("sessionId").and_then(|v| v.as_str()).map(String::from))
-                .unwrap_or_else(|| "unknown".to_string());
-            
-            sessions.entry(session_key).or_insert_with(Vec::new).push(entry);
-        }
-        
-        // Process each session separately
-        for (_session_key, session_entries) in sessions {
-            // Sort by timestamp to ensure proper delta calculation
-            let mut sorted_entries = session_entries;
-            sorted_entries.sort_by_key(|e| e.timestamp);
-            
-            // Track previous cumulative values for this session
-            let mut prev_input = 0u32;
-            let mut prev_output = 0u32;
-            let mut prev_cache_create = 0u32;
-            let mut prev_cache_read = 0u32;
-            
-            for entry in sorted_entries {
+
+        // Only add the delta (new tokens) not the cumulative total
+        self.for_each_session_delta(entries, |_, _, _, delta| breakdown.add(&delta));
+
+        breakdown
+    }
+
+    /// Calculate weighted tokens (applying model-specific multipliers and handling cumulative counts)
+    pub fn calculate_weighted_tokens<E: Borrow<ParsedEntry>>(&self, entries: &[E]) -> u64 {
+        let mut total_weighted = 0u64;
+
+        self.for_each_session_delta(entries, |_, message, _, delta| {
+            // Apply model weight
+            let weight = message.model.as_ref()
+                .map(|model| self.get_model_rate_limit_weight(model))
+                .unwrap_or(1);
+
+            total_weighted += delta.total_tokens() * weight as u64;
+        });
+
+        total_weighted
+    }
+
+    /// Reported and calculated cost of the entries that carry a `costUSD`, or `None` when none do
+    pub fn check_reported_cost<E: Borrow<ParsedEntry>>(&self, entries: &[E]) -> Option<CostCheck> {
+        let mut check: Option<CostCheck> = None;
+
+        self.for_each_session_delta(entries, |entry, message, usage, delta| {
+            let Some(reported) = entry.cost_usd else {
+                return;
+            };
+            let Some(calculated) = message.model.as_ref().and_then(|model| self.cost_for_delta(model, usage, &delta)) else {
+                return;
+            };
+
+            let check = check.get_or_insert_with(CostCheck::default);
+            check.reported += reported;
+            check.calculated += calculated;
+        });
+
+        check
+    }
+
+    /// Whether `check` differs by more than the configured threshold, logging the details when it does
+    pub fn cost_diverges(&self, context: &str, check: Option<CostCheck>) -> bool {
+        let Some(check) = check.filter(|check| check.diverges(self.divergence_percent)) else {
+            return false;
+        };
+
+        debug_with_context(context, &format!(
+            "Calculated cost ${:.4} is {:.1}% off the reported ${:.4} (threshold {}%); the pricing table may be out of date",
+            check.calculated,
+            check.divergence_percent(),
+            check.reported,
+            self.divergence_percent
+        ));
+        true
+    }
+
+    /// Group the counted entries by session (transcript file, else `sessionId`), sort each session
+    /// by timestamp and call `f` with every entry that has usage, its message, usage and its token
+    /// delta against the previous entry of the same session. Claude Code records usage
+    /// cumulatively within a session; this is how every total here turns it into new tokens.
+    pub fn for_each_session_delta<E: Borrow<ParsedEntry>>(&self, entries: &[E], mut f: impl FnMut(&ParsedEntry, &MessageInfo, &UsageInfo, TokenBreakdown)) {
+        for session in self.sessions(entries) {
+            let mut deltas = SessionDeltas::default();
+            for entry in session {
                 if let Some(message) = &entry.message {
                     if let Some(usage) = &message.usage {
-                        let input_now = usage.input_tokens.unwrap_or(0);
-                        let output_now = usage.output_tokens.unwrap_or(0);
-                        let cache_create_now = usage.cache_creation_input_tokens.unwrap_or(0);
-                        let cache_read_now = usage.cache_read_input_tokens.unwrap_or(0);
-                        
-                        // Calculate deltas (new tokens since last message in this session)
-                        // Use saturating_sub to handle session boundaries where counts reset
-                        let delta_input = input_now.saturating_sub(prev_input);
-                        let delta_output = output_now.saturating_sub(prev_output);
-                        let delta_cache_create = cache_create_now.saturating_sub(prev_cache_create);
-                        let delta_cache_read = cache_read_now.saturating_sub(prev_cache_read);
-                        
-                        // Only add the delta (new tokens) not the cumulative total
-                        breakdown.input_tokens += delta_input;
-                        breakdown.output_tokens += delta_output;
-                        breakdown.cache_creation_input_tokens += delta_cache_create;
-                        breakdown.cache_read_input_tokens += delta_cache_read;
-                        
-                        // Update previous values for next iteration
-                        prev_input = input_now;
-                        prev_output = output_now;
-                        prev_cache_create = cache_create_now;
-                        prev_cache_read = cache_read_now;
-                    } else {
-                        // No usage data - keep previous counters unchanged
-                        // DO NOT reset them as this would cause the next entry to be counted in full
+                        f(entry, message, usage, deltas.next(usage));
                     }
                 }
             }
         }
-        
-        breakdown
     }
 
-    /// Calculate weighted tokens (applying model-specific multipliers and handling cumulative counts)
-    pub fn calculate_weighted_tokens(&self, entries: &[ParsedEntry]) -> u32 {
-        use std::collections::HashMap;
-        
-        // Group entries by session (source file)
-        let mut sessions: HashMap<String, Vec<&ParsedEntry>> = HashMap::new();
-        
+    /// The counted entries grouped by session, each sorted by timestamp
+    fn sessions<'a, E: Borrow<ParsedEntry>>(&self, entries: &'a [E]) -> impl Iterator<Item = Vec<&'a ParsedEntry>> {
+        let mut sessions: HashMap<Arc<str>, Vec<&ParsedEntry>> = HashMap::new();
+
         for entry in entries {
-            let session_key = entry.source_file.clone()
-                .or_else(|| entry.raw.get("sessionId").and_then(|v| v.as_str()).map(String::from))
-                .unwrap_or_else(|| "unknown".to_string());
-            
-            sessions.entry(session_key).or_insert_with(Vec::new).push(entry);
-        }
-        
-        let mut total_weighted = 0u32;
-        
-        // Process each session separately
-        for (_session_key, session_entries) in sessions {
-            // Sort by timestamp to ensure proper delta calculation
-            let mut sorted_entries = session_entries;
-            sorted_entries.sort_by_key(|e| e.timestamp);
-            
-            // Track previous cumulative values for this session
-            let mut prev_input = 0u32;
-            let mut prev_output = 0u32;
-            let mut prev_cache_create = 0u32;
-            let mut prev_cache_read = 0u32;
-            
-            for entry in sorted_entries {
-                if let Some(message) = &entry.message {
-                    if let Some(usage) = &message.usage {
-                        let input_now = usage.input_tokens.unwrap_or(0);
-                        let output_now = usage.output_tokens.unwrap_or(0);
-                        let cache_create_now = usage.cache_creation_input_tokens.unwrap_or(0);
-                        let cache_read_now = usage.cache_read_input_tokens.unwrap_or(0);
-                        
-                        // Calculate deltas for this session
-                        let delta_input = input_now.saturating_sub(prev_input);
-                        let delta_output = output_now.saturating_sub(prev_output);
-                        let delta_cache_create = cache_create_now.saturating_sub(prev_cache_create);
-                        let delta_cache_read = cache_read_now.saturating_sub(prev_cache_read);
-                        
-                        let delta_total = delta_input + delta_output + delta_cache_create + delta_cache_read;
-                        
-                        // Apply model weight
-                        let weight = if let Some(model) = &message.model {
-                            self.get_model_rate_limit_weight(model)
-                        } else {
-                            1
-                        };
-                        
-                        total_weighted += delta_total * weight;
-                        
-                        // Update previous values for next iteration
-                        prev_input = input_now;
-                        prev_output = output_now;
-                        prev_cache_create = cache_create_now;
-                        prev_cache_read = cache_read_now;
-                    } else {
-                        // No usage data - keep previous counters unchanged
-                        // DO NOT reset them as this would cause the next entry to be counted in full
-                    }
-                }
+            let entry = entry.borrow();
+            if !self.counts_entry(entry) {
+                continue;
             }
+            sessions.entry(session_key(entry)).or_default().push(entry);
         }
-        
-        total_weighted
+
+        sessions.into_values().map(|mut session| {
+            session.sort_by_key(|e| e.timestamp);
+            session
+        })
+    }
+}
+
+
+/// Running counters of one session, turning cumulative usage into per-entry deltas
+#[derive(Debug, Clone, Default)]
+pub struct SessionDeltas {
+    previous: TokenBreakdown,
+}
+
+impl SessionDeltas {
+    /// Tokens added by `usage` since the previous entry of the session.
+    /// Entries without usage should simply be skipped: resetting the counters
+    /// would count the next entry in full.
+    pub fn next(&mut self, usage: &UsageInfo) -> TokenBreakdown {
+        let now = TokenBreakdown {
+            input_tokens: usage.input_tokens.unwrap_or(0) as u64,
+            output_tokens: usage.output_tokens.unwrap_or(0) as u64,
+            cache_creation_input_tokens: usage.cache_creation_input_tokens.unwrap_or(0) as u64,
+            cache_read_input_tokens: usage.cache_read_input_tokens.unwrap_or(0) as u64,
+            cache_creation_1h_input_tokens: usage.cache_creation.as_ref()
+                .and_then(|cache_creation| cache_creation.ephemeral_1h_input_tokens)
+                .map(u64::from),
+        };
+
+        // Use saturating_sub to handle session boundaries where counts reset
+        let delta = TokenBreakdown {
+            input_tokens: now.input_tokens.saturating_sub(self.previous.input_tokens),
+            output_tokens: now.output_tokens.saturating_sub(self.previous.output_tokens),
+            cache_creation_input_tokens: now.cache_creation_input_tokens.saturating_sub(self.previous.cache_creation_input_tokens),
+            cache_read_input_tokens: now.cache_read_input_tokens.saturating_sub(self.previous.cache_read_input_tokens),
+            cache_creation_1h_input_tokens: now.cache_creation_1h_input_tokens
+                .map(|tokens| tokens.saturating_sub(self.previous.cache_creation_1h_input_tokens.unwrap_or(0))),
+        };
+
+        self.previous = now;
+        delta
     }
 }
 
@@ -366,26 +743,110 @@ impl Default for PricingService {
     }
 }
 
+/// Running usage totals for one group of entries, produced by `DataAggregator::fold_usage_by`
+/// and `PricingService::usage_totals`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageFold {
+    pub cost: f64,
+    pub tokens: TokenBreakdown,
+    pub weighted_tokens: u64,
+    pub entry_count: u32,
+    /// Reported against calculated cost of the entries that carry a `costUSD`
+    pub cost_check: Option<CostCheck>,
+}
+
+impl UsageFold {
+    /// Fold another group's totals into this one
+    pub fn merge(&mut self, other: &UsageFold) {
+        self.cost += other.cost;
+        self.tokens.add(&other.tokens);
+        self.weighted_tokens += other.weighted_tokens;
+        self.entry_count += other.entry_count;
+        if let Some(other_check) = other.cost_check {
+            let check = self.cost_check.get_or_insert_with(CostCheck::default);
+            check.reported += other_check.reported;
+            check.calculated += other_check.calculated;
+        }
+    }
+
+    /// Fold in `entry`, the next entry (in timestamp order) of the session whose counters are
+    /// `deltas`. Every entry counts as a message; only its usage delta adds tokens and cost.
+    pub fn add_entry(&mut self, entry: &ParsedEntry, deltas: &mut SessionDeltas, pricing_service: &PricingService) {
+        self.entry_count += 1;
+
+        let Some(message) = &entry.message else {
+            return;
+        };
+        let Some(usage) = &message.usage else {
+            return;
+        };
+
+        let delta = deltas.next(usage);
+        self.add_tokens(message.model.as_deref(), usage, &delta, entry.cost_usd, pricing_service);
+    }
+
+    /// Add the tokens that entries of `model` added, priced at the service tier `usage` names;
+    /// `reported` is the `costUSD` those entries carry, if they carry one. Entries aren't counted.
+    pub fn add_tokens(
+        &mut self,
+        model: Option<&str>,
+        usage: &UsageInfo,
+        delta: &TokenBreakdown,
+        reported: Option<f64>,
+        pricing_service: &PricingService,
+    ) {
+        if let Some(model) = model {
+            let cost = pricing_service.cost_for_delta(model, usage, delta).unwrap_or(0.0);
+            self.cost += cost;
+            if let Some(reported) = reported {
+                let check = self.cost_check.get_or_insert_with(CostCheck::default);
+                check.reported += reported;
+                check.calculated += cost;
+            }
+            self.weighted_tokens += delta.total_tokens() * pricing_service.get_model_rate_limit_weight(model) as u64;
+        } else {
+            self.weighted_tokens += delta.total_tokens();
+        }
+        self.tokens.add(delta);
+    }
+}
+
 /// Token usage breakdown
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct TokenBreakdown {
-    pub input_tokens: u32,
-    pub output_tokens: u32,
-    pub cache_creation_input_tokens: u32,
-    pub cache_read_input_tokens: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    /// Part of `cache_creation_input_tokens` written with a 1-hour TTL, when the usage reports the split
+    pub cache_creation_1h_input_tokens: Option<u64>,
 }
 
 impl TokenBreakdown {
-    pub fn total_tokens(&self) -> u32 {
+    pub fn total_tokens(&self) -> u64 {
         self.input_tokens + self.output_tokens + 
         self.cache_creation_input_tokens + self.cache_read_input_tokens
     }
+
+    /// Input and output only, leaving out cache reads and writes
+    pub fn uncached_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    pub fn add(&mut self, other: &TokenBreakdown) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+        if let Some(tokens) = other.cache_creation_1h_input_tokens {
+            *self.cache_creation_1h_input_tokens.get_or_insert(0) += tokens;
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::utils::claude::MessageInfo;
 
     #[test]
     fn test_sonnet_pricing() {
@@ -395,6 +856,8 @@ mod tests {
             output_tokens: Some(500000),  // 0.5M tokens
             cache_creation_input_tokens: None,
             cache_read_input_tokens: None,
+            cache_creation: None,
+            service_tier: None,
         };
         
         let cost = pricing_service.calculate_cost_for_usage("claude-3-5-sonnet", &usage).unwrap();
@@ -402,6 +865,55 @@ mod tests {
         assert!((cost - expected).abs() < 0.001);
     }
 
+    #[test]
+    fn test_batch_discount() {
+        let mut usage = UsageInfo {
+            input_tokens: Some(1000000),
+            output_tokens: Some(500000),
+            cache_creation_input_tokens: None,
+            cache_read_input_tokens: None,
+            cache_creation: None,
+            service_tier: Some(intern("batch")),
+        };
+        let regular = 3.0 + (0.5 * 15.0);
+
+        let pricing_service = PricingService::new().with_batch_pricing(BatchPricing::default());
+        let cost = pricing_service.calculate_cost_for_usage("claude-3-5-sonnet", &usage).unwrap();
+        assert!((cost - regular * BATCH_PRICE_FACTOR).abs() < 0.001);
+
+        let no_discount = PricingService::new().with_batch_pricing(BatchPricing { discount: false, assume_batch: false });
+        let cost = no_discount.calculate_cost_for_usage("claude-3-5-sonnet", &usage).unwrap();
+        assert!((cost - regular).abs() < 0.001);
+
+        usage.service_tier = Some(intern("standard"));
+        let cost = pricing_service.calculate_cost_for_usage("claude-3-5-sonnet", &usage).unwrap();
+        assert!((cost - regular).abs() < 0.001);
+
+        let assume_batch = PricingService::new().with_batch_pricing(BatchPricing { discount: true, assume_batch: true });
+        let cost = assume_batch.calculate_cost_for_usage("claude-3-5-sonnet", &usage).unwrap();
+        assert!((cost - regular * BATCH_PRICE_FACTOR).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_cache_write_ttl_pricing() {
+        let usage: UsageInfo = serde_json::from_value(serde_json::json!({
+            "input_tokens": 0, "output_tokens": 0, "cache_read_input_tokens": 0,
+            "cache_creation_input_tokens": 1000000,
+            "cache_creation": { "ephemeral_5m_input_tokens": 400000, "ephemeral_1h_input_tokens": 600000 }
+        })).unwrap();
+        let pricing_service = PricingService::new().with_default_cache_ttl(CacheTtl::FiveMinutes);
+        let cost = pricing_service.calculate_cost_for_usage("claude-3-5-sonnet", &usage).unwrap();
+        assert!((cost - (0.4 * 3.75 + 0.6 * 6.0)).abs() < 0.001);
+
+        // Without a reported split, the default TTL applies to every cache write
+        let usage = UsageInfo { cache_creation: None, ..usage };
+        let cost = pricing_service.calculate_cost_for_usage("claude-3-5-sonnet", &usage).unwrap();
+        assert!((cost - 3.75).abs() < 0.001);
+        let cost = pricing_service.with_default_cache_ttl(CacheTtl::OneHour)
+            .calculate_cost_for_usage("claude-3-5-sonnet", &usage).unwrap();
+        assert!((cost - 6.0).abs() < 0.001);
+    }
+
     #[test]
     fn test_model_weight_calculation() {
         let pricing_service = PricingService::new();
@@ -410,4 +922,39 @@ mod tests {
         assert_eq!(pricing_service.get_model_rate_limit_weight("claude-3-5-sonnet"), 1);
         assert_eq!(pricing_service.get_model_rate_limit_weight("claude-3-5-haiku"), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_reported_cost_divergence() {
+        let entries = crate::utils::claude::parse_jsonl_content(concat!(
+            r#"{"timestamp":"2024-01-01T10:00:00.000Z","message":{"id":"msg-1","usage":{"input_tokens":1000,"output_tokens":500},"model":"claude-3-5-sonnet"},"costUSD":0.0105,"requestId":"req-1"}"#, "\n",
+            r#"{"timestamp":"2024-01-01T10:01:00.000Z","message":{"id":"msg-2","usage":{"input_tokens":9000,"output_tokens":9000},"model":"claude-3-5-sonnet"},"requestId":"req-2"}"#,
+        )).unwrap();
+        let pricing_service = PricingService::new().with_divergence_percent(5.0);
+
+        // Only the entry with a `costUSD` is compared, and it matches the table
+        let check = pricing_service.check_reported_cost(&entries).unwrap();
+        assert!((check.calculated - 0.0105).abs() < 1e-9);
+        assert!(!pricing_service.cost_diverges("test", Some(check)));
+
+        let off = CostCheck { reported: 0.02, calculated: 0.0105 };
+        assert!((off.divergence_percent() - 47.5).abs() < 1e-9);
+        assert!(pricing_service.cost_diverges("test", Some(off)));
+        assert!(pricing_service.check_reported_cost(&entries[1..]).is_none());
+        assert!(!pricing_service.with_divergence_percent(0.0).cost_diverges("test", Some(off)));
+    }
+
+    #[test]
+    fn test_sidechain_toggle() {
+        let entries = crate::utils::claude::parse_jsonl_content(concat!(
+            r#"{"timestamp":"2024-01-01T10:00:00.000Z","message":{"id":"msg-1","usage":{"input_tokens":1000,"output_tokens":500},"model":"claude-3-5-sonnet"},"requestId":"req-1"}"#, "\n",
+            r#"{"timestamp":"2024-01-01T10:01:00.000Z","isSidechain":true,"sessionId":"agent","message":{"id":"msg-2","usage":{"input_tokens":2000,"output_tokens":1000},"model":"claude-3-5-sonnet"},"requestId":"req-2"}"#,
+        )).unwrap();
+
+        let included = PricingService::new().with_include_sidechains(true);
+        assert_eq!(included.calculate_token_breakdown(&entries).total_tokens(), 4500);
+
+        let excluded = PricingService::new().with_include_sidechains(false);
+        assert_eq!(excluded.calculate_token_breakdown(&entries).total_tokens(), 1500);
+        assert!((excluded.calculate_total_cost(&entries).unwrap() - 0.0105).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::utils::atomic_write::write_atomic;
+use crate::utils::cache_dir::cache_root;
+use crate::utils::logger::debug_with_context;
+
+/// Appended to a segment rendered from the last good value instead of fresh data
+pub const STALE_MARKER: &str = "⟳";
+
+/// Cached renderings older than this are dropped when the cache is saved
+const MAX_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedSegment {
+    pub text: String,
+    pub rendered_at: u64,
+}
+
+/// Last successful rendering of each segment, persisted between invocations so a segment
+/// that times out or fails can fall back to its previous output. Entries are keyed by
+/// working directory as well as segment name, since git and directory output depend on it.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SegmentCache {
+    segments: HashMap<String, CachedSegment>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl SegmentCache {
+    /// Default location: `<cache root>/segments.json`
+    pub fn default_path() -> Option<PathBuf> {
+        cache_root().map(|dir| dir.join("segments.json"))
+    }
+
+    /// Load the cache from `path`; a missing or unreadable file yields an empty cache
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let mut cache = path
+            .as_deref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| serde_json::from_slice::<SegmentCache>(&bytes).ok())
+            .unwrap_or_default();
+
+        cache.path = path;
+        cache
+    }
+
+    pub fn get(&self, segment: &str) -> Option<&CachedSegment> {
+        self.segments.get(&cache_key(segment))
+    }
+
+    /// The cached rendering of `segment` if it is younger than `ttl` (never for a zero TTL)
+    pub fn get_fresh(&self, segment: &str, ttl: Duration) -> Option<&CachedSegment> {
+        if ttl.is_zero() {
+            return None;
+        }
+
+        self.get(segment)
+            .filter(|cached| now_secs().saturating_sub(cached.rendered_at) < ttl.as_secs())
+    }
+
+    /// Remember the fresh rendering of `segment`, cached for `ttl`
+    pub fn store(&mut self, segment: &str, text: &str, ttl: Duration) {
+        let key = cache_key(segment);
+        if let Some(cached) = self.segments.get_mut(&key).filter(|cached| cached.text == text) {
+            // Unchanged text only needs saving when the timestamp starts a new TTL window;
+            // without a TTL it just matters for pruning, so the file is left untouched
+            cached.rendered_at = now_secs();
+            self.dirty |= !ttl.is_zero();
+            return;
+        }
+
+        self.segments.insert(key, CachedSegment { text: text.to_string(), rendered_at: now_secs() });
+        self.dirty = true;
+    }
+
+    /// Write the cache back if anything changed (atomically via rename)
+    pub fn save(&mut self) -> Result<()> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let cutoff = now_secs().saturating_sub(MAX_AGE_SECS);
+        self.segments.retain(|_, cached| cached.rendered_at >= cutoff);
+
+        write_atomic(&path, &serde_json::to_vec(self)?)?;
+        self.dirty = false;
+
+        debug_with_context("segment_cache", &format!("Saved segment cache to {}", path.display()));
+        Ok(())
+    }
+}
+
+/// Render `cached` with the stale marker, keeping it inside the segment's colors
+pub fn mark_stale(cached: &CachedSegment) -> String {
+    const RESET: &str = "\x1b[0m";
+
+    if cached.text.is_empty() {
+        return String::new();
+    }
+
+    // Insert before any trailing padding and color reset so the segment keeps its shape
+    let (body, reset) = match cached.text.strip_suffix(RESET) {
+        Some(body) => (body, RESET),
+        None => (cached.text.as_str(), ""),
+    };
+    let content = body.trim_end();
+    let padding = &body[content.len()..];
+
+    format!("{} {}{}{}", content, STALE_MARKER, padding, reset)
+}
+
+fn cache_key(segment: &str) -> String {
+    let cwd = std::env::current_dir().map(|dir| dir.to_string_lossy().to_string()).unwrap_or_default();
+    format!("{}|{}", cwd, segment)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
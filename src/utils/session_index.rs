@@ -0,0 +1,278 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::UNIX_EPOCH;
+
+use crate::utils::atomic_write::write_atomic;
+use crate::utils::cache_dir::cache_root;
+use crate::utils::claude::ParsedEntry;
+use crate::utils::logger::debug_with_context;
+
+/// Bumped whenever the index layout changes so an old index is rebuilt rather than misread
+const INDEX_VERSION: u32 = 1;
+
+/// What the index knows about one transcript, as of the size and mtime it was indexed at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionRecord {
+    /// The session the transcript is named after (`<session id>.jsonl`)
+    pub session_id: String,
+    /// The project directory the transcript is in
+    pub project: String,
+    pub first_timestamp: Option<DateTime<Utc>>,
+    pub last_timestamp: Option<DateTime<Utc>>,
+    pub size: u64,
+    pub mtime_nanos: u128,
+}
+
+/// Transcripts by path with their session, project and the time span of their entries, kept in
+/// `<cache root>/session_index.bin`. Records are refreshed whenever a transcript is read, so the
+/// index follows the transcripts incrementally; a record only counts while the transcript still
+/// has the size and mtime it was indexed at.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionIndex {
+    version: u32,
+    records: BTreeMap<PathBuf, SessionRecord>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+fn metadata_stamp(metadata: &Metadata) -> Option<(u64, u128)> {
+    let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((metadata.len(), mtime.as_nanos()))
+}
+
+/// The session id a transcript is named after: its file name up to the first `.`, so rotated
+/// and archived copies (`<id>.jsonl.1`, `<id>.jsonl.gz`) belong to the same session
+fn session_id_of(transcript: &Path) -> String {
+    let name = transcript.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    name.split('.').next().unwrap_or_default().to_string()
+}
+
+impl Default for SessionIndex {
+    fn default() -> Self {
+        Self { version: INDEX_VERSION, records: BTreeMap::new(), dirty: false }
+    }
+}
+
+impl SessionIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the index from `path`; a missing, unreadable or outdated index loads empty
+    pub fn load(path: &Path) -> Self {
+        std::fs::read(path)
+            .ok()
+            .and_then(|bytes| bincode::deserialize::<SessionIndex>(&bytes).ok())
+            .filter(|index| index.version == INDEX_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// Write the index to `path` if anything changed since it was loaded, dropping transcripts
+    /// that no longer exist (written atomically via rename)
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.records.retain(|transcript, _| transcript.exists());
+
+        write_atomic(path, &bincode::serialize(self)?)?;
+
+        self.dirty = false;
+        debug_with_context("session_index", &format!("Saved {} transcript(s)", self.records.len()));
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// The record for `transcript`, if it is still current for a file with `metadata`
+    pub fn record(&self, transcript: &Path, metadata: &Metadata) -> Option<&SessionRecord> {
+        let record = self.records.get(transcript)?;
+        (metadata_stamp(metadata) == Some((record.size, record.mtime_nanos))).then_some(record)
+    }
+
+    /// Record the entries just read from `transcript`, which had `metadata` when it was read
+    pub fn observe(&mut self, transcript: &Path, metadata: &Metadata, entries: &[ParsedEntry]) {
+        let Some((size, mtime_nanos)) = metadata_stamp(metadata) else {
+            return;
+        };
+
+        let record = SessionRecord {
+            session_id: session_id_of(transcript),
+            project: transcript
+                .parent()
+                .and_then(|dir| dir.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            first_timestamp: entries.iter().map(|entry| entry.timestamp).min(),
+            last_timestamp: entries.iter().map(|entry| entry.timestamp).max(),
+            size,
+            mtime_nanos,
+        };
+
+        if self.records.get(transcript) != Some(&record) {
+            self.records.insert(transcript.to_path_buf(), record);
+            self.dirty = true;
+        }
+    }
+
+    /// Whether `transcript` may hold entries at or after `since`. Only a transcript indexed
+    /// unchanged whose last entry is older can be passed over; anything else may.
+    pub fn may_have_entries_since(&self, transcript: &Path, metadata: &Metadata, since: DateTime<Utc>) -> bool {
+        self.record(transcript, metadata)
+            .is_none_or(|record| record.last_timestamp.is_some_and(|last| last >= since))
+    }
+
+    /// The indexed `<session_id>.jsonl` transcript under one of `claude_paths`, if any
+    pub fn find_session(&self, session_id: &str, claude_paths: &[PathBuf]) -> Option<&Path> {
+        let file_name = format!("{}.jsonl", session_id);
+        self.records
+            .keys()
+            .find(|transcript| {
+                transcript.file_name().is_some_and(|name| name.to_string_lossy() == file_name)
+                    && claude_paths.iter().any(|claude_path| transcript.starts_with(claude_path))
+            })
+            .map(PathBuf::as_path)
+    }
+}
+
+/// Bumped whenever the uuid index layout changes
+const UUID_INDEX_VERSION: u32 = 1;
+
+/// The entry uuids of one transcript, as of the size and mtime it was indexed at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UuidRecord {
+    size: u64,
+    mtime_nanos: u128,
+    /// xxh3 hashes of the entries' `uuid`s, sorted
+    uuids: Vec<u64>,
+}
+
+/// The entry uuids of every transcript in one project directory, kept in
+/// `<cache root>/uuids/<project hash>.bin`, so a `parentUuid` that points outside a session's
+/// transcript can be traced to the transcript it continues without parsing the whole project.
+/// Only transcripts whose size or mtime changed since they were indexed are read again.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectUuidIndex {
+    version: u32,
+    transcripts: BTreeMap<PathBuf, UuidRecord>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl Default for ProjectUuidIndex {
+    fn default() -> Self {
+        Self { version: UUID_INDEX_VERSION, transcripts: BTreeMap::new(), dirty: false }
+    }
+}
+
+fn uuid_hash(uuid: &str) -> u64 {
+    xxhash_rust::xxh3::xxh3_64(uuid.as_bytes())
+}
+
+impl ProjectUuidIndex {
+    /// Where the uuid index of `project_dir` is kept, or `None` without a cache directory
+    pub fn path_for(project_dir: &Path) -> Option<PathBuf> {
+        let hash = xxhash_rust::xxh3::xxh3_64(project_dir.to_string_lossy().as_bytes());
+        cache_root().map(|dir| dir.join("uuids").join(format!("{:016x}.bin", hash)))
+    }
+
+    /// Load the index from `path`; a missing, unreadable or outdated index loads empty
+    pub fn load(path: &Path) -> Self {
+        std::fs::read(path)
+            .ok()
+            .and_then(|bytes| bincode::deserialize::<ProjectUuidIndex>(&bytes).ok())
+            .filter(|index| index.version == UUID_INDEX_VERSION)
+            .unwrap_or_default()
+    }
+
+    /// Write the index to `path` if anything changed since it was loaded (atomically via rename)
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        write_atomic(path, &bincode::serialize(self)?)?;
+
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Whether `transcript` is indexed as of `metadata`
+    pub fn is_current(&self, transcript: &Path, metadata: &Metadata) -> bool {
+        self.transcripts
+            .get(transcript)
+            .is_some_and(|record| metadata_stamp(metadata) == Some((record.size, record.mtime_nanos)))
+    }
+
+    /// Index the uuids of the entries just read from `transcript`, which had `metadata`
+    pub fn insert(&mut self, transcript: &Path, metadata: &Metadata, entries: &[ParsedEntry]) {
+        let Some((size, mtime_nanos)) = metadata_stamp(metadata) else {
+            return;
+        };
+
+        let mut uuids: Vec<u64> = entries.iter().filter_map(|entry| entry.raw.get("uuid")?.as_str()).map(uuid_hash).collect();
+        uuids.sort_unstable();
+        uuids.dedup();
+        self.transcripts.insert(transcript.to_path_buf(), UuidRecord { size, mtime_nanos, uuids });
+        self.dirty = true;
+    }
+
+    /// Forget transcripts not in `present`
+    pub fn retain(&mut self, present: &[PathBuf]) {
+        let before = self.transcripts.len();
+        self.transcripts.retain(|transcript, _| present.contains(transcript));
+        self.dirty |= self.transcripts.len() != before;
+    }
+
+    /// A transcript, other than those in `skip`, with an entry whose uuid is one of `uuids`
+    pub fn find<'a>(&self, uuids: impl IntoIterator<Item = &'a str>, skip: &[PathBuf]) -> Option<&Path> {
+        let hashes: Vec<u64> = uuids.into_iter().map(uuid_hash).collect();
+        self.transcripts
+            .iter()
+            .filter(|(transcript, _)| !skip.contains(transcript))
+            .find(|(_, record)| hashes.iter().any(|hash| record.uuids.binary_search(hash).is_ok()))
+            .map(|(transcript, _)| transcript.as_path())
+    }
+}
+
+/// Where the session index is kept, or `None` when disabled via `CLAUDE_POWERLINE_NO_SESSION_INDEX=1`
+pub fn session_index_path() -> Option<PathBuf> {
+    if std::env::var("CLAUDE_POWERLINE_NO_SESSION_INDEX").is_ok_and(|v| !v.is_empty() && v != "0" && v != "false") {
+        return None;
+    }
+
+    cache_root().map(|dir| dir.join("session_index.bin"))
+}
+
+/// The process's session index, loaded on first use
+static SESSION_INDEX: OnceLock<Option<Mutex<SessionIndex>>> = OnceLock::new();
+
+/// Run `f` on the process's session index; `None` when the index is disabled
+pub fn with_session_index<T>(f: impl FnOnce(&mut SessionIndex) -> T) -> Option<T> {
+    let index = SESSION_INDEX
+        .get_or_init(|| session_index_path().map(|path| Mutex::new(SessionIndex::load(&path))))
+        .as_ref()?;
+    let mut index = index.lock().ok()?;
+    Some(f(&mut index))
+}
+
+/// Persist what this process added to the session index; failures are only logged
+pub fn save_session_index() {
+    let Some(path) = session_index_path() else {
+        return;
+    };
+    if let Some(Err(e)) = with_session_index(|index| index.save(&path)) {
+        debug_with_context("session_index", &format!("Failed to save the session index: {:#}", e));
+    }
+}
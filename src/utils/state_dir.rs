@@ -0,0 +1,27 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const LOG_FILE: &str = "claude-powerline.log";
+
+/// State root configured via `stateDir`, set once at startup before any state is touched
+static STATE_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `dir` as the root of every persistent state file and log; only the first call has an effect
+pub fn set_state_root(dir: PathBuf) {
+    let _ = STATE_ROOT.set(dir);
+}
+
+/// Root of state that should survive a cache clear (alert history, push throttling, logs): the
+/// configured `stateDir`, or `<XDG state dir>/claude-powerline` (`$XDG_STATE_HOME`, by default
+/// `~/.local/state`). Platforms without a state directory use the local data directory.
+pub fn state_root() -> Option<PathBuf> {
+    STATE_ROOT
+        .get()
+        .cloned()
+        .or_else(|| dirs::state_dir().or_else(dirs::data_local_dir).map(|dir| dir.join("claude-powerline")))
+}
+
+/// Default log file, used under `CLAUDE_POWERLINE_DEBUG` or when `logging.file` is unset
+pub fn log_file_path() -> Option<PathBuf> {
+    state_root().map(|dir| dir.join("logs").join(LOG_FILE))
+}
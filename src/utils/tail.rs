@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes read per step when scanning a file backwards
+const TAIL_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Walk the lines of `path` from last to first, handing each non-empty line to `visit`
+/// until it returns `Some`. Only as much of the file as needed is read, so finding a
+/// recent line costs the same regardless of how long the file has grown.
+///
+/// Returns the value produced by `visit` and the number of lines visited.
+pub fn find_last_line<T>(path: &Path, mut visit: impl FnMut(&mut [u8]) -> Option<T>) -> Result<(Option<T>, usize)> {
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let mut pos = file.metadata()?.len();
+
+    // Bytes already read that belong to a line whose start we have not reached yet
+    let mut carry: Vec<u8> = Vec::new();
+    let mut visited = 0;
+
+    while pos > 0 {
+        // Grow the read with the pending line so very long lines are not re-copied per chunk
+        let read_size = (TAIL_CHUNK_SIZE.max(carry.len()) as u64).min(pos);
+        pos -= read_size;
+
+        let mut buffer = vec![0u8; read_size as usize];
+        file.seek(SeekFrom::Start(pos))?;
+        file.read_exact(&mut buffer)?;
+        buffer.extend_from_slice(&carry);
+
+        // Everything after the first newline is made of complete lines; the part before it
+        // is only complete once the start of the file is reached
+        let first_complete = match memchr::memchr(b'\n', &buffer) {
+            Some(i) => i + 1,
+            None if pos == 0 => 0,
+            None => {
+                carry = buffer;
+                continue;
+            }
+        };
+
+        if let Some(found) = visit_lines_rev(&mut buffer[first_complete..], &mut visit, &mut visited) {
+            return Ok((Some(found), visited));
+        }
+
+        buffer.truncate(first_complete);
+        carry = buffer;
+        if pos == 0 && !carry.is_empty() {
+            if let Some(found) = visit_lines_rev(&mut carry, &mut visit, &mut visited) {
+                return Ok((Some(found), visited));
+            }
+        }
+    }
+
+    Ok((None, visited))
+}
+
+fn visit_lines_rev<T>(
+    bytes: &mut [u8],
+    visit: &mut impl FnMut(&mut [u8]) -> Option<T>,
+    visited: &mut usize,
+) -> Option<T> {
+    for line in bytes.rsplit_mut(|&b| b == b'\n') {
+        if line.iter().all(|b| b.is_ascii_whitespace()) {
+            continue;
+        }
+
+        *visited += 1;
+        if let Some(found) = visit(line) {
+            return Some(found);
+        }
+    }
+
+    None
+}
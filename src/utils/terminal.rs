@@ -0,0 +1,435 @@
+use std::env;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+/// Set when output goes somewhere other than the terminal (e.g. an SVG render) and must carry 24-bit colors
+static FORCE_RGB_COLORS: OnceLock<()> = OnceLock::new();
+
+/// Whether `enable_virtual_terminal` switched the Windows console into ANSI mode
+static VIRTUAL_TERMINAL: OnceLock<bool> = OnceLock::new();
+
+/// Set by `--color always|never`, which overrides every environment variable
+static COLOR_MODE: OnceLock<ColorMode> = OnceLock::new();
+
+/// The terminal's background, detected once per process
+static BACKGROUND: OnceLock<Option<Background>> = OnceLock::new();
+
+/// Whether the terminal draws on a dark or a light background
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Background {
+    Dark,
+    Light,
+}
+
+/// Whether to emit colors, as `--color` says
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Decide from the environment
+    Auto,
+    Always,
+    Never,
+}
+
+impl std::str::FromStr for ColorMode {
+    type Err = anyhow::Error;
+
+    fn from_str(mode: &str) -> anyhow::Result<Self> {
+        match mode {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            other => anyhow::bail!("Unknown color mode: {} (expected auto, always or never)", other),
+        }
+    }
+}
+
+/// Use `mode` instead of the environment to decide whether to emit colors; only the first call has an effect
+pub fn set_color_mode(mode: ColorMode) {
+    let _ = COLOR_MODE.set(mode);
+}
+
+/// The mode set with `set_color_mode`, `Auto` if none was
+pub fn color_mode() -> ColorMode {
+    COLOR_MODE.get().copied().unwrap_or(ColorMode::Auto)
+}
+
+/// Emit 24-bit colors from now on regardless of NO_COLOR, TERM and COLORTERM
+pub fn force_rgb_colors() {
+    let _ = FORCE_RGB_COLORS.set(());
+}
+
+/// Turn on ANSI escape processing for the Windows console stdout is attached to, so colors
+/// aren't printed as raw escapes. Output piped to Claude Code is left alone. A no-op elsewhere.
+pub fn enable_virtual_terminal() -> bool {
+    *VIRTUAL_TERMINAL.get_or_init(windows_console::enable_virtual_terminal)
+}
+
+/// Whether ANSI colors should be emitted: as `--color` says, then as an environment variable in
+/// `color_override` says, otherwise unless TERM is dumb or unset (Windows terminals don't set
+/// TERM, so there an unset TERM still means color)
+pub fn should_use_colors() -> bool {
+    if FORCE_RGB_COLORS.get().is_some() {
+        return true;
+    }
+    match color_mode() {
+        ColorMode::Always => return true,
+        ColorMode::Never => return false,
+        ColorMode::Auto => {}
+    }
+    match color_override() {
+        Some((_, enabled)) => enabled,
+        None => match env::var("TERM") {
+            Ok(term) => !term.is_empty() && term != "dumb",
+            Err(_) => cfg!(windows),
+        },
+    }
+}
+
+/// The environment variable that turns colors on or off regardless of TERM, and which way
+pub fn color_override() -> Option<(&'static str, bool)> {
+    color_override_from(|name| env::var(name).ok())
+}
+
+/// `color_override` with variables looked up through `var`, in the conventional precedence:
+/// `FORCE_COLOR` (on unless `0` or `false`), then `CLICOLOR_FORCE` (on unless empty or `0`),
+/// then a non-empty `NO_COLOR` (off), then `CLICOLOR=0` (off)
+pub fn color_override_from(var: impl Fn(&str) -> Option<String>) -> Option<(&'static str, bool)> {
+    if let Some(level) = var("FORCE_COLOR") {
+        return Some(("FORCE_COLOR", !matches!(level.trim(), "0" | "false")));
+    }
+    if var("CLICOLOR_FORCE").is_some_and(|force| !force.is_empty() && force != "0") {
+        return Some(("CLICOLOR_FORCE", true));
+    }
+    if var("NO_COLOR").is_some_and(|no_color| !no_color.is_empty()) {
+        return Some(("NO_COLOR", false));
+    }
+    if var("CLICOLOR").as_deref() == Some("0") {
+        return Some(("CLICOLOR", false));
+    }
+    None
+}
+
+/// Whether the terminal advertises 24-bit color support
+pub fn supports_rgb_colors() -> bool {
+    FORCE_RGB_COLORS.get().is_some() ||
+    // `FORCE_COLOR=3` asks for 24-bit color by convention
+    env::var("FORCE_COLOR").is_ok_and(|level| level.trim() == "3") ||
+    env::var("COLORTERM").is_ok_and(|ct| ct.contains("truecolor") || ct.contains("24bit")) ||
+    // Windows Terminal, and any Windows 10+ console once virtual terminal mode is on
+    env::var_os("WT_SESSION").is_some() ||
+    VIRTUAL_TERMINAL.get().copied().unwrap_or(false) ||
+    env::var("TERM").is_ok_and(|term|
+        term.contains("256") ||
+        term.contains("color") ||
+        term == "xterm-kitty" ||
+        term == "alacritty"
+    )
+}
+
+/// Width of the terminal in cells: `COLUMNS` if set, else the Windows console window's width
+pub fn terminal_width() -> Option<usize> {
+    env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.trim().parse().ok())
+        .filter(|&columns| columns > 0)
+        .or_else(windows_console::window_width)
+}
+
+/// The terminal's background: from `COLORFGBG` when the terminal exports it, otherwise by
+/// asking the terminal (OSC 11). The query is only sent when stdout is a terminal, so its reply
+/// can't end up in the input of a program that pipes the output, such as Claude Code.
+pub fn terminal_background() -> Option<Background> {
+    *BACKGROUND.get_or_init(|| {
+        env::var("COLORFGBG")
+            .ok()
+            .and_then(|value| parse_colorfgbg(&value))
+            .or_else(|| {
+                std::io::stdout()
+                    .is_terminal()
+                    .then(tty_query::background_color)
+                    .flatten()
+                    .and_then(|reply| parse_background_reply(&reply))
+            })
+    })
+}
+
+/// `COLORFGBG` (`fg;bg`, or `fg;default;bg`) in ANSI color numbers: 7 and 9-15 are light backgrounds
+pub fn parse_colorfgbg(value: &str) -> Option<Background> {
+    match value.rsplit(';').next()?.trim().parse::<u8>().ok()? {
+        7 | 9..=15 => Some(Background::Light),
+        0..=6 | 8 => Some(Background::Dark),
+        _ => None,
+    }
+}
+
+/// The terminal's reply to an OSC 11 query, `ESC ] 11 ; rgb:RRRR/GGGG/BBBB` (1-4 hex digits per
+/// channel) ended by BEL or ST, judged by its relative luminance
+pub fn parse_background_reply(reply: &str) -> Option<Background> {
+    let rgb = &reply[reply.find("rgb:")? + 4..];
+    let rgb = rgb.trim_end_matches(['\x07', '\\']).trim_end_matches('\x1b');
+
+    let mut channels = rgb.split('/').map(|channel| {
+        let value = u32::from_str_radix(channel, 16).ok()?;
+        let max = 16u32.checked_pow(channel.len() as u32)?.checked_sub(1).filter(|&max| max > 0)?;
+        Some(value as f64 / max as f64)
+    });
+    let (r, g, b) = (channels.next()??, channels.next()??, channels.next()??);
+
+    let luminance = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    Some(if luminance < 0.5 { Background::Dark } else { Background::Light })
+}
+
+/// Terminal cells `text` occupies once printed: ANSI escapes take none, wide (CJK, emoji)
+/// characters two, and zero-width joiners and variation selectors none
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            skip_escape(&mut chars);
+            continue;
+        }
+        width += char_width(c);
+    }
+
+    width
+}
+
+/// `text` with its ANSI escapes (colors, OSC hyperlinks) removed
+pub fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            skip_escape(&mut chars);
+            continue;
+        }
+        plain.push(c);
+    }
+
+    plain
+}
+
+/// Cut `text` down to at most `max_width` terminal cells, ending with `…` when anything was
+/// dropped. Escapes are kept (so colors still reset) and a wide character is never split.
+pub fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+
+    let budget = max_width.saturating_sub(1);
+    let mut result = String::with_capacity(text.len());
+    let mut width = 0;
+    let mut truncated = false;
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            let rest = chars.as_str();
+            skip_escape(&mut chars);
+            result.push(c);
+            result.push_str(&rest[..rest.len() - chars.as_str().len()]);
+            continue;
+        }
+        if truncated {
+            continue;
+        }
+
+        let cells = char_width(c);
+        if width + cells > budget {
+            if max_width > 0 {
+                result.push('…');
+            }
+            truncated = true;
+            continue;
+        }
+        width += cells;
+        result.push(c);
+    }
+
+    result
+}
+
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1FAFF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// Advance past the rest of an escape sequence whose ESC was just consumed: CSI up to its
+/// final byte, OSC up to BEL or ESC \, anything else one character
+fn skip_escape(chars: &mut std::str::Chars) {
+    match chars.next() {
+        Some('[') => {
+            for c in chars.by_ref() {
+                if ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+        }
+        Some(']') => {
+            while let Some(c) = chars.next() {
+                if c == '\x07' {
+                    break;
+                }
+                if c == '\x1b' {
+                    chars.next();
+                    break;
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(unix)]
+mod tty_query {
+    use std::fs::{File, OpenOptions};
+    use std::io::{Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::time::{Duration, Instant};
+
+    /// How long to wait for terminals that ignore the query
+    const REPLY_TIMEOUT: Duration = Duration::from_millis(200);
+
+    /// Ask the controlling terminal for its background color (OSC 11) and return the raw reply
+    pub fn background_color() -> Option<String> {
+        let mut tty = OpenOptions::new().read(true).write(true).open("/dev/tty").ok()?;
+        let fd = tty.as_raw_fd();
+
+        // SAFETY: termios calls on the tty opened above; the original settings are restored
+        // before returning, whatever the outcome of the query
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(fd, &mut original) != 0 {
+                return None;
+            }
+            // Unbuffered and unechoed, with reads giving up after a tenth of a second
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+            raw.c_cc[libc::VMIN] = 0;
+            raw.c_cc[libc::VTIME] = 1;
+            if libc::tcsetattr(fd, libc::TCSANOW, &raw) != 0 {
+                return None;
+            }
+
+            let reply = query(&mut tty);
+            libc::tcsetattr(fd, libc::TCSANOW, &original);
+            reply
+        }
+    }
+
+    fn query(tty: &mut File) -> Option<String> {
+        tty.write_all(b"\x1b]11;?\x07").ok()?;
+        tty.flush().ok()?;
+
+        let started = Instant::now();
+        let mut reply = Vec::new();
+        let mut buffer = [0u8; 64];
+        while started.elapsed() < REPLY_TIMEOUT {
+            let read = tty.read(&mut buffer).ok()?;
+            reply.extend_from_slice(&buffer[..read]);
+            if reply.ends_with(b"\x07") || reply.ends_with(b"\x1b\\") {
+                return String::from_utf8(reply).ok();
+            }
+        }
+        None
+    }
+}
+
+#[cfg(not(unix))]
+mod tty_query {
+    pub fn background_color() -> Option<String> {
+        None
+    }
+}
+
+#[cfg(windows)]
+mod windows_console {
+    use std::ffi::c_void;
+
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    #[repr(C)]
+    #[derive(Default)]
+    #[allow(dead_code)]
+    struct Coord {
+        x: i16,
+        y: i16,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    #[allow(dead_code)]
+    struct SmallRect {
+        left: i16,
+        top: i16,
+        right: i16,
+        bottom: i16,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    #[allow(dead_code)]
+    struct ConsoleScreenBufferInfo {
+        size: Coord,
+        cursor_position: Coord,
+        attributes: u16,
+        window: SmallRect,
+        maximum_window_size: Coord,
+    }
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(std_handle: u32) -> *mut c_void;
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+        fn GetConsoleScreenBufferInfo(console: *mut c_void, info: *mut ConsoleScreenBufferInfo) -> i32;
+    }
+
+    pub fn enable_virtual_terminal() -> bool {
+        // SAFETY: plain Win32 console calls on this process's own stdout handle; GetConsoleMode
+        // fails (and nothing is changed) when stdout is a pipe or file rather than a console
+        unsafe {
+            let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+            let mut mode = 0;
+            if handle.is_null() || GetConsoleMode(handle, &mut mode) == 0 {
+                return false;
+            }
+            mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+        }
+    }
+
+    pub fn window_width() -> Option<usize> {
+        let mut info = ConsoleScreenBufferInfo::default();
+        // SAFETY: `info` is a correctly laid out CONSOLE_SCREEN_BUFFER_INFO the call only writes into
+        let ok = unsafe { GetConsoleScreenBufferInfo(GetStdHandle(STD_OUTPUT_HANDLE), &mut info) != 0 };
+        let width = info.window.right - info.window.left + 1;
+        (ok && width > 0).then_some(width as usize)
+    }
+}
+
+#[cfg(not(windows))]
+mod windows_console {
+    pub fn enable_virtual_terminal() -> bool {
+        false
+    }
+
+    pub fn window_width() -> Option<usize> {
+        None
+    }
+}
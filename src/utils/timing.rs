@@ -0,0 +1,155 @@
+use crate::utils::logger::{log_enabled, log_event, LogLevel};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long a segment took to compute and how much transcript data it parsed
+#[derive(Debug, Clone)]
+pub struct SegmentTiming {
+    pub name: String,
+    pub duration: Duration,
+    pub files_parsed: usize,
+    pub entries_parsed: usize,
+    /// The segment was still running when the render deadline expired
+    pub timed_out: bool,
+    /// The segment was served from the segment cache without being recomputed
+    pub from_cache: bool,
+}
+
+impl SegmentTiming {
+    /// Timing for a segment abandoned at the render deadline after `elapsed`
+    pub fn missed_deadline(name: &str, elapsed: Duration) -> Self {
+        Self {
+            name: name.to_string(),
+            duration: elapsed,
+            files_parsed: 0,
+            entries_parsed: 0,
+            timed_out: true,
+            from_cache: false,
+        }
+    }
+
+    /// Timing for a segment served from the segment cache
+    pub fn cached(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            duration: Duration::ZERO,
+            files_parsed: 0,
+            entries_parsed: 0,
+            timed_out: false,
+            from_cache: true,
+        }
+    }
+}
+
+/// Context structured stage events are logged under (`CLAUDE_POWERLINE_DEBUG=timing` shows only them)
+pub const TIMING_LOG_CONTEXT: &str = "timing";
+
+#[derive(Default)]
+struct ParseCounters {
+    /// Segment being timed, attached to the stage events it emits
+    segment: String,
+    files: AtomicUsize,
+    entries: AtomicUsize,
+}
+
+tokio::task_local! {
+    static PARSE_COUNTERS: Arc<ParseCounters>;
+}
+
+/// Record parsed files/entries against the segment currently being timed (no-op outside `timed`)
+pub fn record_parse(files: usize, entries: usize) {
+    let _ = PARSE_COUNTERS.try_with(|counters| {
+        counters.files.fetch_add(files, Ordering::Relaxed);
+        counters.entries.fetch_add(entries, Ordering::Relaxed);
+    });
+}
+
+/// Emit a structured debug event for one pipeline stage (`discovery`, `parse`, `dedupe`,
+/// `compute`, `render`) as a JSON line, e.g.
+/// `{"event":"stage","stage":"parse","segment":"today","duration_ms":12.345,"files":40,"entries":9120,...}`.
+/// Inside `timed` the event names the segment being rendered. Nothing is built unless
+/// debug logging is enabled for the `timing` context.
+pub fn record_stage(stage: &str, duration: Duration, files: Option<usize>, entries: Option<usize>) {
+    if !log_enabled(LogLevel::Debug, TIMING_LOG_CONTEXT) {
+        return;
+    }
+
+    let mut event = serde_json::json!({
+        "ts": chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        "pid": std::process::id(),
+        "event": "stage",
+        "stage": stage,
+        "duration_ms": (duration.as_secs_f64() * 1_000_000.0).round() / 1000.0,
+    });
+    if let Ok(segment) = PARSE_COUNTERS.try_with(|counters| counters.segment.clone()) {
+        event["segment"] = segment.into();
+    }
+    if let Some(files) = files {
+        event["files"] = files.into();
+    }
+    if let Some(entries) = entries {
+        event["entries"] = entries.into();
+    }
+
+    log_event(LogLevel::Debug, TIMING_LOG_CONTEXT, &event.to_string());
+}
+
+/// Run `future`, measuring wall time and the parse work it records via `record_parse`
+pub async fn timed<F: Future>(name: &str, future: F) -> (F::Output, SegmentTiming) {
+    let counters = Arc::new(ParseCounters { segment: name.to_string(), ..Default::default() });
+    let start = Instant::now();
+    let output = PARSE_COUNTERS.scope(counters.clone(), future).await;
+
+    let timing = SegmentTiming {
+        name: name.to_string(),
+        duration: start.elapsed(),
+        files_parsed: counters.files.load(Ordering::Relaxed),
+        entries_parsed: counters.entries.load(Ordering::Relaxed),
+        timed_out: false,
+        from_cache: false,
+    };
+
+    (output, timing)
+}
+
+/// Format timings as aligned lines, slowest first, followed by the total
+pub fn format_timings(timings: &[SegmentTiming], total: Duration) -> String {
+    let mut sorted: Vec<&SegmentTiming> = timings.iter().collect();
+    sorted.sort_by_key(|t| std::cmp::Reverse(t.duration));
+
+    let name_width = sorted.iter().map(|t| t.name.len()).max().unwrap_or(0).max("total".len());
+    let mut lines: Vec<String> = sorted
+        .iter()
+        .map(|t| {
+            if t.timed_out || t.from_cache {
+                return format!(
+                    "{:<width$}  {:>8.2}ms  {}",
+                    t.name,
+                    t.duration.as_secs_f64() * 1000.0,
+                    if t.timed_out { "missed render deadline" } else { "served from cache" },
+                    width = name_width
+                );
+            }
+
+            format!(
+                "{:<width$}  {:>8.2}ms  {} files, {} entries",
+                t.name,
+                t.duration.as_secs_f64() * 1000.0,
+                t.files_parsed,
+                t.entries_parsed,
+                width = name_width
+            )
+        })
+        .collect();
+
+    lines.push(format!(
+        "{:<width$}  {:>8.2}ms",
+        "total",
+        total.as_secs_f64() * 1000.0,
+        width = name_width
+    ));
+
+    lines.join("\n")
+}
@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use crate::utils::atomic_write::write_atomic;
+use crate::utils::logger::debug_with_context;
+use crate::utils::state_dir::state_root;
+
+/// The version of this binary
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The GitHub repository the releases are published in, `repository` in Cargo.toml
+const REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
+
+/// Where the releases are for people, as opposed to `github_releases_url`
+pub const RELEASES_PAGE: &str = concat!(env!("CARGO_PKG_REPOSITORY"), "/releases/latest");
+
+/// GitHub's latest-release endpoint for `REPOSITORY`; `CLAUDE_POWERLINE_UPDATE_URL` points the
+/// check elsewhere (a mirror)
+pub fn github_releases_url() -> String {
+    let repo = REPOSITORY.trim_start_matches("https://github.com/").trim_end_matches('/');
+    format!("https://api.github.com/repos/{}/releases/latest", repo)
+}
+
+/// Give up on a release lookup that hasn't answered within this many seconds
+const FETCH_TIMEOUT_SECS: u64 = 10;
+
+/// The outcome of the last release lookup, persisted in `<state root>/update_check.json` so the
+/// statusline asks at most once per interval
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UpdateState {
+    /// When a lookup last started, successful or not
+    pub checked: Option<DateTime<Utc>>,
+    /// The newest released version found, without a leading `v`
+    pub latest: Option<String>,
+}
+
+impl UpdateState {
+    pub fn default_path() -> Option<PathBuf> {
+        state_root().map(|dir| dir.join("update_check.json"))
+    }
+
+    /// Load the state from `path`; a missing or unreadable file yields a state that was never checked
+    pub fn load(path: &Path) -> Self {
+        std::fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        write_atomic(path, &serde_json::to_vec(self)?)
+    }
+
+    /// Whether the last lookup is more than `interval` old (or there was none)
+    pub fn is_due(&self, now: DateTime<Utc>, interval: Duration) -> bool {
+        self.checked.is_none_or(|checked| now - checked >= interval)
+    }
+
+    /// The latest version, if it is newer than `current`
+    pub fn newer_than(&self, current: &str) -> Option<&str> {
+        self.latest.as_deref().filter(|latest| is_newer(latest, current))
+    }
+}
+
+/// Whether version `candidate` comes after `current`, comparing the dot-separated numbers of
+/// each (`1.10.0` > `1.9.2`); a leading `v` and any `-prerelease` or `+build` suffix are ignored
+pub fn is_newer(candidate: &str, current: &str) -> bool {
+    fn numbers(version: &str) -> Vec<u64> {
+        let version = version.trim().trim_start_matches('v');
+        let core = version.split(['-', '+']).next().unwrap_or(version);
+        core.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+    }
+
+    let (mut candidate, mut current) = (numbers(candidate), numbers(current));
+    let len = candidate.len().max(current.len());
+    candidate.resize(len, 0);
+    current.resize(len, 0);
+    candidate > current
+}
+
+/// Ask `url` (a GitHub latest-release endpoint) for the newest version, through `curl` like the webhooks
+pub async fn fetch_latest_version(url: &str) -> Result<String> {
+    let child = tokio::process::Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location", "--max-time"])
+        .arg(FETCH_TIMEOUT_SECS.to_string())
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .args(["--user-agent", concat!("claude-powerline/", env!("CARGO_PKG_VERSION"))])
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Failed to run curl")?;
+
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        anyhow::bail!("curl exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    let release: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .context("Release lookup did not return JSON")?;
+    let tag = release.get("tag_name").and_then(|tag| tag.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Release lookup returned no tag_name"))?;
+    Ok(tag.trim_start_matches('v').to_string())
+}
+
+/// The release endpoint to ask: `CLAUDE_POWERLINE_UPDATE_URL`, or GitHub
+pub fn releases_url() -> String {
+    std::env::var("CLAUDE_POWERLINE_UPDATE_URL")
+        .ok()
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(github_releases_url)
+}
+
+/// Look the latest release up now and record it at `path`
+pub async fn check_for_update(path: Option<&Path>) -> Result<UpdateState> {
+    let mut state = path.map(UpdateState::load).unwrap_or_default();
+    state.checked = Some(Utc::now());
+    state.latest = Some(fetch_latest_version(&releases_url()).await?);
+
+    if let Some(path) = path {
+        state.save(path)?;
+    }
+    Ok(state)
+}
+
+/// Start a lookup in a separate `claude-powerline --check-update` process, which outlives this
+/// render, so the statusline never waits on the network. It gets this process's arguments too,
+/// so it loads the same config and state directory. The state is stamped first so renders in
+/// the meantime don't start lookups of their own.
+pub fn spawn_update_check(path: &Path, mut state: UpdateState) {
+    state.checked = Some(Utc::now());
+    if let Err(e) = state.save(path) {
+        debug_with_context("update_check", &format!("Failed to save update state: {:#}", e));
+        return;
+    }
+
+    let spawned = std::env::current_exe().and_then(|exe| {
+        std::process::Command::new(exe)
+            .args(std::env::args_os().skip(1))
+            .arg("--check-update")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+    });
+    if let Err(e) = spawned {
+        debug_with_context("update_check", &format!("Failed to start update check: {}", e));
+    }
+}
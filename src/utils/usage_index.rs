@@ -0,0 +1,390 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::utils::archive::{is_compressed, read_compressed};
+use crate::utils::cache_dir::cache_root;
+use crate::utils::claude::{create_unique_hash, entry_content_key, CacheCreation, MessageInfo, ParsedEntry, UsageInfo};
+use crate::utils::data_aggregation::{DataAggregator, TranscriptFile};
+use crate::utils::intern::intern;
+use crate::utils::lines::complete_lines_len;
+use crate::utils::logger::debug_with_context;
+use crate::utils::pricing::{PricingService, TokenBreakdown, UsageFold};
+
+/// Bumped whenever the table layout changes; an index with another layout is rebuilt
+const SCHEMA_VERSION: i64 = 2;
+
+/// Columns of an indexed entry after its key, in insert and select order
+const ENTRY_COLUMNS: &str = "timestamp, source_file, session_id, request_id, message_id, uuid, parent_uuid, cwd, \
+     model, service_tier, has_message, has_usage, input_tokens, output_tokens, cache_creation_tokens, \
+     cache_read_tokens, cache_creation_5m_tokens, cache_creation_1h_tokens, cost_usd, is_sidechain, \
+     response_time_ms, lines_added, lines_removed";
+
+/// Token deltas taken per transcript, as `SessionDeltas` takes them, summed per model and
+/// service tier. Rows with and without a 1-hour cache split or a reported cost are summed
+/// apart, and every entry on its own when entry costs are rounded (`?3`).
+const USAGE_SINCE_SQL: &str = "
+    WITH counted AS (
+        SELECT rowid AS position, key, source_file, timestamp, model, service_tier, cost_usd,
+            input_tokens, output_tokens, cache_creation_tokens, cache_read_tokens, cache_creation_1h_tokens
+        FROM entries
+        WHERE timestamp >= ?1 AND has_usage AND (?2 OR is_sidechain IS NOT 1)
+    ),
+    deltas AS (
+        SELECT key, model, service_tier, cost_usd,
+            MAX(input_tokens - LAG(input_tokens, 1, 0) OVER transcript, 0) AS input_tokens,
+            MAX(output_tokens - LAG(output_tokens, 1, 0) OVER transcript, 0) AS output_tokens,
+            MAX(cache_creation_tokens - LAG(cache_creation_tokens, 1, 0) OVER transcript, 0) AS cache_creation_tokens,
+            MAX(cache_read_tokens - LAG(cache_read_tokens, 1, 0) OVER transcript, 0) AS cache_read_tokens,
+            MAX(cache_creation_1h_tokens - COALESCE(LAG(cache_creation_1h_tokens) OVER transcript, 0), 0) AS cache_creation_1h_tokens
+        FROM counted
+        WINDOW transcript AS (PARTITION BY source_file ORDER BY timestamp, position)
+    )
+    SELECT model, service_tier, SUM(input_tokens), SUM(output_tokens), SUM(cache_creation_tokens),
+        SUM(cache_read_tokens), SUM(cache_creation_1h_tokens), CASE WHEN COUNT(cost_usd) > 0 THEN TOTAL(cost_usd) END
+    FROM deltas
+    GROUP BY model, service_tier, cache_creation_1h_tokens IS NULL, cost_usd IS NULL, CASE WHEN ?3 THEN key END";
+
+/// Resolve the index location from `CLAUDE_POWERLINE_INDEX`: `1`/`true` selects the default
+/// location in the cache root, any other non-empty value is used as the database path
+pub fn index_path_from_env() -> Option<PathBuf> {
+    let value = std::env::var("CLAUDE_POWERLINE_INDEX").ok()?;
+    match value.trim() {
+        "" | "0" | "false" => None,
+        "1" | "true" => cache_root().map(|dir| dir.join("usage-index.sqlite")),
+        path => Some(PathBuf::from(path)),
+    }
+}
+
+/// Embedded SQLite store of deduplicated transcript entries. Each transcript is ingested once
+/// (and then only its appended bytes), and time-window queries are answered from an index
+/// on the entry timestamp instead of re-parsing every file.
+pub struct UsageIndex {
+    conn: Connection,
+}
+
+impl UsageIndex {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create index directory: {}", parent.display()))?;
+        }
+
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open usage index: {}", path.display()))?;
+
+        conn.execute_batch("PRAGMA journal_mode = WAL;")?;
+
+        // The index only holds what the transcripts do, so one with an older layout is re-ingested
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            conn.execute_batch(&format!(
+                "DROP TABLE IF EXISTS entries; DROP TABLE IF EXISTS files; PRAGMA user_version = {};",
+                SCHEMA_VERSION
+            ))?;
+        }
+
+        // The fields the segments and usage queries read, as columns; the JSON lines aren't kept
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS files (
+                 path TEXT PRIMARY KEY,
+                 size INTEGER NOT NULL,
+                 mtime INTEGER NOT NULL,
+                 offset INTEGER NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS entries (
+                 key TEXT PRIMARY KEY,
+                 timestamp INTEGER NOT NULL,
+                 source_file TEXT NOT NULL,
+                 session_id TEXT,
+                 request_id TEXT,
+                 message_id TEXT,
+                 uuid TEXT,
+                 parent_uuid TEXT,
+                 cwd TEXT,
+                 model TEXT,
+                 service_tier TEXT,
+                 has_message INTEGER NOT NULL,
+                 has_usage INTEGER NOT NULL,
+                 input_tokens INTEGER NOT NULL,
+                 output_tokens INTEGER NOT NULL,
+                 cache_creation_tokens INTEGER NOT NULL,
+                 cache_read_tokens INTEGER NOT NULL,
+                 cache_creation_5m_tokens INTEGER,
+                 cache_creation_1h_tokens INTEGER,
+                 cost_usd REAL,
+                 is_sidechain INTEGER,
+                 response_time_ms REAL,
+                 lines_added INTEGER,
+                 lines_removed INTEGER
+             );
+             CREATE INDEX IF NOT EXISTS entries_timestamp ON entries(timestamp);",
+        )?;
+
+        Ok(Self { conn })
+    }
+
+    /// Ingest new or changed transcript files; returns the number of entries added
+    pub fn sync(&mut self, transcript_files: &[PathBuf]) -> Result<usize> {
+        let files: Vec<_> = transcript_files.iter().filter_map(|path| TranscriptFile::stat(path).ok()).collect();
+        self.sync_files(&files)
+    }
+
+    /// `sync` for transcripts whose metadata discovery already read
+    pub fn sync_files(&mut self, transcript_files: &[TranscriptFile]) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let aggregator = DataAggregator::new();
+        let mut added = 0;
+
+        for TranscriptFile { path, metadata } in transcript_files {
+            let size = metadata.len() as i64;
+            let mtime = metadata.modified().ok()
+                .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or(0);
+            let path_str = path.to_string_lossy().to_string();
+
+            let stored: Option<(i64, i64, i64)> = tx
+                .query_row(
+                    "SELECT size, mtime, offset FROM files WHERE path = ?1",
+                    params![path_str],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?;
+
+            let mut offset = match stored {
+                Some((stored_size, stored_mtime, _)) if stored_size == size && stored_mtime == mtime => continue,
+                // Rewritten or truncated: drop what we had and start over
+                Some((_, _, offset)) if size < offset => {
+                    tx.execute("DELETE FROM entries WHERE source_file = ?1", params![path_str])?;
+                    0
+                }
+                Some((_, _, offset)) => offset,
+                None => 0,
+            };
+
+            let appended = if is_compressed(path) {
+                // Archives are rewritten whole rather than appended to, so they're ingested from the start
+                if stored.is_some() {
+                    tx.execute("DELETE FROM entries WHERE source_file = ?1", params![path_str])?;
+                }
+                offset = 0;
+                let mut content = read_compressed(path)?;
+                if !content.ends_with(b"\n") {
+                    content.push(b'\n');
+                }
+                content
+            } else {
+                let mut file = File::open(path)?;
+                file.seek(SeekFrom::Start(offset as u64))?;
+                let mut appended = Vec::new();
+                file.take((size - offset) as u64).read_to_end(&mut appended)?;
+                appended
+            };
+
+            // Only complete lines are ingested; a partially written line is picked up next time
+            let complete = complete_lines_len(&appended);
+
+            for raw_line in appended[..complete].split_inclusive(|&b| b == b'\n') {
+                let line = String::from_utf8_lossy(raw_line);
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                let Ok(Some(entry)) = aggregator.parse_jsonl_line(line) else {
+                    continue;
+                };
+
+                // Without ids, copies of a line in overlapping directories share a content key
+                let key = create_unique_hash(&entry)
+                    .unwrap_or_else(|| format!("{:032x}", entry_content_key(&entry)));
+
+                // Of duplicated entries the earliest is kept, as `load_all_entries` keeps it
+                let timestamp = entry.timestamp.timestamp_millis();
+                let stored: Option<i64> = tx
+                    .prepare_cached("SELECT timestamp FROM entries WHERE key = ?1")?
+                    .query_row(params![key], |row| row.get(0))
+                    .optional()?;
+                if stored.is_some_and(|stored| stored <= timestamp) {
+                    continue;
+                }
+
+                insert_entry(&tx, &key, &path_str, &entry)?;
+                if stored.is_none() {
+                    added += 1;
+                }
+            }
+
+            offset += complete as i64;
+            tx.execute(
+                "INSERT OR REPLACE INTO files (path, size, mtime, offset) VALUES (?1, ?2, ?3, ?4)",
+                params![path_str, size, mtime, offset],
+            )?;
+        }
+
+        tx.commit()?;
+        debug_with_context("usage_index", &format!("Synced {} files, {} new entries", transcript_files.len(), added));
+
+        Ok(added)
+    }
+
+    /// All indexed entries at or after `since` (or every entry), oldest first. Entries are built
+    /// from the indexed columns, so their `raw` fields are the ones the segments read.
+    pub fn entries_since(&self, since: Option<DateTime<Utc>>) -> Result<Vec<ParsedEntry>> {
+        let since = since.map(|t| t.timestamp_millis()).unwrap_or(i64::MIN);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM entries WHERE timestamp >= ?1 ORDER BY timestamp",
+            ENTRY_COLUMNS
+        ))?;
+
+        let entries = stmt
+            .query_map(params![since], entry_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    /// Usage of the indexed entries at or after `since`, as `DataAggregator::fold_usage_since`
+    /// folds it. Deltas and sums are taken in SQL; only the per-model sums are priced here.
+    pub fn usage_since(&self, since: DateTime<Utc>, pricing_service: &PricingService) -> Result<UsageFold> {
+        let since = since.timestamp_millis();
+        let sidechains = pricing_service.includes_sidechains();
+        let mut fold = UsageFold {
+            entry_count: self.conn.query_row(
+                "SELECT COUNT(*) FROM entries WHERE timestamp >= ?1 AND (?2 OR is_sidechain IS NOT 1)",
+                params![since, sidechains],
+                |row| row.get(0),
+            )?,
+            ..UsageFold::default()
+        };
+
+        let mut stmt = self.conn.prepare(USAGE_SINCE_SQL)?;
+        let rows = stmt.query_map(params![since, sidechains, pricing_service.rounds_entry_costs()], |row| {
+            let tokens = TokenBreakdown {
+                input_tokens: row.get::<_, i64>(2)? as u64,
+                output_tokens: row.get::<_, i64>(3)? as u64,
+                cache_creation_input_tokens: row.get::<_, i64>(4)? as u64,
+                cache_read_input_tokens: row.get::<_, i64>(5)? as u64,
+                cache_creation_1h_input_tokens: row.get::<_, Option<i64>>(6)?.map(|tokens| tokens as u64),
+            };
+            Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<String>>(1)?, tokens, row.get::<_, Option<f64>>(7)?))
+        })?;
+
+        for row in rows {
+            let (model, service_tier, tokens, reported) = row?;
+            let usage = UsageInfo {
+                input_tokens: None,
+                output_tokens: None,
+                cache_creation_input_tokens: None,
+                cache_read_input_tokens: None,
+                cache_creation: None,
+                service_tier: service_tier.as_deref().map(intern),
+            };
+            fold.add_tokens(model.as_deref(), &usage, &tokens, reported, pricing_service);
+        }
+
+        Ok(fold)
+    }
+}
+
+/// Insert (or replace) the entry stored under `key`
+fn insert_entry(tx: &rusqlite::Transaction, key: &str, source_file: &str, entry: &ParsedEntry) -> Result<()> {
+    let raw_str = |field: &str| entry.raw.get(field).and_then(|v| v.as_str());
+    let lines = |field: &str| entry.raw.get("cost").and_then(|cost| cost.get(field)).and_then(|v| v.as_u64()).map(|n| n as i64);
+    let message = entry.message.as_ref();
+    let usage = message.and_then(|m| m.usage.as_ref());
+    let tokens = |count: fn(&UsageInfo) -> Option<u32>| usage.and_then(count).unwrap_or(0) as i64;
+    let cache_creation = usage.and_then(|u| u.cache_creation.as_ref());
+
+    tx.prepare_cached(&format!(
+        "INSERT OR REPLACE INTO entries (key, {}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)",
+        ENTRY_COLUMNS
+    ))?
+    .execute(params![
+        key,
+        entry.timestamp.timestamp_millis(),
+        source_file,
+        raw_str("sessionId"),
+        raw_str("requestId"),
+        message.and_then(|m| m.id.as_deref()),
+        raw_str("uuid"),
+        raw_str("parentUuid"),
+        raw_str("cwd"),
+        message.and_then(|m| m.model.as_deref()),
+        usage.and_then(|u| u.service_tier.as_deref()),
+        message.is_some(),
+        usage.is_some(),
+        tokens(|u| u.input_tokens),
+        tokens(|u| u.output_tokens),
+        tokens(|u| u.cache_creation_input_tokens),
+        tokens(|u| u.cache_read_input_tokens),
+        cache_creation.and_then(|c| c.ephemeral_5m_input_tokens),
+        cache_creation.and_then(|c| c.ephemeral_1h_input_tokens),
+        entry.cost_usd,
+        entry.is_sidechain,
+        entry.raw.get("response_time_ms").or_else(|| entry.raw.get("duration_ms")).and_then(|v| v.as_f64()),
+        lines("total_lines_added"),
+        lines("total_lines_removed"),
+    ])?;
+
+    Ok(())
+}
+
+/// Rebuild an entry from the `ENTRY_COLUMNS` of a row
+fn entry_from_row(row: &Row) -> rusqlite::Result<ParsedEntry> {
+    let mut raw = HashMap::new();
+    for (index, field) in [(2, "sessionId"), (3, "requestId"), (5, "uuid"), (6, "parentUuid"), (7, "cwd")] {
+        if let Some(value) = row.get::<_, Option<String>>(index)? {
+            raw.insert(field.to_string(), serde_json::Value::String(value));
+        }
+    }
+    if let Some(response_time) = row.get::<_, Option<f64>>(20)? {
+        raw.insert("response_time_ms".to_string(), serde_json::Value::from(response_time));
+    }
+    let mut cost = serde_json::Map::new();
+    for (index, field) in [(21, "total_lines_added"), (22, "total_lines_removed")] {
+        if let Some(lines) = row.get::<_, Option<i64>>(index)? {
+            cost.insert(field.to_string(), serde_json::Value::from(lines));
+        }
+    }
+    if !cost.is_empty() {
+        raw.insert("cost".to_string(), serde_json::Value::Object(cost));
+    }
+
+    let tokens = |index| row.get::<_, u32>(index).map(Some);
+    let (ephemeral_5m_input_tokens, ephemeral_1h_input_tokens) = (row.get(16)?, row.get(17)?);
+    let usage = row.get::<_, bool>(11)?.then(|| -> rusqlite::Result<UsageInfo> {
+        Ok(UsageInfo {
+            input_tokens: tokens(12)?,
+            output_tokens: tokens(13)?,
+            cache_creation_input_tokens: tokens(14)?,
+            cache_read_input_tokens: tokens(15)?,
+            cache_creation: (ephemeral_5m_input_tokens, ephemeral_1h_input_tokens)
+                .ne(&(None, None))
+                .then_some(CacheCreation { ephemeral_5m_input_tokens, ephemeral_1h_input_tokens }),
+            service_tier: row.get::<_, Option<String>>(9)?.as_deref().map(intern),
+        })
+    }).transpose()?;
+    let message = row.get::<_, bool>(10)?.then(|| -> rusqlite::Result<MessageInfo> {
+        Ok(MessageInfo {
+            id: row.get(4)?,
+            usage,
+            model: row.get::<_, Option<String>>(8)?.as_deref().map(intern),
+        })
+    }).transpose()?;
+
+    Ok(ParsedEntry {
+        timestamp: DateTime::<Utc>::from_timestamp_millis(row.get(0)?).unwrap_or_default(),
+        message,
+        cost_usd: row.get(18)?,
+        source_file: Some(intern(&row.get::<_, String>(1)?)),
+        is_sidechain: row.get(19)?,
+        raw,
+    })
+}
@@ -0,0 +1,173 @@
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Notify;
+
+use crate::utils::archive::{is_compressed, is_transcript_path, read_compressed};
+use crate::utils::claude::ParsedEntry;
+use crate::utils::data_aggregation::DataAggregator;
+use crate::utils::intern::intern;
+use crate::utils::lines::complete_lines_len;
+use crate::utils::logger::debug_with_context;
+use crate::utils::parse_errors::{record_parse_stats, ParseStats};
+
+/// Entries parsed so far from one transcript, the byte offset parsing stopped at and what
+/// parsing skipped and recovered up to there
+struct PrewarmedFile {
+    offset: u64,
+    entries: Arc<[ParsedEntry]>,
+    parse_stats: ParseStats,
+}
+
+/// Pre-warmed transcript entries, populated by `TranscriptWatcher` and read by `DataAggregator`
+static PREWARMED: OnceLock<DashMap<PathBuf, PrewarmedFile>> = OnceLock::new();
+
+fn prewarmed() -> &'static DashMap<PathBuf, PrewarmedFile> {
+    PREWARMED.get_or_init(DashMap::new)
+}
+
+/// Return the pre-warmed entries for a transcript if they cover the whole file on disk.
+/// They are shared with the watcher rather than copied.
+pub fn prewarmed_entries(path: &Path) -> Option<Arc<[ParsedEntry]>> {
+    PREWARMED.get()?.get(path)?;
+    prewarmed_entries_for(path, std::fs::metadata(path).ok()?.len())
+}
+
+/// `prewarmed_entries` for a transcript already known to be `len` bytes long
+pub fn prewarmed_entries_for(path: &Path, len: u64) -> Option<Arc<[ParsedEntry]>> {
+    let cached = PREWARMED.get()?.get(path)?;
+    (cached.offset == len).then(|| cached.entries.clone())
+}
+
+/// Parse any complete lines appended to `path` since the last call and add them to the pre-warmed cache,
+/// recording the transcript's parse statistics so far. A file that shrank (rewritten or truncated)
+/// is parsed again from the start, as is a gzip-compressed transcript that changed at all.
+pub fn ingest_transcript(path: &Path) -> Result<usize> {
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let len = file.metadata()?.len();
+
+    let cache = prewarmed();
+    let mut state = cache
+        .entry(path.to_path_buf())
+        .or_insert_with(|| PrewarmedFile { offset: 0, entries: Arc::from([]), parse_stats: ParseStats::default() });
+
+    if len < state.offset {
+        state.offset = 0;
+        state.entries = Arc::from([]);
+        state.parse_stats = ParseStats::default();
+    }
+    if len == state.offset {
+        return Ok(0);
+    }
+
+    let source_file = intern(&path.to_string_lossy());
+
+    // A compressed transcript can't be read from an offset, so a changed archive is parsed whole
+    if is_compressed(path) {
+        let (mut entries, stats) = DataAggregator::new().parse_jsonl_buffer(&mut read_compressed(path)?);
+        for entry in &mut entries {
+            entry.source_file = Some(source_file.clone());
+        }
+        let added = entries.len();
+        state.entries = Arc::from(entries);
+        state.parse_stats = stats;
+        record_parse_stats(path, &state.parse_stats);
+
+        state.offset = len;
+        return Ok(added);
+    }
+
+    file.seek(SeekFrom::Start(state.offset))?;
+    let mut appended = Vec::with_capacity((len - state.offset) as usize);
+    file.take(len - state.offset).read_to_end(&mut appended)?;
+
+    // Leave a partially written trailing line for the next event
+    let complete = complete_lines_len(&appended);
+    if complete == 0 {
+        return Ok(0);
+    }
+
+    let (mut entries, stats) = DataAggregator::new().parse_jsonl_buffer(&mut appended[..complete]);
+    for entry in &mut entries {
+        entry.source_file = Some(source_file.clone());
+    }
+    let added = entries.len();
+    // Renders holding the previous entries keep them; appends are rarer than renders
+    state.entries = state.entries.iter().cloned().chain(entries).collect();
+    state.parse_stats.add(&stats);
+    record_parse_stats(path, &state.parse_stats);
+
+    state.offset += complete as u64;
+    Ok(added)
+}
+
+/// Background watcher that keeps transcript entries parsed as Claude writes them,
+/// so renders in watch mode never have to parse a cold file
+pub struct TranscriptWatcher {
+    _watcher: RecommendedWatcher,
+    changed: Arc<Notify>,
+}
+
+impl TranscriptWatcher {
+    /// Pre-warm every transcript under `<claude_path>/projects` and start watching for appends
+    pub fn start(claude_paths: &[PathBuf]) -> Result<Self> {
+        let changed = Arc::new(Notify::new());
+        let notify_changed = changed.clone();
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            // Reads (including our own renders) show up as access events; only writes matter
+            let Ok(event) = event else {
+                return;
+            };
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+
+            let mut added = 0;
+            for path in event.paths.iter().filter(|p| is_transcript_path(p)) {
+                match ingest_transcript(path) {
+                    Ok(count) => added += count,
+                    Err(e) => debug_with_context("watcher", &format!("Failed to ingest {}: {}", path.display(), e)),
+                }
+            }
+
+            if added > 0 {
+                debug_with_context("watcher", &format!("Ingested {} new entries", added));
+                notify_changed.notify_one();
+            }
+        })?;
+
+        for claude_path in claude_paths {
+            let projects_dir = claude_path.join("projects");
+            if !projects_dir.exists() {
+                continue;
+            }
+
+            watcher.watch(&projects_dir, RecursiveMode::Recursive)
+                .with_context(|| format!("Failed to watch {}", projects_dir.display()))?;
+
+            for entry in walkdir::WalkDir::new(&projects_dir).min_depth(2).max_depth(2) {
+                let Ok(entry) = entry else {
+                    continue;
+                };
+                if is_transcript_path(entry.path()) {
+                    let _ = ingest_transcript(entry.path());
+                }
+            }
+        }
+
+        debug_with_context("watcher", &format!("Pre-warmed {} transcript(s)", prewarmed().len()));
+
+        Ok(Self { _watcher: watcher, changed })
+    }
+
+    /// Wait until a watched transcript gains new entries
+    pub async fn changed(&self) {
+        self.changed.notified().await;
+    }
+}
@@ -0,0 +1,178 @@
+use crate::utils::cache_dir::cache_root;
+use crate::utils::logger::debug_with_context;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+/// Where WSL mounts Windows drives (`C:` is `/mnt/c`) unless `automount.root` changes it
+const WSL_MOUNT_ROOT: &str = "/mnt";
+
+/// Windows profile folders under `C:\Users` that never hold a user's Claude data
+const SHARED_PROFILES: [&str; 4] = ["Public", "Default", "Default User", "All Users"];
+
+/// How long the Windows user's profile folder, asked from Windows, is reused
+const USER_PROFILE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Cache file holding the Windows user's profile folder
+const USER_PROFILE_CACHE: &str = "wsl_user_profile";
+
+/// `wslAllProfiles`: scan every Windows profile's Claude data, set once at startup
+static ALL_PROFILES: OnceLock<bool> = OnceLock::new();
+
+/// Scan the Claude data of every Windows profile under `C:\Users` instead of only the current
+/// user's; only the first call has an effect
+pub fn set_wsl_all_profiles(all: bool) {
+    let _ = ALL_PROFILES.set(all);
+}
+
+/// Whether this process runs inside the Windows Subsystem for Linux
+pub fn is_wsl() -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+
+    std::env::var_os("WSL_DISTRO_NAME").is_some()
+        || std::env::var_os("WSL_INTEROP").is_some()
+        || std::fs::read_to_string("/proc/sys/kernel/osrelease")
+            .is_ok_and(|release| release.to_lowercase().contains("microsoft"))
+}
+
+/// `C:\Users\me\.claude` (or `C:/Users/me/.claude`) as its WSL mount path `/mnt/c/Users/me/.claude`
+pub fn windows_to_wsl_path(path: &str) -> Option<PathBuf> {
+    let (drive, rest) = split_drive(path)?;
+    let rest = rest.trim_start_matches(['\\', '/']).replace('\\', "/");
+
+    let mut wsl = format!("{}/{}", WSL_MOUNT_ROOT, drive.to_ascii_lowercase());
+    if !rest.is_empty() {
+        wsl.push('/');
+        wsl.push_str(&rest);
+    }
+    Some(PathBuf::from(wsl))
+}
+
+/// `/mnt/c/Users/me/.claude` as the Windows path `C:\Users\me\.claude`
+pub fn wsl_to_windows_path(path: &str) -> Option<PathBuf> {
+    let rest = path.strip_prefix(WSL_MOUNT_ROOT)?.strip_prefix('/')?;
+    let (drive, rest) = rest.split_once('/').unwrap_or((rest, ""));
+    let mut chars = drive.chars();
+    let letter = chars.next().filter(|c| c.is_ascii_alphabetic() && chars.next().is_none())?;
+
+    Some(PathBuf::from(format!("{}:\\{}", letter.to_ascii_uppercase(), rest.replace('/', "\\"))))
+}
+
+/// Translate a path written for the other side of a WSL setup into one this process can open:
+/// drive paths become `/mnt/<drive>/...` on Linux and `/mnt/<drive>/...` becomes `X:\...` on
+/// Windows. Anything else (including `\\wsl$\...` shares) is returned unchanged.
+pub fn bridge_path(path: &Path) -> PathBuf {
+    let Some(text) = path.to_str() else {
+        return path.to_path_buf();
+    };
+
+    let bridged = if cfg!(windows) {
+        wsl_to_windows_path(text)
+    } else {
+        windows_to_wsl_path(text)
+    };
+    bridged.unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Claude directories of the Windows user, as seen from WSL: `%USERPROFILE%\.claude` and
+/// `%APPDATA%\Claude`. With `wslAllProfiles` every profile under `C:\Users` is scanned instead.
+pub fn windows_claude_dirs() -> Vec<PathBuf> {
+    let profiles = if ALL_PROFILES.get().copied().unwrap_or(false) {
+        all_windows_profiles()
+    } else {
+        windows_user_profile().into_iter().collect()
+    };
+
+    let mut dirs: Vec<PathBuf> = profiles
+        .iter()
+        .flat_map(|profile| {
+            [
+                profile.join(".claude"),
+                profile.join("AppData").join("Roaming").join("Claude"),
+            ]
+        })
+        .filter(|dir| dir.join("projects").is_dir())
+        .collect();
+
+    dirs.sort();
+    dirs
+}
+
+fn all_windows_profiles() -> Vec<PathBuf> {
+    let users = Path::new(WSL_MOUNT_ROOT).join("c").join("Users");
+    let Ok(profiles) = std::fs::read_dir(&users) else {
+        return Vec::new();
+    };
+
+    profiles
+        .flatten()
+        .filter(|profile| {
+            let name = profile.file_name();
+            !SHARED_PROFILES.iter().any(|shared| name == *shared)
+        })
+        .map(|profile| profile.path())
+        .collect()
+}
+
+/// The current Windows user's profile folder as a WSL path. Windows is asked (`wslvar`, else
+/// `cmd.exe`) at most once per `USER_PROFILE_TTL`; the answer is kept in the cache directory.
+pub fn windows_user_profile() -> Option<PathBuf> {
+    let cache = cache_root().map(|root| root.join(USER_PROFILE_CACHE));
+    let fresh = |path: &Path| {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| SystemTime::now().duration_since(modified).is_ok_and(|age| age < USER_PROFILE_TTL))
+    };
+    if let Some(cached) = cache.as_deref().filter(|path| fresh(path)).and_then(|path| std::fs::read_to_string(path).ok()) {
+        return user_profile_from_output(&cached);
+    }
+
+    let output = [("wslvar", &["USERPROFILE"][..]), ("cmd.exe", &["/c", "echo", "%USERPROFILE%"][..])]
+        .iter()
+        .find_map(|(program, args)| {
+            // cmd.exe complains about a UNC working directory, so run it from the C: drive
+            let output = Command::new(program)
+                .args(*args)
+                .current_dir(Path::new(WSL_MOUNT_ROOT).join("c"))
+                .stdin(Stdio::null())
+                .stderr(Stdio::null())
+                .output()
+                .ok()?;
+            let text = String::from_utf8_lossy(&output.stdout).into_owned();
+            (output.status.success() && user_profile_from_output(&text).is_some()).then_some(text)
+        });
+    let Some(output) = output else {
+        debug_with_context("wsl", "Couldn't ask Windows for the user's profile folder");
+        return None;
+    };
+
+    if let Some(cache) = &cache {
+        if let Some(parent) = cache.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = std::fs::write(cache, output.trim());
+    }
+    user_profile_from_output(&output)
+}
+
+/// `C:\Users\me` as printed by `wslvar USERPROFILE` or `cmd.exe /c echo %USERPROFILE%`, as the
+/// WSL path `/mnt/c/Users/me`; `None` when the variable wasn't expanded
+pub fn user_profile_from_output(output: &str) -> Option<PathBuf> {
+    let profile = output.trim();
+    if profile.contains('%') {
+        return None;
+    }
+    windows_to_wsl_path(profile)
+}
+
+/// Split `C:\rest` or `C:/rest` into its drive letter and the rest
+fn split_drive(path: &str) -> Option<(char, &str)> {
+    let mut chars = path.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    let rest = chars.as_str().strip_prefix(':')?;
+
+    (rest.is_empty() || rest.starts_with(['\\', '/'])).then_some((drive, rest))
+}
@@ -0,0 +1,169 @@
+use claude_powerline_rust::alerts::*;
+use claude_powerline_rust::config::{BudgetConfig, WebhookConfig};
+use claude_powerline_rust::segments::{BlockInfo, TodayInfo};
+use chrono::{Duration, Utc};
+
+fn budgets() -> BudgetConfig {
+    serde_json::from_value(serde_json::json!({
+        "today": { "amount": 10.0, "warningThreshold": 75 },
+        "block": { "amount": 100000, "type": "tokens" }
+    }))
+    .unwrap()
+}
+
+fn usage(today_cost: f64, block_tokens: u64) -> UsageSnapshot {
+    UsageSnapshot {
+        today: TodayInfo { cost: Some(today_cost), ..Default::default() },
+        block: BlockInfo { tokens: Some(block_tokens), ..Default::default() },
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_budget_alerts_fire_once_per_threshold_crossing() {
+    let mut state = AlertState::default();
+    let budgets = budgets();
+    let now = Utc::now();
+
+    assert!(state.detect(Some(&budgets), &usage(5.0, 1000), now).is_empty());
+
+    let events = state.detect(Some(&budgets), &usage(8.0, 1000), now);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, AlertKind::BudgetWarning);
+    assert_eq!(events[0].scope, "today");
+    assert_eq!(events[0].message, "Today usage is nearing its budget: $8.00 of $10.00 (80%)");
+
+    // Staying past the threshold doesn't repeat the alert
+    assert!(state.detect(Some(&budgets), &usage(8.5, 1000), now).is_empty());
+
+    let events = state.detect(Some(&budgets), &usage(8.5, 120000), now);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, AlertKind::BudgetExceeded);
+    assert_eq!(events[0].message, "Block usage exceeded its budget: 120000 tokens of 100000 tokens (120%)");
+
+    // A new day drops back below the threshold silently, and crossing it again alerts again
+    assert!(state.detect(Some(&budgets), &usage(0.5, 120000), now).is_empty());
+    assert_eq!(state.detect(Some(&budgets), &usage(9.0, 120000), now).len(), 1);
+}
+
+#[test]
+fn test_block_reset_fires_when_the_block_ends() {
+    let mut state = AlertState::default();
+    let now = Utc::now();
+    let reset = now + Duration::hours(1);
+
+    let mut snapshot = UsageSnapshot::default();
+    snapshot.block.reset_time = Some(reset);
+    assert!(state.detect(None, &snapshot, now).is_empty());
+    assert!(state.detect(None, &snapshot, now + Duration::minutes(30)).is_empty());
+
+    snapshot.block.reset_time = None;
+    let events = state.detect(None, &snapshot, reset + Duration::minutes(1));
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].kind, AlertKind::BlockReset);
+
+    assert!(state.detect(None, &snapshot, reset + Duration::minutes(2)).is_empty());
+}
+
+#[test]
+fn test_debounce_drops_repeats_within_the_window() {
+    let mut state = AlertState::default();
+    let budgets = budgets();
+    let now = Utc::now();
+
+    let first = state.detect(Some(&budgets), &usage(8.0, 0), now);
+    assert_eq!(state.debounce(first, Duration::hours(1)).len(), 1);
+
+    // Flapping around the threshold within the window is swallowed
+    state.detect(Some(&budgets), &usage(7.0, 0), now);
+    let again = state.detect(Some(&budgets), &usage(8.0, 0), now + Duration::minutes(10));
+    assert_eq!(again.len(), 1);
+    assert!(state.debounce(again, Duration::hours(1)).is_empty());
+
+    state.detect(Some(&budgets), &usage(7.0, 0), now);
+    let later = state.detect(Some(&budgets), &usage(8.0, 0), now + Duration::hours(2));
+    assert_eq!(state.debounce(later, Duration::hours(1)).len(), 1);
+}
+
+#[test]
+fn test_webhook_bodies() {
+    let mut state = AlertState::default();
+    let event = state.detect(Some(&budgets()), &usage(12.0, 0), Utc::now()).remove(0);
+
+    let json = WebhookConfig { url: "https://example.com/hook".to_string(), format: None };
+    let body: serde_json::Value = serde_json::from_str(&webhook_body(&json, &event).unwrap()).unwrap();
+    assert_eq!(body["event"], "budget_exceeded");
+    assert_eq!(body["scope"], "today");
+    assert_eq!(body["limit"], 10.0);
+
+    let slack = WebhookConfig { format: Some("slack".to_string()), ..json };
+    let body: serde_json::Value = serde_json::from_str(&webhook_body(&slack, &event).unwrap()).unwrap();
+    assert_eq!(body["text"], ":warning: Today usage exceeded its budget: $12.00 of $10.00 (120%)");
+}
+
+#[test]
+fn test_context_alert_fires_when_context_runs_low() {
+    let mut state = AlertState::default();
+    let now = Utc::now();
+    let mut snapshot = UsageSnapshot::default();
+
+    assert!(state.detect_context(10, &snapshot, now).is_none());
+
+    snapshot.context = Some(claude_powerline_rust::segments::ContextInfo { context_left_percentage: 40, ..Default::default() });
+    assert!(state.detect_context(10, &snapshot, now).is_none());
+
+    snapshot.context.as_mut().unwrap().context_left_percentage = 8;
+    let event = state.detect_context(10, &snapshot, now).unwrap();
+    assert_eq!(event.kind, AlertKind::ContextCritical);
+    assert_eq!(event.message, "Context window is nearly full: 8% left");
+    assert!(state.detect_context(10, &snapshot, now).is_none());
+}
+
+#[test]
+fn test_desktop_notifications_only_for_urgent_events() {
+    let mut state = AlertState::default();
+    let budgets = budgets();
+    let now = Utc::now();
+
+    let warning = state.detect(Some(&budgets), &usage(8.0, 0), now).remove(0);
+    let exceeded = state.detect(Some(&budgets), &usage(11.0, 0), now).remove(0);
+    assert!(!wants_desktop_notification(&warning));
+    assert!(wants_desktop_notification(&exceeded));
+}
+
+#[cfg(not(feature = "desktop-notifications"))]
+#[tokio::test]
+async fn test_desktop_notification_is_skipped_without_the_feature() {
+    let mut state = AlertState::default();
+    let exceeded = state.detect(Some(&budgets()), &usage(11.0, 0), Utc::now()).remove(0);
+    assert!(show_desktop_notification(&exceeded).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_usage_snapshot_follows_the_configured_cost_source() {
+    use claude_powerline_rust::segments::SegmentContext;
+    use claude_powerline_rust::utils::HookCostLedger;
+    use std::sync::Arc;
+
+    // Hidden, but still read from the hook as configured
+    let config: claude_powerline_rust::config::Config = serde_json::from_value(serde_json::json!({
+        "segments": {
+            "today": { "enabled": false, "costSource": "hook" },
+            "block": { "enabled": true, "costSource": "hook" }
+        }
+    }))
+    .unwrap();
+    let now = Utc::now();
+    let mut ledger = HookCostLedger::load(None);
+    ledger.record("a", 1.0, now - Duration::seconds(2));
+    ledger.record("a", 3.5, now - Duration::seconds(1));
+
+    let theme = Arc::new(claude_powerline_rust::themes::get_theme("dark"));
+    let ctx = SegmentContext::new(Arc::new(config), theme).with_hook(None, Some(ledger));
+    let snapshot = UsageSnapshot::collect(&ctx, false).await.unwrap();
+
+    assert_eq!(snapshot.today.cost, Some(2.5));
+    assert_eq!(snapshot.block.cost, Some(2.5));
+    // Hook data carries no token counts
+    assert_eq!(snapshot.block.tokens, None);
+}
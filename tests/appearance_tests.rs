@@ -0,0 +1,18 @@
+use claude_powerline_rust::utils::terminal::Background;
+use claude_powerline_rust::utils::{set_cache_root, system_appearance};
+use tempfile::TempDir;
+
+#[test]
+fn test_system_appearance_is_served_from_the_cache_while_fresh() {
+    let cache_dir = TempDir::new().unwrap();
+    set_cache_root(cache_dir.path().to_path_buf());
+
+    std::fs::write(cache_dir.path().join("system_appearance"), "light").unwrap();
+    assert_eq!(system_appearance(), Some(Background::Light));
+
+    std::fs::write(cache_dir.path().join("system_appearance"), "dark").unwrap();
+    assert_eq!(system_appearance(), Some(Background::Dark));
+
+    std::fs::write(cache_dir.path().join("system_appearance"), "unknown").unwrap();
+    assert_eq!(system_appearance(), None);
+}
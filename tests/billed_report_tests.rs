@@ -0,0 +1,54 @@
+use claude_powerline_rust::config::TeamConfig;
+use claude_powerline_rust::reports::*;
+use claude_powerline_rust::segments::{set_cost_format, CostFormat};
+use claude_powerline_rust::test_utils::{ClaudeDirFixture, Transcript, TranscriptEntry};
+use chrono::{Duration, Utc};
+
+// The cost format is process-wide and set once, so this lives in its own test binary
+#[tokio::test]
+async fn test_report_rows_add_up_to_the_billed_total() {
+    set_cost_format(CostFormat { multiplier: 1.21, ..Default::default() });
+
+    let claude = ClaudeDirFixture::new().unwrap();
+    let now = Utc::now();
+    let yesterday = (now.date_naive() - Duration::days(1)).and_hms_opt(10, 0, 0).unwrap().and_utc();
+    claude.write_transcript("-work-a", &Transcript::new("session-1")
+        .entry(TranscriptEntry::assistant(yesterday).model("claude-3-5-sonnet").field("cwd", "/work/a").usage(1000, 500))).unwrap();
+    claude.write_transcript("-work-b", &Transcript::new("session-2")
+        .entry(TranscriptEntry::assistant(now).model("claude-3-opus").field("cwd", "/work/b").usage(2000, 1000))).unwrap();
+    std::env::set_var("CLAUDE_CONFIG_DIR", claude.path());
+    std::env::set_var("CLAUDE_POWERLINE_ENTRY_CACHE", "0");
+
+    let sum = |rows: &serde_json::Value| -> f64 {
+        rows.as_array().unwrap().iter().map(|row| row["billed_cost_usd"].as_f64().unwrap()).sum()
+    };
+    let csv_sum = |csv: &str, column: usize| -> f64 {
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap().split(',').nth(column), Some("billed_cost_usd"));
+        lines.map(|line| line.rsplit(',').nth(2).unwrap().parse::<f64>().unwrap()).sum()
+    };
+
+    let report = UsageReport::load(2).await.unwrap();
+    let billed = report.total_cost() * 1.21;
+    let json: serde_json::Value = serde_json::from_str(&report.render_json().unwrap()).unwrap();
+    assert!((json["total"]["billed_cost_usd"].as_f64().unwrap() - billed).abs() < 1e-9);
+    assert_eq!(json["cost_multiplier"].as_f64(), Some(1.21));
+    assert!((sum(&json["days"]) - billed).abs() < 1e-9);
+    assert!((sum(&json["models"]) - billed).abs() < 1e-9);
+    assert!((json["days"][0]["billed_cost_usd"].as_f64().unwrap() - json["days"][0]["cost"].as_f64().unwrap() * 1.21).abs() < 1e-9);
+    assert!((csv_sum(&report.render_csv(), 2) - billed).abs() < 1e-3);
+
+    let projects = ProjectUsageReport::load(2).await.unwrap();
+    let json: serde_json::Value = serde_json::from_str(&projects.render_json().unwrap()).unwrap();
+    assert!((sum(&json["projects"]) - json["total"]["billed_cost_usd"].as_f64().unwrap()).abs() < 1e-9);
+    assert!((csv_sum(&projects.render_csv(), 2) - billed).abs() < 1e-3);
+
+    let team = TeamConfig { self_name: Some("dana".to_string()), members: None };
+    let team = TeamUsageReport::load(&team, 2).await.unwrap();
+    let json: serde_json::Value = serde_json::from_str(&team.render_json().unwrap()).unwrap();
+    assert!((sum(&json["people"][0]["days"]) - json["total"]["billed_cost_usd"].as_f64().unwrap()).abs() < 1e-9);
+    assert!((csv_sum(&team.render_csv(), 3) - billed).abs() < 1e-3);
+
+    std::env::remove_var("CLAUDE_CONFIG_DIR");
+    std::env::remove_var("CLAUDE_POWERLINE_ENTRY_CACHE");
+}
@@ -0,0 +1,158 @@
+use claude_powerline_rust::utils::*;
+use std::io::Write;
+use tempfile::TempDir;
+
+#[test]
+fn test_entry_cache_round_trip_and_invalidation() {
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = temp_dir.path().join("cache");
+    let transcript = temp_dir.path().join("session.jsonl");
+    std::fs::write(&transcript, r#"{"timestamp":"2024-01-01T10:00:00.123Z","sessionId":"s-1","cwd":"/work/app","message":{"id":"msg-1","usage":{"input_tokens":100,"output_tokens":50,"cache_read_input_tokens":7},"model":"claude-3-5-sonnet"},"costUSD":0.01,"requestId":"req-1","isSidechain":false}
+"#).unwrap();
+
+    let entries = parse_jsonl_content(&std::fs::read_to_string(&transcript).unwrap()).unwrap();
+    assert!(load_cached_entries(&cache_dir, &transcript).is_none());
+    store_cached_entries(&cache_dir, &transcript, &entries, &ParseStats::default()).unwrap();
+
+    let cached = load_cached_entries(&cache_dir, &transcript).unwrap();
+    assert_eq!(cached.len(), 1);
+    let entry = &cached[0];
+    assert_eq!(entry.timestamp, entries[0].timestamp);
+    assert_eq!(entry.cost_usd, Some(0.01));
+    assert_eq!(entry.is_sidechain, Some(false));
+    assert_eq!(entry.source_file.as_deref(), Some(transcript.to_string_lossy().as_ref()));
+    assert_eq!(entry.message.as_ref().unwrap().usage.as_ref().unwrap().cache_read_input_tokens, Some(7));
+    assert_eq!(entry.raw.get("requestId").and_then(|v| v.as_str()), Some("req-1"));
+    assert_eq!(entry.raw.get("cwd").and_then(|v| v.as_str()), Some("/work/app"));
+    assert_eq!(create_unique_hash(entry), create_unique_hash(&entries[0]));
+
+    // Appending to the transcript changes its size, so the sidecar no longer applies
+    let mut file = std::fs::OpenOptions::new().append(true).open(&transcript).unwrap();
+    writeln!(file, "{{}}").unwrap();
+    assert!(load_cached_entries(&cache_dir, &transcript).is_none());
+}
+
+/// Source serving fixed entries, so the metrics segment can read a cold parse or a warm sidecar
+struct FixedSource(Vec<ParsedEntry>);
+
+impl claude_powerline_rust::sources::UsageSource for FixedSource {
+    fn name(&self) -> &'static str {
+        "fixed"
+    }
+
+    fn entries_since(&self, since: chrono::DateTime<chrono::Utc>) -> futures::future::BoxFuture<'_, anyhow::Result<Vec<ParsedEntry>>> {
+        Box::pin(async move { Ok(self.0.iter().filter(|entry| entry.timestamp >= since).cloned().collect()) })
+    }
+
+    fn session_entries<'a>(&'a self, _: &'a str) -> futures::future::BoxFuture<'a, anyhow::Result<Option<Vec<ParsedEntry>>>> {
+        Box::pin(async { Ok(None) })
+    }
+}
+
+#[tokio::test]
+async fn test_warm_sidecar_renders_the_same_metrics_as_a_cold_parse() {
+    use claude_powerline_rust::segments::MetricsSegment;
+
+    let temp_dir = TempDir::new().unwrap();
+    let cache_dir = temp_dir.path().join("cache");
+    let transcript = temp_dir.path().join("session.jsonl");
+    let now = chrono::Utc::now();
+    let ts = |minutes: i64| (now - chrono::Duration::minutes(minutes)).format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+    std::fs::write(&transcript, format!(
+        "{{\"timestamp\":\"{}\",\"message\":{{\"id\":\"msg-1\",\"usage\":{{\"input_tokens\":100,\"output_tokens\":50}}}},\"requestId\":\"req-1\",\"duration_ms\":1200,\"cost\":{{\"total_lines_added\":12,\"total_lines_removed\":3}}}}\n\
+         {{\"timestamp\":\"{}\",\"type\":\"user\",\"content\":[\"no ids\"],\"cost\":{{\"total_lines_added\":5,\"total_lines_removed\":1}}}}\n",
+        ts(20),
+        ts(5),
+    )).unwrap();
+
+    let cold = parse_jsonl_content(&std::fs::read_to_string(&transcript).unwrap()).unwrap();
+    store_cached_entries(&cache_dir, &transcript, &cold, &ParseStats::default()).unwrap();
+    let warm = load_cached_entries(&cache_dir, &transcript).unwrap();
+
+    // Nested fields survive the sidecar, so deduplication keys agree too
+    for (cold, warm) in cold.iter().zip(&warm) {
+        assert_eq!(warm.raw, cold.raw);
+        assert_eq!(dedupe_key(warm), dedupe_key(cold));
+    }
+
+    let segment = MetricsSegment::new();
+    let cold = segment.get_metrics_info_from(&SharedEntries::with_source(std::sync::Arc::new(FixedSource(cold)))).await.unwrap();
+    let warm = segment.get_metrics_info_from(&SharedEntries::with_source(std::sync::Arc::new(FixedSource(warm)))).await.unwrap();
+
+    assert_eq!(cold.lines_added, Some(17));
+    assert_eq!(cold.lines_removed, Some(4));
+    assert_eq!(format!("{:?}", warm), format!("{:?}", cold));
+}
+
+#[test]
+fn test_cache_eviction_removes_least_recently_used_first() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    std::fs::create_dir_all(root.join("entries")).unwrap();
+
+    let now = std::time::SystemTime::now();
+    for (name, age_secs) in [("entries/old.bin", 300), ("entries/newer.bin", 200), ("segments.json", 100)] {
+        let path = root.join(name);
+        std::fs::write(&path, vec![0u8; 1000]).unwrap();
+        let time = now - std::time::Duration::from_secs(age_secs);
+        let times = std::fs::FileTimes::new().set_accessed(time).set_modified(time);
+        std::fs::File::options().write(true).open(&path).unwrap().set_times(times).unwrap();
+    }
+    assert_eq!(cache_size(root), 3000);
+
+    // Already within the cap: nothing to do
+    assert_eq!(evict_to_size(root, 5000).unwrap(), 0);
+
+    assert_eq!(evict_to_size(root, 2000).unwrap(), 1000);
+    assert!(!root.join("entries/old.bin").exists());
+    assert!(root.join("entries/newer.bin").exists());
+    assert!(root.join("segments.json").exists());
+
+    assert_eq!(clear_cache(root).unwrap(), 2000);
+    assert!(!root.exists());
+    assert_eq!(clear_cache(root).unwrap(), 0);
+}
+
+#[test]
+fn test_cache_clear_leaves_other_files_alone() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path().join("shared");
+    std::fs::create_dir_all(root.join("entries")).unwrap();
+    std::fs::create_dir_all(root.join("other-tool")).unwrap();
+    for (name, size) in [
+        ("entries/a.bin", 100),
+        ("session_index.bin", 10),
+        ("session_index.tmp42", 5),
+        ("usage-index.sqlite-wal", 20),
+        ("other-tool/data.bin", 1000),
+        ("notes.txt", 1000),
+        ("segments-backup.json", 1000),
+    ] {
+        std::fs::write(root.join(name), vec![0u8; size]).unwrap();
+    }
+
+    // Only this tool's files count towards the cache and get evicted
+    assert_eq!(cache_size(&root), 115);
+    evict_to_size(&root, 0).unwrap();
+    assert!(root.join("notes.txt").exists());
+
+    clear_cache(&root).unwrap();
+    assert!(!root.join("entries").exists());
+    assert!(!root.join("session_index.bin").exists());
+    assert!(!root.join("usage-index.sqlite-wal").exists());
+    assert!(root.join("other-tool/data.bin").exists());
+    assert!(root.join("notes.txt").exists());
+    assert!(root.join("segments-backup.json").exists());
+}
+
+#[test]
+fn test_ttl_cache_expires_entries() {
+    let cache: Cache<&str, u32> = Cache::new(std::time::Duration::from_secs(60));
+    cache.insert("kept", 1);
+    cache.insert_with_ttl("expired", 2, std::time::Duration::ZERO);
+    std::thread::sleep(std::time::Duration::from_millis(5));
+
+    assert_eq!(cache.get(&"kept"), Some(1));
+    assert_eq!(cache.get(&"expired"), None);
+    assert_eq!(cache.len(), 1);
+}
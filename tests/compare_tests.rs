@@ -0,0 +1,94 @@
+use claude_powerline_rust::compare::{split_original_segments, CompareReport, OriginalCommand, SegmentMatch};
+use claude_powerline_rust::config::Config;
+
+#[test]
+fn test_split_original_segments() {
+    let output = "\x1b[48;2;1;2;3m \u{f07c} ~/app \x1b[38;2;1;2;3m\u{e0b0}\x1b[0m\x1b[48;2;4;5;6m  main  ✓ \x1b[0m\u{e0b0}\n\x1b[1m $1.23  (45K) \u{e0b0}\n";
+    assert_eq!(split_original_segments(output), vec!["\u{f07c} ~/app", "main ✓", "$1.23 (45K)"]);
+    assert!(split_original_segments("\n\x1b[0m\n").is_empty());
+}
+
+#[test]
+fn test_compare_report_pairs_segments_by_position() {
+    let ours = vec![
+        ("directory".to_string(), "\x1b[1m ~/app \x1b[0m".to_string()),
+        ("git".to_string(), String::new()),
+        ("session".to_string(), " $1.23  (45K) ".to_string()),
+        ("today".to_string(), " $4.00 ".to_string()),
+    ];
+    let theirs = vec!["~/app".to_string(), "$1.25 (45K)".to_string()];
+    let report = CompareReport::new("claude-powerline".to_string(), ours, theirs);
+
+    let statuses: Vec<_> = report.segments.iter().map(|segment| segment.status()).collect();
+    assert_eq!(statuses, vec![SegmentMatch::Same, SegmentMatch::Differs, SegmentMatch::OnlyOurs]);
+    assert_eq!(report.segments[1].name.as_deref(), Some("session"));
+    assert_eq!(report.differences(), 2);
+
+    let rendered = report.render(false);
+    assert!(rendered.contains("✓ directory  ~/app"), "{}", rendered);
+    assert!(rendered.contains("≠ session    $1.23 (45K)"), "{}", rendered);
+    assert!(rendered.contains("  original   $1.25 (45K)"), "{}", rendered);
+    assert!(rendered.contains("+ today      $4.00  (not in the original)"), "{}", rendered);
+    assert!(rendered.ends_with("2 of 3 segments differ"), "{}", rendered);
+
+    // Only the words that differ are highlighted
+    let colored = report.render(true);
+    assert!(colored.contains("\x1b[1;31m$1.23\x1b[0m (45K)"), "{}", colored);
+
+    let only_original = CompareReport::new(String::new(), Vec::new(), vec!["☉ 12".to_string()]);
+    assert_eq!(only_original.segments[0].status(), SegmentMatch::OnlyOriginal);
+    assert!(only_original.render(false).contains("- (original)  ☉ 12  (only in the original)"));
+}
+
+#[test]
+fn test_original_command_parse() {
+    let command = OriginalCommand::parse("npx -y @owloops/claude-powerline").unwrap();
+    assert_eq!(command.program, std::path::PathBuf::from("npx"));
+    assert_eq!(command.args, vec!["-y", "@owloops/claude-powerline"]);
+    assert_eq!(command.display(), "npx -y @owloops/claude-powerline");
+    assert!(OriginalCommand::parse("   ").is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_find_original_skips_this_binary() {
+    use claude_powerline_rust::compare::find_original_on_path;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let cargo_bin = temp_dir.path().join("cargo-bin");
+    let npm_bin = temp_dir.path().join("npm-bin");
+    let package = temp_dir.path().join("lib/node_modules/@owloops/claude-powerline/dist");
+    for dir in [&cargo_bin, &npm_bin, &package] {
+        std::fs::create_dir_all(dir).unwrap();
+    }
+
+    // A Rust build under the shared name, then npm's symlink to the Node script
+    std::fs::write(cargo_bin.join("claude-powerline"), b"\x7fELF").unwrap();
+    std::fs::write(package.join("index.js"), "#!/usr/bin/env node\n").unwrap();
+    std::fs::set_permissions(package.join("index.js"), std::fs::Permissions::from_mode(0o755)).unwrap();
+    symlink(package.join("index.js"), npm_bin.join("claude-powerline")).unwrap();
+
+    let path_var = std::env::join_paths([&cargo_bin, &npm_bin]).unwrap();
+    assert_eq!(find_original_on_path(&path_var), Some(npm_bin.join("claude-powerline")));
+    assert_eq!(find_original_on_path(&std::env::join_paths([&cargo_bin]).unwrap()), None);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_original_runs_with_config_and_hook_input() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let script = temp_dir.path().join("original.sh");
+    // Prints its style flag, whether its config file carries the theme, then the hook JSON
+    std::fs::write(
+        &script,
+        "printf '%s\\n' \"$1\"\nconfig=\"${2#--config=}\"\ngrep -q '\"theme\"' \"$config\" && echo config-ok\ncat\n",
+    ).unwrap();
+
+    let original = OriginalCommand::parse(&format!("sh {}", script.display())).unwrap();
+    let output = original.render(&Config::default(), Some("{\"session_id\":\"abc\"}")).await.unwrap();
+    assert_eq!(output, "--style=powerline\nconfig-ok\n{\"session_id\":\"abc\"}");
+
+    let failing = OriginalCommand::parse("false").unwrap();
+    assert!(failing.render(&Config::default(), None).await.is_err());
+}
@@ -0,0 +1,84 @@
+use claude_powerline_rust::doctor::*;
+use tempfile::TempDir;
+use tokio::fs;
+
+#[tokio::test]
+async fn test_doctor_counts_transcripts() {
+    let temp_dir = TempDir::new().unwrap();
+    let claude_paths = vec![temp_dir.path().to_path_buf()];
+
+    let check = check_transcripts(&claude_paths).await;
+    assert_eq!(check.status, CheckStatus::Warn);
+    assert!(check.message.contains("0 project(s)"));
+
+    let project_dir = temp_dir.path().join("projects").join("-home-me-app");
+    fs::create_dir_all(&project_dir).await.unwrap();
+    let check = check_transcripts(&claude_paths).await;
+    assert_eq!(check.status, CheckStatus::Warn);
+    assert!(check.hint.is_some());
+
+    fs::write(project_dir.join("a.jsonl"), "").await.unwrap();
+    fs::write(project_dir.join("b.jsonl"), "").await.unwrap();
+    fs::write(project_dir.join("notes.txt"), "").await.unwrap();
+    let check = check_transcripts(&claude_paths).await;
+    assert_eq!(check.status, CheckStatus::Ok);
+    assert!(check.message.starts_with("2 transcript file(s) across 1 project(s)"));
+}
+
+#[tokio::test]
+async fn test_doctor_status_line_settings() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = temp_dir.path().join("settings.json");
+    let files = vec![settings.clone()];
+
+    let check = check_status_line(&files).await;
+    assert_eq!(check.status, CheckStatus::Fail);
+    assert!(check.hint.as_ref().unwrap().contains("statusLine"));
+
+    fs::write(&settings, r#"{"statusLine": {"type": "command", "command": "other-tool"}}"#).await.unwrap();
+    assert_eq!(check_status_line(&files).await.status, CheckStatus::Warn);
+
+    fs::write(&settings, r#"{"statusLine": {"type": "command", "command": "claude-powerline --theme nord"}}"#).await.unwrap();
+    assert_eq!(check_status_line(&files).await.status, CheckStatus::Ok);
+
+    fs::write(&settings, "{ not json").await.unwrap();
+    let check = check_status_line(&files).await;
+    assert_eq!(check.status, CheckStatus::Fail);
+    assert!(check.message.contains("not valid JSON"));
+
+    let report = DoctorReport { checks: vec![check] };
+    assert!(report.has_failures());
+    assert!(report.render(false).contains("✗ statusLine hook"));
+}
+
+#[tokio::test]
+async fn test_doctor_checks_the_config_and_profile_it_was_given() {
+    let temp_dir = TempDir::new().unwrap();
+    let broken = temp_dir.path().join("broken.json");
+    fs::write(&broken, "{ not json").await.unwrap();
+    let valid = temp_dir.path().join("valid.json");
+    fs::write(&valid, "{}").await.unwrap();
+
+    // `doctor --config` reports on that file, not the one CLAUDE_POWERLINE_CONFIG names
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_claude-powerline"))
+        .args(["doctor", "--config", broken.to_str().unwrap()])
+        .env("CLAUDE_POWERLINE_CONFIG", &valid)
+        .env_remove("CLAUDE_POWERLINE_PROFILE")
+        .env("NO_COLOR", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!output.status.success());
+    assert!(stdout.contains("✗ Config file"), "{}", stdout);
+    assert!(stdout.contains(broken.to_str().unwrap()), "{}", stdout);
+
+    let config = temp_dir.path().join("config.json");
+    fs::write(&config, r#"{"profiles": {"work": {"theme": "nord"}}}"#).await.unwrap();
+    let check = check_config_file(Some(config.clone()), Some("work")).await;
+    assert_eq!(check.status, CheckStatus::Ok);
+    assert!(check.message.ends_with("(profile work)"));
+
+    let check = check_config_file(Some(config), Some("home")).await;
+    assert_eq!(check.status, CheckStatus::Fail);
+    assert!(check.message.contains("Unknown profile `home`"));
+}
@@ -0,0 +1,48 @@
+use claude_powerline_rust::utils::{with_file_lock, write_atomic};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+#[test]
+fn test_file_lock_serializes_load_modify_save() {
+    let dir = TempDir::new().unwrap();
+    let path = Arc::new(dir.path().join("state").join("counter"));
+
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let path = Arc::clone(&path);
+            std::thread::spawn(move || {
+                for _ in 0..25 {
+                    with_file_lock(&path, || {
+                        let count: u32 = std::fs::read_to_string(&*path).ok().and_then(|s| s.parse().ok()).unwrap_or(0);
+                        std::fs::write(&*path, (count + 1).to_string()).unwrap();
+                    });
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    assert_eq!(std::fs::read_to_string(&*path).unwrap(), "200");
+    assert!(dir.path().join("state").join("counter.lock").exists());
+}
+
+#[test]
+fn test_write_atomic_replaces_the_file_without_leftovers() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("state").join("alerts.json");
+
+    write_atomic(&path, b"first").unwrap();
+    write_atomic(&path, b"second").unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), b"second");
+
+    let names: Vec<_> = std::fs::read_dir(dir.path().join("state")).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+    assert_eq!(names, ["alerts.json"]);
+
+    // A failed rename (here onto a directory) leaves no temporary file behind
+    let blocked = dir.path().join("blocked");
+    std::fs::create_dir_all(blocked.join("inner")).unwrap();
+    assert!(write_atomic(&blocked, b"data").is_err());
+    assert!(!dir.path().join(format!("blocked.tmp{}", std::process::id())).exists());
+}
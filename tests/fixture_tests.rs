@@ -0,0 +1,46 @@
+use chrono::{Duration, Utc};
+use claude_powerline_rust::test_utils::{ClaudeDirFixture, Transcript, TranscriptEntry};
+use claude_powerline_rust::utils::{parse_jsonl_content, DataAggregator};
+
+#[tokio::test]
+async fn test_fixture_transcripts_parse_like_claude_code() {
+    let claude = ClaudeDirFixture::new().unwrap();
+    let now = Utc::now();
+    let transcript = Transcript::new("fixture-session")
+        .entry(TranscriptEntry::user(now - Duration::minutes(3)))
+        .entry(TranscriptEntry::assistant(now - Duration::minutes(2)).usage(1000, 500).cache(200, 3000).cost(0.05))
+        .entry(TranscriptEntry::assistant(now - Duration::minutes(1)).model("claude-3-opus").usage(10, 5).sidechain(true).field("cwd", "/work/app"));
+    let path = claude.write_transcript("my-project", &transcript).unwrap();
+    assert_eq!(path, claude.path().join("projects").join("my-project").join("fixture-session.jsonl"));
+
+    let entries = DataAggregator::new().load_session_entries(&path).await.unwrap();
+    assert_eq!(entries.len(), 3);
+    assert!(entries.iter().all(|entry| entry.raw["sessionId"] == "fixture-session"));
+
+    let usage = entries[1].message.as_ref().unwrap().usage.as_ref().unwrap();
+    assert_eq!((usage.input_tokens, usage.output_tokens), (Some(1000), Some(500)));
+    assert_eq!((usage.cache_creation_input_tokens, usage.cache_read_input_tokens), (Some(200), Some(3000)));
+    assert_eq!(entries[1].cost_usd, Some(0.05));
+
+    let sidechain = &entries[2];
+    assert_eq!(sidechain.is_sidechain, Some(true));
+    assert_eq!(sidechain.message.as_ref().unwrap().model.as_deref(), Some("claude-3-opus"));
+    assert_eq!(sidechain.raw["cwd"], "/work/app");
+    // Each entry follows the one before it
+    assert_eq!(sidechain.raw["parentUuid"], entries[1].raw["uuid"]);
+}
+
+#[test]
+fn test_fixture_entries_have_distinct_ids() {
+    let now = Utc::now();
+    let jsonl = Transcript::new("s")
+        .entry(TranscriptEntry::assistant(now).usage(1, 1))
+        .entry(TranscriptEntry::assistant(now).usage(1, 1))
+        .unchained()
+        .to_jsonl();
+
+    let entries = parse_jsonl_content(&jsonl).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_ne!(entries[0].message.as_ref().unwrap().id, entries[1].message.as_ref().unwrap().id);
+    assert!(entries.iter().all(|entry| !entry.raw.contains_key("parentUuid")));
+}
@@ -0,0 +1,83 @@
+use claude_powerline_rust::utils::HookCostLedger;
+use chrono::{Duration, TimeZone, Utc};
+
+#[test]
+fn test_hook_costs_add_up_session_growth() {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 10, 20, 0).unwrap();
+    let mut ledger = HookCostLedger::load(None);
+
+    // A session's first report is its baseline, not cost spent now
+    ledger.record("a", 1.0, start);
+    assert_eq!(ledger.cost_since(start), 0.0);
+
+    ledger.record("a", 1.5, start + Duration::minutes(30));
+    ledger.record("b", 0.25, start + Duration::minutes(40));
+    ledger.record("b", 0.5, start + Duration::minutes(42));
+    // Re-reporting the same total adds nothing
+    ledger.record("b", 0.5, start + Duration::minutes(45));
+
+    assert!((ledger.cost_since(start) - 0.75).abs() < 1e-9);
+    assert!((ledger.cost_since(start + Duration::minutes(35)) - 0.25).abs() < 1e-9);
+
+    // A session whose total went down was cleared and counts from zero again
+    ledger.record("a", 0.5, start + Duration::minutes(50));
+    assert!((ledger.cost_since(start) - 1.25).abs() < 1e-9);
+}
+
+#[test]
+fn test_hook_costs_active_block() {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 10, 20, 0).unwrap();
+    let mut ledger = HookCostLedger::load(None);
+    ledger.record("a", 0.0, start - Duration::minutes(1));
+
+    ledger.record("a", 2.0, start);
+    ledger.record("a", 3.0, start + Duration::hours(1));
+
+    let block = ledger.active_block(start + Duration::hours(2)).unwrap();
+    assert_eq!(block.start, Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap());
+    assert!((block.cost - 3.0).abs() < 1e-9);
+    assert_eq!(block.last - block.first, Duration::hours(1));
+
+    // Past the block's 5 hours, cost recorded afterwards starts a new block
+    assert!(ledger.active_block(start + Duration::hours(5)).is_none());
+    ledger.record("a", 3.5, start + Duration::hours(5));
+    let block = ledger.active_block(start + Duration::hours(5)).unwrap();
+    assert!((block.cost - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_hook_costs_in_a_range() {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 10, 20, 0).unwrap();
+    let mut ledger = HookCostLedger::load(None);
+    ledger.record("a", 0.0, start - Duration::minutes(1));
+
+    ledger.record("a", 1.0, start);
+    ledger.record("a", 1.5, start + Duration::hours(1));
+    ledger.record("a", 2.5, start + Duration::hours(2));
+
+    let cost = ledger.cost_in(start + Duration::minutes(30)..start + Duration::hours(2));
+    assert!((cost - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_hook_costs_keep_a_week_merged_by_hour() {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 10, 20, 0).unwrap();
+    let mut ledger = HookCostLedger::load(None);
+    ledger.record("a", 0.0, start - Duration::minutes(1));
+
+    ledger.record("a", 1.0, start);
+    ledger.record("a", 1.5, start + Duration::minutes(10));
+    ledger.record("a", 2.0, start + Duration::minutes(30));
+    let later = start + Duration::days(3);
+    ledger.record("a", 2.5, later);
+
+    // Three days on, the first hour's samples are one, stamped at the start of the hour
+    let first_hour = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+    assert!((ledger.cost_in(first_hour..first_hour + Duration::minutes(1)) - 2.0).abs() < 1e-9);
+    assert!((ledger.cost_since(start) - 0.5).abs() < 1e-9);
+    assert!((ledger.cost_since(first_hour) - 2.5).abs() < 1e-9);
+
+    // Past 8 days they are dropped
+    ledger.record("a", 3.0, start + Duration::days(8));
+    assert!((ledger.cost_since(first_hour) - 1.0).abs() < 1e-9);
+}
@@ -1,5 +1,6 @@
 use claude_powerline_rust::{config, themes::*};
 use std::process::Command;
+use std::path::Path;
 use tempfile::TempDir;
 use tokio::fs;
 
@@ -46,7 +47,7 @@ async fn test_full_statusline_generation() {
     
     // Test the binary execution
     let output = Command::new("./target/release/claude-powerline")
-        .args(&[
+        .args([
             "--config", config_path.to_str().unwrap(),
             "--theme", "dark"
         ])
@@ -121,6 +122,134 @@ async fn test_config_loading() {
     assert!(!config.segments.today.as_ref().unwrap().enabled);
 }
 
+#[test]
+fn test_project_config_merges_over_global() {
+    let mut global = serde_json::json!({
+        "theme": "nord",
+        "style": "powerline",
+        "segments": {},
+        "segments": {
+            "git": {"enabled": true, "showSha": true, "showUpstream": true},
+            "today": {"enabled": true, "type": "cost"}
+        },
+        "claudePaths": ["/a", "/b"]
+    });
+    let project = serde_json::json!({
+        "segments": {"git": {"showSha": false}},
+        "claudePaths": ["/c"]
+    });
+    config::merge_config_values(&mut global, project);
+
+    let config: config::Config = serde_json::from_value(global).unwrap();
+    assert_eq!(config.theme, "nord");
+    let git = config.segments.git.as_ref().unwrap();
+    assert_eq!(git.show_sha, Some(false));
+    assert_eq!(git.show_upstream, Some(true));
+    assert_eq!(config.segments.today.as_ref().unwrap().display_type.as_deref(), Some("cost"));
+    // Arrays are replaced, not appended to
+    assert_eq!(config.claude_paths.unwrap(), vec![std::path::PathBuf::from("/c")]);
+}
+
+#[tokio::test]
+async fn test_commented_config_loading() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let config_content = r#"{
+        // Annotated like an example config
+        "theme": "nord",
+        "style": "minimal", /* or "powerline" */
+        "segments": {
+            "git": {"enabled": true, "showSha": false, /* hide the hash */ },
+            "today": {"enabled": false},
+        },
+        "claudePaths": ["//server/share/.claude", "C:\\Users\\me\\.claude"],
+    }"#;
+
+    let config_path = temp_dir.path().join("commented-config.json");
+    fs::write(&config_path, config_content).await.unwrap();
+
+    let config = config::load_config(Some(config_path)).await.unwrap();
+    assert_eq!(config.segments.git.as_ref().unwrap().show_sha, Some(false));
+    assert!(!config.segments.today.as_ref().unwrap().enabled);
+
+    // Comment markers and commas inside strings are kept
+    let paths = config.claude_paths.unwrap();
+    assert_eq!(paths[0].to_str(), Some("//server/share/.claude"));
+    assert_eq!(paths[1].to_str(), Some(r"C:\Users\me\.claude"));
+    assert_eq!(config::strip_json_comments(r#"{"a": "x, }"} // end"#).trim_end(), r#"{"a": "x, }"}"#);
+}
+
+#[tokio::test]
+async fn test_config_profiles() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let config_content = r#"{
+        "theme": "dark",
+        "style": "powerline",
+        "segments": {},
+        "budget": {"today": {"amount": 50.0}},
+        "claudePaths": ["/home/me/.claude"],
+        "profiles": {
+            "work": {
+                "theme": "nord",
+                "budget": {"today": {"amount": 200.0}},
+                "claudePaths": ["/home/me/.claude-work"]
+            }
+        }
+    }"#;
+
+    let config_path = temp_dir.path().join("profiles-config.json");
+    fs::write(&config_path, config_content).await.unwrap();
+
+    let base = config::load_config(Some(config_path.clone())).await.unwrap();
+    assert_eq!(base.theme, "dark");
+    assert_eq!(base.budget.unwrap().today.unwrap().amount, 50.0);
+
+    let work = config::load_profile_config(Some(config_path.clone()), Some("work")).await.unwrap();
+    assert_eq!(work.theme, "nord");
+    assert_eq!(work.budget.unwrap().today.unwrap().amount, 200.0);
+    assert_eq!(work.claude_paths.unwrap()[0].to_str(), Some("/home/me/.claude-work"));
+    // Settings the profile leaves alone come from the rest of the file
+    assert_eq!(work.style, "powerline");
+
+    let err = config::load_profile_config(Some(config_path), Some("personal")).await.unwrap_err();
+    assert!(format!("{:#}", err).contains("configured: work"));
+}
+
+#[test]
+fn test_profile_selection_rules() {
+    let config = serde_json::json!({
+        "profiles": {
+            "work": { "match": { "cwd": ["/home/me/work/**"] }, "theme": "nord" },
+            "client": { "match": { "cwd": ["/home/me/work/client"] } },
+            "oss": { "match": { "cwd": ["/home/me/src/*/oss"] } },
+            "ci": { "match": { "env": ["CI", "CLAUDE_ACCOUNT=ci"] } },
+            "manual": { "theme": "light" }
+        }
+    });
+    let no_env = |_: &str| None;
+    let select = |cwd: &str| config::matching_profile(&config, Path::new(cwd), no_env);
+
+    assert_eq!(select("/home/me/work").as_deref(), Some("work"));
+    assert_eq!(select("/home/me/work/api/src").as_deref(), Some("work"));
+    // The more specific directory wins
+    assert_eq!(select("/home/me/work/client/app").as_deref(), Some("client"));
+    assert_eq!(select("/home/me/src/rust/oss/crate").as_deref(), Some("oss"));
+    assert_eq!(select("/home/me/workshop"), None);
+
+    let ci_env = |var: &str| (var == "CLAUDE_ACCOUNT").then(|| "ci".to_string());
+    assert_eq!(config::matching_profile(&config, Path::new("/tmp"), ci_env).as_deref(), Some("ci"));
+    assert_eq!(config::matching_profile(&config, Path::new("/home/me/work"), ci_env).as_deref(), Some("work"));
+    let other_env = |var: &str| (var == "CLAUDE_ACCOUNT").then(|| "home".to_string());
+    assert_eq!(config::matching_profile(&config, Path::new("/tmp"), other_env), None);
+
+    // Selection rules don't end up in the config
+    let mut value = config.clone();
+    config::apply_profile(&mut value, "work").unwrap();
+    assert_eq!(value["theme"], "nord");
+    assert!(value.get("match").is_none());
+}
+
 #[tokio::test] 
 async fn test_theme_colors() {
     let dark_theme = get_theme("dark");
@@ -164,6 +293,102 @@ async fn test_environment_variable_overrides() {
     std::env::remove_var("CLAUDE_POWERLINE_STYLE");
 }
 
+#[test]
+fn test_segment_environment_overrides() {
+    let mut config = config::Config::default();
+    config.segments.metrics = None;
+
+    let vars = [
+        ("CLAUDE_POWERLINE_GIT_SHOW_SHA", "false"),
+        ("CLAUDE_POWERLINE_GIT_SHOW_STASH_COUNT", "1"),
+        ("CLAUDE_POWERLINE_TODAY_TYPE", "tokens"),
+        ("CLAUDE_POWERLINE_CONTEXT_ENABLED", "off"),
+        ("CLAUDE_POWERLINE_METRICS_SHOW_DURATION", "true"),
+        // Ignored: unknown option, a value of the wrong type and a non-segment variable
+        ("CLAUDE_POWERLINE_GIT_SHOW_NOTHING", "true"),
+        ("CLAUDE_POWERLINE_DIRECTORY_SHOW_BASENAME", "maybe"),
+        ("CLAUDE_POWERLINE_DEBUG", "1"),
+    ]
+    .map(|(name, value)| (name.to_string(), value.to_string()));
+    config::apply_segment_overrides(&mut config, vars);
+
+    let git = config.segments.git.as_ref().unwrap();
+    assert_eq!(git.show_sha, Some(false));
+    assert_eq!(git.show_stash_count, Some(true));
+    assert_eq!(config.segments.today.as_ref().unwrap().display_type.as_deref(), Some("tokens"));
+    assert!(!config.segments.context.as_ref().unwrap().enabled);
+    assert_eq!(config.segments.directory.as_ref().unwrap().show_basename, Some(false));
+
+    // A segment missing from the config starts from its defaults
+    let metrics = config.segments.metrics.as_ref().unwrap();
+    assert!(metrics.enabled);
+    assert_eq!(metrics.show_duration, Some(true));
+}
+
+#[test]
+fn test_tool_variables_are_not_read_as_segment_overrides() {
+    use claude_powerline_rust::config::env_segment_override;
+
+    assert_eq!(env_segment_override("CLAUDE_POWERLINE_GIT_SHOW_SHA"), Some(("git", "SHOW_SHA")));
+    // Not matched, so never checked against the session segment's options and warned about
+    assert_eq!(env_segment_override("CLAUDE_POWERLINE_NO_SESSION_INDEX"), None);
+    assert_eq!(env_segment_override("CLAUDE_POWERLINE_DEBUG"), None);
+    // Starts with the update segment's name, but is the release lookup's own setting
+    assert_eq!(env_segment_override("CLAUDE_POWERLINE_UPDATE_URL"), None);
+
+    let mut config = config::Config::default();
+    let before = serde_json::to_value(&config.segments).unwrap();
+    config::apply_segment_overrides(&mut config, [("CLAUDE_POWERLINE_NO_SESSION_INDEX".to_string(), "1".to_string())]);
+    assert_eq!(serde_json::to_value(&config.segments).unwrap(), before);
+}
+
+#[test]
+fn test_reserved_variables_match_the_variables_the_source_reads() {
+    use std::collections::BTreeSet;
+
+    // Every `"CLAUDE_POWERLINE_<NAME>"` literal in the source names a variable the tool reads
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    let mut read = BTreeSet::new();
+    for entry in walkdir::WalkDir::new(&src).into_iter().filter_map(|entry| entry.ok()) {
+        if entry.path().extension().is_none_or(|ext| ext != "rs") {
+            continue;
+        }
+        let source = std::fs::read_to_string(entry.path()).unwrap();
+        for literal in source.split("\"CLAUDE_POWERLINE_").skip(1) {
+            let name: String = literal.chars().take_while(|c| c.is_ascii_uppercase() || *c == '_').collect();
+            if !name.is_empty() && literal[name.len()..].starts_with('"') {
+                read.insert(name);
+            }
+        }
+    }
+
+    let reserved: BTreeSet<String> = config::RESERVED_VARIABLES.iter().map(|name| name.to_string()).collect();
+    assert_eq!(read, reserved);
+}
+
+#[test]
+fn test_every_registered_segment_can_be_overridden_from_the_environment() {
+    use claude_powerline_rust::config::env_segment_override;
+    use claude_powerline_rust::segments::SegmentRegistry;
+
+    let mut config = config::Config::default();
+    config.segments = config::SegmentConfig::with_every_segment();
+    let registry = SegmentRegistry::from_config(&config);
+    let names: Vec<&str> = registry.iter().map(|segment| segment.name()).collect();
+    assert!(names.contains(&"health") && names.contains(&"update") && names.contains(&"team"));
+
+    for name in names {
+        let variable = format!("CLAUDE_POWERLINE_{}_ENABLED", name.to_ascii_uppercase());
+        assert_eq!(env_segment_override(&variable), Some((name, "ENABLED")), "{}", variable);
+
+        // Opt-in segments left out of the config start from their defaults
+        let mut config = config::Config::default();
+        config::apply_segment_overrides(&mut config, [(variable, "true".to_string())]);
+        let segments = serde_json::to_value(&config.segments).unwrap();
+        assert_eq!(segments[name]["enabled"], true, "{}", name);
+    }
+}
+
 #[tokio::test]
 async fn test_performance_with_large_transcript() {
     let temp_dir = TempDir::new().unwrap();
@@ -198,7 +423,7 @@ async fn test_performance_with_large_transcript() {
     let start = std::time::Instant::now();
     
     let output = Command::new("./target/release/claude-powerline")
-        .args(&["--theme", "dark"])
+        .args(["--theme", "dark"])
         .output()
         .expect("Failed to execute claude-powerline");
     
@@ -216,7 +441,7 @@ async fn test_performance_with_large_transcript() {
 
 #[tokio::test]
 async fn test_cli_argument_parsing() {
-    let temp_dir = TempDir::new().unwrap();
+    let _temp_dir = TempDir::new().unwrap();
     
     // Test help flag
     let output = Command::new("./target/release/claude-powerline")
@@ -232,7 +457,7 @@ async fn test_cli_argument_parsing() {
     
     // Test theme argument
     let output = Command::new("./target/release/claude-powerline")
-        .args(&["--theme", "nord", "--style", "minimal"])
+        .args(["--theme", "nord", "--style", "minimal"])
         .output()
         .expect("Failed to execute with theme args");
     
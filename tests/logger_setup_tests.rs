@@ -0,0 +1,27 @@
+use claude_powerline_rust::utils::*;
+use tempfile::TempDir;
+
+// Its own test binary: file logging is set up once per process
+
+#[test]
+fn test_messages_logged_before_file_logging_is_set_are_kept() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("powerline.log");
+
+    // Config loading logs before its `logging` section is applied
+    warn_with_context("config", "Ignoring CLAUDE_POWERLINE_GIT_SHOW_SHA=bogus");
+    debug_with_context("config", "below the file's level");
+    set_file_logging(FileLogSettings {
+        path: path.clone(),
+        level: LogLevel::Warn,
+        max_bytes: DEFAULT_LOG_MAX_BYTES,
+        max_files: DEFAULT_LOG_MAX_FILES,
+    });
+    error_with_context("main", "after setup");
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 2, "{}", log);
+    assert!(lines[0].ends_with("[WARN] config: Ignoring CLAUDE_POWERLINE_GIT_SHOW_SHA=bogus"));
+    assert!(lines[1].ends_with("[ERROR] main: after setup"));
+}
@@ -0,0 +1,76 @@
+use claude_powerline_rust::config::LoggingConfig;
+use claude_powerline_rust::utils::*;
+use tempfile::TempDir;
+
+#[test]
+fn test_logging_config_settings() {
+    assert!(LoggingConfig::default().file_log_settings().is_none());
+
+    let config: LoggingConfig = serde_json::from_value(serde_json::json!({
+        "enabled": true,
+        "file": "/var/log/powerline.log",
+        "level": "INFO",
+        "maxSizeMb": 5
+    }))
+    .unwrap();
+    let settings = config.file_log_settings().unwrap();
+    assert_eq!(settings.path, std::path::PathBuf::from("/var/log/powerline.log"));
+    assert_eq!(settings.level, LogLevel::Info);
+    assert_eq!(settings.max_bytes, 5 * 1024 * 1024);
+    assert_eq!(settings.max_files, DEFAULT_LOG_MAX_FILES);
+
+    let huge: LoggingConfig = serde_json::from_value(serde_json::json!({ "enabled": true, "file": "/var/log/powerline.log", "maxSizeMb": u64::MAX })).unwrap();
+    assert_eq!(huge.file_log_settings().unwrap().max_bytes, u64::MAX);
+}
+
+#[test]
+fn test_file_log_filters_by_level_and_rotates() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("logs").join("powerline.log");
+    set_file_logging(FileLogSettings {
+        path: path.clone(),
+        level: LogLevel::Warn,
+        max_bytes: 200,
+        max_files: 2,
+    });
+
+    debug_with_context("test", "not written");
+    info_with_context("test", "not written either");
+    for i in 0..12 {
+        warn_with_context("test", &format!("segment failed {}", i));
+    }
+    error_with_context("test", "last");
+
+    let current = std::fs::read_to_string(&path).unwrap();
+    assert!(current.trim_end().ends_with("[ERROR] test: last"));
+    assert!(!current.contains("not written"));
+
+    // Older lines were shifted into numbered files, and no more than `max_files` are kept
+    let rotated = std::fs::read_to_string(dir.path().join("logs").join("powerline.log.1")).unwrap();
+    assert!(rotated.contains("[WARN] test: segment failed"));
+    assert!(dir.path().join("logs").join("powerline.log.2").exists());
+    assert!(!dir.path().join("logs").join("powerline.log.3").exists());
+    assert!(std::fs::metadata(&path).unwrap().len() <= 200 + 100);
+}
+
+#[test]
+fn test_log_filter_levels_and_modules() {
+    let all = LogFilter::parse("1").unwrap();
+    assert!(all.enabled(LogLevel::Debug, Some("git")));
+    assert!(!all.enabled(LogLevel::Trace, Some("git")));
+    assert!(LogFilter::parse("off").is_none());
+
+    // A bare module enables just that module, at debug
+    let git = LogFilter::parse("git").unwrap();
+    assert!(git.enabled(LogLevel::Debug, Some("git")));
+    assert!(!git.enabled(LogLevel::Error, Some("render")));
+    assert!(!git.enabled(LogLevel::Error, None));
+
+    let mixed = LogFilter::parse("warn, git=trace, segment").unwrap();
+    assert_eq!(mixed.default, Some(LogLevel::Warn));
+    assert!(mixed.enabled(LogLevel::Trace, Some("git")));
+    assert!(mixed.enabled(LogLevel::Debug, Some("segment_cache")));
+    assert!(!mixed.enabled(LogLevel::Debug, Some("segments")));
+    assert!(!mixed.enabled(LogLevel::Info, Some("render")));
+    assert!(mixed.enabled(LogLevel::Warn, Some("render")));
+}
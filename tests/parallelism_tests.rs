@@ -0,0 +1,42 @@
+use claude_powerline_rust::utils::{configured_threads, init_thread_pool, parse_concurrency, set_parallelism};
+
+#[test]
+fn test_parallelism_settings() {
+    std::env::remove_var("CLAUDE_POWERLINE_THREADS");
+    std::env::remove_var("CLAUDE_POWERLINE_PARSE_CONCURRENCY");
+
+    set_parallelism(Some(3), Some(5));
+    // Only the first call takes effect
+    set_parallelism(Some(8), Some(8));
+    assert_eq!(configured_threads(), Some(3));
+    assert_eq!(parse_concurrency(), 5);
+
+    init_thread_pool();
+    assert_eq!(rayon::current_num_threads(), 3);
+
+    // The environment wins over the config; nonsense and zero are ignored
+    std::env::set_var("CLAUDE_POWERLINE_PARSE_CONCURRENCY", "2");
+    assert_eq!(parse_concurrency(), 2);
+    std::env::set_var("CLAUDE_POWERLINE_PARSE_CONCURRENCY", "0");
+    assert_eq!(parse_concurrency(), 5);
+    std::env::set_var("CLAUDE_POWERLINE_THREADS", "lots");
+    assert_eq!(configured_threads(), Some(3));
+    std::env::remove_var("CLAUDE_POWERLINE_PARSE_CONCURRENCY");
+    std::env::remove_var("CLAUDE_POWERLINE_THREADS");
+}
+
+#[test]
+fn test_runtime_flavor() {
+    use claude_powerline_rust::utils::{build_runtime, RuntimeFlavor};
+
+    assert_eq!(RuntimeFlavor::default(), RuntimeFlavor::CurrentThread);
+    assert_eq!("multi-thread".parse::<RuntimeFlavor>().unwrap(), RuntimeFlavor::MultiThread);
+    assert_eq!("Current_Thread".parse::<RuntimeFlavor>().unwrap(), RuntimeFlavor::CurrentThread);
+    assert!("threaded".parse::<RuntimeFlavor>().is_err());
+
+    for flavor in [RuntimeFlavor::CurrentThread, RuntimeFlavor::MultiThread] {
+        let runtime = build_runtime(flavor).unwrap();
+        let answer = runtime.block_on(async { tokio::spawn(async { 42 }).await.unwrap() });
+        assert_eq!(answer, 42);
+    }
+}
@@ -0,0 +1,111 @@
+use claude_powerline_rust::utils::*;
+use tempfile::TempDir;
+
+const TRANSCRIPT: &str = r#"{"timestamp":"2024-01-01T10:00:00.000Z","message":{"id":"msg-1","usage":{"input_tokens":100,"output_tokens":50},"model":"claude-3-5-sonnet"},"requestId":"req-1"}
+{"type":"summary","summary":"no timestamp, not an error"}
+{"timestamp":"2024-01-01T10:05:00.000Z","message":{"id":"msg-2","usage":{"input_tok
+not json at all
+{"timestamp":"2024-01-01T10:10:00.000Z","message":{"id":"msg-3","usage":{"input_tokens":200,"output_tokens":80},"model":"claude-3-5-sonnet"},"requestId":"req-3"}
+"#;
+
+#[test]
+fn test_parse_counts_only_invalid_json() {
+    let mut buffer = TRANSCRIPT.as_bytes().to_vec();
+    let (entries, invalid_lines) = parse_jsonl_bytes_counting(&mut buffer);
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(invalid_lines, 2);
+}
+
+#[tokio::test]
+async fn test_parse_errors_are_recorded_per_file_and_survive_the_entry_cache() {
+    let temp_dir = TempDir::new().unwrap();
+    let transcript = temp_dir.path().join("corrupt.jsonl");
+    std::fs::write(&transcript, TRANSCRIPT).unwrap();
+
+    let entries = DataAggregator::new().load_session_entries(&transcript).await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(parse_error_counts().contains(&(transcript.clone(), 2)));
+    assert!(total_parse_errors() >= 2);
+
+    // A transcript served from its sidecar reports the count recorded when it was parsed
+    let cache_dir = temp_dir.path().join("cache");
+    store_cached_entries(&cache_dir, &transcript, &entries, &ParseStats { skipped_lines: 2, ..ParseStats::default() }).unwrap();
+    record_parse_errors(&transcript, 0);
+    assert!(!parse_error_counts().iter().any(|(path, _)| path == &transcript));
+
+    load_cached_entries(&cache_dir, &transcript).unwrap();
+    assert!(parse_error_counts().contains(&(transcript.clone(), 2)));
+}
+
+fn entry(id: u32) -> String {
+    format!(r#"{{"timestamp":"2024-01-01T10:{:02}:00.000Z","message":{{"id":"msg-{}","usage":{{"input_tokens":100,"output_tokens":50}},"model":"claude-3-5-sonnet"}},"requestId":"req-{}"}}"#, id, id, id)
+}
+
+#[test]
+fn test_parse_recovers_marks_concatenated_and_interleaved_lines() {
+    let interrupted = &entry(4)[..60];
+    let content = format!(
+        "\u{feff}{}\n{}{}\n{}{}\n{}\n",
+        entry(1),
+        entry(2),
+        entry(3),
+        interrupted,
+        entry(5),
+        entry(6)
+    );
+    let mut buffer = content.into_bytes();
+    let (entries, stats) = parse_jsonl_bytes_with_stats(&mut buffer);
+
+    let ids: Vec<_> = entries.iter().map(|e| e.message.as_ref().unwrap().id.clone().unwrap()).collect();
+    assert_eq!(ids, ["msg-1", "msg-2", "msg-3", "msg-5", "msg-6"]);
+    assert_eq!(stats.skipped_lines, 0);
+    assert_eq!(stats.recovered_lines, 3);
+    assert_eq!(stats.recovered_bytes, entry(1).len() + entry(2).len() + entry(3).len() + entry(5).len());
+    assert_eq!(stats.skipped_bytes, interrupted.len());
+    assert_eq!(stats.pending_bytes, 0);
+}
+
+#[test]
+fn test_parse_counts_unterminated_last_line_as_pending() {
+    let partial = &entry(2)[..70];
+    let mut buffer = format!("{}\n{}", entry(1), partial).into_bytes();
+    let (entries, stats) = parse_jsonl_bytes_with_stats(&mut buffer);
+
+    assert_eq!(entries.len(), 1);
+    assert!(stats.is_clean());
+    assert_eq!(stats.pending_bytes, partial.len());
+
+    // The same cut-off line with more written after it is skipped
+    let mut buffer = format!("{}\n{}\n", entry(1), partial).into_bytes();
+    let (_, stats) = parse_jsonl_bytes_with_stats(&mut buffer);
+    assert_eq!(stats.skipped_lines, 1);
+    assert_eq!(stats.skipped_bytes, partial.len());
+}
+
+#[test]
+fn test_parse_recovers_concatenated_entries_with_escaped_strings() {
+    let escaped = |id: u32| entry(id).replace(r#""model""#, r#""content":"line\none \"quoted\"","model""#);
+    let interrupted = &escaped(3)[..120];
+    let mut buffer = format!("{}{}\n{}{}\n", escaped(1), escaped(2), interrupted, escaped(4)).into_bytes();
+
+    let (entries, stats) = parse_jsonl_bytes_with_stats(&mut buffer);
+    let ids: Vec<_> = entries.iter().filter_map(|entry| entry.message.as_ref()?.id.clone()).collect();
+    assert_eq!(ids, ["msg-1", "msg-2", "msg-4"]);
+    assert_eq!(stats.recovered_lines, 2);
+}
+
+#[tokio::test]
+async fn test_recovery_statistics_are_recorded() {
+    let temp_dir = TempDir::new().unwrap();
+    let transcript = temp_dir.path().join("joined.jsonl");
+    std::fs::write(&transcript, format!("{}{}\n", entry(1), entry(2))).unwrap();
+
+    let entries = DataAggregator::new().load_session_entries(&transcript).await.unwrap();
+    assert_eq!(entries.len(), 2);
+
+    let stats = parse_stats().into_iter().find(|(path, _)| path == &transcript).unwrap().1;
+    assert_eq!(stats.recovered_lines, 1);
+    assert_eq!(stats.skipped_lines, 0);
+    assert!(!parse_error_counts().iter().any(|(path, _)| path == &transcript));
+}
@@ -0,0 +1,155 @@
+use chrono::{Duration, TimeZone, Utc};
+use claude_powerline_rust::test_utils::{Transcript, TranscriptEntry};
+use claude_powerline_rust::utils::*;
+
+fn sessions() -> Vec<ParsedEntry> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+    // Session `a` records usage cumulatively; `b` is a single Opus response
+    let a = Transcript::new("a")
+        .entry(TranscriptEntry::user(start))
+        .entry(TranscriptEntry::assistant(start + Duration::minutes(1)).usage(100, 10))
+        .entry(TranscriptEntry::assistant(start + Duration::minutes(2)).usage(250, 40));
+    let b = Transcript::new("b")
+        .entry(TranscriptEntry::assistant(start + Duration::minutes(3)).model("claude-3-opus").usage(50, 5));
+
+    let mut entries = parse_jsonl_content(&b.to_jsonl()).unwrap();
+    // Out of order on purpose: deltas follow timestamps within a session, not input order
+    entries.extend(parse_jsonl_content(&a.to_jsonl()).unwrap().into_iter().rev());
+    entries
+}
+
+#[test]
+fn test_usage_totals_from_cumulative_sessions() {
+    let pricing = PricingService::with_defaults();
+    let totals = pricing.usage_totals(&sessions());
+
+    assert_eq!(totals.entry_count, 4);
+    assert_eq!(totals.tokens, TokenBreakdown { input_tokens: 300, output_tokens: 45, ..Default::default() });
+    assert_eq!(totals.weighted_tokens, 290 + 55 * 5);
+
+    let sonnet = pricing.cost_for_tokens("claude-3-5-sonnet", &TokenBreakdown { input_tokens: 250, output_tokens: 40, ..Default::default() }).unwrap();
+    let opus = pricing.cost_for_tokens("claude-3-opus", &TokenBreakdown { input_tokens: 50, output_tokens: 5, ..Default::default() }).unwrap();
+    assert!((totals.cost - (sonnet + opus)).abs() < 1e-12);
+
+    // The same math as the separate totals
+    let entries = sessions();
+    assert_eq!(totals.tokens, pricing.calculate_token_breakdown(&entries));
+    assert_eq!(totals.weighted_tokens, pricing.calculate_weighted_tokens(&entries));
+    assert!((totals.cost - pricing.calculate_total_cost(&entries).unwrap()).abs() < 1e-12);
+    assert_eq!(pricing.usage_totals::<ParsedEntry>(&[]), UsageFold::default());
+}
+
+#[test]
+fn test_session_deltas_in_timestamp_order() {
+    let mut deltas: Vec<(String, u64, u64)> = Vec::new();
+    PricingService::with_defaults().for_each_session_delta(&sessions(), |entry, _, _, delta| {
+        let session = entry.raw.get("sessionId").and_then(|id| id.as_str()).unwrap_or_default().to_string();
+        deltas.push((session, delta.input_tokens, delta.output_tokens));
+    });
+    deltas.sort();
+
+    assert_eq!(deltas, vec![
+        ("a".to_string(), 100, 10),
+        ("a".to_string(), 150, 30),
+        ("b".to_string(), 50, 5),
+    ]);
+
+    // A counter that drops (a new session in the same file) counts from zero again
+    let mut counters = SessionDeltas::default();
+    let usage = |input| UsageInfo { input_tokens: Some(input), ..serde_json::from_str("{}").unwrap() };
+    assert_eq!(counters.next(&usage(500)).input_tokens, 500);
+    assert_eq!(counters.next(&usage(200)).input_tokens, 0);
+    assert_eq!(counters.next(&usage(260)).input_tokens, 60);
+}
+
+#[test]
+fn test_default_pricing_ignores_startup_options() {
+    let mut entries = sessions();
+    entries.extend(parse_jsonl_content(&Transcript::new("agent")
+        .entry(TranscriptEntry::assistant(Utc.with_ymd_and_hms(2024, 1, 1, 11, 0, 0).unwrap()).usage(1000, 0).sidechain(true))
+        .to_jsonl()).unwrap());
+
+    // Startup options reach `new` but not `with_defaults`
+    set_include_sidechains(false);
+    assert_eq!(PricingService::new().usage_totals(&entries).tokens.input_tokens, 300);
+    assert_eq!(PricingService::with_defaults().usage_totals(&entries).tokens.input_tokens, 1300);
+    assert_eq!(PricingService::with_defaults().with_include_sidechains(false).usage_totals(&entries).entry_count, 4);
+}
+
+#[test]
+fn test_usage_by_model_splits_sessions_at_model_switches() {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap();
+    let transcript = Transcript::new("switch")
+        .entry(TranscriptEntry::user(start))
+        .entry(TranscriptEntry::assistant(start + Duration::minutes(1)).usage(100, 10))
+        .entry(TranscriptEntry::assistant(start + Duration::minutes(2)).model("claude-3-opus").usage(250, 40));
+    let entries = parse_jsonl_content(&transcript.to_jsonl()).unwrap();
+
+    let pricing = PricingService::with_defaults();
+    let by_model = pricing.usage_by_model(&entries);
+    assert_eq!(by_model.len(), 3);
+    assert_eq!(by_model[UNKNOWN_MODEL].entry_count, 1);
+    assert_eq!(by_model[UNKNOWN_MODEL].tokens, TokenBreakdown::default());
+
+    // Opus is charged only for what it added on top of the Sonnet turn
+    let sonnet = &by_model["claude-3-5-sonnet"];
+    let opus = &by_model["claude-3-opus"];
+    assert_eq!((sonnet.tokens.input_tokens, sonnet.tokens.output_tokens), (100, 10));
+    assert_eq!((opus.tokens.input_tokens, opus.tokens.output_tokens), (150, 30));
+    assert_eq!(opus.weighted_tokens, 180 * 5);
+
+    let mut summed = UsageFold::default();
+    by_model.values().for_each(|fold| summed.merge(fold));
+    let totals = pricing.usage_totals(&entries);
+    assert_eq!((summed.tokens, summed.entry_count), (totals.tokens, totals.entry_count));
+    assert!((summed.cost - totals.cost).abs() < 1e-12);
+}
+
+#[test]
+fn test_pricing_file_rates_come_before_built_ins() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("rates.json");
+    std::fs::write(&path, r#"{
+        // Negotiated rate
+        "claude-3-5-sonnet": { "input": 2.0, "output": 10.0, "cacheRead": 0.5 },
+        "acme-llm": { "input": 1.0, "output": 1.0, "cacheWrite5m": 1.0, "cacheWrite1h": 1.0 }
+    }"#).unwrap();
+
+    let pricing = PricingService::from_file(&path).unwrap();
+    let sonnet = pricing.get_model_pricing("claude-3-5-sonnet-20241022").unwrap();
+    assert_eq!((sonnet.input, sonnet.output, sonnet.cache_read), (2.0, 10.0, 0.5));
+    // Cache write rates left out follow the input rate
+    assert_eq!((sonnet.cache_write_5m, sonnet.cache_write_1h), (2.5, 4.0));
+    assert_eq!(pricing.get_model_pricing("acme-llm").unwrap().cache_read, 0.1);
+    // Models the file doesn't list keep their built-in prices
+    assert_eq!(pricing.get_model_pricing("claude-3-opus").unwrap().input, 15.0);
+    assert_eq!(PricingService::with_defaults().get_model_pricing("claude-3-5-sonnet").unwrap().input, 3.0);
+
+    let tokens = TokenBreakdown { input_tokens: 1_000_000, output_tokens: 1_000_000, ..Default::default() };
+    assert!((pricing.cost_for_tokens("acme-llm", &tokens).unwrap() - 2.0).abs() < 1e-12);
+
+    for invalid in [r#"{ "m": { "input": -1, "output": 1 } }"#, r#"{ "m": { "input": 1, "outptu": 1 } }"#, "[]"] {
+        std::fs::write(&path, invalid).unwrap();
+        assert!(PricingService::from_file(&path).is_err(), "{}", invalid);
+    }
+    assert!(PricingService::from_file(&temp_dir.path().join("missing.json")).is_err());
+}
+
+#[test]
+fn test_entry_rounding_rounds_before_summing() {
+    // Entry costs are $0.00045, $0.0009 and $0.001125
+    let entries = sessions();
+    let exact = PricingService::with_defaults().usage_totals(&entries).cost;
+    assert!((exact - 0.002475).abs() < 1e-12);
+
+    let rounded = |mode| {
+        PricingService::with_defaults()
+            .with_entry_rounding(Some(CostRounding { mode, decimals: 4 }))
+            .usage_totals(&entries)
+            .cost
+    };
+    assert!((rounded(RoundingMode::HalfUp) - 0.0025).abs() < 1e-12);
+    assert!((rounded(RoundingMode::HalfEven) - 0.0024).abs() < 1e-12);
+    assert!((rounded(RoundingMode::Truncate) - 0.0024).abs() < 1e-12);
+    assert!((round_cost(exact, 4, RoundingMode::HalfEven) - 0.0025).abs() < 1e-12);
+}
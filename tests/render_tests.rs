@@ -0,0 +1,58 @@
+use claude_powerline_rust::render::*;
+use claude_powerline_rust::utils::{display_width, truncate_to_width};
+
+#[test]
+fn test_parse_ansi_tracks_colors_and_resets() {
+    let line = "\x1b[48;2;45;55;72m\x1b[38;2;226;232;240m ~/code \x1b[0m ⮀ \x1b[48;5;196m\x1b[1m hot \x1b[0m\x1b]8;;https://example.com\x07link\x1b]8;;\x07";
+    let spans = parse_ansi(line);
+
+    assert_eq!(spans.len(), 4);
+    assert_eq!(spans[0], StyledSpan {
+        text: " ~/code ".to_string(),
+        fg: Some((226, 232, 240)),
+        bg: Some((45, 55, 72)),
+        bold: false,
+    });
+    assert_eq!(spans[1].text, " ⮀ ");
+    assert_eq!((spans[1].fg, spans[1].bg), (None, None));
+    assert_eq!(spans[2].bg, Some((255, 0, 0)));
+    assert!(spans[2].bold);
+    // OSC hyperlinks are dropped, their text kept
+    assert_eq!(spans[3].text, "link");
+}
+
+#[test]
+fn test_render_svg_draws_segments_on_a_cell_grid() {
+    let line = "\x1b[48;2;56;161;105m\x1b[38;2;247;250;252m ⎇ main \x1b[0m  \x1b[48;2;214;158;46m\x1b[38;2;26;32;44m $1 & <2> \x1b[0m";
+    let options = SvgOptions { font_size: 10.0, padding: 0.0, ..Default::default() };
+    let svg = render_svg(line, &options);
+
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"120\" height=\"15\""));
+    assert!(svg.contains(r##"<rect x="0.0" y="0.0" width="48.0" height="15.0" fill="#38a169"/>"##));
+    // The second segment starts after the 8 cells of the first and the 2-cell gap
+    assert!(svg.contains(r##"<rect x="60.0" y="0.0" width="60.0" height="15.0" fill="#d69e2e"/>"##));
+    assert!(svg.contains(r##"fill="#1a202c" textLength="60.0" lengthAdjust="spacing"> $1 &amp; &lt;2&gt; </text>"##));
+    // Whitespace-only gaps get no text element
+    assert_eq!(svg.matches("<text ").count(), 2);
+    assert!(svg.trim_end().ends_with("</svg>"));
+}
+
+#[test]
+fn test_display_width_counts_terminal_cells() {
+    assert_eq!(display_width("\x1b[48;2;1;2;3m ~/app \x1b[0m"), 7);
+    // Wide emoji and CJK take two cells; variation selectors none
+    assert_eq!(display_width("🔥 コード"), 9);
+    assert_eq!(display_width("❤\u{fe0f}"), 1);
+}
+
+#[test]
+fn test_truncate_to_width_keeps_escapes_and_wide_glyphs_whole() {
+    let line = "\x1b[41m 日本語 \x1b[0m";
+    assert_eq!(truncate_to_width(line, 8), line);
+
+    // Four cells leave room for " 日" plus the ellipsis; the next wide glyph would not fit
+    let truncated = truncate_to_width(line, 4);
+    assert_eq!(truncated, "\x1b[41m 日…\x1b[0m");
+    assert_eq!(display_width(&truncated), 4);
+    assert_eq!(truncate_to_width(line, 3), "\x1b[41m …\x1b[0m");
+}
@@ -0,0 +1,38 @@
+use claude_powerline_rust::config::{config_schema, Config};
+use serde_json::json;
+
+#[test]
+fn test_config_schema_follows_the_config_structs() {
+    let schema = config_schema();
+    let properties = &schema["properties"];
+
+    assert_eq!(properties["theme"]["type"], "string");
+    assert_eq!(properties["theme"]["default"], "dark");
+    // Fields with defaults can be left out of a config file
+    assert!(schema.get("required").is_none());
+    let config: Config = serde_json::from_str("{}").unwrap();
+    assert_eq!((config.theme.as_str(), config.style.as_str()), ("dark", "minimal"));
+    assert!(config.segments.git.is_some());
+
+    assert_eq!(properties["segments"]["allOf"], json!([{ "$ref": "#/definitions/SegmentConfig" }]));
+    assert_eq!(properties["colors"]["additionalProperties"]["$ref"], "#/definitions/ThemeColors");
+    assert_eq!(properties["claudePaths"]["items"]["type"], "string");
+
+    // Renamed fields appear under their config-file names, with their doc comments
+    let git = &schema["definitions"]["GitConfig"];
+    assert_eq!(git["properties"]["showSha"]["type"], json!(["boolean", "null"]));
+    assert_eq!(git["required"], json!(["enabled"]));
+    assert_eq!(schema["definitions"]["BlockConfig"]["properties"]["type"]["type"], json!(["string", "null"]));
+    assert_eq!(properties["cacheDir"]["description"], "Root of every on-disk cache; defaults to `<XDG cache dir>/claude-powerline`");
+
+    // The flattened layout options sit beside each segment's own
+    assert_eq!(git["properties"]["minWidth"]["type"], json!(["integer", "null"]));
+    assert!(git["description"].as_str().unwrap().starts_with("The repository's branch"));
+    assert_eq!(schema["definitions"]["HealthConfig"]["properties"]["priority"]["type"], json!(["integer", "null"]));
+
+    // Every key of a serialized config is described
+    let defaults = serde_json::to_value(Config::default()).unwrap();
+    for key in defaults.as_object().unwrap().keys() {
+        assert!(properties.get(key).is_some(), "{} missing from schema", key);
+    }
+}
@@ -0,0 +1,109 @@
+use claude_powerline_rust::utils::*;
+use tempfile::TempDir;
+
+#[test]
+fn test_segment_cache_round_trip() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("cache").join("segments.json");
+
+    let mut cache = SegmentCache::load(Some(path.clone()));
+    assert!(cache.get("today").is_none());
+
+    cache.store("today", " $1.23 ", std::time::Duration::ZERO);
+    cache.save().unwrap();
+    assert!(path.exists());
+
+    let reloaded = SegmentCache::load(Some(path.clone()));
+    assert_eq!(reloaded.get("today").unwrap().text, " $1.23 ");
+    assert!(reloaded.get("block").is_none());
+
+    // A corrupt file is treated as an empty cache
+    std::fs::write(&path, "not json").unwrap();
+    assert!(SegmentCache::load(Some(path)).get("today").is_none());
+}
+
+#[test]
+fn test_mark_stale_keeps_colors_and_padding() {
+    let colored = CachedSegment { text: "\x1b[48;5;1m $1.23 \x1b[0m".to_string(), rendered_at: 0 };
+    assert_eq!(mark_stale(&colored), format!("\x1b[48;5;1m $1.23 {} \x1b[0m", STALE_MARKER));
+
+    let plain = CachedSegment { text: "🤖 Sonnet 4".to_string(), rendered_at: 0 };
+    assert_eq!(mark_stale(&plain), format!("🤖 Sonnet 4 {}", STALE_MARKER));
+
+    let empty = CachedSegment { text: String::new(), rendered_at: 0 };
+    assert_eq!(mark_stale(&empty), "");
+}
+
+#[test]
+fn test_get_fresh_respects_ttl() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut cache = SegmentCache::load(Some(temp_dir.path().join("segments.json")));
+    cache.store("git", " main ", std::time::Duration::from_secs(60));
+
+    assert_eq!(cache.get_fresh("git", std::time::Duration::from_secs(60)).unwrap().text, " main ");
+    assert!(cache.get_fresh("git", std::time::Duration::ZERO).is_none());
+    assert!(cache.get_fresh("today", std::time::Duration::from_secs(60)).is_none());
+}
+
+#[test]
+fn test_unchanged_rendering_restarts_ttl_across_processes() {
+    use std::time::Duration;
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("segments.json");
+    let ttl = Duration::from_secs(60);
+
+    let mut cache = SegmentCache::load(Some(path.clone()));
+    cache.store("git", " main ", ttl);
+    cache.save().unwrap();
+
+    // Age the saved rendering past its TTL, as a later process would find it
+    let mut saved: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    for cached in saved["segments"].as_object_mut().unwrap().values_mut() {
+        cached["rendered_at"] = serde_json::json!(0);
+    }
+    std::fs::write(&path, serde_json::to_vec(&saved).unwrap()).unwrap();
+
+    let mut cache = SegmentCache::load(Some(path.clone()));
+    assert!(cache.get_fresh("git", ttl).is_none());
+    cache.store("git", " main ", ttl);
+    cache.save().unwrap();
+
+    let reloaded = SegmentCache::load(Some(path));
+    assert_eq!(reloaded.get_fresh("git", ttl).unwrap().text, " main ");
+}
+
+#[test]
+fn test_cache_config_ttl_defaults() {
+    use claude_powerline_rust::config::CacheConfig;
+    use std::time::Duration;
+
+    let defaults = CacheConfig::default();
+    assert_eq!(defaults.ttl_for("git"), Duration::from_secs(CacheConfig::DEFAULT_GIT_TTL_SECS));
+    assert_eq!(defaults.ttl_for("today"), Duration::ZERO);
+    assert_eq!(defaults.ttl_for("team"), Duration::from_secs(CacheConfig::DEFAULT_TEAM_TTL_SECS));
+
+    let config: CacheConfig = serde_json::from_str(r#"{ "git": 0, "today": 30, "team": 300 }"#).unwrap();
+    assert_eq!(config.ttl_for("git"), Duration::ZERO);
+    assert_eq!(config.ttl_for("today"), Duration::from_secs(30));
+    assert_eq!(config.ttl_for("team"), Duration::from_secs(300));
+    assert_eq!(config.ttl_for("unknown"), Duration::ZERO);
+}
+
+#[test]
+fn test_cache_config_disable_ttls_keeps_other_options() {
+    use claude_powerline_rust::config::CacheConfig;
+    use std::time::Duration;
+
+    let mut config: CacheConfig = serde_json::from_str(r#"{ "today": 30, "health": 60, "maxSizeMb": 7 }"#).unwrap();
+    config.disable_ttls();
+    assert_eq!(config.ttl_for("today"), Duration::ZERO);
+    assert_eq!(config.ttl_for("health"), Duration::ZERO);
+    assert_eq!(config.ttl_for("git"), Duration::ZERO);
+    assert_eq!(config.ttl_for("team"), Duration::ZERO);
+    assert_eq!(config.session, None);
+    assert_eq!(config.max_size_bytes(), 7 * 1024 * 1024);
+
+    let huge: CacheConfig = serde_json::from_value(serde_json::json!({ "maxSizeMb": u64::MAX })).unwrap();
+    assert_eq!(huge.max_size_bytes(), u64::MAX);
+}
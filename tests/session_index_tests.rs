@@ -0,0 +1,104 @@
+use chrono::{Duration, TimeZone, Utc};
+use claude_powerline_rust::utils::{parse_jsonl_content, ProjectUuidIndex, SessionIndex};
+use tempfile::TempDir;
+
+fn transcript(first: &str, last: &str) -> String {
+    format!(
+        "{{\"timestamp\":\"{}\",\"message\":{{\"id\":\"msg-1\"}},\"requestId\":\"req-1\"}}\n{{\"timestamp\":\"{}\",\"message\":{{\"id\":\"msg-2\"}},\"requestId\":\"req-2\"}}\n",
+        first, last
+    )
+}
+
+#[test]
+fn test_session_index_records_sessions_and_time_spans() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("projects").join("-home-me-app");
+    std::fs::create_dir_all(&project).unwrap();
+    let path = project.join("abc-123.jsonl");
+    let content = transcript("2024-01-01T10:00:00.000Z", "2024-01-01T12:30:00.000Z");
+    std::fs::write(&path, &content).unwrap();
+
+    let mut index = SessionIndex::new();
+    let metadata = std::fs::metadata(&path).unwrap();
+    index.observe(&path, &metadata, &parse_jsonl_content(&content).unwrap());
+
+    let record = index.record(&path, &metadata).unwrap();
+    assert_eq!(record.session_id, "abc-123");
+    assert_eq!(record.project, "-home-me-app");
+    assert_eq!(record.first_timestamp, Some(Utc.with_ymd_and_hms(2024, 1, 1, 10, 0, 0).unwrap()));
+    assert_eq!(record.last_timestamp, Some(Utc.with_ymd_and_hms(2024, 1, 1, 12, 30, 0).unwrap()));
+
+    let last = record.last_timestamp.unwrap();
+    assert!(index.may_have_entries_since(&path, &metadata, last));
+    assert!(!index.may_have_entries_since(&path, &metadata, last + Duration::minutes(1)));
+
+    let claude_paths = vec![temp_dir.path().to_path_buf()];
+    assert_eq!(index.find_session("abc-123", &claude_paths), Some(path.as_path()));
+    assert_eq!(index.find_session("abc-123", &[temp_dir.path().join("elsewhere")]), None);
+    assert_eq!(index.find_session("other", &claude_paths), None);
+
+    // Once the transcript grows, its record no longer counts until it is read again
+    std::fs::write(&path, format!("{}{}", content, transcript("2024-01-02T09:00:00.000Z", "2024-01-02T09:05:00.000Z"))).unwrap();
+    let grown = std::fs::metadata(&path).unwrap();
+    assert!(index.record(&path, &grown).is_none());
+    assert!(index.may_have_entries_since(&path, &grown, last + Duration::minutes(1)));
+}
+
+#[test]
+fn test_session_index_persists_and_prunes_missing_transcripts() {
+    let temp_dir = TempDir::new().unwrap();
+    let index_path = temp_dir.path().join("cache").join("session_index.bin");
+    let kept = temp_dir.path().join("kept.jsonl");
+    let deleted = temp_dir.path().join("deleted.jsonl");
+    let content = transcript("2024-01-01T10:00:00.000Z", "2024-01-01T11:00:00.000Z");
+    std::fs::write(&kept, &content).unwrap();
+    std::fs::write(&deleted, &content).unwrap();
+
+    let entries = parse_jsonl_content(&content).unwrap();
+    let mut index = SessionIndex::new();
+    index.observe(&kept, &std::fs::metadata(&kept).unwrap(), &entries);
+    index.observe(&deleted, &std::fs::metadata(&deleted).unwrap(), &entries);
+    std::fs::remove_file(&deleted).unwrap();
+    index.save(&index_path).unwrap();
+
+    let loaded = SessionIndex::load(&index_path);
+    assert_eq!(loaded.len(), 1);
+    assert!(loaded.record(&kept, &std::fs::metadata(&kept).unwrap()).is_some());
+
+    // An unchanged index isn't written again
+    let mut loaded = loaded;
+    loaded.observe(&kept, &std::fs::metadata(&kept).unwrap(), &entries);
+    std::fs::remove_file(&index_path).unwrap();
+    loaded.save(&index_path).unwrap();
+    assert!(!index_path.exists());
+
+    assert!(SessionIndex::load(&temp_dir.path().join("missing.bin")).is_empty());
+}
+
+#[test]
+fn test_uuid_index_finds_the_transcript_holding_a_parent() {
+    let temp_dir = TempDir::new().unwrap();
+    let a = temp_dir.path().join("a.jsonl");
+    let b = temp_dir.path().join("b.jsonl");
+    std::fs::write(&a, "{\"timestamp\":\"2024-01-01T10:00:00.000Z\",\"uuid\":\"a1\"}\n").unwrap();
+    std::fs::write(&b, "{\"timestamp\":\"2024-01-01T11:00:00.000Z\",\"uuid\":\"b1\",\"parentUuid\":\"a1\"}\n").unwrap();
+
+    let mut index = ProjectUuidIndex::default();
+    for path in [&a, &b] {
+        let metadata = std::fs::metadata(path).unwrap();
+        assert!(!index.is_current(path, &metadata));
+        index.insert(path, &metadata, &parse_jsonl_content(&std::fs::read_to_string(path).unwrap()).unwrap());
+        assert!(index.is_current(path, &metadata));
+    }
+    assert_eq!(index.find(["a1"], std::slice::from_ref(&b)), Some(a.as_path()));
+    assert_eq!(index.find(["a1"], std::slice::from_ref(&a)), None);
+    assert_eq!(index.find(["missing"], &[]), None);
+
+    // Saved and loaded back, it still knows the transcripts, until they're gone
+    let index_path = temp_dir.path().join("uuids.bin");
+    index.save(&index_path).unwrap();
+    let mut loaded = ProjectUuidIndex::load(&index_path);
+    assert_eq!(loaded.find(["b1"], &[]), Some(b.as_path()));
+    loaded.retain(std::slice::from_ref(&a));
+    assert_eq!(loaded.find(["b1"], &[]), None);
+}
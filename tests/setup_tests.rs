@@ -0,0 +1,159 @@
+use claude_powerline_rust::setup::{install_status_line, remove_status_line, status_line_command, RemoveOutcome, SetupOutcome};
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn test_setup_installs_status_line() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = temp_dir.path().join(".claude").join("settings.json");
+
+    let outcome = install_status_line(&settings, "claude-powerline --theme nord").unwrap();
+    assert_eq!(outcome, SetupOutcome::Installed { backup: None });
+
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&settings).unwrap()).unwrap();
+    assert_eq!(written["statusLine"]["type"], "command");
+    assert_eq!(written["statusLine"]["command"], "claude-powerline --theme nord");
+
+    // Running it again changes nothing
+    assert_eq!(install_status_line(&settings, "claude-powerline --theme nord").unwrap(), SetupOutcome::Unchanged);
+    assert!(!temp_dir.path().join(".claude").join("settings.json.claude-powerline.bak").exists());
+}
+
+#[test]
+fn test_setup_updates_and_backs_up() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = temp_dir.path().join("settings.json");
+    let original = r#"{"model": "opus", "statusLine": {"type": "command", "command": "~/bin/old-statusline", "padding": 0}}"#;
+    std::fs::write(&settings, original).unwrap();
+
+    let outcome = install_status_line(&settings, "claude-powerline").unwrap();
+    let backup = temp_dir.path().join("settings.json.claude-powerline.bak");
+    assert_eq!(outcome, SetupOutcome::Updated { previous: "~/bin/old-statusline".to_string(), backup: backup.clone() });
+    assert_eq!(std::fs::read_to_string(&backup).unwrap(), original);
+
+    // Other settings and statusLine options are kept
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&settings).unwrap()).unwrap();
+    assert_eq!(written["model"], "opus");
+    assert_eq!(written["statusLine"]["padding"], 0);
+    assert_eq!(written["statusLine"]["command"], "claude-powerline");
+
+    // A later change keeps the first backup
+    install_status_line(&settings, "claude-powerline --style powerline").unwrap();
+    assert_eq!(std::fs::read_to_string(&backup).unwrap(), original);
+    assert!(temp_dir.path().join("settings.json.claude-powerline.bak.1").exists());
+}
+
+#[test]
+fn test_setup_keeps_the_order_of_settings() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = temp_dir.path().join("settings.json");
+    std::fs::write(&settings, r#"{"permissions": {}, "model": "opus", "statusLine": {"type": "command", "command": "old"}, "env": {}}"#).unwrap();
+
+    let keys = || -> Vec<String> {
+        let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&settings).unwrap()).unwrap();
+        written.as_object().unwrap().keys().cloned().collect()
+    };
+
+    install_status_line(&settings, "/opt/bin/claude-powerline").unwrap();
+    assert_eq!(keys(), vec!["permissions", "model", "statusLine", "env"]);
+
+    remove_status_line(&settings, Path::new("/opt/bin/claude-powerline")).unwrap();
+    assert_eq!(keys(), vec!["permissions", "model", "statusLine", "env"]);
+}
+
+#[test]
+fn test_setup_leaves_invalid_settings_alone() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = temp_dir.path().join("settings.json");
+    std::fs::write(&settings, "{ not json").unwrap();
+
+    assert!(install_status_line(&settings, "claude-powerline").is_err());
+    assert_eq!(std::fs::read_to_string(&settings).unwrap(), "{ not json");
+    assert!(!temp_dir.path().join("settings.json.claude-powerline.bak").exists());
+}
+
+#[test]
+fn test_status_line_command_quoting() {
+    let flags = vec!["--theme".to_string(), "tokyo-night".to_string()];
+    assert_eq!(
+        status_line_command(Path::new("/usr/local/bin/claude-powerline"), &flags),
+        "/usr/local/bin/claude-powerline --theme tokyo-night"
+    );
+    assert_eq!(
+        status_line_command(Path::new("/Users/me/My Tools/claude-powerline"), &[]),
+        "\"/Users/me/My Tools/claude-powerline\""
+    );
+}
+
+#[test]
+fn test_setup_remove_restores_replaced_status_line() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = temp_dir.path().join("settings.json");
+    let binary = Path::new("/opt/bin/claude-powerline");
+    std::fs::write(&settings, r#"{"model": "opus", "statusLine": {"type": "command", "command": "~/bin/old-statusline"}}"#).unwrap();
+
+    install_status_line(&settings, "/opt/bin/claude-powerline").unwrap();
+    install_status_line(&settings, "/opt/bin/claude-powerline --theme nord").unwrap();
+
+    let outcome = remove_status_line(&settings, binary).unwrap();
+    let RemoveOutcome::Restored { command, from, .. } = outcome else {
+        panic!("expected the replaced statusLine back, got {:?}", outcome);
+    };
+    assert_eq!(command, "~/bin/old-statusline");
+    assert_eq!(from, temp_dir.path().join("settings.json.claude-powerline.bak"));
+
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&settings).unwrap()).unwrap();
+    assert_eq!(written["statusLine"]["command"], "~/bin/old-statusline");
+    assert_eq!(written["model"], "opus");
+
+    // Someone else's statusLine is never removed
+    assert_eq!(
+        remove_status_line(&settings, binary).unwrap(),
+        RemoveOutcome::NotInstalled { other: Some("~/bin/old-statusline".to_string()) }
+    );
+}
+
+#[test]
+fn test_setup_remove_deletes_added_status_line() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = temp_dir.path().join("settings.json");
+    std::fs::write(&settings, r#"{"model": "opus"}"#).unwrap();
+
+    install_status_line(&settings, "/opt/bin/claude-powerline").unwrap();
+    assert!(matches!(
+        remove_status_line(&settings, Path::new("/opt/bin/claude-powerline")).unwrap(),
+        RemoveOutcome::Removed { .. }
+    ));
+
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&settings).unwrap()).unwrap();
+    assert!(written.get("statusLine").is_none());
+    assert_eq!(written["model"], "opus");
+}
+
+#[test]
+fn test_setup_remove_leaves_npm_claude_powerline_alone() {
+    let temp_dir = TempDir::new().unwrap();
+    let settings = temp_dir.path().join("settings.json");
+    let binary = Path::new("/opt/bin/claude-powerline");
+    let npm = "npx -y @owloops/claude-powerline --style=powerline";
+    let original = format!(r#"{{"statusLine": {{"type": "command", "command": "{}"}}}}"#, npm);
+    std::fs::write(&settings, &original).unwrap();
+    // A backup of the user's own, not made by setup
+    std::fs::write(temp_dir.path().join("settings.json.bak"), r#"{"statusLine": {"type": "command", "command": "~/bin/older"}}"#).unwrap();
+
+    // Setup never added the npm package's statusLine, so it isn't taken out
+    assert_eq!(
+        remove_status_line(&settings, binary).unwrap(),
+        RemoveOutcome::NotInstalled { other: Some(npm.to_string()) }
+    );
+    assert_eq!(std::fs::read_to_string(&settings).unwrap(), original);
+
+    // Once replaced, removing puts the npm command back rather than the user's own backup
+    install_status_line(&settings, "/opt/bin/claude-powerline --theme nord").unwrap();
+    let outcome = remove_status_line(&settings, binary).unwrap();
+    let RemoveOutcome::Restored { command, from, .. } = outcome else {
+        panic!("expected the npm statusLine back, got {:?}", outcome);
+    };
+    assert_eq!(command, npm);
+    assert_eq!(from, temp_dir.path().join("settings.json.claude-powerline.bak"));
+}
@@ -0,0 +1,173 @@
+use claude_powerline_rust::segments::*;
+use claude_powerline_rust::utils::*;
+use chrono::{Duration, Utc};
+use claude_powerline_rust::test_utils::{ClaudeDirFixture, Transcript, TranscriptEntry};
+
+/// Tests here point CLAUDE_CONFIG_DIR at their own fixtures, so they must not overlap
+static ENV_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+#[tokio::test]
+async fn test_shared_entries_load_once_for_all_segments() {
+    let _guard = ENV_LOCK.lock().await;
+    let claude = ClaudeDirFixture::new().unwrap();
+    let now = Utc::now();
+    let transcript = Transcript::new("session")
+        .entry(TranscriptEntry::assistant(now - Duration::hours(3)).model("claude-3-opus").usage(1000, 500))
+        .entry(TranscriptEntry::assistant(now - Duration::minutes(10)).usage(1500, 750));
+    claude.write_transcript("test-project", &transcript).unwrap();
+
+    std::env::set_var("CLAUDE_CONFIG_DIR", claude.path());
+    std::env::set_var("CLAUDE_POWERLINE_ENTRY_CACHE", "0");
+
+    let shared = SharedEntries::new();
+    let (_, timing) = timed("first", shared.all()).await;
+    assert_eq!(timing.files_parsed, 1);
+    assert_eq!(timing.entries_parsed, 2);
+
+    // Later consumers reuse the loaded entries without touching the files again
+    let (recent, timing) = timed("second", shared.since(now - Duration::hours(1))).await;
+    assert_eq!(recent.unwrap().len(), 1);
+    assert_eq!(timing.files_parsed, 0);
+
+    let model_info = ModelSegment::new().get_current_model_info_from(&shared).await.unwrap();
+    assert_eq!(model_info.display_name.as_deref(), Some("Sonnet 3.5"));
+
+    let block_info = BlockSegment::new().get_active_block_info_from(&shared).await.unwrap();
+    assert!(block_info.tokens.is_some());
+
+    std::env::remove_var("CLAUDE_CONFIG_DIR");
+    std::env::remove_var("CLAUDE_POWERLINE_ENTRY_CACHE");
+}
+
+#[tokio::test]
+async fn test_fold_usage_matches_materialized_totals() {
+    let _guard = ENV_LOCK.lock().await;
+    let claude = ClaudeDirFixture::new().unwrap();
+    let now = Utc::now();
+    // Cumulative usage within a session, a duplicate copied into a second transcript,
+    // and an entry outside the fold window
+    let first = TranscriptEntry::assistant(now - Duration::minutes(30)).usage(100, 10);
+    claude.write_transcript("fold-project", &Transcript::new("a")
+        .entry(first.clone())
+        .entry(TranscriptEntry::assistant(now - Duration::minutes(20)).usage(250, 40))
+        .entry(TranscriptEntry::assistant("2020-01-01T00:00:00Z".parse().unwrap()).usage(999, 0))
+    ).unwrap();
+    claude.write_transcript("fold-project", &Transcript::new("b")
+        .entry(first.session("a"))
+        .entry(TranscriptEntry::assistant(now - Duration::minutes(10)).model("claude-3-opus").usage(50, 5))
+    ).unwrap();
+
+    std::env::set_var("CLAUDE_CONFIG_DIR", claude.path());
+    std::env::set_var("CLAUDE_POWERLINE_ENTRY_CACHE", "0");
+
+    let since = now - Duration::hours(2);
+    let fold = DataAggregator::new().fold_usage_since(since).await.unwrap();
+
+    let entries: Vec<_> = DataAggregator::new().load_all_entries().await.unwrap()
+        .into_iter()
+        .filter(|entry| entry.timestamp >= since)
+        .collect();
+    let pricing = PricingService::new();
+
+    assert_eq!(fold.entry_count, 3);
+    assert_eq!(fold.entry_count as usize, entries.len());
+    assert_eq!(fold.tokens.total_tokens(), pricing.calculate_token_breakdown(&entries).total_tokens());
+    assert_eq!(fold.weighted_tokens, pricing.calculate_weighted_tokens(&entries));
+    assert!((fold.cost - pricing.calculate_total_cost(&entries).unwrap()).abs() < 1e-9);
+
+    let totals = pricing.usage_totals(&entries);
+    assert_eq!(totals.tokens, fold.tokens);
+    assert_eq!((totals.entry_count, totals.weighted_tokens), (fold.entry_count, fold.weighted_tokens));
+    assert!((totals.cost - fold.cost).abs() < 1e-9);
+
+    std::env::remove_var("CLAUDE_CONFIG_DIR");
+    std::env::remove_var("CLAUDE_POWERLINE_ENTRY_CACHE");
+}
+
+#[tokio::test]
+async fn test_fold_usage_counts_the_earliest_copy_of_a_duplicate() {
+    let _guard = ENV_LOCK.lock().await;
+    let claude = ClaudeDirFixture::new().unwrap();
+    let now = Utc::now();
+    let today = now - Duration::minutes(10);
+    let earlier = now - Duration::days(2);
+    // Each transcript holds the earlier copy of one duplicate and the later copy of the other,
+    // so whichever is read first, a copy it folded loses to one read after it
+    let x = |at| TranscriptEntry::assistant(at).message_id("msg-x").request_id("req-x").usage(100, 10);
+    let z = |at| TranscriptEntry::assistant(at).message_id("msg-z").request_id("req-z").model("claude-3-opus").usage(200, 20);
+    claude.write_transcript("dup-project", &Transcript::new("a")
+        .entry(x(earlier))
+        .entry(z(today))
+        .entry(TranscriptEntry::assistant(now - Duration::minutes(5)).usage(300, 30))
+    ).unwrap();
+    claude.write_transcript("dup-project", &Transcript::new("b")
+        .entry(z(earlier))
+        .entry(x(today + Duration::minutes(1)))
+    ).unwrap();
+
+    std::env::set_var("CLAUDE_CONFIG_DIR", claude.path());
+    std::env::set_var("CLAUDE_POWERLINE_ENTRY_CACHE", "0");
+
+    let folds = DataAggregator::new().fold_usage_by(|entry| Some(entry.timestamp.date_naive())).await.unwrap();
+    let entries = DataAggregator::new().load_all_entries().await.unwrap();
+    let pricing = PricingService::new();
+
+    assert_eq!(folds[&earlier.date_naive()].entry_count, 2);
+    for (day, fold) in &folds {
+        let day_entries: Vec<_> = entries.iter().filter(|entry| entry.timestamp.date_naive() == *day).cloned().collect();
+        let totals = pricing.usage_totals(&day_entries);
+        assert_eq!(totals.tokens, fold.tokens, "{}", day);
+        assert_eq!((totals.entry_count, totals.weighted_tokens), (fold.entry_count, fold.weighted_tokens), "{}", day);
+        assert!((totals.cost - fold.cost).abs() < 1e-9, "{}", day);
+    }
+
+    std::env::remove_var("CLAUDE_CONFIG_DIR");
+    std::env::remove_var("CLAUDE_POWERLINE_ENTRY_CACHE");
+}
+
+/// Source serving fixed entries, standing in for a non-transcript backend
+struct StaticSource(Vec<ParsedEntry>);
+
+impl claude_powerline_rust::sources::UsageSource for StaticSource {
+    fn name(&self) -> &'static str {
+        "static"
+    }
+
+    fn entries_since(&self, since: chrono::DateTime<Utc>) -> futures::future::BoxFuture<'_, anyhow::Result<Vec<ParsedEntry>>> {
+        let entries = self.0.iter().filter(|entry| entry.timestamp >= since).cloned().collect();
+        Box::pin(async move { Ok(entries) })
+    }
+
+    fn session_entries<'a>(&'a self, session_id: &'a str) -> futures::future::BoxFuture<'a, anyhow::Result<Option<Vec<ParsedEntry>>>> {
+        let entries = (session_id == "s-1").then(|| self.0.clone());
+        Box::pin(async move { Ok(entries) })
+    }
+}
+
+#[tokio::test]
+async fn test_segments_read_from_pluggable_source() {
+    use claude_powerline_rust::sources::UsageSource;
+    let _guard = ENV_LOCK.lock().await;
+
+    let now = Utc::now();
+    let ts = |minutes: i64| (now - Duration::minutes(minutes)).format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string();
+    let entries = parse_jsonl_content(&format!(
+        "{{\"timestamp\":\"{}\",\"message\":{{\"id\":\"m-1\",\"usage\":{{\"input_tokens\":100,\"output_tokens\":10}},\"model\":\"claude-3-opus\"}},\"requestId\":\"r-1\"}}\n\
+         {{\"timestamp\":\"{}\",\"message\":{{\"id\":\"m-2\",\"usage\":{{\"input_tokens\":200,\"output_tokens\":20}},\"model\":\"claude-3-5-sonnet\"}},\"requestId\":\"r-2\"}}\n",
+        ts(90), ts(5)
+    )).unwrap();
+    let source = std::sync::Arc::new(StaticSource(entries));
+
+    let in_range = source.entries_in(now - Duration::hours(2)..now - Duration::hours(1)).await.unwrap();
+    assert_eq!(in_range.len(), 1);
+
+    let shared = SharedEntries::with_source(source.clone());
+    assert_eq!(shared.all().await.unwrap().len(), 2);
+    let model_info = ModelSegment::new().get_current_model_info_from(&shared).await.unwrap();
+    assert_eq!(model_info.display_name.as_deref(), Some("Sonnet 3.5"));
+
+    std::env::set_var("CLAUDE_SESSION_ID", "s-1");
+    let session_info = SessionSegment::new().get_session_info_from(source.as_ref()).await.unwrap();
+    std::env::remove_var("CLAUDE_SESSION_ID");
+    assert_eq!(session_info.message_count, Some(2));
+}
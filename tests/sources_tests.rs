@@ -0,0 +1,302 @@
+use claude_powerline_rust::sources::*;
+use claude_powerline_rust::utils::*;
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+fn write_json(path: std::path::PathBuf, value: serde_json::Value) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, serde_json::to_vec(&value).unwrap()).unwrap();
+}
+
+/// An OpenCode data directory with one session of two assistant replies and a user message
+fn opencode_fixture() -> TempDir {
+    let data_dir = TempDir::new().unwrap();
+    let storage = data_dir.path().join("storage");
+    let now = Utc::now();
+
+    write_json(storage.join("session/proj-1/ses_1.json"), serde_json::json!({
+        "id": "ses_1", "projectID": "proj-1", "directory": "/work/app"
+    }));
+    write_json(storage.join("message/ses_1/msg_1.json"), serde_json::json!({
+        "id": "msg_1", "sessionID": "ses_1", "role": "user",
+        "time": { "created": (now - Duration::minutes(31)).timestamp_millis() }
+    }));
+    write_json(storage.join("message/ses_1/msg_2.json"), serde_json::json!({
+        "id": "msg_2", "sessionID": "ses_1", "role": "assistant", "modelID": "claude-sonnet-4-20250514",
+        "time": { "created": (now - Duration::minutes(30)).timestamp_millis() },
+        "cost": 0.01,
+        "tokens": { "input": 100, "output": 20, "reasoning": 5, "cache": { "read": 1000, "write": 50 } }
+    }));
+    write_json(storage.join("message/ses_1/msg_3.json"), serde_json::json!({
+        "id": "msg_3", "sessionID": "ses_1", "role": "assistant", "modelID": "claude-sonnet-4-20250514",
+        "time": { "created": (now - Duration::minutes(10)).timestamp_millis() },
+        "tokens": { "input": 200, "output": 40, "reasoning": 0, "cache": { "read": 2000, "write": 0 } }
+    }));
+
+    data_dir
+}
+
+#[tokio::test]
+async fn test_opencode_source_maps_messages_to_cumulative_entries() {
+    let data_dir = opencode_fixture();
+    let source = OpenCodeSource::new(data_dir.path().to_path_buf());
+
+    let entries = source.entries_since(Utc::now() - Duration::hours(1)).await.unwrap();
+    assert_eq!(entries.len(), 2);
+
+    let last = &entries[1];
+    let usage = last.message.as_ref().unwrap().usage.as_ref().unwrap();
+    assert_eq!(usage.input_tokens, Some(300));
+    assert_eq!(usage.output_tokens, Some(65));
+    assert_eq!(usage.cache_read_input_tokens, Some(3000));
+    assert_eq!(usage.cache_creation_input_tokens, Some(50));
+    assert_eq!(last.raw.get("cwd").and_then(|v| v.as_str()), Some("/work/app"));
+
+    // Deltas recover each message's own tokens
+    let breakdown = PricingService::new().calculate_token_breakdown(&entries);
+    assert_eq!(breakdown.total_tokens(), 300 + 65 + 3000 + 50);
+
+    let recent = source.entries_since(Utc::now() - Duration::minutes(20)).await.unwrap();
+    assert_eq!(recent.len(), 1);
+
+    assert_eq!(source.session_entries("ses_1").await.unwrap().unwrap().len(), 2);
+    assert!(source.session_entries("ses_missing").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_opencode_running_totals_saturate() {
+    let data_dir = TempDir::new().unwrap();
+    let storage = data_dir.path().join("storage");
+    let created = (Utc::now() - Duration::minutes(5)).timestamp_millis();
+    for id in ["msg_1", "msg_2"] {
+        write_json(storage.join(format!("message/ses_huge/{id}.json")), serde_json::json!({
+            "id": id, "sessionID": "ses_huge", "role": "assistant", "modelID": "claude-sonnet-4-20250514",
+            "time": { "created": created },
+            "tokens": {
+                "input": 3_000_000_000u32, "output": 3_000_000_000u32, "reasoning": 3_000_000_000u32,
+                "cache": { "read": 3_000_000_000u32, "write": 3_000_000_000u32 }
+            }
+        }));
+    }
+
+    let entries = OpenCodeSource::new(data_dir.path().to_path_buf()).entries_since(Utc::now() - Duration::hours(1)).await.unwrap();
+    let usage = entries[1].message.as_ref().unwrap().usage.as_ref().unwrap();
+    assert_eq!((usage.input_tokens, usage.output_tokens), (Some(u32::MAX), Some(u32::MAX)));
+    assert_eq!((usage.cache_read_input_tokens, usage.cache_creation_input_tokens), (Some(u32::MAX), Some(u32::MAX)));
+}
+
+#[tokio::test]
+async fn test_merged_source_combines_sources_in_time_order() {
+    let first = opencode_fixture();
+    let second = opencode_fixture();
+    let merged = MergedSource::new(vec![
+        Arc::new(OpenCodeSource::new(first.path().to_path_buf())),
+        Arc::new(OpenCodeSource::new(second.path().to_path_buf())),
+    ]);
+
+    let entries = merged.entries_since(Utc::now() - Duration::hours(1)).await.unwrap();
+    assert_eq!(entries.len(), 4);
+    assert!(entries.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+    assert_eq!(merged.session_entries("ses_1").await.unwrap().unwrap().len(), 2);
+}
+
+/// A Codex home with one rollout: two turns, a repeated total and a rate-limit-only event
+fn codex_fixture() -> TempDir {
+    let home = TempDir::new().unwrap();
+    let now = Utc::now();
+    let at = |minutes: i64| (now - Duration::minutes(minutes)).to_rfc3339();
+    let token_count = |minutes: i64, input: u32, cached: u32, output: u32| serde_json::json!({
+        "timestamp": at(minutes), "type": "event_msg",
+        "payload": { "type": "token_count", "info": { "total_token_usage": {
+            "input_tokens": input, "cached_input_tokens": cached, "output_tokens": output,
+            "reasoning_output_tokens": 0, "total_tokens": input + output
+        } } }
+    });
+
+    let lines = [
+        serde_json::json!({ "timestamp": at(31), "type": "session_meta", "payload": { "id": "sess-1", "cwd": "/work/cli" } }),
+        serde_json::json!({ "timestamp": at(31), "type": "turn_context", "payload": { "cwd": "/work/cli", "model": "gpt-5-mini" } }),
+        serde_json::json!({ "timestamp": at(30), "type": "event_msg", "payload": { "type": "token_count", "info": null } }),
+        token_count(30, 1000, 400, 50),
+        token_count(30, 1000, 400, 50),
+        token_count(10, 3000, 2000, 150),
+    ];
+    let content: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+
+    let path = home.path().join("sessions/2025/09/20/rollout-2025-09-20T10-00-00-sess-1.jsonl");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content.join("\n")).unwrap();
+
+    home
+}
+
+#[tokio::test]
+async fn test_codex_source_reads_cumulative_token_counts() {
+    let home = codex_fixture();
+    let source = CodexSource::new(home.path().to_path_buf());
+
+    let entries = source.entries_since(Utc::now() - Duration::hours(1)).await.unwrap();
+    assert_eq!(entries.len(), 2);
+
+    let last = &entries[1];
+    let message = last.message.as_ref().unwrap();
+    let usage = message.usage.as_ref().unwrap();
+    assert_eq!(message.model.as_deref(), Some("gpt-5-mini"));
+    assert_eq!(usage.input_tokens, Some(1000));
+    assert_eq!(usage.cache_read_input_tokens, Some(2000));
+    assert_eq!(usage.output_tokens, Some(150));
+    assert_eq!(last.raw.get("sessionId").and_then(|v| v.as_str()), Some("sess-1"));
+    assert_eq!(last.raw.get("cwd").and_then(|v| v.as_str()), Some("/work/cli"));
+
+    // 1000 uncached input, 2000 cached, 150 output at gpt-5-mini rates
+    let cost = PricingService::new().calculate_total_cost(&entries).unwrap();
+    let expected = (1000.0 * 0.25 + 2000.0 * 0.025 + 150.0 * 2.0) / 1_000_000.0;
+    assert!((cost - expected).abs() < 1e-9, "cost {} != {}", cost, expected);
+
+    assert_eq!(source.session_entries("sess-1").await.unwrap().unwrap().len(), 2);
+    assert!(source.session_entries("sess-missing").await.unwrap().is_none());
+}
+
+/// A Gemini CLI home with one recorded chat: a user prompt and two replies
+fn gemini_fixture() -> TempDir {
+    let home = TempDir::new().unwrap();
+    let now = Utc::now();
+    let at = |minutes: i64| (now - Duration::minutes(minutes)).to_rfc3339();
+
+    write_json(home.path().join("tmp/project-hash/chats/session-2025-09-20T10-00-abcdef12.json"), serde_json::json!({
+        "sessionId": "abcdef12-3456-7890", "projectHash": "project-hash",
+        "startTime": at(31), "lastUpdated": at(10),
+        "messages": [
+            { "id": "m1", "timestamp": at(31), "type": "user", "content": "hi" },
+            { "id": "m2", "timestamp": at(30), "type": "gemini", "content": "hello", "model": "gemini-2.5-flash",
+              "tokens": { "input": 1000, "output": 40, "cached": 600, "thoughts": 10, "tool": 0, "total": 1050 } },
+            { "id": "m3", "timestamp": at(10), "type": "gemini", "content": "done", "model": "gemini-2.5-flash",
+              "tokens": { "input": 2000, "output": 60, "cached": 1400, "thoughts": 0, "tool": 0, "total": 2060 } }
+        ]
+    }));
+
+    home
+}
+
+#[tokio::test]
+async fn test_gemini_source_maps_replies_to_cumulative_entries() {
+    let home = gemini_fixture();
+    let source = GeminiSource::new(home.path().to_path_buf());
+
+    let entries = source.entries_since(Utc::now() - Duration::hours(1)).await.unwrap();
+    assert_eq!(entries.len(), 2);
+
+    let usage = entries[1].message.as_ref().unwrap().usage.as_ref().unwrap();
+    assert_eq!(usage.input_tokens, Some(1000));
+    assert_eq!(usage.cache_read_input_tokens, Some(2000));
+    assert_eq!(usage.output_tokens, Some(110));
+
+    // 1000 uncached input, 2000 cached, 110 output at gemini-2.5-flash rates
+    let cost = PricingService::new().calculate_total_cost(&entries).unwrap();
+    let expected = (1000.0 * 0.30 + 2000.0 * 0.03 + 110.0 * 2.50) / 1_000_000.0;
+    assert!((cost - expected).abs() < 1e-9, "cost {} != {}", cost, expected);
+
+    assert_eq!(source.session_entries("abcdef12-3456-7890").await.unwrap().unwrap().len(), 2);
+    assert!(source.session_entries("abcdef12-other").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_gemini_running_totals_saturate() {
+    let home = TempDir::new().unwrap();
+    let at = (Utc::now() - Duration::minutes(5)).to_rfc3339();
+    let reply = |id: &str| serde_json::json!({
+        "id": id, "timestamp": at, "type": "gemini", "content": "ok", "model": "gemini-2.5-flash",
+        "tokens": { "input": 3_000_000_000u32, "output": 3_000_000_000u32, "cached": 0, "thoughts": 3_000_000_000u32 }
+    });
+    write_json(home.path().join("tmp/project-hash/chats/session-huge.json"), serde_json::json!({
+        "sessionId": "huge", "projectHash": "project-hash", "startTime": at, "lastUpdated": at,
+        "messages": [reply("m1"), reply("m2")]
+    }));
+
+    let entries = GeminiSource::new(home.path().to_path_buf()).entries_since(Utc::now() - Duration::hours(1)).await.unwrap();
+    let usage = entries[1].message.as_ref().unwrap().usage.as_ref().unwrap();
+    assert_eq!((usage.input_tokens, usage.output_tokens), (Some(u32::MAX), Some(u32::MAX)));
+}
+
+#[tokio::test]
+async fn test_segment_reads_its_own_source() {
+    let home = gemini_fixture();
+    let config = claude_powerline_rust::config::Config {
+        sources: serde_json::from_value(serde_json::json!({ "geminiDir": home.path() })).unwrap(),
+        ..Default::default()
+    };
+
+    let ctx = claude_powerline_rust::segments::SegmentContext::new(
+        Arc::new(config),
+        Arc::new(claude_powerline_rust::themes::get_theme("dark")),
+    );
+
+    let gemini = ctx.entries_for(Some("gemini"));
+    assert_eq!(gemini.source().name(), "gemini");
+    assert_eq!(gemini.all().await.unwrap().len(), 2);
+    assert!(Arc::ptr_eq(&gemini, &ctx.entries_for(Some("gemini"))));
+
+    assert!(Arc::ptr_eq(&ctx.entries_for(None), &ctx.shared));
+    assert!(Arc::ptr_eq(&ctx.entries_for(Some("unknown")), &ctx.shared));
+}
+
+#[test]
+fn test_gpt_models_resolve_to_their_own_pricing() {
+    let pricing = PricingService::new();
+
+    assert_eq!(pricing.get_model_pricing("gpt-5-mini-2025-08-07").unwrap().input, 0.25);
+    assert_eq!(pricing.get_model_pricing("gpt-5-codex").unwrap().input, 1.25);
+    assert_eq!(pricing.get_model_pricing("gpt-4o-mini").unwrap().cache_read, 0.075);
+    assert_eq!(pricing.get_model_pricing("gemini-2.5-flash-lite").unwrap().input, 0.10);
+    assert_eq!(pricing.get_model_pricing("claude-sonnet-4-20250514").unwrap().input, 3.0);
+}
+
+#[test]
+fn test_source_selection_from_config() {
+    let mut config = claude_powerline_rust::config::Config::default();
+    assert_eq!(source_from_config(&config).name(), "claude");
+
+    config.sources = serde_json::from_str(r#"{ "enabled": ["claude", "opencode"], "opencodeDir": "/tmp/opencode" }"#).unwrap();
+    assert_eq!(source_from_config(&config).name(), "merged");
+
+    config.sources = serde_json::from_str(r#"{ "enabled": ["opencode"] }"#).unwrap();
+    assert_eq!(source_from_config(&config).name(), "opencode");
+
+    config.sources = serde_json::from_str(r#"{ "enabled": ["codex"], "codexDir": "/tmp/codex" }"#).unwrap();
+    assert_eq!(source_from_config(&config).name(), "codex");
+}
+
+#[tokio::test]
+async fn test_claude_session_chain_follows_resumes_and_branches() {
+    let claude_dir = TempDir::new().unwrap();
+    let project = claude_dir.path().join("projects/-work-app");
+    std::fs::create_dir_all(&project).unwrap();
+
+    let line = |session: &str, uuid: &str, parent: Option<&str>, id: &str, minute: u32| {
+        serde_json::json!({
+            "timestamp": format!("2024-01-01T10:{:02}:00.000Z", minute),
+            "sessionId": session, "uuid": uuid, "parentUuid": parent, "requestId": format!("req-{}", id),
+            "message": { "id": id, "usage": { "input_tokens": 10, "output_tokens": 5 } }
+        }).to_string()
+    };
+    let write = |name: &str, lines: &[String]| std::fs::write(project.join(name), lines.join("\n")).unwrap();
+
+    // `b` resumes `a`, repeating its history under `a`'s session id; `c` branches off `b`'s last entry
+    write("a.jsonl", &[line("a", "a1", None, "m1", 0), line("a", "a2", Some("a1"), "m2", 1)]);
+    write("b.jsonl", &[line("a", "a1", None, "m1", 0), line("a", "a2", Some("a1"), "m2", 1), line("b", "b1", Some("a2"), "m3", 2)]);
+    write("c.jsonl", &[line("c", "c1", Some("b1"), "m4", 3)]);
+    write("d.jsonl", &[line("d", "d1", None, "m5", 4)]);
+    std::env::set_var("CLAUDE_CONFIG_DIR", claude_dir.path());
+
+    let source = ClaudeTranscriptSource;
+    assert_eq!(source.session_entries("c").await.unwrap().unwrap().len(), 1);
+
+    let chain = source.session_chain_entries("c").await.unwrap().unwrap();
+    assert_eq!(chain.sessions, 3);
+    let ids: Vec<_> = chain.entries.iter().filter_map(|entry| entry.message.as_ref()?.id.clone()).collect();
+    assert_eq!(ids, ["m1", "m2", "m3", "m4"]);
+
+    let chain = source.session_chain_entries("a").await.unwrap().unwrap();
+    assert_eq!((chain.entries.len(), chain.sessions), (2, 1));
+}
@@ -0,0 +1,24 @@
+use claude_powerline_rust::utils::{debug_with_context, finish_file_logging_setup, log_file_path, set_state_root, state_root};
+use tempfile::TempDir;
+
+#[test]
+fn test_debug_log_goes_to_the_state_root() {
+    let dir = TempDir::new().unwrap();
+    set_state_root(dir.path().to_path_buf());
+    assert_eq!(state_root().unwrap(), dir.path());
+
+    let log = log_file_path().unwrap();
+    assert_eq!(log, dir.path().join("logs").join("claude-powerline.log"));
+
+    std::env::set_var("CLAUDE_POWERLINE_DEBUG", "1");
+    debug_with_context("state", "first line");
+    debug_with_context("state", "second line");
+    // As `main` does once the config's directories are applied
+    finish_file_logging_setup();
+
+    let contents = std::fs::read_to_string(&log).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].ends_with("[DEBUG] state: first line"));
+    assert!(lines[1].ends_with("[DEBUG] state: second line"));
+}
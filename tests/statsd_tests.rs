@@ -0,0 +1,62 @@
+use claude_powerline_rust::config::{Config, StatsdConfig};
+use claude_powerline_rust::segments::SegmentContext;
+use claude_powerline_rust::sources::OpenCodeSource;
+use claude_powerline_rust::statsd::*;
+use claude_powerline_rust::themes::get_theme;
+use claude_powerline_rust::utils::{set_cache_root, set_state_root};
+use std::net::UdpSocket;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[test]
+fn test_format_metrics_plain_and_tagged() {
+    let metrics = [Metric::counter("pushes", 1.0), Metric::gauge("today.cost_usd", 1.25)];
+
+    assert_eq!(
+        format_metrics("cp", &metrics, &[]),
+        "cp.pushes:1|c\ncp.today.cost_usd:1.25|g"
+    );
+    assert_eq!(
+        format_metrics("cp", &metrics[1..], &["team:platform".to_string(), "env:dev".to_string()]),
+        "cp.today.cost_usd:1.25|g|#team:platform,env:dev"
+    );
+}
+
+#[tokio::test]
+async fn test_push_sends_usage_metrics_and_is_throttled() {
+    let cache_dir = TempDir::new().unwrap();
+    set_cache_root(cache_dir.path().join("cache"));
+    set_state_root(cache_dir.path().join("state"));
+
+    let agent = UdpSocket::bind("127.0.0.1:0").unwrap();
+    agent.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    let config = StatsdConfig {
+        enabled: true,
+        address: Some(agent.local_addr().unwrap().to_string()),
+        prefix: Some("test".to_string()),
+        tags: None,
+        interval_secs: Some(3600),
+    };
+
+    // An empty source keeps real transcripts on the machine out of the numbers
+    let empty = TempDir::new().unwrap();
+    let ctx = SegmentContext::with_source(
+        Arc::new(Config::default()),
+        Arc::new(get_theme("dark")),
+        Arc::new(OpenCodeSource::new(empty.path().to_path_buf())),
+    );
+
+    assert!(push_if_due(&config, &ctx).await.unwrap());
+
+    let mut buf = [0u8; 2048];
+    let len = agent.recv(&mut buf).unwrap();
+    let payload = std::str::from_utf8(&buf[..len]).unwrap();
+    assert!(payload.lines().any(|line| line == "test.pushes:1|c"));
+    assert!(payload.lines().any(|line| line == "test.today.cost_usd:0|g"));
+    assert!(payload.lines().any(|line| line.starts_with("test.block.burn_rate_usd_per_hour:")));
+
+    // Within the interval nothing is sent
+    assert!(!push_if_due(&config, &ctx).await.unwrap());
+    assert!(!push_if_due(&StatsdConfig { enabled: false, ..config }, &ctx).await.unwrap());
+}
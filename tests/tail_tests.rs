@@ -0,0 +1,44 @@
+use claude_powerline_rust::utils::*;
+use tempfile::TempDir;
+
+fn lines_from_end(path: &std::path::Path, stop_at: &str) -> (Vec<String>, Option<String>, usize) {
+    let mut seen = Vec::new();
+    let (found, visited) = find_last_line(path, |line| {
+        let line = String::from_utf8_lossy(line).trim().to_string();
+        seen.push(line.clone());
+        (line == stop_at).then_some(line)
+    }).unwrap();
+    (seen, found, visited)
+}
+
+#[test]
+fn test_find_last_line_reads_backwards() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("session.jsonl");
+
+    // A line longer than the read chunk, blank lines, CRLF endings and no trailing newline
+    let long = "x".repeat(200 * 1024);
+    std::fs::write(&path, format!("first\r\n{}\n\nsecond\r\nthird", long)).unwrap();
+
+    let (seen, found, visited) = lines_from_end(&path, "second");
+    assert_eq!(found.as_deref(), Some("second"));
+    assert_eq!(seen, vec!["third", "second"]);
+    assert_eq!(visited, 2);
+
+    let (seen, found, visited) = lines_from_end(&path, "missing");
+    assert!(found.is_none());
+    assert_eq!(visited, 4);
+    assert_eq!(seen[2].len(), long.len());
+    assert_eq!(seen[3], "first");
+}
+
+#[test]
+fn test_find_last_line_empty_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("empty.jsonl");
+    std::fs::write(&path, "").unwrap();
+
+    let (found, visited) = find_last_line(&path, |_| Some(())).unwrap();
+    assert!(found.is_none());
+    assert_eq!(visited, 0);
+}
@@ -0,0 +1,185 @@
+use claude_powerline_rust::config::ThemeColors;
+use claude_powerline_rust::segments::Separators;
+use claude_powerline_rust::themes::{apply_critical_colors, apply_heat_colors, apply_theme_colors, get_theme, TextAttributes};
+use claude_powerline_rust::utils::terminal::force_rgb_colors;
+use std::collections::HashMap;
+
+#[test]
+fn test_transparent_style_colors_only_the_text() {
+    force_rgb_colors();
+
+    // dark's git segment: white text on green
+    let solid = apply_theme_colors(" main ", "git", &get_theme("dark"));
+    assert_eq!(solid, "\x1b[48;2;56;161;105m\x1b[38;2;247;250;252m main \x1b[0m");
+
+    let mut theme = get_theme("dark");
+    theme.transparent = true;
+    let transparent = apply_theme_colors(" main ", "git", &theme);
+    assert_eq!(transparent, "\x1b[38;2;247;250;252m main \x1b[0m");
+
+    // No powerline glyphs either, just a plain bar between segments
+    assert_eq!(Separators::for_style("transparent", Some("flame"), Some("rounded"), None), Separators::plain(" │ "));
+}
+
+#[test]
+fn test_color_overrides_and_text_attributes() {
+    force_rgb_colors();
+
+    let overrides: HashMap<String, ThemeColors> = serde_json::from_str(r##"{
+        "model": { "bold": true },
+        "directory": { "fg": "#000000", "dim": true, "italic": true },
+        "metrics": { "underline": false }
+    }"##).unwrap();
+    let mut theme = get_theme("dark");
+    theme.apply_overrides(&overrides);
+
+    // Attributes come after the colors; left-out colors keep the theme's
+    assert_eq!(
+        apply_theme_colors(" opus ", "model", &theme),
+        "\x1b[48;2;237;137;54m\x1b[38;2;247;250;252m\x1b[1m opus \x1b[0m"
+    );
+    assert_eq!(
+        apply_theme_colors(" ~/app ", "directory", &theme),
+        "\x1b[48;2;45;55;72m\x1b[38;2;0;0;0m\x1b[2;3m ~/app \x1b[0m"
+    );
+    assert_eq!(theme.attributes["metrics"], TextAttributes::default());
+    assert_eq!(TextAttributes::default().sgr(), "");
+}
+
+#[test]
+fn test_critical_colors_invert_unless_themed() {
+    force_rgb_colors();
+
+    // dark's context segment is white on red; critical swaps them and turns bold
+    let mut theme = get_theme("dark");
+    assert_eq!(
+        apply_critical_colors(" 5% ", "context", &theme),
+        "\x1b[48;2;247;250;252m\x1b[38;2;229;62;62m\x1b[1m 5% \x1b[0m"
+    );
+
+    let overrides: HashMap<String, ThemeColors> =
+        serde_json::from_str(r##"{ "context_critical": { "bg": "#ffff00", "fg": "#000000" } }"##).unwrap();
+    theme.apply_overrides(&overrides);
+    assert_eq!(
+        apply_critical_colors(" 5% ", "context", &theme),
+        "\x1b[48;2;255;255;0m\x1b[38;2;0;0;0m 5% \x1b[0m"
+    );
+}
+
+#[test]
+fn test_background_detection_parsing() {
+    use claude_powerline_rust::utils::terminal::{parse_background_reply, parse_colorfgbg, Background};
+
+    assert_eq!(parse_colorfgbg("15;0"), Some(Background::Dark));
+    assert_eq!(parse_colorfgbg("0;15"), Some(Background::Light));
+    assert_eq!(parse_colorfgbg("0;default;7"), Some(Background::Light));
+    assert_eq!(parse_colorfgbg("default"), None);
+
+    assert_eq!(parse_background_reply("\x1b]11;rgb:1e1e/1e1e/2e2e\x07"), Some(Background::Dark));
+    assert_eq!(parse_background_reply("\x1b]11;rgb:fdfd/f6f6/e3e3\x1b\\"), Some(Background::Light));
+    assert_eq!(parse_background_reply("\x1b]11;rgb:ff/ff/ff\x07"), Some(Background::Light));
+    assert_eq!(parse_background_reply("\x1b]11;?\x07"), None);
+
+    // Explicit themes pass through untouched
+    assert_eq!(claude_powerline_rust::themes::resolve_theme_name("nord", None), "nord");
+}
+
+#[test]
+fn test_system_appearance_parsing() {
+    use claude_powerline_rust::utils::appearance::{parse_apps_use_light_theme, parse_color_scheme, parse_gtk_theme};
+    use claude_powerline_rust::utils::terminal::Background;
+
+    assert_eq!(parse_color_scheme("'prefer-dark'\n"), Some(Background::Dark));
+    assert_eq!(parse_color_scheme("'default'\n"), Some(Background::Light));
+    assert_eq!(parse_color_scheme("''"), None);
+    assert_eq!(parse_gtk_theme("'Adwaita-dark'"), Background::Dark);
+    assert_eq!(parse_gtk_theme("'Yaru'"), Background::Light);
+
+    let reg = "\r\nHKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize\r\n    AppsUseLightTheme    REG_DWORD    0x0\r\n";
+    assert_eq!(parse_apps_use_light_theme(reg), Some(Background::Dark));
+    assert_eq!(parse_apps_use_light_theme(&reg.replace("0x0", "0x1")), Some(Background::Light));
+    assert_eq!(parse_apps_use_light_theme("ERROR: not found"), None);
+}
+
+#[test]
+fn test_heat_colors_interpolate_theme_colors() {
+    force_rgb_colors();
+    let theme = get_theme("dark");
+
+    // dark: heat_low #38a169 (green), heat_mid #d69e2e (yellow), heat_high #e53e3e (red); today's text stays #1a202c
+    let heat = |level: f64| apply_heat_colors(" $4.20 ", "today", level, &theme);
+    assert_eq!(heat(0.0), "\x1b[48;2;56;161;105m\x1b[38;2;26;32;44m $4.20 \x1b[0m");
+    assert_eq!(heat(0.5), "\x1b[48;2;214;158;46m\x1b[38;2;26;32;44m $4.20 \x1b[0m");
+    assert_eq!(heat(1.0), "\x1b[48;2;229;62;62m\x1b[38;2;26;32;44m $4.20 \x1b[0m");
+    assert_eq!(heat(3.0), heat(1.0));
+    assert_eq!(heat(0.25), "\x1b[48;2;135;160;76m\x1b[38;2;26;32;44m $4.20 \x1b[0m");
+
+    // The stops are their own entries, not borrowed from other segments
+    let mut theme = get_theme("nord");
+    assert_eq!(apply_heat_colors(" $4.20 ", "today", 0.0, &theme), "\x1b[48;2;163;190;140m\x1b[38;2;46;52;64m $4.20 \x1b[0m");
+    theme.colors.insert("git".to_string(), ("#000000".to_string(), "#ffffff".to_string()));
+    theme.colors.insert("heat_high".to_string(), ("#ff0000".to_string(), "#ffffff".to_string()));
+    assert_eq!(apply_heat_colors(" $4.20 ", "today", 0.0, &theme), "\x1b[48;2;163;190;140m\x1b[38;2;46;52;64m $4.20 \x1b[0m");
+    assert_eq!(apply_heat_colors(" $4.20 ", "today", 1.0, &theme), "\x1b[48;2;255;0;0m\x1b[38;2;46;52;64m $4.20 \x1b[0m");
+}
+
+#[test]
+fn test_color_override_precedence() {
+    use claude_powerline_rust::utils::terminal::color_override_from;
+
+    let env = |vars: &'static [(&'static str, &'static str)]| {
+        move |name: &str| vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string())
+    };
+
+    assert_eq!(color_override_from(env(&[])), None);
+    assert_eq!(color_override_from(env(&[("NO_COLOR", "1")])), Some(("NO_COLOR", false)));
+    // An empty NO_COLOR doesn't count
+    assert_eq!(color_override_from(env(&[("NO_COLOR", "")])), None);
+    assert_eq!(color_override_from(env(&[("CLICOLOR", "0")])), Some(("CLICOLOR", false)));
+    assert_eq!(color_override_from(env(&[("CLICOLOR", "1")])), None);
+
+    // Forcing wins over disabling
+    assert_eq!(color_override_from(env(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")])), Some(("CLICOLOR_FORCE", true)));
+    assert_eq!(color_override_from(env(&[("CLICOLOR_FORCE", "0"), ("CLICOLOR", "0")])), Some(("CLICOLOR", false)));
+    assert_eq!(color_override_from(env(&[("FORCE_COLOR", ""), ("NO_COLOR", "1")])), Some(("FORCE_COLOR", true)));
+    assert_eq!(color_override_from(env(&[("FORCE_COLOR", "0"), ("CLICOLOR_FORCE", "1")])), Some(("FORCE_COLOR", false)));
+}
+
+#[test]
+fn test_color_mode_flag_values() {
+    use claude_powerline_rust::utils::terminal::ColorMode;
+
+    assert_eq!("auto".parse::<ColorMode>().unwrap(), ColorMode::Auto);
+    assert_eq!("always".parse::<ColorMode>().unwrap(), ColorMode::Always);
+    assert_eq!("never".parse::<ColorMode>().unwrap(), ColorMode::Never);
+    assert!("yes".parse::<ColorMode>().is_err());
+}
+
+#[test]
+fn test_theme_file() {
+    use claude_powerline_rust::themes::load_theme_file;
+
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("my-theme.json");
+    std::fs::write(&path, r##"{
+        // Work in progress
+        "extends": "nord",
+        "colors": { "git": { "bg": "#a3be8c", "bold": true } },
+    }"##).unwrap();
+
+    let theme = load_theme_file(&path, "dark").unwrap();
+    let nord = get_theme("nord");
+    assert_eq!(theme.colors["git"], ("#a3be8c".to_string(), nord.colors["git"].1.clone()));
+    assert!(theme.attributes["git"].bold);
+    assert_eq!(theme.colors["context"], nord.colors["context"]);
+
+    // Without `extends`, the configured theme is the base
+    std::fs::write(&path, r##"{ "colors": { "model": { "fg": "#ffffff" } } }"##).unwrap();
+    let theme = load_theme_file(&path, "rose-pine").unwrap();
+    assert_eq!(theme.colors["git"], get_theme("rose-pine").colors["git"]);
+    assert_eq!(theme.colors["model"].1, "#ffffff");
+
+    std::fs::write(&path, "{ not json").unwrap();
+    assert!(load_theme_file(&path, "dark").is_err());
+    assert!(load_theme_file(&temp_dir.path().join("missing.json"), "dark").is_err());
+}
@@ -0,0 +1,77 @@
+use claude_powerline_rust::utils::*;
+use std::time::Duration;
+use tempfile::TempDir;
+
+#[tokio::test]
+async fn test_timed_records_parse_counts() {
+    let temp_dir = TempDir::new().unwrap();
+    let transcript_path = temp_dir.path().join("session.jsonl");
+    std::fs::write(&transcript_path, r#"{"timestamp":"2024-01-01T10:00:00.000Z","message":{"id":"msg-1","usage":{"input_tokens":100,"output_tokens":50},"model":"claude-3-5-sonnet"},"requestId":"req-1"}
+{"timestamp":"2024-01-01T10:05:00.000Z","message":{"id":"msg-2","usage":{"input_tokens":200,"output_tokens":80},"model":"claude-3-5-sonnet"},"requestId":"req-2"}"#).unwrap();
+
+    let aggregator = DataAggregator::new();
+    let (entries, timing) = timed("session", aggregator.load_session_entries(&transcript_path)).await;
+
+    assert_eq!(entries.unwrap().len(), 2);
+    assert_eq!(timing.name, "session");
+    assert_eq!(timing.files_parsed, 1);
+    assert_eq!(timing.entries_parsed, 2);
+
+    // Recording outside of a timed scope is a no-op
+    record_parse(5, 5);
+    let (_, idle) = timed("idle", async {}).await;
+    assert_eq!(idle.files_parsed, 0);
+
+    let output = format_timings(&[timing, idle], Duration::from_millis(3));
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[2].starts_with("total"));
+    assert!(output.contains("1 files, 2 entries"));
+}
+
+#[test]
+fn test_format_timings_marks_missed_deadline() {
+    let missed = SegmentTiming::missed_deadline("context", Duration::from_millis(300));
+    assert!(missed.timed_out);
+
+    let output = format_timings(&[missed], Duration::from_millis(301));
+    assert!(output.lines().next().unwrap().contains("missed render deadline"));
+}
+
+#[tokio::test]
+async fn test_record_stage_writes_json_lines() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("timing.log");
+    set_file_logging(FileLogSettings {
+        path: path.clone(),
+        level: LogLevel::Debug,
+        max_bytes: DEFAULT_LOG_MAX_BYTES,
+        max_files: 1,
+    });
+
+    timed("today", async {
+        record_stage("parse", Duration::from_micros(1500), Some(2), Some(10));
+    })
+    .await;
+    record_stage("dedupe", Duration::from_millis(2), None, Some(8));
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    let events: Vec<serde_json::Value> = log
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(events.len(), 2);
+
+    assert_eq!(events[0]["event"], "stage");
+    assert_eq!(events[0]["stage"], "parse");
+    assert_eq!(events[0]["segment"], "today");
+    assert_eq!(events[0]["duration_ms"], 1.5);
+    assert_eq!(events[0]["files"], 2);
+    assert_eq!(events[0]["entries"], 10);
+
+    // Outside a timed segment there is nothing to attribute the stage to
+    assert_eq!(events[1]["stage"], "dedupe");
+    assert!(events[1].get("segment").is_none());
+    assert!(events[1].get("files").is_none());
+}
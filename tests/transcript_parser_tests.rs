@@ -1,7 +1,6 @@
 use claude_powerline_rust::utils::claude::*;
-use claude_powerline_rust::utils::*;
-use chrono::{DateTime, Utc};
-use std::path::PathBuf;
+use claude_powerline_rust::utils::{complete_lines_len, split_lines_mut, DataAggregator};
+use chrono::Utc;
 use tempfile::TempDir;
 use tokio::fs;
 
@@ -52,6 +51,22 @@ async fn test_transcript_parser_today_entries() {
     
     assert_eq!(entries.len(), 2);
     assert!(entries.iter().all(|e| e.timestamp.date_naive() == today.date_naive()));
+
+    // A second load shares the cached entries instead of copying them
+    let again = parser.get_today_entries().await.unwrap();
+    assert!(std::sync::Arc::ptr_eq(&entries[0], &again[0]));
+
+    // A transcript that grew since is parsed again rather than served stale
+    let appended = format!(
+        "\n{{\"timestamp\":\"{}\",\"message\":{{\"id\":\"msg-today-3\",\"usage\":{{\"input_tokens\":10,\"output_tokens\":5}}}},\"requestId\":\"req-3\"}}\n",
+        today.format("%Y-%m-%dT%H:%M:%S%.3fZ")
+    );
+    let mut file = std::fs::OpenOptions::new().append(true).open(&transcript_path).unwrap();
+    std::io::Write::write_all(&mut file, appended.as_bytes()).unwrap();
+    drop(file);
+
+    let grown = parser.get_today_entries().await.unwrap();
+    assert_eq!(grown.len(), 3);
 }
 
 #[tokio::test]
@@ -93,6 +108,7 @@ async fn test_unique_hash_generation() {
             model: None,
         }),
         cost_usd: None,
+        source_file: None,
         is_sidechain: None,
         raw: [("requestId".to_string(), serde_json::Value::String("req-456".to_string()))]
             .into_iter()
@@ -115,4 +131,298 @@ async fn test_claude_paths_discovery() {
     assert!(paths.contains(&claude_dir));
     
     std::env::remove_var("CLAUDE_CONFIG_DIR");
-}
\ No newline at end of file
+}
+#[tokio::test]
+async fn test_latest_transcript_mtime() {
+    let temp_dir = TempDir::new().unwrap();
+    let claude_paths = vec![temp_dir.path().to_path_buf()];
+    assert!(latest_transcript_mtime(&claude_paths).await.is_none());
+
+    let project_dir = temp_dir.path().join("projects").join("test-project");
+    fs::create_dir_all(&project_dir).await.unwrap();
+    fs::write(project_dir.join("notes.txt"), "ignored").await.unwrap();
+    assert!(latest_transcript_mtime(&claude_paths).await.is_none());
+
+    let transcript = project_dir.join("session.jsonl");
+    fs::write(&transcript, "{}").await.unwrap();
+    let modified = std::fs::metadata(&transcript).unwrap().modified().unwrap();
+    assert_eq!(latest_transcript_mtime(&claude_paths).await, Some(modified));
+}
+
+#[test]
+fn test_split_lines_mut() {
+    let mut buffer = b"first\r\n\nsecond\nthird".to_vec();
+    let mut lines = split_lines_mut(&mut buffer);
+
+    let mut seen = Vec::new();
+    while let Some(line) = lines.next() {
+        seen.push((line.to_vec(), lines.last_terminated()));
+    }
+    assert_eq!(seen, [
+        (b"first\r".to_vec(), true),
+        (Vec::new(), true),
+        (b"second".to_vec(), true),
+        (b"third".to_vec(), false),
+    ]);
+
+    // A trailing newline doesn't add an empty line
+    let mut buffer = b"only\n".to_vec();
+    assert_eq!(split_lines_mut(&mut buffer).count(), 1);
+    assert_eq!(split_lines_mut(&mut []).count(), 0);
+
+    assert_eq!(complete_lines_len(b"a\nb\npartial"), 4);
+    assert_eq!(complete_lines_len(b"partial"), 0);
+}
+
+#[test]
+fn test_parse_jsonl_bytes_in_place() {
+    let mut buffer = b"{\"timestamp\":\"2024-01-01T10:00:00.000Z\",\"summary\":\"quote \\\" and \\u00e9\",\"message\":{\"id\":\"msg-001\",\"model\":\"claude-3-5-sonnet\"}}\r\n\n  \nnot json\n{\"timestamp\":\"2024-01-01T10:01:00.000Z\",\"costUSD\":0.5}"
+        .to_vec();
+
+    let entries = parse_jsonl_bytes(&mut buffer);
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].raw.get("summary").and_then(|v| v.as_str()), Some("quote \" and é"));
+    assert_eq!(entries[0].message.as_ref().unwrap().model.as_deref(), Some("claude-3-5-sonnet"));
+    assert_eq!(entries[1].cost_usd, Some(0.5));
+}
+
+#[test]
+fn test_mmap_threshold_override() {
+    std::env::remove_var("CLAUDE_POWERLINE_MMAP_THRESHOLD");
+    assert_eq!(mmap_threshold(), DEFAULT_MMAP_THRESHOLD);
+
+    std::env::set_var("CLAUDE_POWERLINE_MMAP_THRESHOLD", "4096");
+    assert_eq!(mmap_threshold(), 4096);
+
+    std::env::set_var("CLAUDE_POWERLINE_MMAP_THRESHOLD", "lots");
+    assert_eq!(mmap_threshold(), DEFAULT_MMAP_THRESHOLD);
+
+    std::env::remove_var("CLAUDE_POWERLINE_MMAP_THRESHOLD");
+}
+
+#[tokio::test]
+async fn test_aggregator_session_entries() {
+    std::env::set_var("CLAUDE_POWERLINE_ENTRY_CACHE", "0");
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("session.jsonl");
+    let recent = Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ");
+    fs::write(&path, format!(
+        "{{\"timestamp\":\"2020-01-01T10:00:00.000Z\",\"message\":{{\"id\":\"msg-old\"}}}}\r\n\
+         {{\"timestamp\":\"{}\",\"cwd\":\"C:\\\\work\",\"message\":{{\"id\":\"msg-1\",\"usage\":{{\"input_tokens\":100,\"cache_read_input_tokens\":7}},\"model\":\"claude-3-5-sonnet\"}},\"costUSD\":0.01,\"isSidechain\":true}}\n\
+         \n{{broken\n",
+        recent
+    )).await.unwrap();
+
+    let entries = DataAggregator::new().load_session_entries(&path).await.unwrap();
+    assert_eq!(entries.len(), 2);
+
+    let entries = DataAggregator::new().with_time_filter(24).load_session_entries(&path).await.unwrap();
+    assert_eq!(entries.len(), 1);
+    let entry = &entries[0];
+    let usage = entry.message.as_ref().unwrap().usage.as_ref().unwrap();
+    assert_eq!(usage.input_tokens, Some(100));
+    assert_eq!(usage.cache_read_input_tokens, Some(7));
+    assert_eq!(entry.cost_usd, Some(0.01));
+    assert_eq!(entry.is_sidechain, Some(true));
+    assert_eq!(entry.raw.get("cwd").and_then(|v| v.as_str()), Some("C:\\work"));
+    assert_eq!(entry.source_file.as_deref(), Some(path.to_string_lossy().as_ref()));
+    std::env::remove_var("CLAUDE_POWERLINE_ENTRY_CACHE");
+}
+
+#[test]
+fn test_deduplicate_entries_stats() {
+    let entries = parse_jsonl_content(r#"{"timestamp":"2024-01-01T10:00:00.000Z","message":{"id":"msg-1"},"requestId":"req-1","costUSD":0.1}
+{"timestamp":"2024-01-01T10:01:00.000Z","message":{"id":"msg-1"},"requestId":"req-1","costUSD":0.2}
+{"timestamp":"2024-01-01T10:02:00.000Z","message":{"id":"msg-1"},"requestId":"req-2"}
+{"timestamp":"2024-01-01T10:03:00.000Z","message":{"id":"msg-2"}}"#).unwrap();
+
+    assert_eq!(unique_entry_key(&entries[0]), unique_entry_key(&entries[1]));
+    assert_ne!(unique_entry_key(&entries[0]), unique_entry_key(&entries[2]));
+    assert!(unique_entry_key(&entries[3]).is_none());
+
+    let (deduplicated, stats) = deduplicate_entries(entries);
+    assert_eq!(stats, DedupeStats { total: 4, unique: 2, duplicates: 1, unkeyed: 1 });
+    assert_eq!(deduplicated.len(), 3);
+    assert_eq!(deduplicated[0].cost_usd, Some(0.1));
+}
+
+#[test]
+fn test_parsed_entries_share_interned_strings() {
+    let entries = parse_jsonl_content(r#"{"timestamp":"2024-01-01T10:00:00.000Z","message":{"id":"msg-1","model":"claude-3-5-sonnet"}}
+{"timestamp":"2024-01-01T10:01:00.000Z","message":{"id":"msg-2","model":"claude-3-5-sonnet"}}"#).unwrap();
+
+    let first = entries[0].message.as_ref().unwrap().model.clone().unwrap();
+    let second = entries[1].message.as_ref().unwrap().model.clone().unwrap();
+    assert!(std::sync::Arc::ptr_eq(&first, &second));
+    assert!(std::sync::Arc::ptr_eq(&first, &claude_powerline_rust::utils::intern("claude-3-5-sonnet")));
+}
+
+#[test]
+fn test_rotated_and_archived_transcript_names() {
+    use claude_powerline_rust::utils::archive::is_transcript_file_name;
+
+    for name in ["s.jsonl", "s.jsonl.gz", "s.jsonl.1", "s.jsonl.2.gz", "s.jsonl-20240101", "s.jsonl.2024-01-01.gz"] {
+        assert!(is_transcript_file_name(name), "{}", name);
+    }
+    for name in ["s.json", "s.jsonl.bak", "s.jsonl.tmp123", "s.jsonl.", "s.gz", "notes.txt"] {
+        assert!(!is_transcript_file_name(name), "{}", name);
+    }
+}
+
+#[test]
+fn test_read_compressed_transcript() {
+    use claude_powerline_rust::utils::archive::read_compressed;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    let temp_dir = TempDir::new().unwrap();
+    let archive = temp_dir.path().join("session.jsonl.gz");
+    let line = r#"{"timestamp":"2024-01-01T10:00:00.000Z","message":{"id":"msg-1","usage":{"input_tokens":10,"output_tokens":5}},"requestId":"req-1"}"#;
+
+    // Two gzip members, as left by appending one archive to another
+    let mut bytes = Vec::new();
+    for _ in 0..2 {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        writeln!(encoder, "{}", line).unwrap();
+        bytes.extend(encoder.finish().unwrap());
+    }
+    std::fs::write(&archive, bytes).unwrap();
+
+    let mut content = read_compressed(&archive).unwrap();
+    assert_eq!(parse_jsonl_bytes(&mut content).len(), 2);
+}
+
+#[test]
+fn test_expand_transcript_globs() {
+    let temp_dir = TempDir::new().unwrap();
+    let share = temp_dir.path().join("share");
+    for dir in ["laptop/app", "desktop/app", "desktop/api/deep"] {
+        std::fs::create_dir_all(share.join(dir)).unwrap();
+    }
+    for file in ["laptop/app/a.jsonl", "desktop/app/b.jsonl.gz", "desktop/api/deep/c.jsonl", "desktop/app/notes.txt"] {
+        std::fs::write(share.join(file), "").unwrap();
+    }
+
+    let expand = |globs: &[&str]| {
+        let globs: Vec<String> = globs.iter().map(|glob| glob.to_string()).collect();
+        let mut names: Vec<String> = expand_transcript_globs(&globs)
+            .iter()
+            .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    };
+
+    // `*` stays within one directory level, `**` crosses any number
+    assert_eq!(expand(&[&format!("{}/*/app/*", share.display())]), ["a.jsonl", "b.jsonl.gz"]);
+    assert_eq!(expand(&[&format!("{}/desktop/**/*.jsonl", share.display())]), ["c.jsonl"]);
+    // A plain directory is searched recursively; overlapping entries count once
+    assert_eq!(
+        expand(&[&share.join("desktop").display().to_string(), &format!("{}/desktop/app/*", share.display())]),
+        ["b.jsonl.gz", "c.jsonl"]
+    );
+    assert!(expand(&[&temp_dir.path().join("missing/*.jsonl").display().to_string()]).is_empty());
+}
+
+#[test]
+fn test_deduplicate_entries_without_ids_by_content() {
+    let entries = parse_jsonl_content(r#"{"timestamp":"2024-01-01T10:00:00.000Z","message":{"usage":{"input_tokens":10}},"costUSD":0.1}
+{"timestamp":"2024-01-01T10:00:00.000Z","message":{"usage":{"input_tokens":10}},"costUSD":0.1}
+{"timestamp":"2024-01-01T10:00:00.000Z","message":{"usage":{"input_tokens":20}},"costUSD":0.1}
+{"timestamp":"2024-01-01T10:01:00.000Z","uuid":"u-1","type":"user"}
+{"timestamp":"2024-01-01T10:01:00.000Z","uuid":"u-1","type":"user","cwd":"/other/mount"}"#).unwrap();
+
+    // Copies of a line collapse; a line's `uuid` identifies it even where other fields differ
+    assert_eq!(entry_content_key(&entries[0]), entry_content_key(&entries[1]));
+    assert_ne!(entry_content_key(&entries[0]), entry_content_key(&entries[2]));
+    assert_eq!(dedupe_key(&entries[3]), dedupe_key(&entries[4]));
+
+    let (deduplicated, stats) = deduplicate_entries(entries);
+    assert_eq!(stats, DedupeStats { total: 5, unique: 0, duplicates: 2, unkeyed: 3 });
+    assert_eq!(deduplicated.len(), 3);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dedupe_canonical_paths() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("claude/projects/app");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::create_dir_all(temp_dir.path().join("config")).unwrap();
+    std::os::unix::fs::symlink(temp_dir.path().join("claude/projects"), temp_dir.path().join("config/projects")).unwrap();
+
+    let linked = temp_dir.path().join("config/projects/app");
+    let missing = temp_dir.path().join("missing");
+    let paths = dedupe_canonical_paths(vec![project.clone(), linked, missing.clone(), missing.clone()]);
+    assert_eq!(paths, vec![project, missing]);
+}
+
+#[tokio::test]
+async fn test_project_paths_cache_sees_new_projects() {
+    let temp_dir = TempDir::new().unwrap();
+    let claude_paths = vec![temp_dir.path().to_path_buf()];
+    let projects = temp_dir.path().join("projects");
+    std::fs::create_dir_all(projects.join("first")).unwrap();
+
+    assert_eq!(find_project_paths(&claude_paths).await.unwrap(), vec![projects.join("first")]);
+    // Served from the cache until the projects directory changes
+    assert_eq!(cached_project_paths(&claude_paths).unwrap(), vec![projects.join("first")]);
+
+    std::fs::create_dir(projects.join("second")).unwrap();
+    let mut paths = cached_project_paths(&claude_paths).unwrap();
+    paths.sort();
+    assert_eq!(paths, vec![projects.join("first"), projects.join("second")]);
+}
+
+#[tokio::test]
+async fn test_find_transcript_file_remembers_misses_briefly() {
+    let temp_dir = TempDir::new().unwrap();
+    let project_dir = temp_dir.path().join("projects").join("project");
+    fs::create_dir_all(&project_dir).await.unwrap();
+    std::env::set_var("CLAUDE_CONFIG_DIR", temp_dir.path().to_str().unwrap());
+
+    assert_eq!(find_transcript_file("fresh-session").await.unwrap(), None);
+
+    // A transcript appearing right after a miss isn't seen until the miss expires
+    let transcript = project_dir.join("fresh-session.jsonl");
+    fs::write(&transcript, "").await.unwrap();
+    assert_eq!(find_transcript_file("fresh-session").await.unwrap(), None);
+
+    tokio::time::sleep(MISSING_TRANSCRIPT_TTL + std::time::Duration::from_millis(100)).await;
+    assert_eq!(find_transcript_file("fresh-session").await.unwrap(), Some(transcript.clone()));
+
+    // A remembered transcript that moves is looked for again
+    let moved_dir = temp_dir.path().join("projects").join("moved");
+    fs::create_dir_all(&moved_dir).await.unwrap();
+    fs::rename(&transcript, moved_dir.join("fresh-session.jsonl")).await.unwrap();
+    assert_eq!(find_transcript_file("fresh-session").await.unwrap(), Some(moved_dir.join("fresh-session.jsonl")));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_discovery_stats_each_transcript_once() {
+    let temp_dir = TempDir::new().unwrap();
+    let projects = temp_dir.path().join("projects");
+    for project in ["first", "second", "third"] {
+        std::fs::create_dir_all(projects.join(project)).unwrap();
+    }
+    let transcript = projects.join("first").join("session.jsonl");
+    std::fs::write(&transcript, "{}\n").unwrap();
+    std::os::unix::fs::symlink(&transcript, projects.join("second").join("linked.jsonl")).unwrap();
+
+    let old = projects.join("third").join("old.jsonl");
+    std::fs::write(&old, "{}\n").unwrap();
+    let two_days_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(48 * 3600);
+    std::fs::File::options().write(true).open(&old).unwrap().set_modified(two_days_ago).unwrap();
+
+    let project_paths: Vec<_> = ["first", "second", "third"].iter().map(|p| projects.join(p)).collect();
+    let files = DataAggregator::new().discover_transcript_files(&project_paths).unwrap();
+    let paths: Vec<_> = files.iter().map(|file| file.path.clone()).collect();
+    // The link leads to a transcript already found
+    assert_eq!(paths, vec![transcript.clone(), old]);
+    assert_eq!(files[0].metadata.len(), 3);
+
+    let recent = DataAggregator::new().with_time_filter(24).discover_transcript_files(&project_paths).unwrap();
+    assert_eq!(recent.iter().map(|file| file.path.clone()).collect::<Vec<_>>(), vec![transcript]);
+}
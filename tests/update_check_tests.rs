@@ -0,0 +1,64 @@
+use chrono::{Duration, Utc};
+use claude_powerline_rust::{
+    config::Config,
+    segments::{Segment, SegmentContext, UpdateSegment},
+    themes::get_theme,
+    utils::{github_releases_url, is_newer, terminal::force_rgb_colors, UpdateState, CURRENT_VERSION, RELEASES_PAGE},
+};
+use std::sync::Arc;
+use tempfile::TempDir;
+
+#[test]
+fn test_version_comparison() {
+    assert!(is_newer("1.4.0", "1.2.0"));
+    assert!(is_newer("v1.10.0", "1.9.2"));
+    assert!(is_newer("2.0", "1.9.9"));
+    assert!(!is_newer("1.2.0", "1.2.0"));
+    assert!(!is_newer("v1.2", "1.2.0"));
+    assert!(!is_newer("1.2.0-rc.1", "1.2.0"));
+    assert!(!is_newer("1.1.9", "1.2.0"));
+}
+
+#[test]
+fn test_update_state_interval() {
+    let now = Utc::now();
+    assert!(UpdateState::default().is_due(now, Duration::hours(24)));
+
+    let state = UpdateState { checked: Some(now - Duration::hours(3)), latest: Some("99.0.0".to_string()) };
+    assert!(!state.is_due(now, Duration::hours(24)));
+    assert!(state.is_due(now, Duration::hours(2)));
+    assert_eq!(state.newer_than(CURRENT_VERSION), Some("99.0.0"));
+    assert_eq!(state.newer_than("100.0.0"), None);
+}
+
+#[tokio::test]
+async fn test_update_segment_shows_newer_release() {
+    force_rgb_colors();
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("update_check.json");
+    let ctx = SegmentContext::new(Arc::new(Config::default()), Arc::new(get_theme("dark")));
+
+    // A recent lookup, so no new one is started
+    let state = UpdateState { checked: Some(Utc::now()), latest: Some("99.1.0".to_string()) };
+    state.save(&path).unwrap();
+    let segment = UpdateSegment::new().with_state_path(path.clone());
+    let newer = segment.data(&ctx).await.unwrap();
+    assert_eq!(newer.as_deref(), Some("99.1.0"));
+    assert!(segment.render(newer, &ctx).unwrap().contains("⬆ v99.1.0"));
+    assert_eq!(UpdateState::load(&path), state);
+
+    // Nothing to show while this is the latest release
+    UpdateState { checked: Some(Utc::now()), latest: Some(CURRENT_VERSION.to_string()) }.save(&path).unwrap();
+    let newer = segment.data(&ctx).await.unwrap();
+    assert_eq!(segment.render(newer, &ctx).unwrap(), "");
+}
+
+#[test]
+fn test_release_urls_follow_the_package_repository() {
+    let repository = env!("CARGO_PKG_REPOSITORY");
+    assert_eq!(RELEASES_PAGE, format!("{}/releases/latest", repository));
+
+    let api = github_releases_url();
+    assert!(api.starts_with("https://api.github.com/repos/"));
+    assert!(api.ends_with(&format!("{}/releases/latest", repository.trim_start_matches("https://github.com"))));
+}
@@ -0,0 +1,96 @@
+#![cfg(feature = "sqlite-index")]
+
+use claude_powerline_rust::utils::*;
+use chrono::{Duration, Utc};
+use std::io::Write;
+use tempfile::TempDir;
+
+fn line(id: &str, hours_ago: i64) -> String {
+    format!(
+        r#"{{"timestamp":"{}","message":{{"id":"{}","usage":{{"input_tokens":100,"output_tokens":50}},"model":"claude-3-5-sonnet"}},"requestId":"req-{}"}}"#,
+        (Utc::now() - Duration::hours(hours_ago)).format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+        id,
+        id
+    )
+}
+
+#[test]
+fn test_usage_index_ingests_incrementally_and_dedupes() {
+    let temp_dir = TempDir::new().unwrap();
+    let first = temp_dir.path().join("a.jsonl");
+    let second = temp_dir.path().join("b.jsonl");
+    std::fs::write(&first, format!("{}\n{}\n", line("old", 48), line("msg-1", 2))).unwrap();
+    // The same message copied into another transcript (e.g. a resumed session)
+    std::fs::write(&second, format!("{}\n", line("msg-1", 2))).unwrap();
+
+    let files = vec![first.clone(), second.clone()];
+    let mut index = UsageIndex::open(&temp_dir.path().join("index.sqlite")).unwrap();
+    assert_eq!(index.sync(&files).unwrap(), 2);
+
+    // Unchanged files are skipped
+    assert_eq!(index.sync(&files).unwrap(), 0);
+
+    let mut file = std::fs::OpenOptions::new().append(true).open(&first).unwrap();
+    writeln!(file, "{}", line("msg-2", 1)).unwrap();
+    write!(file, "{{\"partial").unwrap();
+    assert_eq!(index.sync(&files).unwrap(), 1);
+
+    assert_eq!(index.entries_since(None).unwrap().len(), 3);
+    let recent = index.entries_since(Some(Utc::now() - Duration::hours(24))).unwrap();
+    assert_eq!(recent.len(), 2);
+    assert!(recent[0].timestamp <= recent[1].timestamp);
+    assert_eq!(recent[0].source_file.as_deref(), Some(first.to_string_lossy().as_ref()));
+}
+
+fn usage_line(hours_ago: i64, session: &str, fields: &str) -> String {
+    format!(
+        r#"{{"timestamp":"{}","sessionId":"{}","cwd":"/work/{}",{}}}"#,
+        (Utc::now() - Duration::hours(hours_ago)).format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+        session,
+        session,
+        fields
+    )
+}
+
+#[test]
+fn test_usage_index_sums_usage_like_the_entry_fold() {
+    let temp_dir = TempDir::new().unwrap();
+    let first = temp_dir.path().join("s1.jsonl");
+    let second = temp_dir.path().join("s2.jsonl");
+    // Cumulative usage across two models, a 1-hour cache split and a reported cost
+    std::fs::write(&first, [
+        usage_line(48, "s1", r#""message":{"id":"m0","model":"claude-3-5-sonnet","usage":{"input_tokens":900,"output_tokens":900}}"#),
+        usage_line(5, "s1", r#""message":{"id":"m1","model":"claude-3-5-sonnet","usage":{"input_tokens":100,"output_tokens":50}}"#),
+        usage_line(4, "s1", r#""message":{"id":"m2","model":"claude-3-5-sonnet","usage":{"input_tokens":300,"output_tokens":120,"cache_creation_input_tokens":1000,"cache_creation":{"ephemeral_5m_input_tokens":400,"ephemeral_1h_input_tokens":600}}}"#),
+        usage_line(3, "s1", r#""type":"user","message":{"id":"m3","usage":{"input_tokens":350,"output_tokens":120}}"#),
+        usage_line(2, "s1", r#""costUSD":0.25,"message":{"id":"m4","model":"claude-3-opus","usage":{"input_tokens":500,"output_tokens":200,"cache_read_input_tokens":2000}}"#),
+    ].join("\n") + "\n").unwrap();
+    std::fs::write(&second, [
+        usage_line(3, "s2", r#""message":{"id":"n1","model":"claude-3-opus","usage":{"input_tokens":40,"output_tokens":10}}"#),
+        usage_line(2, "s2", r#""isSidechain":true,"message":{"id":"n2","model":"claude-3-opus","usage":{"input_tokens":80,"output_tokens":30}}"#),
+    ].join("\n") + "\n").unwrap();
+
+    let mut index = UsageIndex::open(&temp_dir.path().join("index.sqlite")).unwrap();
+    assert_eq!(index.sync(&[first, second]).unwrap(), 7);
+
+    let since = Utc::now() - Duration::hours(24);
+    let pricing_service = PricingService::with_defaults();
+    let entries = index.entries_since(Some(since)).unwrap();
+    let expected = pricing_service.usage_totals(&entries);
+    let usage = index.usage_since(since, &pricing_service).unwrap();
+
+    assert_eq!(usage.entry_count, expected.entry_count);
+    assert_eq!(usage.tokens, expected.tokens);
+    assert_eq!(usage.weighted_tokens, expected.weighted_tokens);
+    assert!((usage.cost - expected.cost).abs() < 1e-9);
+    let (check, expected_check) = (usage.cost_check.unwrap(), expected.cost_check.unwrap());
+    assert!((check.reported - expected_check.reported).abs() < 1e-9);
+    assert!((check.calculated - expected_check.calculated).abs() < 1e-9);
+    assert_eq!(usage.tokens.cache_creation_1h_input_tokens, Some(600));
+
+    // Entries are rebuilt from the columns with the fields the segments read
+    let entry = entries.iter().find(|entry| entry.raw.get("sessionId").and_then(|v| v.as_str()) == Some("s2")).unwrap();
+    assert_eq!(entry.raw.get("cwd").and_then(|v| v.as_str()), Some("/work/s2"));
+    let usage = entry.message.as_ref().and_then(|message| message.usage.as_ref()).unwrap();
+    assert_eq!((usage.input_tokens, usage.output_tokens), (Some(40), Some(10)));
+}
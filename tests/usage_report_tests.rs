@@ -0,0 +1,310 @@
+use claude_powerline_rust::config::{TeamConfig, TeamMember};
+use claude_powerline_rust::reports::*;
+use claude_powerline_rust::test_utils::{ClaudeDirFixture, Transcript, TranscriptEntry};
+use claude_powerline_rust::utils::claude::parse_jsonl_content;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+/// Tests here point CLAUDE_CONFIG_DIR at their own fixtures, so they must not overlap
+static ENV_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+const SONNET: &str = "claude-3-5-sonnet";
+
+/// `hour` o'clock (UTC) on `date`
+fn at(date: NaiveDate, hour: u32) -> DateTime<Utc> {
+    date.and_hms_opt(hour, 0, 0).unwrap().and_utc()
+}
+
+/// Point the local Claude directory at `claude`
+fn use_claude_dir(claude: &ClaudeDirFixture) {
+    std::env::set_var("CLAUDE_CONFIG_DIR", claude.path());
+    std::env::set_var("CLAUDE_POWERLINE_ENTRY_CACHE", "0");
+}
+
+fn reset_claude_dir() {
+    std::env::remove_var("CLAUDE_CONFIG_DIR");
+    std::env::remove_var("CLAUDE_POWERLINE_ENTRY_CACHE");
+}
+
+#[tokio::test]
+async fn test_usage_report_groups_entries_by_day() {
+    let _guard = ENV_LOCK.lock().await;
+    let claude = ClaudeDirFixture::new().unwrap();
+    let now = Utc::now();
+    let today = now.date_naive();
+    let start = today - Duration::days(2);
+    claude.write_transcript("app", &Transcript::new("morning")
+        .entry(TranscriptEntry::assistant(at(start, 10)).model(SONNET).usage(1000, 500))).unwrap();
+    claude.write_transcript("app", &Transcript::new("evening")
+        .entry(TranscriptEntry::assistant(at(start, 18)).model(SONNET).usage(2000, 1000))).unwrap();
+    claude.write_transcript("app", &Transcript::new("latest")
+        .entry(TranscriptEntry::assistant(now).model(SONNET).usage(400, 100))).unwrap();
+    claude.write_transcript("app", &Transcript::new("old")
+        .entry(TranscriptEntry::assistant(at(today - Duration::days(5), 12)).model(SONNET).usage(9999, 9999))).unwrap();
+    use_claude_dir(&claude);
+
+    let report = UsageReport::load(3).await.unwrap();
+
+    // Every day in the range gets a row, even without usage
+    assert_eq!(report.days.len(), 3);
+    assert_eq!(report.days[0].date, start);
+    assert_eq!(report.days[0].message_count, 2);
+    assert_eq!(report.days[1].message_count, 0);
+    assert_eq!(report.days[1].tokens, 0);
+    assert_eq!(report.days[2].message_count, 1);
+    assert_eq!(report.days[2].tokens, 500);
+
+    // Entries outside the range are ignored
+    assert_eq!(report.total_messages(), 3);
+    assert!(report.total_cost() > 0.0);
+
+    let table = report.render_table(false);
+    assert!(table.contains(&(start + Duration::days(1)).to_string()));
+    assert!(table.contains("Total"));
+    assert!(!table.contains("\x1b["));
+
+    reset_claude_dir();
+}
+
+#[tokio::test]
+async fn test_usage_report_csv_and_json_output() {
+    let _guard = ENV_LOCK.lock().await;
+    let claude = ClaudeDirFixture::new().unwrap();
+    let today = Utc::now().date_naive();
+    let yesterday = today - Duration::days(1);
+    claude.write_transcript("app", &Transcript::new("session")
+        .entry(TranscriptEntry::assistant(at(yesterday, 10)).model(SONNET).usage(1000, 500))).unwrap();
+    use_claude_dir(&claude);
+
+    let report = UsageReport::load(2).await.unwrap();
+
+    let csv = report.render_csv();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines[0], "date,cost_usd,billed_cost_usd,tokens,messages");
+    assert_eq!(lines[1], format!("{},0.0105,0.0105,1500,1", yesterday));
+    assert_eq!(lines[2], format!("{},0.0000,0.0000,0,0", today));
+
+    let json: serde_json::Value = serde_json::from_str(&report.render_json().unwrap()).unwrap();
+    assert_eq!(json["days"].as_array().unwrap().len(), 2);
+    assert_eq!(json["days"][0]["date"], yesterday.to_string());
+    assert_eq!(json["days"][0]["tokens"], 1500);
+    assert_eq!(json["total"]["message_count"], 1);
+
+    reset_claude_dir();
+}
+
+#[tokio::test]
+async fn test_render_report_dispatches_on_format() {
+    let _guard = ENV_LOCK.lock().await;
+    let claude = ClaudeDirFixture::new().unwrap();
+    use_claude_dir(&claude);
+
+    let report = UsageReport::load(1).await.unwrap();
+
+    assert_eq!(render_report(&report, "csv", false).unwrap(), report.render_csv());
+    assert_eq!(render_report(&report, "table", false).unwrap(), report.render_table(false));
+    assert!(render_report(&report, "html", false).unwrap().contains("<table"));
+    assert!(render_report(&report, "yaml", false).is_err());
+
+    reset_claude_dir();
+}
+
+#[tokio::test]
+async fn test_usage_report_groups_entries_by_project() {
+    let _guard = ENV_LOCK.lock().await;
+    let claude = ClaudeDirFixture::new().unwrap();
+    let now = Utc::now();
+    let yesterday = now.date_naive() - Duration::days(1);
+    let in_project = |timestamp, cwd: &str| TranscriptEntry::assistant(timestamp).model(SONNET).field("cwd", cwd);
+    claude.write_transcript("-work-client-a", &Transcript::new("a1")
+        .entry(in_project(at(yesterday, 10), "/work/client-a").usage(1000, 500))).unwrap();
+    claude.write_transcript("-work-client-a", &Transcript::new("a2")
+        .entry(in_project(now, "/work/client-a").usage(1000, 500))).unwrap();
+    claude.write_transcript("-work-client--b", &Transcript::new("b")
+        .entry(in_project(now, "/work/client, b").usage(100, 50))).unwrap();
+    // Entries without a cwd fall back to the decoded transcript directory name
+    claude.write_transcript("-home-me-scratch", &Transcript::new("scratch")
+        .entry(TranscriptEntry::assistant(at(yesterday, 10)).model(SONNET).usage(1000, 500))).unwrap();
+    use_claude_dir(&claude);
+
+    let report = ProjectUsageReport::load(2).await.unwrap();
+
+    assert_eq!(report.projects.len(), 3);
+    let client_a = report.projects.iter().find(|p| p.project == "/work/client-a").unwrap();
+    assert_eq!(client_a.message_count, 2);
+    assert!(client_a.tokens > 0);
+    // Sorted by cost, highest first
+    assert!(report.projects.windows(2).all(|w| w[0].cost >= w[1].cost));
+    assert!(report.projects.iter().any(|p| p.project == "/home/me/scratch"));
+    assert_eq!(report.total_messages(), 4);
+
+    let csv = report.render_csv();
+    assert_eq!(csv.lines().next().unwrap(), "project,cost_usd,billed_cost_usd,tokens,messages");
+    assert!(csv.contains("\"/work/client, b\",0.0011,0.0011,150,1"));
+
+    let json: serde_json::Value = serde_json::from_str(&report.render_json().unwrap()).unwrap();
+    assert_eq!(json["projects"].as_array().unwrap().len(), 3);
+    assert_eq!(json["start"], yesterday.to_string());
+
+    assert!(report.render_table(false).contains("/work/client-a"));
+
+    reset_claude_dir();
+}
+
+#[tokio::test]
+async fn test_usage_report_markdown_and_html_output() {
+    let _guard = ENV_LOCK.lock().await;
+    let claude = ClaudeDirFixture::new().unwrap();
+    let now = Utc::now();
+    let today = now.date_naive();
+    let yesterday = today - Duration::days(1);
+    claude.write_transcript("app", &Transcript::new("session-1")
+        .entry(TranscriptEntry::assistant(at(yesterday, 10)).model(SONNET).usage(1000, 500))).unwrap();
+    claude.write_transcript("app", &Transcript::new("session-2")
+        .entry(TranscriptEntry::assistant(now).model("claude-3-opus").usage(1000, 500))).unwrap();
+    claude.write_transcript("app", &Transcript::new("session-3")
+        .entry(TranscriptEntry::assistant(now).model("<custom>|model").usage(100, 50))).unwrap();
+    use_claude_dir(&claude);
+
+    let report = UsageReport::load(2).await.unwrap();
+
+    // Models are sorted by cost, highest first
+    assert_eq!(report.models.len(), 3);
+    assert_eq!(report.models[0].model, "claude-3-opus");
+    assert_eq!(report.models.iter().map(|m| m.message_count).sum::<u32>(), 3);
+
+    let markdown = report.render_markdown();
+    assert!(markdown.starts_with(&format!("# Claude usage, {} to {}", yesterday, today)));
+    assert!(markdown.contains("## By day"));
+    assert!(markdown.contains("| Date | Cost | Tokens | Messages |\n| :--- | ---: | ---: | ---: |"));
+    assert!(markdown.contains(&format!("| {} | $0.01 | 1,500 | 1 |", yesterday)));
+    assert!(markdown.contains("## By model"));
+    assert!(markdown.contains("| <custom>\\|model |"));
+    assert!(markdown.contains("| **Total** |"));
+
+    let html = report.render_html();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains(&format!("<h1>Claude usage, {} to {}</h1>", yesterday, today)));
+    assert!(html.contains("<td>&lt;custom&gt;|model</td>"));
+    assert!(html.contains("<tfoot>"));
+    assert_eq!(html.matches("<table>").count(), 2);
+    assert!(html.trim_end().ends_with("</html>"));
+
+    let json: serde_json::Value = serde_json::from_str(&report.render_json().unwrap()).unwrap();
+    assert_eq!(json["models"].as_array().unwrap().len(), 3);
+
+    reset_claude_dir();
+}
+
+#[tokio::test]
+async fn test_team_report_has_a_column_per_person() {
+    let _guard = ENV_LOCK.lock().await;
+    let dana = ClaudeDirFixture::new().unwrap();
+    let alex = ClaudeDirFixture::new().unwrap();
+    let now = Utc::now();
+    let today = now.date_naive();
+    let yesterday = today - Duration::days(1);
+    dana.write_transcript("app", &Transcript::new("dana-session")
+        .entry(TranscriptEntry::assistant(at(yesterday, 10)).model(SONNET).usage(1000, 500))).unwrap();
+    alex.write_transcript("app", &Transcript::new("alex-session")
+        .entry(TranscriptEntry::assistant(now).model(SONNET).usage(2000, 1000))).unwrap();
+    use_claude_dir(&dana);
+
+    let team = TeamConfig {
+        self_name: Some("dana".to_string()),
+        members: Some(vec![TeamMember { name: "alex, jr".to_string(), paths: vec![alex.path().to_path_buf()] }]),
+    };
+    let report = TeamUsageReport::load(&team, 2).await.unwrap();
+
+    assert_eq!(report.people.len(), 2);
+    assert_eq!(report.people[1].days.len(), 2);
+    assert!((report.total_cost() - 0.0315).abs() < 1e-9);
+
+    let table = report.render_table(false);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines[0], format!("Team usage, {} to {}", yesterday, today));
+    assert!(lines[2].starts_with("Date") && lines[2].ends_with("dana  alex, jr  Total"), "{}", table);
+    let cells = |line: &str| line.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+    assert_eq!(cells(lines[4]), vec![yesterday.to_string(), "$0.01".into(), "$0.00".into(), "$0.01".into()], "{}", table);
+    assert_eq!(cells(lines.last().unwrap()), vec!["Total", "$0.01", "$0.02", "$0.03"], "{}", table);
+
+    let csv = report.render_csv();
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows[0], "date,person,cost_usd,billed_cost_usd,tokens,messages");
+    assert_eq!(rows[1], format!("{},dana,0.0105,0.0105,1500,1", yesterday));
+    assert_eq!(rows[4], format!("{},\"alex, jr\",0.0210,0.0210,3000,1", today));
+
+    let json: serde_json::Value = serde_json::from_str(&report.render_json().unwrap()).unwrap();
+    assert_eq!(json["people"][0]["name"], "dana");
+    assert_eq!(json["people"][1]["total"]["tokens"], 3000);
+    assert_eq!(json["total"]["message_count"], 2);
+    // Without a multiplier the billed total is still present, equal to the raw cost
+    assert_eq!(json["total"]["billed_cost_usd"], json["total"]["cost"]);
+    assert_eq!(json["cost_multiplier"], 1.0);
+
+    reset_claude_dir();
+}
+
+#[tokio::test]
+async fn test_team_report_reads_each_members_directory() {
+    let alex = ClaudeDirFixture::new().unwrap();
+    let now = chrono::Utc::now();
+    alex.write_transcript("app", &Transcript::new("alex-session")
+        .entry(TranscriptEntry::assistant(now).usage(100, 10)))
+        .unwrap();
+
+    let team = TeamConfig {
+        self_name: Some("dana".to_string()),
+        members: Some(vec![
+            TeamMember { name: "alex".to_string(), paths: vec![alex.path().to_path_buf()] },
+            TeamMember { name: "sam".to_string(), paths: vec![alex.path().join("missing")] },
+        ]),
+    };
+    let report = TeamUsageReport::load(&team, 1).await.unwrap();
+
+    let names: Vec<&str> = report.people.iter().map(|person| person.name.as_str()).collect();
+    assert_eq!(names, vec!["dana", "alex", "sam"]);
+    assert_eq!(report.people[1].days[0].message_count, 1);
+    assert_eq!(report.people[1].days[0].tokens, 110);
+    assert_eq!(report.people[2].total_cost(), 0.0);
+}
+
+#[tokio::test]
+async fn test_team_report_clamps_huge_day_counts() {
+    assert_eq!((report_days(0), report_days(30), report_days(u32::MAX)), (1, 30, MAX_REPORT_DAYS));
+
+    let alex = ClaudeDirFixture::new().unwrap();
+    let team = TeamConfig {
+        self_name: None,
+        members: Some(vec![TeamMember { name: "alex".to_string(), paths: vec![alex.path().to_path_buf()] }]),
+    };
+    let report = TeamUsageReport::load(&team, u32::MAX).await.unwrap();
+    assert_eq!(report.people.last().unwrap().days.len(), MAX_REPORT_DAYS as usize);
+}
+
+#[tokio::test]
+async fn test_report_totals_fold_past_u32_max() {
+    use claude_powerline_rust::utils::PricingService;
+
+    let _guard = ENV_LOCK.lock().await;
+    let claude = ClaudeDirFixture::new().unwrap();
+    let now = Utc::now();
+    // Three sessions of heavy cache reads, 9 billion tokens between them
+    let entries: Vec<TranscriptEntry> = ["a", "b", "c"]
+        .iter()
+        .map(|session| TranscriptEntry::assistant(now).session(session).cache(0, 3_000_000_000))
+        .collect();
+    for (session, entry) in ["a", "b", "c"].iter().zip(&entries) {
+        claude.write_transcript("app", &Transcript::new(session).entry(entry.clone())).unwrap();
+    }
+
+    let jsonl: Vec<String> = entries.iter().map(|entry| entry.to_json().to_string()).collect();
+    let fold = PricingService::with_defaults().usage_totals(&parse_jsonl_content(&jsonl.join("\n")).unwrap());
+    assert_eq!(fold.tokens.total_tokens(), 9_000_000_000);
+    assert_eq!(fold.weighted_tokens, 9_000_000_000);
+
+    use_claude_dir(&claude);
+    let report = UsageReport::load(1).await.unwrap();
+    assert_eq!(report.total_tokens(), 9_000_000_000);
+
+    reset_claude_dir();
+}
@@ -0,0 +1,116 @@
+use claude_powerline_rust::utils::*;
+use std::io::Write;
+use tempfile::TempDir;
+
+fn line(id: &str) -> String {
+    format!(
+        r#"{{"timestamp":"2024-01-01T10:00:00.000Z","message":{{"id":"{}","usage":{{"input_tokens":100,"output_tokens":50}},"model":"claude-3-5-sonnet"}},"requestId":"req-{}"}}"#,
+        id, id
+    )
+}
+
+#[test]
+fn test_ingest_transcript_parses_appended_lines() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("session.jsonl");
+    std::fs::write(&path, format!("{}\n", line("msg-1"))).unwrap();
+
+    assert!(prewarmed_entries(&path).is_none());
+    assert_eq!(ingest_transcript(&path).unwrap(), 1);
+    let entries = prewarmed_entries(&path).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].source_file.as_deref(), Some(path.to_string_lossy().as_ref()));
+
+    // A half-written line is left for later and the cache is not served meanwhile
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+    let second = line("msg-2");
+    let (head, tail) = second.split_at(20);
+    write!(file, "{}", head).unwrap();
+    assert_eq!(ingest_transcript(&path).unwrap(), 0);
+    assert!(prewarmed_entries(&path).is_none());
+
+    writeln!(file, "{}", tail).unwrap();
+    assert_eq!(ingest_transcript(&path).unwrap(), 1);
+    assert_eq!(prewarmed_entries(&path).unwrap().len(), 2);
+
+    // Rewritten (shorter) files are parsed from scratch
+    std::fs::write(&path, format!("{}\n", line("msg-3"))).unwrap();
+    assert_eq!(ingest_transcript(&path).unwrap(), 1);
+    let entries = prewarmed_entries(&path).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].message.as_ref().unwrap().id.as_deref(), Some("msg-3"));
+}
+
+#[test]
+fn test_ingest_transcript_shares_entries_and_records_parse_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("corrupt.jsonl");
+    std::fs::write(&path, format!("{}\nnot json\n", line("msg-1"))).unwrap();
+
+    assert_eq!(ingest_transcript(&path).unwrap(), 1);
+    assert!(parse_error_counts().contains(&(path.clone(), 1)));
+
+    // Renders between appends get the same entries without a copy
+    let first = prewarmed_entries(&path).unwrap();
+    assert!(std::sync::Arc::ptr_eq(&first, &prewarmed_entries(&path).unwrap()));
+
+    // Counts add up across appends, since each ingest parses only the new lines
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+    writeln!(file, "{{ broken").unwrap();
+    writeln!(file, "{}", line("msg-2")).unwrap();
+    assert_eq!(ingest_transcript(&path).unwrap(), 1);
+    assert!(parse_error_counts().contains(&(path.clone(), 2)));
+    assert_eq!(prewarmed_entries(&path).unwrap().len(), 2);
+    assert_eq!(first.len(), 1);
+}
+
+#[test]
+fn test_ingest_transcript_reads_compressed_archives() {
+    use flate2::{write::GzEncoder, Compression};
+
+    let gzip = |lines: &[String]| {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        for line in lines {
+            writeln!(encoder, "{}", line).unwrap();
+        }
+        encoder.finish().unwrap()
+    };
+
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("session.jsonl.gz");
+    assert!(is_transcript_path(&path));
+
+    std::fs::write(&path, gzip(&[line("msg-1")])).unwrap();
+    assert_eq!(ingest_transcript(&path).unwrap(), 1);
+    assert_eq!(ingest_transcript(&path).unwrap(), 0);
+    let entries = prewarmed_entries(&path).unwrap();
+    assert_eq!(entries[0].source_file.as_deref(), Some(path.to_string_lossy().as_ref()));
+
+    // A rewritten archive replaces the entries rather than adding to them
+    std::fs::write(&path, gzip(&[line("msg-1"), line("msg-2")])).unwrap();
+    assert_eq!(ingest_transcript(&path).unwrap(), 2);
+    assert_eq!(prewarmed_entries(&path).unwrap().len(), 2);
+}
+
+#[test]
+fn test_config_reload_names_settings_that_need_a_restart() {
+    use claude_powerline_rust::config::Config;
+    let parse = |value: serde_json::Value| -> Config {
+        let mut config = serde_json::json!({ "theme": "dark", "style": "minimal", "segments": {} });
+        config.as_object_mut().unwrap().extend(value.as_object().unwrap().clone());
+        serde_json::from_value(config).unwrap()
+    };
+
+    let before = parse(serde_json::json!({ "display": { "costUnit": "dollars" }, "claudePaths": ["/a"] }));
+    assert!(before.changed_startup_keys(&before.clone()).is_empty());
+
+    // Theme, layout and segment options apply on reload; these don't
+    let after = parse(serde_json::json!({
+        "theme": "nord",
+        "display": { "costUnit": "cents", "maxWidth": 80 },
+        "claudePaths": ["/a"],
+        "pricing": { "includeSidechains": false },
+        "performance": { "threads": 2, "renderDeadlineMs": 100 }
+    }));
+    assert_eq!(before.changed_startup_keys(&after), ["pricing", "display.costUnit", "performance.threads"]);
+}
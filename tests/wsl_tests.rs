@@ -0,0 +1,70 @@
+use claude_powerline_rust::config::Config;
+use claude_powerline_rust::utils::{bridge_path, user_profile_from_output, windows_to_wsl_path, wsl_to_windows_path};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn test_windows_paths_map_to_wsl_mounts() {
+    assert_eq!(
+        windows_to_wsl_path(r"C:\Users\me\.claude"),
+        Some(PathBuf::from("/mnt/c/Users/me/.claude"))
+    );
+    assert_eq!(windows_to_wsl_path("D:/work/data"), Some(PathBuf::from("/mnt/d/work/data")));
+    assert_eq!(windows_to_wsl_path(r"E:\"), Some(PathBuf::from("/mnt/e")));
+
+    assert_eq!(windows_to_wsl_path("/home/me/.claude"), None);
+    assert_eq!(windows_to_wsl_path(r"\\wsl$\Ubuntu\home\me\.claude"), None);
+    assert_eq!(windows_to_wsl_path("C:relative"), None);
+}
+
+#[test]
+fn test_wsl_mounts_map_to_windows_paths() {
+    assert_eq!(
+        wsl_to_windows_path("/mnt/c/Users/me/.claude"),
+        Some(PathBuf::from(r"C:\Users\me\.claude"))
+    );
+    assert_eq!(wsl_to_windows_path("/mnt/d"), Some(PathBuf::from(r"D:\")));
+
+    assert_eq!(wsl_to_windows_path("/mnt/wsl/shared"), None);
+    assert_eq!(wsl_to_windows_path("/home/me/.claude"), None);
+}
+
+#[cfg(not(windows))]
+#[test]
+fn test_bridge_path_translates_windows_paths_only() {
+    assert_eq!(bridge_path(Path::new(r"C:\Users\me\.claude")), PathBuf::from("/mnt/c/Users/me/.claude"));
+    assert_eq!(bridge_path(Path::new("/home/me/.claude")), PathBuf::from("/home/me/.claude"));
+}
+
+#[test]
+fn test_claude_paths_config() {
+    let config: Config = serde_json::from_value(serde_json::json!({
+        "theme": "dark",
+        "style": "minimal",
+        "segments": {},
+        "claudePaths": ["/home/me/.claude", r"C:\Users\me\.claude"]
+    }))
+    .unwrap();
+
+    assert_eq!(config.claude_paths.unwrap().len(), 2);
+}
+
+#[test]
+fn test_user_profile_from_windows_output() {
+    assert_eq!(user_profile_from_output("C:\\Users\\me\r\n"), Some(PathBuf::from("/mnt/c/Users/me")));
+    // cmd.exe echoes the variable back unexpanded when it isn't set
+    assert_eq!(user_profile_from_output("%USERPROFILE%\r\n"), None);
+    assert_eq!(user_profile_from_output(""), None);
+}
+
+#[test]
+fn test_wsl_all_profiles_config() {
+    let config: Config = serde_json::from_value(serde_json::json!({
+        "theme": "dark",
+        "style": "minimal",
+        "segments": {},
+        "wslAllProfiles": true
+    }))
+    .unwrap();
+
+    assert_eq!(config.wsl_all_profiles, Some(true));
+}